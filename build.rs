@@ -8,4 +8,7 @@ fn main() {
 
     #[cfg(not(windows))]
     println!("cargo:rerun-if-changed=resources/windows/icon.ico");
+
+    // Locale files are built into the binary; a new one needs a rebuild
+    println!("cargo:rerun-if-changed=locales");
 }
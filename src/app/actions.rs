@@ -1,9 +1,13 @@
 use super::PdfReaderApp;
 use crate::app::menu::{
-    CloseTab, FirstPage, FitPage, FitWidth, FitWidthCentered, FullScreen, LastPage, NextPage,
-    OpenFile, PrevPage, Print, Quit, RefreshMenus, ResetZoom, RotateClockwise,
-    RotateCounterClockwise, ToggleSidebar, ToggleTheme, ZoomIn, ZoomOut,
+    CloseTab, CommandPalette, Copy, CopyAsHtml, CopyAsMarkdown, CopyRawText, ExportFormData,
+    ExportImages, ExportText, FirstPage, FitPage, FitWidth, FitWidthCentered, FullScreen,
+    ImportFormData, LastPage, MergePdfs, NextPage, OpenFile, OrganizePages, Preferences, PrevPage,
+    Print, Quit, RefreshMenus, ResetZoom, RotateClockwise, RotateCounterClockwise, SelectAll,
+    SelectLanguage, SelectTheme, ShowShortcuts, SplitPdf, ToggleSidebar, ToggleTheme, ZoomIn,
+    ZoomOut,
 };
+use crate::pdf::text_export::TextExportFormat;
 use gpui::{prelude::*, App, WindowHandle};
 
 /// Helper to register window update actions
@@ -116,6 +120,29 @@ pub fn register_actions(cx: &mut App, window_handle: WindowHandle<PdfReaderApp>)
     register_window_action::<ToggleTheme, _>(cx, &window_handle, |app, cx| {
         app.toggle_theme(cx);
     });
+    register_window_action::<CommandPalette, _>(cx, &window_handle, |app, cx| {
+        app.open_command_palette(cx);
+    });
+    register_window_action::<ShowShortcuts, _>(cx, &window_handle, |app, cx| {
+        app.show_shortcuts(cx);
+    });
+    register_window_action::<Preferences, _>(cx, &window_handle, |app, cx| {
+        app.show_preferences(cx);
+    });
+    cx.on_action(move |action: &SelectTheme, cx: &mut App| {
+        window_handle
+            .update(cx, |app: &mut PdfReaderApp, _window, cx| {
+                app.select_theme(action.0.clone(), cx);
+            })
+            .ok();
+    });
+    cx.on_action(move |action: &SelectLanguage, cx: &mut App| {
+        window_handle
+            .update(cx, |app: &mut PdfReaderApp, _window, cx| {
+                app.select_language(action.0.clone(), cx);
+            })
+            .ok();
+    });
 
     // Fullscreen action
     cx.on_action({
@@ -132,6 +159,56 @@ pub fn register_actions(cx: &mut App, window_handle: WindowHandle<PdfReaderApp>)
     register_window_action::<Print, _>(cx, &window_handle, |app, cx| {
         app.print(cx);
     });
+
+    // Form data actions
+    register_window_action::<ExportFormData, _>(cx, &window_handle, |app, cx| {
+        app.export_form_data(cx);
+    });
+    register_window_action::<ImportFormData, _>(cx, &window_handle, |app, cx| {
+        app.import_form_data(cx);
+    });
+
+    // Page organizer action
+    register_window_action::<OrganizePages, _>(cx, &window_handle, |app, cx| {
+        app.toggle_page_organizer(cx);
+    });
+
+    // Merge action
+    register_window_action::<MergePdfs, _>(cx, &window_handle, |app, cx| {
+        app.show_merge_dialog(cx);
+    });
+
+    // Split action
+    register_window_action::<SplitPdf, _>(cx, &window_handle, |app, cx| {
+        app.show_split_dialog(cx);
+    });
+
+    // Export pages as images action
+    register_window_action::<ExportImages, _>(cx, &window_handle, |app, cx| {
+        app.show_image_export_dialog(cx);
+    });
+
+    // Export text action
+    register_window_action::<ExportText, _>(cx, &window_handle, |app, cx| {
+        app.export_text(cx);
+    });
+
+    // Copy actions
+    register_window_action::<Copy, _>(cx, &window_handle, |app, cx| {
+        app.copy_selected_text(cx);
+    });
+    register_window_action::<CopyRawText, _>(cx, &window_handle, |app, cx| {
+        app.copy_raw_text(cx);
+    });
+    register_window_action::<CopyAsMarkdown, _>(cx, &window_handle, |app, cx| {
+        app.copy_selection_as(TextExportFormat::Markdown, cx);
+    });
+    register_window_action::<CopyAsHtml, _>(cx, &window_handle, |app, cx| {
+        app.copy_selection_as(TextExportFormat::Html, cx);
+    });
+    register_window_action::<SelectAll, _>(cx, &window_handle, |app, cx| {
+        app.select_all_text(cx);
+    });
 }
 
 /// Update application menus based on current language
@@ -0,0 +1,248 @@
+//! Command palette: every action, recent file and outline entry, found by
+//! typing a few letters of its name
+
+use super::dialog::Dialog;
+use super::keymap::action_label;
+use super::text_input::TextInput;
+use super::PdfReaderApp;
+use crate::pdf::OutlineItem;
+use crate::tr;
+use gpui::*;
+use std::path::PathBuf;
+
+/// Actions that make no sense to run from the palette
+const HIDDEN_ACTIONS: &[&str] = &[
+    "CommandPalette",
+    "RefreshMenus",
+    "SelectTheme",
+    "SelectLanguage",
+];
+
+/// What choosing an item does
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaletteTarget {
+    /// Run the action of this name, without its namespace
+    Action(String),
+    RecentFile(PathBuf),
+    /// Go to this page of the active document
+    Outline(usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct PaletteItem {
+    pub label: String,
+    /// Kind of item, or where it leads
+    pub detail: String,
+    pub shortcut: Option<String>,
+    pub target: PaletteTarget,
+}
+
+pub struct CommandPalette {
+    pub query: TextInput,
+    pub items: Vec<PaletteItem>,
+    /// Indices into `items` matching the query, best first
+    pub matches: Vec<usize>,
+    pub selected: usize,
+    pub scroll: ScrollHandle,
+}
+
+impl CommandPalette {
+    pub fn new(items: Vec<PaletteItem>) -> Self {
+        let mut palette = Self {
+            query: TextInput::default(),
+            items,
+            matches: Vec::new(),
+            selected: 0,
+            scroll: ScrollHandle::new(),
+        };
+        palette.refilter();
+        palette
+    }
+
+    pub fn handle_key(&mut self, keystroke: &Keystroke, cx: &mut App) {
+        match keystroke.key.as_str() {
+            "up" => self.select(self.selected.saturating_sub(1)),
+            "down" => self.select(self.selected + 1),
+            _ => {
+                if self.query.handle_key(keystroke, cx) {
+                    self.refilter();
+                }
+            }
+        }
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.matches.len().saturating_sub(1));
+        self.scroll.scroll_to_item(self.selected);
+    }
+
+    pub fn selected_item(&self) -> Option<&PaletteItem> {
+        self.items.get(*self.matches.get(self.selected)?)
+    }
+
+    fn refilter(&mut self) {
+        let query = self.query.text.trim();
+        let mut scored: Vec<(i32, usize)> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| Some((fuzzy_score(query, &item.label)?, index)))
+            .collect();
+        // Best first; equal scores keep their order, actions before files
+        scored.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+        self.matches = scored.into_iter().map(|(_, index)| index).collect();
+        self.select(0);
+    }
+}
+
+/// How well `query` matches `text`: its characters must appear in order,
+/// scoring higher when they are next to each other or start words. None when
+/// they do not all appear.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+    for wanted in query.chars().flat_map(char::to_lowercase) {
+        if wanted.is_whitespace() {
+            continue;
+        }
+        let found = position + text[position..].iter().position(|&c| c == wanted)?;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 8;
+        }
+        // Letters skipped over count against the match
+        score -= (found - position).min(3) as i32;
+        previous = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+/// "FitWidthCentered" as "Fit Width Centered", for actions without a menu item
+fn humanize(name: &str) -> String {
+    let mut text = String::new();
+    for c in name.chars() {
+        if c.is_uppercase() && !text.is_empty() {
+            text.push(' ');
+        }
+        text.push(c);
+    }
+    text
+}
+
+/// Outline entries and their children, depth first
+fn flatten_outline(items: &[OutlineItem], out: &mut Vec<PaletteItem>) {
+    for item in items {
+        out.push(PaletteItem {
+            label: item.title.clone(),
+            detail: tr!("palette.outline", "page" => item.page + 1),
+            shortcut: None,
+            target: PaletteTarget::Outline(item.page),
+        });
+        flatten_outline(&item.children, out);
+    }
+}
+
+impl PdfReaderApp {
+    pub fn open_command_palette(&mut self, cx: &mut Context<Self>) {
+        let mut items = Vec::new();
+        let mut names: Vec<&str> = cx
+            .all_action_names()
+            .iter()
+            .filter_map(|name| name.strip_prefix("lingpdf::"))
+            .filter(|name| !HIDDEN_ACTIONS.contains(name))
+            .collect();
+        names.sort();
+        for name in names {
+            items.push(PaletteItem {
+                label: action_label(name).unwrap_or_else(|| humanize(name)),
+                detail: String::new(),
+                shortcut: self.keymap.keys_for(name).map(|b| b.keys_text()),
+                target: PaletteTarget::Action(name.to_string()),
+            });
+        }
+        for path in self.state.get_recent_files() {
+            let path = PathBuf::from(path);
+            items.push(PaletteItem {
+                label: path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                detail: tr!("palette.recent_file", "path" => path.display()),
+                shortcut: None,
+                target: PaletteTarget::RecentFile(path),
+            });
+        }
+        let outline = self
+            .state
+            .get_active_tab_id()
+            .and_then(|id| self.state.tabs.get_tab(id))
+            .and_then(|tab| tab.outline_items);
+        if let Some(outline) = outline {
+            flatten_outline(&outline, &mut items);
+        }
+
+        self.open_dialog(
+            Dialog::CommandPalette(Box::new(CommandPalette::new(items))),
+            cx,
+        );
+    }
+
+    /// Close the palette and do what its selected item says
+    pub fn run_palette_item(&mut self, cx: &mut Context<Self>) {
+        let Some(Dialog::CommandPalette(palette)) = &self.dialog else {
+            return;
+        };
+        let Some(target) = palette.selected_item().map(|item| item.target.clone()) else {
+            return;
+        };
+        self.close_dialog(cx);
+
+        match target {
+            PaletteTarget::Action(name) => {
+                // Run once the palette's own update is over, like a menu item
+                cx.defer(
+                    move |cx| match cx.build_action(&format!("lingpdf::{}", name), None) {
+                        Ok(action) => cx.dispatch_action(action.as_ref()),
+                        Err(e) => log::warn!("Cannot run {}: {}", name, e),
+                    },
+                );
+            }
+            PaletteTarget::RecentFile(path) => {
+                self.open_file_in_new_tab(path, cx);
+            }
+            PaletteTarget::Outline(page) => {
+                if let Some(tab_id) = self.state.get_active_tab_id() {
+                    let _ = self.state.navigate_to_page(page);
+                    self.render_current_tab_page(tab_id, cx);
+                    cx.notify();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fuzzy_score, humanize};
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("fwc", "Fit Width (Centered)").is_some());
+        assert!(fuzzy_score("rcc", "Rotate Counter-Clockwise").is_some());
+        assert_eq!(fuzzy_score("zx", "Zoom In"), None);
+        assert_eq!(fuzzy_score("", "Zoom In"), Some(0));
+        // Word starts and runs of letters beat scattered ones
+        assert!(fuzzy_score("zo", "Zoom Out").unwrap() > fuzzy_score("zo", "Fit Zone").unwrap());
+        assert!(
+            fuzzy_score("fit p", "Fit Page").unwrap()
+                > fuzzy_score("fit p", "Fit Width").unwrap_or(-99)
+        );
+        assert_eq!(humanize("FitWidthCentered"), "Fit Width Centered");
+    }
+}
@@ -0,0 +1,222 @@
+//! Reading and writing the config file: versioned, migrated forward from
+//! older versions, set aside when unreadable and replaced atomically
+
+use super::state::AppConfig;
+use crate::tr;
+use serde_json::{Map, Value};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Version of the config file this build writes
+pub const CONFIG_VERSION: u32 = 3;
+
+/// Steps moving a config up one version; entry `i` turns version `i + 1`
+/// into version `i + 2`
+const MIGRATIONS: [fn(&mut Map<String, Value>); CONFIG_VERSION as usize - 1] = [v1_to_v2, v2_to_v3];
+
+/// Version 1 files were written before the version field existed. Fields added
+/// since then have serde defaults, so there is nothing else to change.
+fn v1_to_v2(_config: &mut Map<String, Value>) {}
+
+/// Languages were stored by English name until they came from the locale
+/// files; they are now stored by locale code
+fn v2_to_v3(config: &mut Map<String, Value>) {
+    let code = match config.get("language").and_then(Value::as_str) {
+        Some("English") => "en",
+        Some("Chinese") => "zh",
+        Some("Spanish") => "es",
+        _ => return,
+    };
+    config.insert("language".to_string(), Value::from(code));
+}
+
+/// Why the config file is not in use or not up to date, shown until dismissed
+#[derive(Debug, Clone)]
+pub enum ConfigError {
+    /// The file could not be read or understood; settings start from their
+    /// defaults and the file was moved to `backup`, if that worked
+    Unreadable {
+        error: String,
+        backup: Option<PathBuf>,
+    },
+    /// The last save failed; changes are kept until the app quits
+    SaveFailed(String),
+}
+
+impl ConfigError {
+    pub fn message(&self) -> String {
+        match self {
+            ConfigError::Unreadable {
+                error,
+                backup: Some(backup),
+            } => tr!("config.unreadable", "error" => error, "file" => backup.display()),
+            ConfigError::Unreadable {
+                error,
+                backup: None,
+            } => tr!("config.unreadable_no_backup", "error" => error),
+            ConfigError::SaveFailed(error) => tr!("config.save_failed", "error" => error),
+        }
+    }
+}
+
+/// Read the config at `path`, falling back to the defaults when there is
+/// none. An unreadable file is moved aside so the next save cannot lose it.
+pub fn load(path: &Path) -> (AppConfig, Option<ConfigError>) {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return (AppConfig::default(), None),
+        Err(e) => {
+            // Not moved: a file that cannot be read probably cannot be renamed either
+            let error = ConfigError::Unreadable {
+                error: e.to_string(),
+                backup: None,
+            };
+            return (AppConfig::default(), Some(error));
+        }
+    };
+
+    match parse(&content) {
+        Ok((config, version)) => {
+            if version > CONFIG_VERSION {
+                // Settings this build does not know would be lost on the next save
+                log::warn!(
+                    "Config file is version {}, newer than {}; keeping a copy",
+                    version,
+                    CONFIG_VERSION
+                );
+                let backup = backup_path(path);
+                if let Err(e) = fs::copy(path, &backup) {
+                    log::error!("Failed to back up config: {}", e);
+                }
+            }
+            (config, None)
+        }
+        Err(error) => {
+            log::error!("Config file is unreadable: {}", error);
+            let backup = backup_path(path);
+            let backup = match fs::rename(path, &backup) {
+                Ok(()) => Some(backup),
+                Err(e) => {
+                    log::error!("Failed to move unreadable config aside: {}", e);
+                    None
+                }
+            };
+            (
+                AppConfig::default(),
+                Some(ConfigError::Unreadable { error, backup }),
+            )
+        }
+    }
+}
+
+/// Parse the contents of a config file of any version up to this one's,
+/// returning the config and the version it was written as
+pub fn parse(content: &str) -> Result<(AppConfig, u32), String> {
+    let mut value: Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    let Value::Object(map) = &mut value else {
+        return Err(tr!("config.not_an_object"));
+    };
+    let version = match map.get("version") {
+        None => 1,
+        Some(version) => version
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .filter(|&v| v > 0)
+            .ok_or_else(|| tr!("config.invalid_version", "version" => version))?,
+    };
+    for migrate in MIGRATIONS.iter().skip(version as usize - 1) {
+        migrate(map);
+    }
+
+    let mut config: AppConfig = serde_json::from_value(value).map_err(|e| e.to_string())?;
+    config.version = CONFIG_VERSION;
+    Ok((config, version))
+}
+
+/// Write `config` to `path` through a temporary file, so that a crash or a
+/// full disk leaves the previous file whole
+pub fn save(path: &Path, config: &AppConfig) -> io::Result<()> {
+    let content = serde_json::to_string_pretty(config)?;
+    let temp = path.with_extension("json.tmp");
+    let written = File::create(&temp).and_then(|mut file| {
+        file.write_all(content.as_bytes())?;
+        file.sync_all()
+    });
+    let result = written.and_then(|()| fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Where a config file is copied before it is replaced, e.g.
+/// "config.20261018-153000.bak.json"
+fn backup_path(path: &Path) -> PathBuf {
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| String::from("config"));
+    path.with_file_name(format!("{}.{}.bak.json", stem, stamp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load, parse, save, ConfigError, CONFIG_VERSION};
+    use crate::app::state::AppConfig;
+
+    #[test]
+    fn test_parse_versions() {
+        // Written before the version field, and before most settings existed
+        let v1 = r#"{"recent_files": ["/a.pdf"], "default_zoom": 1.5, "language": "Chinese"}"#;
+        let (config, version) = parse(v1).unwrap();
+        assert_eq!(version, 1);
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.language.as_str(), "zh");
+        assert_eq!(config.recent_files, vec!["/a.pdf".to_string()]);
+        assert_eq!(config.default_zoom, 1.5);
+        assert_eq!(config.render_dpi, AppConfig::default().render_dpi);
+
+        // A newer file keeps the settings this build knows
+        let (config, version) = parse(r#"{"version": 99, "default_zoom": 2.0, "new": 1}"#).unwrap();
+        assert_eq!(version, 99);
+        assert_eq!(config.default_zoom, 2.0);
+
+        assert!(parse("{\"recent_files\": [").is_err());
+        assert!(parse("[]").is_err());
+        assert!(parse(r#"{"version": 0}"#).is_err());
+        assert!(parse(r#"{"theme": 3}"#).is_err());
+    }
+
+    #[test]
+    fn test_load_and_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        assert!(load(&path).1.is_none());
+
+        let config = AppConfig {
+            recent_files: vec!["/b.pdf".to_string()],
+            ..AppConfig::default()
+        };
+        save(&path, &config).unwrap();
+        let (loaded, error) = load(&path);
+        assert!(error.is_none());
+        assert_eq!(loaded.recent_files, config.recent_files);
+        assert!(!path.with_extension("json.tmp").exists());
+
+        // A corrupted file is moved aside rather than overwritten later
+        std::fs::write(&path, "{\"recent_files\": [\"/b.pdf\"").unwrap();
+        let (loaded, error) = load(&path);
+        assert!(loaded.recent_files.is_empty());
+        let Some(ConfigError::Unreadable {
+            backup: Some(backup),
+            ..
+        }) = error
+        else {
+            panic!("expected a backup of the unreadable config");
+        };
+        assert!(!path.exists());
+        assert!(std::fs::read_to_string(backup).unwrap().contains("/b.pdf"));
+    }
+}
@@ -0,0 +1,95 @@
+use super::command_palette::CommandPalette;
+use super::image_export::ImageExportDialog;
+use super::merge::MergeDialog;
+use super::preferences::PreferencesDialog;
+use super::print_dialog::PrintDialog;
+use super::split::SplitDialog;
+use super::PdfReaderApp;
+use gpui::*;
+
+/// Modal dialog shown over the main window
+pub enum Dialog {
+    Merge(MergeDialog),
+    Split(SplitDialog),
+    ImageExport(ImageExportDialog),
+    Print(Box<PrintDialog>),
+    /// Every keyboard shortcut, with problems in the keymap file
+    Shortcuts,
+    CommandPalette(Box<CommandPalette>),
+    Preferences(Box<PreferencesDialog>),
+}
+
+impl PdfReaderApp {
+    pub fn open_dialog(&mut self, dialog: Dialog, cx: &mut Context<Self>) {
+        self.dialog = Some(dialog);
+        cx.notify();
+    }
+
+    pub fn close_dialog(&mut self, cx: &mut Context<Self>) {
+        if self.dialog.take().is_some() {
+            cx.notify();
+        }
+    }
+
+    /// Route a key press to the open dialog.
+    /// Returns false when no dialog is open and the key should be handled as usual.
+    pub fn handle_dialog_key(&mut self, keystroke: &Keystroke, cx: &mut Context<Self>) -> bool {
+        let Some(dialog) = self.dialog.as_mut() else {
+            return false;
+        };
+
+        if keystroke.key == "escape" {
+            self.close_dialog(cx);
+            return true;
+        }
+
+        match dialog {
+            Dialog::Merge(merge) => {
+                if keystroke.key == "enter" {
+                    self.run_merge(cx);
+                } else {
+                    merge.handle_key(keystroke, cx);
+                }
+            }
+            Dialog::Split(split) => {
+                if keystroke.key == "enter" {
+                    self.run_split(cx);
+                } else {
+                    split.handle_key(keystroke, cx);
+                }
+            }
+            Dialog::ImageExport(export) => {
+                if keystroke.key == "enter" {
+                    self.run_image_export(cx);
+                } else {
+                    export.handle_key(keystroke, cx);
+                }
+            }
+            Dialog::Print(print) => {
+                if keystroke.key == "enter" {
+                    self.run_print(cx);
+                } else {
+                    print.handle_key(keystroke, cx);
+                    self.refresh_print_preview(cx);
+                }
+            }
+            Dialog::Shortcuts => {}
+            Dialog::CommandPalette(palette) => {
+                if keystroke.key == "enter" {
+                    self.run_palette_item(cx);
+                } else {
+                    palette.handle_key(keystroke, cx);
+                }
+            }
+            Dialog::Preferences(preferences) => {
+                if keystroke.key == "enter" {
+                    self.save_preferences(cx);
+                } else {
+                    preferences.handle_key(keystroke, cx);
+                }
+            }
+        }
+        cx.notify();
+        true
+    }
+}
@@ -0,0 +1,150 @@
+use super::PdfReaderApp;
+use crate::pdf::form_data::{export_form_data, parse_form_data, FormDataFormat};
+use crate::tr;
+use gpui::*;
+
+impl PdfReaderApp {
+    /// Export the form field values of the active document to JSON, XFDF or FDF
+    pub fn export_form_data(&mut self, cx: &mut Context<Self>) {
+        let Some(tab) = self
+            .state
+            .get_active_tab_id()
+            .and_then(|id| self.state.tabs.get_tab(id))
+        else {
+            return;
+        };
+        let Some(pdf_doc) = tab.doc.clone() else {
+            return;
+        };
+
+        let stem = tab
+            .path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| String::from("form"));
+        let source_file = tab.file_name();
+        let dialog_title = tr!("forms.export_dialog");
+
+        cx.spawn(async move |this: WeakEntity<Self>, cx| {
+            let file = rfd::AsyncFileDialog::new()
+                .add_filter("JSON", &["json"])
+                .add_filter("XFDF", &["xfdf"])
+                .add_filter("FDF", &["fdf"])
+                .set_file_name(format!("{}.json", stem))
+                .set_title(&dialog_title)
+                .save_file()
+                .await;
+
+            let Some(file) = file else {
+                return;
+            };
+            let path = file.path().to_path_buf();
+
+            let result = (|| -> anyhow::Result<usize> {
+                let format = FormDataFormat::from_path(&path).unwrap_or(FormDataFormat::Json);
+                let fields = pdf_doc.form_fields()?;
+                let content = export_form_data(&fields, format, Some(&source_file))?;
+                std::fs::write(&path, content)?;
+                Ok(fields.len())
+            })();
+
+            this.update(cx, |this: &mut Self, cx: &mut Context<Self>| match result {
+                Ok(count) => {
+                    this.set_status_message(tr!("forms.exported", "count" => count), cx);
+                }
+                Err(e) => {
+                    log::error!("Failed to export form data: {}", e);
+                    this.set_status_message(tr!("forms.export_failed", "error" => e), cx);
+                }
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Prefill the active document's form from a JSON or XFDF file.
+    /// The filled document is saved to a new file and opened in a new tab.
+    pub fn import_form_data(&mut self, cx: &mut Context<Self>) {
+        let Some(tab) = self
+            .state
+            .get_active_tab_id()
+            .and_then(|id| self.state.tabs.get_tab(id))
+        else {
+            return;
+        };
+        let Some(pdf_doc) = tab.doc.clone() else {
+            return;
+        };
+
+        let stem = tab
+            .path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| String::from("form"));
+        let import_title = tr!("forms.import_dialog");
+        let save_title = tr!("forms.save_filled_dialog");
+
+        cx.spawn(async move |this: WeakEntity<Self>, cx| {
+            let data_file = rfd::AsyncFileDialog::new()
+                .add_filter("Form data", &["json", "xfdf", "xml"])
+                .set_title(&import_title)
+                .pick_file()
+                .await;
+            let Some(data_file) = data_file else {
+                return;
+            };
+
+            let output = rfd::AsyncFileDialog::new()
+                .add_filter("PDF Files", &["pdf"])
+                .set_file_name(format!("{}-filled.pdf", stem))
+                .set_title(&save_title)
+                .save_file()
+                .await;
+            let Some(output) = output else {
+                return;
+            };
+
+            let data_path = data_file.path().to_path_buf();
+            let output_path = output.path().to_path_buf();
+
+            let result = (|| -> anyhow::Result<_> {
+                let format = FormDataFormat::from_path(&data_path).ok_or_else(|| {
+                    anyhow::anyhow!("Unsupported form data file: {}", data_path.display())
+                })?;
+                let content = std::fs::read_to_string(&data_path)?;
+                let values = parse_form_data(&content, format)?;
+                Ok(pdf_doc.fill_form(&values, &output_path)?)
+            })();
+
+            this.update(cx, |this: &mut Self, cx: &mut Context<Self>| match result {
+                Ok(report) => {
+                    for (name, reason) in &report.skipped {
+                        log::warn!("Form field '{}' not filled: {}", name, reason);
+                    }
+
+                    this.open_file_in_new_tab(output_path, cx);
+
+                    let mut not_filled: Vec<String> = report.unmatched.clone();
+                    not_filled.extend(report.skipped.iter().map(|(name, _)| name.clone()));
+
+                    let message = if not_filled.is_empty() {
+                        tr!("forms.imported", "count" => report.filled.len())
+                    } else {
+                        tr!(
+                            "forms.imported_with_unmatched",
+                            "count" => report.filled.len(),
+                            "fields" => not_filled.join(", ")
+                        )
+                    };
+                    this.set_status_message(message, cx);
+                }
+                Err(e) => {
+                    log::error!("Failed to import form data: {}", e);
+                    this.set_status_message(tr!("forms.import_failed", "error" => e), cx);
+                }
+            })
+            .ok();
+        })
+        .detach();
+    }
+}
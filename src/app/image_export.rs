@@ -0,0 +1,282 @@
+use super::dialog::Dialog;
+use super::text_input::TextInput;
+use super::{PdfReaderApp, TaskProgress};
+use crate::pdf::image_export::{
+    image_file_name, ImageExportOptions, PageImageFormat, DEFAULT_IMAGE_TEMPLATE, MAX_EXPORT_DPI,
+    MIN_EXPORT_DPI,
+};
+use crate::pdf::page_range::parse_page_range;
+use crate::tr;
+use gpui::*;
+
+const DEFAULT_DPI: &str = "150";
+const DEFAULT_JPEG_QUALITY: &str = "90";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageExportField {
+    Pages,
+    Dpi,
+    Quality,
+    Template,
+}
+
+/// State of the export-as-images dialog
+pub struct ImageExportDialog {
+    pub tab_id: usize,
+    pub pages: TextInput,
+    pub dpi: TextInput,
+    pub format: PageImageFormat,
+    pub quality: TextInput,
+    pub transparent: bool,
+    pub template: TextInput,
+    pub focused: ImageExportField,
+    /// Validation error shown in the dialog
+    pub error: Option<String>,
+}
+
+impl ImageExportDialog {
+    fn new(tab_id: usize) -> Self {
+        Self {
+            tab_id,
+            pages: TextInput::default(),
+            dpi: TextInput::new(DEFAULT_DPI),
+            format: PageImageFormat::Png,
+            quality: TextInput::new(DEFAULT_JPEG_QUALITY),
+            transparent: false,
+            template: TextInput::new(DEFAULT_IMAGE_TEMPLATE),
+            focused: ImageExportField::Pages,
+            error: None,
+        }
+    }
+
+    /// Text fields that apply to the chosen format, in tab order
+    fn fields(&self) -> Vec<ImageExportField> {
+        let mut fields = vec![ImageExportField::Pages, ImageExportField::Dpi];
+        if self.format == PageImageFormat::Jpeg {
+            fields.push(ImageExportField::Quality);
+        }
+        fields.push(ImageExportField::Template);
+        fields
+    }
+
+    pub fn set_format(&mut self, format: PageImageFormat) {
+        self.format = format;
+        if !self.fields().contains(&self.focused) {
+            self.focused = ImageExportField::Pages;
+        }
+    }
+
+    pub fn handle_key(&mut self, keystroke: &Keystroke, cx: &mut App) {
+        if keystroke.key == "tab" {
+            let fields = self.fields();
+            let index = fields.iter().position(|f| *f == self.focused).unwrap_or(0);
+            self.focused = fields[(index + 1) % fields.len()];
+            return;
+        }
+        let input = match self.focused {
+            ImageExportField::Pages => &mut self.pages,
+            ImageExportField::Dpi => &mut self.dpi,
+            ImageExportField::Quality => &mut self.quality,
+            ImageExportField::Template => &mut self.template,
+        };
+        if input.handle_key(keystroke, cx) {
+            self.error = None;
+        }
+    }
+
+    /// Validate the fields into the pages to export and the render options
+    fn options(&self, page_count: usize) -> Result<(Vec<usize>, ImageExportOptions), String> {
+        let pages = parse_page_range(&self.pages.text, page_count).map_err(|e| e.to_string())?;
+        if pages.is_empty() {
+            return Err(tr!("export_images.no_pages"));
+        }
+
+        let dpi = self
+            .dpi
+            .text
+            .trim()
+            .parse::<f32>()
+            .ok()
+            .filter(|dpi| (MIN_EXPORT_DPI..=MAX_EXPORT_DPI).contains(dpi))
+            .ok_or_else(|| {
+                tr!(
+                    "export_images.invalid_dpi",
+                    "min" => MIN_EXPORT_DPI,
+                    "max" => MAX_EXPORT_DPI
+                )
+            })?;
+
+        let jpeg_quality = self
+            .quality
+            .text
+            .trim()
+            .parse::<u8>()
+            .ok()
+            .filter(|q| (1..=100).contains(q))
+            .ok_or_else(|| tr!("export_images.invalid_quality"))?;
+
+        Ok((
+            pages,
+            ImageExportOptions {
+                dpi,
+                format: self.format,
+                jpeg_quality,
+                transparent: self.transparent,
+            },
+        ))
+    }
+}
+
+impl PdfReaderApp {
+    pub fn show_image_export_dialog(&mut self, cx: &mut Context<Self>) {
+        let Some(tab_id) = self.state.get_active_tab_id() else {
+            return;
+        };
+        if self
+            .state
+            .tabs
+            .get_tab(tab_id)
+            .is_none_or(|t| t.doc.is_none())
+        {
+            return;
+        }
+        self.open_dialog(Dialog::ImageExport(ImageExportDialog::new(tab_id)), cx);
+    }
+
+    pub fn update_image_export_dialog(
+        &mut self,
+        cx: &mut Context<Self>,
+        f: impl FnOnce(&mut ImageExportDialog),
+    ) {
+        if let Some(Dialog::ImageExport(dialog)) = self.dialog.as_mut() {
+            f(dialog);
+            cx.notify();
+        }
+    }
+
+    /// Ask for an output folder, then render the pages one by one in the background
+    pub fn run_image_export(&mut self, cx: &mut Context<Self>) {
+        let Some(Dialog::ImageExport(dialog)) = self.dialog.as_mut() else {
+            return;
+        };
+        let Some(tab) = self.state.tabs.get_tab(dialog.tab_id) else {
+            return;
+        };
+        let Some(pdf_doc) = tab.doc.clone() else {
+            return;
+        };
+
+        if self.progress.is_some() {
+            dialog.error = Some(tr!("export_images.busy"));
+            cx.notify();
+            return;
+        }
+        let (pages, options) = match dialog.options(tab.page_count) {
+            Ok(result) => result,
+            Err(e) => {
+                dialog.error = Some(e);
+                cx.notify();
+                return;
+            }
+        };
+
+        let template = dialog.template.text.clone();
+        let source_name = tab
+            .path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| String::from("page"));
+        let page_count = tab.page_count;
+        let dialog_title = tr!("export_images.folder_dialog");
+
+        cx.spawn(async move |this: WeakEntity<Self>, cx| {
+            let folder = rfd::AsyncFileDialog::new()
+                .set_title(&dialog_title)
+                .pick_folder()
+                .await;
+            let Some(folder) = folder else {
+                return;
+            };
+            let folder = folder.path().to_path_buf();
+
+            let started = this
+                .update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                    if this.progress.is_some() {
+                        return false;
+                    }
+                    this.close_dialog(cx);
+                    this.progress = Some(TaskProgress {
+                        label: tr!("export_images.progress"),
+                        done: 0,
+                        total: pages.len(),
+                        cancelled: false,
+                    });
+                    cx.notify();
+                    true
+                })
+                .unwrap_or(false);
+            if !started {
+                return;
+            }
+
+            let mut exported = 0;
+            let mut error = None;
+            let mut cancelled = false;
+            for page in pages.iter().copied() {
+                cancelled = this
+                    .update(cx, |this: &mut Self, _cx: &mut Context<Self>| {
+                        this.progress.as_ref().is_none_or(|p| p.cancelled)
+                    })
+                    .unwrap_or(true);
+                if cancelled {
+                    break;
+                }
+
+                let doc = pdf_doc.clone();
+                let output = folder.join(image_file_name(
+                    &template,
+                    &source_name,
+                    page,
+                    page_count,
+                    options.format,
+                ));
+                let result = cx
+                    .background_executor()
+                    .spawn(async move { doc.export_page_image(page, &options, &output) })
+                    .await;
+                if let Err(e) = result {
+                    error = Some(e);
+                    break;
+                }
+
+                exported += 1;
+                this.update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                    if let Some(progress) = this.progress.as_mut() {
+                        progress.done = exported;
+                    }
+                    cx.notify();
+                })
+                .ok();
+            }
+
+            this.update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                this.progress = None;
+                let message = match error {
+                    Some(e) => {
+                        log::error!("Failed to export page images: {}", e);
+                        tr!("export_images.failed", "error" => e)
+                    }
+                    None if cancelled => tr!("export_images.cancelled", "count" => exported),
+                    None => tr!(
+                        "export_images.done",
+                        "count" => exported,
+                        "folder" => folder.display()
+                    ),
+                };
+                this.set_status_message(message, cx);
+            })
+            .ok();
+        })
+        .detach();
+    }
+}
@@ -0,0 +1,426 @@
+//! Keyboard shortcuts: the platform's defaults, overridden by the user's
+//! keymap file, matched against key presses as they arrive
+
+use super::dialog::Dialog;
+use super::PdfReaderApp;
+use crate::tr;
+use crate::utils::path::get_keymap_path;
+use gpui::{Context, Keystroke};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Namespace of the app's actions, left out of action names in the keymap file
+const ACTION_NAMESPACE: &str = "lingpdf::";
+
+/// Key sequences and the actions they run, before the user's keymap file
+#[cfg(target_os = "macos")]
+const DEFAULT_BINDINGS: &[(&str, &str)] = &[
+    ("cmd-o", "OpenFile"),
+    ("cmd-w", "CloseTab"),
+    ("cmd-q", "Quit"),
+    ("cmd-p", "Print"),
+    ("cmd-=", "ZoomIn"),
+    ("cmd-+", "ZoomIn"),
+    ("cmd--", "ZoomOut"),
+    ("cmd-0", "ResetZoom"),
+    ("cmd-1", "FitWidth"),
+    ("cmd-2", "FitPage"),
+    ("cmd-r", "RotateClockwise"),
+    ("cmd-b", "ToggleSidebar"),
+    ("cmd-t", "ToggleTheme"),
+    ("cmd-a", "SelectAll"),
+    ("cmd-c", "Copy"),
+    ("cmd-shift-p", "CommandPalette"),
+    ("cmd-,", "Preferences"),
+    // Holding Option copies the text exactly as laid out
+    ("alt-cmd-c", "CopyRawText"),
+    ("ctrl-cmd-f", "FullScreen"),
+    ("left", "PrevPage"),
+    ("right", "NextPage"),
+    ("pageup", "PrevPage"),
+    ("pagedown", "NextPage"),
+    ("home", "FirstPage"),
+    ("end", "LastPage"),
+];
+
+#[cfg(not(target_os = "macos"))]
+const DEFAULT_BINDINGS: &[(&str, &str)] = &[
+    ("ctrl-o", "OpenFile"),
+    ("ctrl-w", "CloseTab"),
+    ("ctrl-q", "Quit"),
+    ("ctrl-p", "Print"),
+    ("ctrl-=", "ZoomIn"),
+    ("ctrl-+", "ZoomIn"),
+    ("ctrl--", "ZoomOut"),
+    ("ctrl-0", "ResetZoom"),
+    ("ctrl-1", "FitWidth"),
+    ("ctrl-2", "FitPage"),
+    ("ctrl-r", "RotateClockwise"),
+    ("ctrl-b", "ToggleSidebar"),
+    ("ctrl-t", "ToggleTheme"),
+    ("ctrl-a", "SelectAll"),
+    ("ctrl-c", "Copy"),
+    ("ctrl-shift-p", "CommandPalette"),
+    ("ctrl-,", "Preferences"),
+    // Holding Alt copies the text exactly as laid out
+    ("ctrl-alt-c", "CopyRawText"),
+    ("f11", "FullScreen"),
+    ("left", "PrevPage"),
+    ("right", "NextPage"),
+    ("pageup", "PrevPage"),
+    ("pagedown", "NextPage"),
+    ("home", "FirstPage"),
+    ("end", "LastPage"),
+];
+
+/// Chords the Vim preset adds over the defaults; its plain keys are read as
+/// Vim commands in `vim.rs`
+const VIM_BINDINGS: &[(&str, &str)] = &[("ctrl-f", "NextPage"), ("ctrl-b", "PrevPage")];
+
+/// Set of shortcuts the keymap file is applied over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum KeymapPreset {
+    #[default]
+    Standard,
+    /// Vim normal-mode keys for moving around
+    Vim,
+}
+
+/// Localized name of an action, from its menu item, by name without the namespace
+pub fn action_label(name: &str) -> Option<String> {
+    let key = match name {
+        "OpenFile" => "menu.open",
+        "CloseTab" => "menu.close_tab",
+        "SaveAs" => "menu.save_as",
+        "ExportFormData" => "menu.export_form_data",
+        "ImportFormData" => "menu.import_form_data",
+        "OrganizePages" => "menu.organize_pages",
+        "MergePdfs" => "menu.merge_pdfs",
+        "SplitPdf" => "menu.split_pdf",
+        "ExportImages" => "menu.export_images",
+        "ExportText" => "menu.export_text",
+        "Print" => "menu.print",
+        "Quit" => "menu.quit",
+        "Copy" => "menu.copy",
+        "CopyRawText" => "menu.copy_raw_text",
+        "CopyAsMarkdown" => "menu.copy_as_markdown",
+        "CopyAsHtml" => "menu.copy_as_html",
+        "SelectAll" => "menu.select_all",
+        "ZoomIn" => "menu.zoom_in",
+        "ZoomOut" => "menu.zoom_out",
+        "ResetZoom" => "menu.reset_zoom",
+        "FitWidth" => "menu.fit_width",
+        "FitWidthCentered" => "menu.fit_width_centered",
+        "FitPage" => "menu.fit_page",
+        "RotateClockwise" => "menu.rotate_clockwise",
+        "RotateCounterClockwise" => "menu.rotate_counter_clockwise",
+        "FullScreen" => "menu.full_screen",
+        "ToggleSidebar" => "menu.toggle_sidebar",
+        "PrevPage" => "menu.prev_page",
+        "NextPage" => "menu.next_page",
+        "FirstPage" => "menu.first_page",
+        "LastPage" => "menu.last_page",
+        "GoToPage" => "menu.go_to_page",
+        "AddToFavorites" => "menu.add_to_favorites",
+        "ToggleTheme" => "menu.toggle_theme",
+        "About" => "menu.about",
+        "ShowShortcuts" => "menu.keyboard_shortcuts",
+        "CommandPalette" => "menu.command_palette",
+        "Preferences" => "menu.preferences",
+        _ => return None,
+    };
+    Some(tr!(key))
+}
+
+/// Where a binding came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingSource {
+    Default,
+    Preset,
+    User,
+}
+
+#[derive(Debug, Clone)]
+pub struct Binding {
+    pub keys: Vec<Keystroke>,
+    /// Action name without the namespace, such as "OpenFile"
+    pub action: String,
+    pub source: BindingSource,
+}
+
+impl Binding {
+    /// The key sequence as written in the keymap file, such as "ctrl-k ctrl-o"
+    pub fn keys_text(&self) -> String {
+        self.keys
+            .iter()
+            .map(|k| k.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// The action's full name, for building it
+    pub fn action_name(&self) -> String {
+        format!("{}{}", ACTION_NAMESPACE, self.action)
+    }
+}
+
+/// What the keys pressed so far amount to
+#[derive(Debug)]
+pub enum KeyMatch<'a> {
+    None,
+    /// The start of a longer sequence; wait for the next key
+    Pending,
+    Complete(&'a Binding),
+}
+
+#[derive(Debug, Default)]
+pub struct Keymap {
+    pub bindings: Vec<Binding>,
+    /// Entries of the keymap file that could not be used
+    pub errors: Vec<String>,
+}
+
+impl Keymap {
+    /// The platform's defaults and `preset` with the user's keymap file
+    /// applied over them
+    pub fn load(actions: &[&str], preset: KeymapPreset) -> Self {
+        let path = get_keymap_path();
+        let mut keymap = Self::defaults();
+        if preset == KeymapPreset::Vim {
+            keymap.add_bindings(VIM_BINDINGS, BindingSource::Preset);
+        }
+        match std::fs::read_to_string(&path) {
+            Ok(source) => keymap.apply(&source, actions),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => keymap.errors.push(format!("{}: {}", path.display(), e)),
+        }
+        for error in &keymap.errors {
+            log::warn!("Keymap: {}", error);
+        }
+        keymap
+    }
+
+    pub fn defaults() -> Self {
+        let mut keymap = Self::default();
+        keymap.add_bindings(DEFAULT_BINDINGS, BindingSource::Default);
+        keymap
+    }
+
+    /// Bind built-in sequences, replacing any earlier bindings of the same keys
+    fn add_bindings(&mut self, bindings: &[(&str, &str)], source: BindingSource) {
+        for (keys, action) in bindings {
+            let keys = parse_keys(keys).expect("built-in key bindings parse");
+            self.bindings.retain(|b| !same_keys(&b.keys, &keys));
+            self.bindings.push(Binding {
+                keys,
+                action: action.to_string(),
+                source,
+            });
+        }
+    }
+
+    /// Apply a keymap file, a JSON object from key sequences to action names.
+    /// A sequence mapped to null loses its default binding.
+    pub fn apply(&mut self, source: &str, actions: &[&str]) {
+        let entries: BTreeMap<String, Option<String>> = match serde_json::from_str(source) {
+            Ok(entries) => entries,
+            Err(e) => {
+                self.errors.push(e.to_string());
+                return;
+            }
+        };
+        let mut seen: Vec<Vec<Keystroke>> = Vec::new();
+        for (text, action) in entries {
+            let keys = match parse_keys(&text) {
+                Ok(keys) => keys,
+                Err(e) => {
+                    self.errors.push(e);
+                    continue;
+                }
+            };
+            // Written differently, such as "ctrl-O" and "ctrl-shift-o"
+            if seen.iter().any(|k| same_keys(k, &keys)) {
+                self.errors
+                    .push(tr!("keymap.duplicate_keys", "keys" => text));
+                continue;
+            }
+            seen.push(keys.clone());
+            let action = action.map(|a| match a.strip_prefix(ACTION_NAMESPACE) {
+                Some(name) => name.to_string(),
+                None => a,
+            });
+            if let Some(ref action) = action {
+                let name = format!("{}{}", ACTION_NAMESPACE, action);
+                if !actions.contains(&name.as_str()) {
+                    self.errors
+                        .push(tr!("keymap.unknown_action", "keys" => text, "action" => action));
+                    continue;
+                }
+            }
+            self.bindings.retain(|b| !same_keys(&b.keys, &keys));
+            if let Some(action) = action {
+                self.bindings.push(Binding {
+                    keys,
+                    action,
+                    source: BindingSource::User,
+                });
+            }
+        }
+    }
+
+    /// Pairs of bindings where the first one's keys start the second's, so
+    /// the second can only be reached by waiting out the first
+    pub fn conflicts(&self) -> Vec<(usize, usize)> {
+        let mut conflicts = Vec::new();
+        for (i, a) in self.bindings.iter().enumerate() {
+            for (j, b) in self.bindings.iter().enumerate() {
+                if i != j
+                    && a.keys.len() < b.keys.len()
+                    && same_keys(&a.keys, &b.keys[..a.keys.len()])
+                {
+                    conflicts.push((i, j));
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// First binding running `action`, for showing next to it
+    pub fn keys_for(&self, action: &str) -> Option<&Binding> {
+        self.bindings.iter().find(|b| b.action == action)
+    }
+
+    /// Look up the keys pressed so far, waiting while a longer sequence could
+    /// still follow
+    pub fn match_keys(&self, pressed: &[Keystroke]) -> KeyMatch<'_> {
+        let starts = |binding: &Binding| {
+            binding.keys.len() >= pressed.len()
+                && binding
+                    .keys
+                    .iter()
+                    .zip(pressed)
+                    .all(|(bound, typed)| keystroke_matches(bound, typed))
+        };
+        if self
+            .bindings
+            .iter()
+            .any(|b| b.keys.len() > pressed.len() && starts(b))
+        {
+            return KeyMatch::Pending;
+        }
+        self.exact_match(pressed)
+    }
+
+    /// The binding for exactly these keys, ignoring longer sequences
+    pub fn exact_match(&self, pressed: &[Keystroke]) -> KeyMatch<'_> {
+        self.bindings
+            .iter()
+            .find(|b| {
+                b.keys.len() == pressed.len()
+                    && b.keys
+                        .iter()
+                        .zip(pressed)
+                        .all(|(bound, typed)| keystroke_matches(bound, typed))
+            })
+            .map_or(KeyMatch::None, KeyMatch::Complete)
+    }
+}
+
+impl PdfReaderApp {
+    /// Read the keymap file again, saying so in the status bar when part of it
+    /// could not be used
+    pub fn load_keymap(&mut self, cx: &mut Context<Self>) {
+        self.keymap = Keymap::load(cx.all_action_names(), self.state.get_keymap_preset());
+        self.vim.reset();
+        self.pending_keys.clear();
+        let problems = self.keymap.errors.len() + self.keymap.conflicts().len();
+        if problems > 0 {
+            self.set_status_message(tr!("keymap.problems", "count" => problems), cx);
+        }
+    }
+
+    pub fn set_keymap_preset(&mut self, preset: KeymapPreset, cx: &mut Context<Self>) {
+        self.state.set_keymap_preset(preset);
+        self.load_keymap(cx);
+        cx.notify();
+    }
+
+    pub fn show_shortcuts(&mut self, cx: &mut Context<Self>) {
+        self.open_dialog(Dialog::Shortcuts, cx);
+    }
+}
+
+/// Split a sequence such as "ctrl-k ctrl-o" into its keystrokes
+fn parse_keys(text: &str) -> Result<Vec<Keystroke>, String> {
+    let keys = text
+        .split_whitespace()
+        .map(|k| Keystroke::parse(k).map_err(|_| tr!("keymap.invalid_keys", "keys" => text)))
+        .collect::<Result<Vec<_>, _>>()?;
+    if keys.is_empty() {
+        return Err(tr!("keymap.invalid_keys", "keys" => text));
+    }
+    Ok(keys)
+}
+
+fn same_keys(a: &[Keystroke], b: &[Keystroke]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .all(|(a, b)| a.key == b.key && a.modifiers == b.modifiers)
+}
+
+/// Whether a typed keystroke is the bound one. Shift is not compared for
+/// punctuation, which some layouts need shift to type at all.
+fn keystroke_matches(bound: &Keystroke, typed: &Keystroke) -> bool {
+    let (b, t) = (bound.modifiers, typed.modifiers);
+    let punctuation = bound.key.len() == 1 && !bound.key.as_bytes()[0].is_ascii_alphanumeric();
+    bound.key.eq_ignore_ascii_case(&typed.key)
+        && b.control == t.control
+        && b.alt == t.alt
+        && b.platform == t.platform
+        && b.function == t.function
+        && (punctuation || b.shift == t.shift)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyMatch, Keymap};
+    use gpui::Keystroke;
+
+    const ACTIONS: &[&str] = &["lingpdf::FirstPage", "lingpdf::LastPage", "lingpdf::Print"];
+
+    fn keys(text: &str) -> Vec<Keystroke> {
+        text.split(' ')
+            .map(|k| Keystroke::parse(k).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_user_keymap() {
+        let mut keymap = Keymap::defaults();
+        keymap.apply(
+            r#"{"g g": "FirstPage", "shift-g": "lingpdf::LastPage", "G": "Print", "home": null, "x": "Nope", "ctrl--x": "Print"}"#,
+            ACTIONS,
+        );
+        assert_eq!(keymap.errors.len(), 3);
+        assert!(matches!(keymap.exact_match(&keys("home")), KeyMatch::None));
+        assert!(matches!(keymap.match_keys(&keys("g")), KeyMatch::Pending));
+        match keymap.match_keys(&keys("g g")) {
+            KeyMatch::Complete(binding) => assert_eq!(binding.action, "FirstPage"),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(matches!(
+            keymap.match_keys(&keys("shift-g")),
+            KeyMatch::Complete(_)
+        ));
+        // Shift alone does not make a different letter key
+        assert!(matches!(
+            keymap.match_keys(&keys("g shift-g")),
+            KeyMatch::None
+        ));
+        assert!(keymap.conflicts().is_empty());
+
+        keymap.apply(r#"{"g": "Print"}"#, ACTIONS);
+        assert_eq!(keymap.conflicts().len(), 1);
+    }
+}
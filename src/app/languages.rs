@@ -0,0 +1,22 @@
+//! Switching the UI language, with every menu and view following at once
+
+use super::menu::RefreshMenus;
+use super::PdfReaderApp;
+use crate::i18n::Language;
+use crate::tr;
+use gpui::*;
+
+impl PdfReaderApp {
+    pub fn select_language(&mut self, language: Language, cx: &mut Context<Self>) {
+        self.language_menu_open = false;
+        if language == self.state.get_language() {
+            cx.notify();
+            return;
+        }
+        self.state.set_language(language.clone());
+        cx.dispatch_action(&RefreshMenus);
+        // Keymap problems are worded when the keymap is read
+        self.load_keymap(cx);
+        self.set_status_message(tr!("language.changed", "name" => language.name()), cx);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::i18n::Language;
+use crate::theme::{user_theme_names, Theme};
 use crate::tr;
 use gpui::*;
 
@@ -7,8 +9,20 @@ actions!(
         OpenFile,
         CloseTab,
         SaveAs,
+        ExportFormData,
+        ImportFormData,
+        OrganizePages,
+        MergePdfs,
+        SplitPdf,
+        ExportImages,
+        ExportText,
         Print,
         Quit,
+        Copy,
+        CopyRawText,
+        CopyAsMarkdown,
+        CopyAsHtml,
+        SelectAll,
         ZoomIn,
         ZoomOut,
         ResetZoom,
@@ -27,10 +41,59 @@ actions!(
         AddToFavorites,
         ToggleTheme,
         About,
+        ShowShortcuts,
+        CommandPalette,
+        Preferences,
         RefreshMenus
     ]
 );
 
+/// Switch to a theme, from the theme menu
+#[derive(Clone, PartialEq, Debug, Action)]
+#[action(namespace = lingpdf, no_json)]
+pub struct SelectTheme(pub Theme);
+
+/// Switch the UI language, from the language menu
+#[derive(Clone, PartialEq, Debug, Action)]
+#[action(namespace = lingpdf, no_json)]
+pub struct SelectLanguage(pub Language);
+
+/// Built-in themes, following the system, then the user's theme files
+fn theme_menu() -> Menu {
+    let mut items = vec![
+        MenuItem::action(tr!("menu.theme_light"), SelectTheme(Theme::Light)),
+        MenuItem::action(tr!("menu.theme_dark"), SelectTheme(Theme::Dark)),
+        MenuItem::action(tr!("menu.theme_system"), SelectTheme(Theme::System)),
+    ];
+    let names = user_theme_names();
+    if !names.is_empty() {
+        items.push(MenuItem::separator());
+    }
+    for name in names {
+        items.push(MenuItem::action(
+            name.clone(),
+            SelectTheme(Theme::Custom(name)),
+        ));
+    }
+    items.push(MenuItem::separator());
+    items.push(MenuItem::action(tr!("menu.toggle_theme"), ToggleTheme));
+    Menu {
+        name: tr!("menu.theme").into(),
+        items,
+    }
+}
+
+/// Every language with a locale file, each named in itself
+fn language_menu() -> Menu {
+    Menu {
+        name: tr!("menu.language").into(),
+        items: Language::all()
+            .into_iter()
+            .map(|language| MenuItem::action(language.name(), SelectLanguage(language)))
+            .collect(),
+    }
+}
+
 pub fn create_menus() -> Vec<Menu> {
     vec![
         Menu {
@@ -41,14 +104,38 @@ pub fn create_menus() -> Vec<Menu> {
                 MenuItem::action(tr!("menu.close_tab"), CloseTab),
                 MenuItem::action(tr!("menu.save_as"), SaveAs),
                 MenuItem::separator(),
+                MenuItem::action(tr!("menu.export_form_data"), ExportFormData),
+                MenuItem::action(tr!("menu.import_form_data"), ImportFormData),
+                MenuItem::separator(),
+                MenuItem::action(tr!("menu.organize_pages"), OrganizePages),
+                MenuItem::action(tr!("menu.merge_pdfs"), MergePdfs),
+                MenuItem::action(tr!("menu.split_pdf"), SplitPdf),
+                MenuItem::action(tr!("menu.export_images"), ExportImages),
+                MenuItem::action(tr!("menu.export_text"), ExportText),
+                MenuItem::separator(),
                 MenuItem::action(tr!("menu.print"), Print),
                 MenuItem::separator(),
                 MenuItem::action(tr!("menu.quit"), Quit),
             ],
         },
+        Menu {
+            name: tr!("menu.edit").into(),
+            items: vec![
+                MenuItem::action(tr!("menu.copy"), Copy),
+                MenuItem::action(tr!("menu.copy_raw_text"), CopyRawText),
+                MenuItem::action(tr!("menu.copy_as_markdown"), CopyAsMarkdown),
+                MenuItem::action(tr!("menu.copy_as_html"), CopyAsHtml),
+                MenuItem::separator(),
+                MenuItem::action(tr!("menu.select_all"), SelectAll),
+                MenuItem::separator(),
+                MenuItem::action(tr!("menu.preferences"), Preferences),
+            ],
+        },
         Menu {
             name: tr!("menu.view").into(),
             items: vec![
+                MenuItem::action(tr!("menu.command_palette"), CommandPalette),
+                MenuItem::separator(),
                 MenuItem::action(tr!("menu.zoom_in"), ZoomIn),
                 MenuItem::action(tr!("menu.zoom_out"), ZoomOut),
                 MenuItem::action(tr!("menu.reset_zoom"), ResetZoom),
@@ -62,7 +149,8 @@ pub fn create_menus() -> Vec<Menu> {
                 MenuItem::separator(),
                 MenuItem::action(tr!("menu.full_screen"), FullScreen),
                 MenuItem::action(tr!("menu.toggle_sidebar"), ToggleSidebar),
-                MenuItem::action(tr!("menu.toggle_theme"), ToggleTheme),
+                MenuItem::submenu(theme_menu()),
+                MenuItem::submenu(language_menu()),
             ],
         },
         Menu {
@@ -86,7 +174,11 @@ pub fn create_menus() -> Vec<Menu> {
         },
         Menu {
             name: tr!("menu.help").into(),
-            items: vec![MenuItem::action(tr!("menu.about"), About)],
+            items: vec![
+                MenuItem::action(tr!("menu.keyboard_shortcuts"), ShowShortcuts),
+                MenuItem::separator(),
+                MenuItem::action(tr!("menu.about"), About),
+            ],
         },
     ]
 }
@@ -0,0 +1,179 @@
+use super::dialog::Dialog;
+use super::text_input::TextInput;
+use super::PdfReaderApp;
+use crate::pdf::merge::{merge_pdfs, MergeInput};
+use crate::tr;
+use gpui::*;
+use std::path::PathBuf;
+
+/// A file in the merge list with its page range field
+pub struct MergeFile {
+    pub path: PathBuf,
+    pub pages: TextInput,
+}
+
+/// State of the merge dialog: the ordered list of files to concatenate
+#[derive(Default)]
+pub struct MergeDialog {
+    pub files: Vec<MergeFile>,
+    /// File whose page range field has keyboard focus
+    pub focused: Option<usize>,
+}
+
+impl MergeDialog {
+    pub fn add_files(&mut self, paths: impl IntoIterator<Item = PathBuf>) {
+        for path in paths {
+            self.files.push(MergeFile {
+                path,
+                pages: TextInput::default(),
+            });
+        }
+    }
+
+    pub fn move_file(&mut self, index: usize, up: bool) {
+        let target = if up {
+            index.checked_sub(1)
+        } else {
+            Some(index + 1)
+        };
+        if let Some(target) = target.filter(|t| *t < self.files.len()) {
+            self.files.swap(index, target);
+            if self.focused == Some(index) {
+                self.focused = Some(target);
+            } else if self.focused == Some(target) {
+                self.focused = Some(index);
+            }
+        }
+    }
+
+    pub fn remove_file(&mut self, index: usize) {
+        if index < self.files.len() {
+            self.files.remove(index);
+            self.focused = None;
+        }
+    }
+
+    pub fn handle_key(&mut self, keystroke: &Keystroke, cx: &mut App) {
+        if keystroke.key == "tab" && !self.files.is_empty() {
+            let next = self.focused.map(|i| i + 1).unwrap_or(0);
+            self.focused = Some(next % self.files.len());
+            return;
+        }
+        if let Some(file) = self.focused.and_then(|i| self.files.get_mut(i)) {
+            file.pages.handle_key(keystroke, cx);
+        }
+    }
+
+    fn inputs(&self) -> Vec<MergeInput> {
+        self.files
+            .iter()
+            .map(|file| {
+                let pages = file.pages.text.trim();
+                MergeInput {
+                    path: file.path.clone(),
+                    pages: (!pages.is_empty()).then(|| pages.to_string()),
+                }
+            })
+            .collect()
+    }
+}
+
+impl PdfReaderApp {
+    /// Open the merge dialog, starting with the active document if there is one
+    pub fn show_merge_dialog(&mut self, cx: &mut Context<Self>) {
+        let mut dialog = MergeDialog::default();
+        if let Some(tab) = self
+            .state
+            .get_active_tab_id()
+            .and_then(|id| self.state.tabs.get_tab(id))
+        {
+            dialog.add_files([tab.path.clone()]);
+        }
+        self.open_dialog(Dialog::Merge(dialog), cx);
+    }
+
+    pub fn update_merge_dialog(
+        &mut self,
+        cx: &mut Context<Self>,
+        f: impl FnOnce(&mut MergeDialog),
+    ) {
+        if let Some(Dialog::Merge(dialog)) = self.dialog.as_mut() {
+            f(dialog);
+            cx.notify();
+        }
+    }
+
+    pub fn merge_add_files(&mut self, cx: &mut Context<Self>) {
+        let dialog_title = tr!("merge.add_dialog");
+        cx.spawn(async move |this: WeakEntity<Self>, cx| {
+            let files = rfd::AsyncFileDialog::new()
+                .add_filter("PDF Files", &["pdf"])
+                .set_title(&dialog_title)
+                .pick_files()
+                .await;
+            let Some(files) = files else {
+                return;
+            };
+            let paths: Vec<PathBuf> = files.iter().map(|f| f.path().to_path_buf()).collect();
+
+            this.update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                this.update_merge_dialog(cx, |dialog| dialog.add_files(paths));
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Ask where to save, merge the listed files and open the result
+    pub fn run_merge(&mut self, cx: &mut Context<Self>) {
+        let Some(Dialog::Merge(dialog)) = self.dialog.as_ref() else {
+            return;
+        };
+        if dialog.files.is_empty() {
+            return;
+        }
+        let inputs = dialog.inputs();
+        let dialog_title = tr!("merge.save_dialog");
+
+        cx.spawn(async move |this: WeakEntity<Self>, cx| {
+            let output = rfd::AsyncFileDialog::new()
+                .add_filter("PDF Files", &["pdf"])
+                .set_file_name("merged.pdf")
+                .set_title(&dialog_title)
+                .save_file()
+                .await;
+            let Some(output) = output else {
+                return;
+            };
+            let output_path = output.path().to_path_buf();
+            let file_count = inputs.len();
+            this.update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                this.set_status_message(tr!("merge.merging"), cx);
+            })
+            .ok();
+
+            let output = output_path.clone();
+            let result = cx
+                .background_executor()
+                .spawn(async move { merge_pdfs(&inputs, &output) })
+                .await;
+
+            this.update(cx, |this: &mut Self, cx: &mut Context<Self>| match result {
+                Ok(page_count) => {
+                    this.close_dialog(cx);
+                    this.open_file_in_new_tab(output_path, cx);
+                    this.set_status_message(
+                        tr!("merge.merged", "files" => file_count, "pages" => page_count),
+                        cx,
+                    );
+                }
+                Err(e) => {
+                    log::error!("Failed to merge PDFs: {}", e);
+                    this.set_status_message(tr!("merge.failed", "error" => e), cx);
+                }
+            })
+            .ok();
+        })
+        .detach();
+    }
+}
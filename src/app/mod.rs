@@ -1,7 +1,12 @@
-use crate::print::show_print_dialog;
+use crate::pdf::color_filter::ColorFilter;
+use crate::pdf::PageText;
 use crate::tr;
 use gpui::*;
 use image::RgbaImage;
+use std::cell::Cell;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::rc::Rc;
 use std::sync::Arc;
 
 pub const FIT_WIDTH_TARGET: f32 = 800.0;
@@ -12,22 +17,65 @@ pub const MAX_ZOOM: f32 = 3.0;
 pub const DPI_SCALE: f32 = 2.0;
 pub const WINDOW_DEFAULT_WIDTH: f32 = 1200.0;
 pub const WINDOW_DEFAULT_HEIGHT: f32 = 800.0;
+pub const STATUS_MESSAGE_DURATION_SECS: u64 = 8;
 
 // Layout constants - must match the actual UI layout
+pub const TITLEBAR_HEIGHT: f32 = 32.0;
 pub const TOOLBAR_HEIGHT: f32 = 32.0;
 pub const STATUS_BAR_HEIGHT: f32 = 20.0;
 pub const SIDEBAR_WIDTH: f32 = 200.0;
 
 pub mod actions;
+pub mod command_palette;
+pub mod config;
+pub mod dialog;
+pub mod forms;
+pub mod image_export;
+pub mod keymap;
+pub mod languages;
 pub mod menu;
+pub mod merge;
+pub mod open;
+pub mod organizer;
+pub mod preferences;
+pub mod print_dialog;
+pub mod print_jobs;
+pub mod rich_copy;
 pub mod shortcuts;
+pub mod snapshot;
+pub mod split;
 pub mod state;
 pub mod tabs;
+pub mod text_export;
+pub mod text_input;
 pub mod text_selection;
+pub mod themes;
 pub mod ui;
+pub mod vim;
 pub mod widgets;
 
-use state::AppState;
+use state::{AppState, ScrollMode};
+use text_selection::TextPosition;
+
+/// Localized name of a page colour filter
+pub fn color_filter_name(filter: ColorFilter) -> String {
+    match filter {
+        ColorFilter::None => tr!("page_filter.none"),
+        ColorFilter::Invert => tr!("page_filter.invert"),
+        ColorFilter::SmartInvert => tr!("page_filter.smart_invert"),
+        ColorFilter::Sepia => tr!("page_filter.sepia"),
+        ColorFilter::Custom { .. } => tr!("page_filter.custom"),
+    }
+}
+
+/// Progress of a long-running task, shown in the status bar
+pub struct TaskProgress {
+    pub label: String,
+    pub done: usize,
+    pub total: usize,
+    /// Set from the status bar; the task stops before its next step
+    pub cancelled: bool,
+}
 
 pub struct PdfReaderApp {
     pub state: Arc<AppState>,
@@ -35,8 +83,36 @@ pub struct PdfReaderApp {
     focus_handle: FocusHandle,
     // Text selection state
     pub is_selecting: bool,
-    pub selection_start: Option<(f32, f32)>,
-    pub selection_end: Option<(f32, f32)>,
+    // Where the selection started and where it currently ends, in content order
+    pub selection_anchor: Option<TextPosition>,
+    pub selection_focus: Option<TextPosition>,
+    // Text of the pages other than the shown one, extracted once per drag
+    pub drag_page_texts: HashMap<usize, PageText>,
+    // Window position of the page image in continuous mode, updated as it is painted
+    pub page_origin: Rc<Cell<Point<Pixels>>>,
+    // Rectangle being dragged out with the snapshot tool
+    pub snapshot_drag: Option<snapshot::SnapshotDrag>,
+    // Transient message shown in the status bar
+    pub status_message: Option<String>,
+    status_message_serial: usize,
+    // Page organizer, open over the tab it was started from
+    pub organizer: Option<organizer::PageOrganizer>,
+    // Modal dialog shown over the window
+    pub dialog: Option<dialog::Dialog>,
+    // Language list opened from the toolbar
+    pub language_menu_open: bool,
+    // Background task in progress, if any
+    pub progress: Option<TaskProgress>,
+    // Print jobs sent this session
+    pub print_jobs: print_jobs::PrintJobs,
+    // Keyboard shortcuts, and the keys of a sequence typed so far
+    pub keymap: keymap::Keymap,
+    pub pending_keys: Vec<Keystroke>,
+    pending_keys_serial: usize,
+    // Vim command being typed, with the Vim preset
+    pub vim: vim::VimState,
+    // Scroll position within the page in smooth scroll mode
+    pub page_scroll: ScrollHandle,
 }
 
 impl PdfReaderApp {
@@ -47,14 +123,59 @@ impl PdfReaderApp {
         let focus_handle = cx.focus_handle();
         focus_handle.focus(window);
 
-        Self {
+        // Themes following the system need redrawing when it changes
+        cx.observe_window_appearance(window, |_this, _window, cx| cx.notify())
+            .detach();
+
+        let mut app = Self {
             state,
             show_sidebar: false,
             focus_handle,
             is_selecting: false,
-            selection_start: None,
-            selection_end: None,
-        }
+            selection_anchor: None,
+            selection_focus: None,
+            drag_page_texts: HashMap::new(),
+            page_origin: Rc::new(Cell::new(Point::default())),
+            snapshot_drag: None,
+            status_message: None,
+            status_message_serial: 0,
+            organizer: None,
+            dialog: None,
+            language_menu_open: false,
+            progress: None,
+            print_jobs: print_jobs::PrintJobs::default(),
+            keymap: keymap::Keymap::default(),
+            pending_keys: Vec::new(),
+            pending_keys_serial: 0,
+            vim: vim::VimState::default(),
+            page_scroll: ScrollHandle::new(),
+        };
+        app.load_keymap(cx);
+        app.watch_themes(cx);
+        app
+    }
+
+    /// Show a message in the status bar for a few seconds
+    pub fn set_status_message(&mut self, message: String, cx: &mut Context<Self>) {
+        self.status_message_serial += 1;
+        let serial = self.status_message_serial;
+        self.status_message = Some(message);
+        cx.notify();
+
+        cx.spawn(async move |this: WeakEntity<Self>, cx| {
+            cx.background_executor()
+                .timer(std::time::Duration::from_secs(STATUS_MESSAGE_DURATION_SECS))
+                .await;
+            this.update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                // A newer message replaced this one; leave it alone
+                if this.status_message_serial == serial {
+                    this.status_message = None;
+                    cx.notify();
+                }
+            })
+            .ok();
+        })
+        .detach();
     }
 
     pub fn fit_width(&mut self, cx: &mut Context<Self>) {
@@ -139,21 +260,34 @@ impl PdfReaderApp {
         }
     }
 
-    pub fn open_file_in_new_tab(&mut self, path: std::path::PathBuf, cx: &mut Context<Self>) {
+    /// Open `path` in a new tab and return the tab, or show why it can't be opened
+    pub fn open_file_in_new_tab(
+        &mut self,
+        path: std::path::PathBuf,
+        cx: &mut Context<Self>,
+    ) -> Option<usize> {
+        let name = path.display().to_string();
         match self.state.open_file_new_tab(path) {
             Ok(tab_id) => {
                 self.show_sidebar = true;
                 self.render_current_tab_page(tab_id, cx);
                 cx.notify();
+                Some(tab_id)
             }
             Err(e) => {
                 log::error!("Failed to open PDF: {}", e);
+                let error = e.to_string();
+                self.set_status_message(tr!("open.failed", "file" => name, "error" => error), cx);
+                None
             }
         }
     }
 
     pub fn close_tab(&mut self, tab_id: usize, cx: &mut Context<Self>) {
         self.state.close_tab(tab_id);
+        if self.organizer.as_ref().is_some_and(|o| o.tab_id == tab_id) {
+            self.organizer = None;
+        }
         cx.notify();
     }
 
@@ -189,9 +323,14 @@ impl PdfReaderApp {
                 let current_page = tab.current_page;
                 let zoom = tab.zoom;
                 let rotation = tab.rotation;
+                let color_filter = tab.color_filter;
+                let scale = self.state.render_scale();
 
                 // Extract text from page
-                // Clear selection when rendering a new page
+                // Clear selection when rendering a new page, except in continuous
+                // mode where it may carry on across pages
+                let continuous = self.state.get_scroll_mode() == ScrollMode::Smooth;
+                let (kept_anchor, kept_focus) = (self.selection_anchor, self.selection_focus);
                 self.clear_selection(_cx);
 
                 match pdf_doc.extract_page_text(current_page) {
@@ -205,7 +344,12 @@ impl PdfReaderApp {
                     }
                 }
 
-                match pdf_doc.render_page(current_page, zoom) {
+                let page_size = pdf_doc.get_page_size(current_page).ok();
+                self.state.tabs.update_tab(tab_id, |tab| {
+                    tab.page_size = page_size;
+                });
+
+                match pdf_doc.render_page_scaled(current_page, zoom, color_filter, scale) {
                     Ok((data, pixmap_width, pixmap_height)) => {
                         let mut scaled_width = pixmap_width;
                         let mut scaled_height = pixmap_height;
@@ -228,8 +372,8 @@ impl PdfReaderApp {
                                 _ => {}
                             }
 
-                            let display_width = (scaled_width as f32 / DPI_SCALE) as u32;
-                            let display_height = (scaled_height as f32 / DPI_SCALE) as u32;
+                            let display_width = (scaled_width as f32 / scale) as u32;
+                            let display_height = (scaled_height as f32 / scale) as u32;
                             let page_dimensions = Some((display_width, display_height));
                             let frame = image::Frame::new(rgba.clone());
                             let render_image = RenderImage::new([frame]);
@@ -245,6 +389,14 @@ impl PdfReaderApp {
                         log::error!("Failed to render page: {}", e);
                     }
                 }
+
+                if continuous {
+                    match (kept_anchor, kept_focus) {
+                        (Some(anchor), Some(focus)) => self.set_text_selection(anchor, focus, _cx),
+                        (anchor, None) => self.selection_anchor = anchor,
+                        _ => {}
+                    }
+                }
             }
         }
     }
@@ -325,91 +477,184 @@ impl PdfReaderApp {
         }
     }
 
-    pub fn toggle_theme(&mut self, cx: &mut Context<Self>) {
-        let current_theme = self.state.get_theme();
-        let new_theme = match current_theme {
-            crate::theme::Theme::Light => crate::theme::Theme::Dark,
-            crate::theme::Theme::Dark => crate::theme::Theme::Light,
+    /// Move the active document on to the next page colour filter
+    pub fn cycle_color_filter(&mut self, cx: &mut Context<Self>) {
+        let Some(tab_id) = self.state.get_active_tab_id() else {
+            return;
         };
-        self.state.set_theme(new_theme);
-        cx.notify();
+        let Some(filter) = self.state.cycle_color_filter() else {
+            return;
+        };
+        self.render_current_tab_page(tab_id, cx);
+        self.set_status_message(
+            tr!("page_filter.changed", "filter" => color_filter_name(filter)),
+            cx,
+        );
     }
 
+    /// Open the print dialog for the active document
     pub fn print(&mut self, cx: &mut Context<Self>) {
-        if let Some(tab_id) = self.state.get_active_tab_id() {
-            if let Some(tab) = self.state.tabs.get_tab(tab_id) {
-                if let Some(ref _pdf_doc) = tab.doc {
-                    let file_path = tab.path.clone();
+        self.open_print_dialog(cx);
+    }
 
-                    cx.spawn(async move |_this: WeakEntity<Self>, _cx| {
-                        match show_print_dialog(&file_path) {
-                            Ok(_) => {
-                                log::info!("Print dialog shown successfully");
-                            }
-                            Err(e) => {
-                                log::error!("Failed to show print dialog: {}", e);
-                            }
-                        }
-                    })
-                    .detach();
-                }
-            }
-        }
+    /// Mapping between the displayed page of the active tab and PDF coordinates
+    fn page_geometry(&self) -> Option<text_selection::PageGeometry> {
+        let tab = self
+            .state
+            .get_active_tab_id()
+            .and_then(|id| self.state.tabs.get_tab(id))?;
+        let (view_width, view_height) = tab.page_dimensions?;
+        let (pdf_width, pdf_height) = tab.page_size?;
+        Some(text_selection::PageGeometry {
+            pdf_width,
+            pdf_height,
+            view_width: view_width as f32,
+            view_height: view_height as f32,
+            rotation: tab.rotation,
+        })
     }
 
-    /// Copy selected text to clipboard
-    pub fn copy_selected_text(&mut self, cx: &mut Context<Self>) {
-        if let Some(tab_id) = self.state.get_active_tab_id() {
-            if let Some(tab) = self.state.tabs.get_tab(tab_id) {
-                let selected_text = tab.selected_text.clone();
-                if !selected_text.is_empty() {
-                    cx.write_to_clipboard(gpui::ClipboardItem::new_string(selected_text));
-                }
-            }
-        }
+    /// Text position under a point on the displayed page
+    pub fn text_position_at(&self, x: f32, y: f32) -> Option<TextPosition> {
+        let tab = self
+            .state
+            .get_active_tab_id()
+            .and_then(|id| self.state.tabs.get_tab(id))?;
+        let geometry = self.page_geometry()?;
+        let index = text_selection::hit_test(tab.page_text.as_ref()?, &geometry, x, y)?;
+        Some(TextPosition {
+            page: tab.current_page,
+            index,
+        })
     }
 
-    /// Update text selection based on mouse coordinates
-    pub fn update_text_selection(
+    /// Select the text flowing from `anchor` to `focus` in content order,
+    /// which may start on an earlier or later page than the one shown
+    pub fn set_text_selection(
         &mut self,
-        start_x: f32,
-        start_y: f32,
-        end_x: f32,
-        end_y: f32,
+        anchor: TextPosition,
+        focus: TextPosition,
         cx: &mut Context<Self>,
     ) {
-        if let Some(tab_id) = self.state.get_active_tab_id() {
-            if let Some(tab) = self.state.tabs.get_tab(tab_id) {
-                if let Some(ref page_text) = tab.page_text {
-                    if let (Some(doc), Some((page_width, page_height))) =
-                        (&tab.doc, tab.page_dimensions)
-                    {
-                        // Get the actual page size in PDF points
-                        if let Ok((pdf_width, pdf_height)) = doc.get_page_size(tab.current_page) {
-                            let (selected_text, selection_regions) =
-                                text_selection::calculate_text_selection(
-                                    page_text,
-                                    pdf_width,
-                                    pdf_height,
-                                    page_width,
-                                    page_height,
-                                    start_x,
-                                    start_y,
-                                    end_x,
-                                    end_y,
-                                );
-
-                            self.state.tabs.update_tab(tab_id, |tab| {
-                                tab.selection_regions = selection_regions;
-                                tab.selected_text = selected_text;
-                            });
+        self.selection_anchor = Some(anchor);
+        self.selection_focus = Some(focus);
 
-                            cx.notify();
+        let Some(tab_id) = self.state.get_active_tab_id() else {
+            return;
+        };
+        let Some(tab) = self.state.tabs.get_tab(tab_id) else {
+            return;
+        };
+        let Some(doc) = tab.doc.clone() else {
+            return;
+        };
+        let (start, end) = (anchor.min(focus), anchor.max(focus));
+        let current_page = tab.current_page;
+
+        let mut selected_text = String::new();
+        let mut selected_chars = Vec::new();
+        let mut selection_regions = Vec::new();
+        for page in start.page..=end.page.min(tab.page_count.saturating_sub(1)) {
+            let page_text = if page == current_page {
+                tab.page_text.as_ref()
+            } else {
+                if let Entry::Vacant(entry) = self.drag_page_texts.entry(page) {
+                    match doc.extract_page_text(page) {
+                        Ok(page_text) => {
+                            entry.insert(page_text);
                         }
+                        Err(e) => log::error!("Failed to extract text from page: {}", e),
                     }
                 }
+                self.drag_page_texts.get(&page)
+            };
+            let Some(page_text) = page_text else {
+                continue;
+            };
+            let len = page_text.chars.len();
+            let from = if page == start.page { start.index } else { 0 }.min(len);
+            let to = if page == end.page { end.index } else { len }.clamp(from, len);
+            let chars = &page_text.chars[from..to];
+
+            let text = text_selection::selection_text(chars);
+            if !text.is_empty() {
+                if !selected_text.is_empty() {
+                    selected_text.push('\n');
+                }
+                selected_text.push_str(&text);
+            }
+            selected_chars.push(chars.to_vec());
+            if page == current_page {
+                if let Some(geometry) = self.page_geometry() {
+                    selection_regions = text_selection::selection_regions(chars, &geometry);
+                }
             }
         }
+
+        self.state.tabs.update_tab(tab_id, |tab| {
+            tab.selection_regions = selection_regions;
+            tab.selected_text = selected_text;
+            tab.selected_chars = selected_chars;
+        });
+        cx.notify();
+    }
+
+    /// Select the word or line under (x, y) and copy it, as on a double or triple click
+    pub fn select_unit_at(
+        &mut self,
+        unit: text_selection::SelectionUnit,
+        x: f32,
+        y: f32,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(tab) = self
+            .state
+            .get_active_tab_id()
+            .and_then(|id| self.state.tabs.get_tab(id))
+        else {
+            return;
+        };
+        let page = tab.current_page;
+        let range = self.page_geometry().and_then(|geometry| {
+            text_selection::unit_range_at(tab.page_text.as_ref()?, &geometry, unit, x, y)
+        });
+
+        match range {
+            Some(range) => {
+                let anchor = TextPosition {
+                    page,
+                    index: range.start,
+                };
+                let focus = TextPosition {
+                    page,
+                    index: range.end,
+                };
+                self.set_text_selection(anchor, focus, cx);
+                self.copy_selected_text(cx);
+            }
+            None => self.clear_selection(cx),
+        }
+    }
+
+    /// Select and copy all text on the current page
+    pub fn select_all_text(&mut self, cx: &mut Context<Self>) {
+        let Some(tab) = self
+            .state
+            .get_active_tab_id()
+            .and_then(|id| self.state.tabs.get_tab(id))
+        else {
+            return;
+        };
+        let Some(len) = tab.page_text.as_ref().map(|t| t.chars.len()) else {
+            return;
+        };
+        let page = tab.current_page;
+        self.set_text_selection(
+            TextPosition { page, index: 0 },
+            TextPosition { page, index: len },
+            cx,
+        );
+        self.copy_selected_text(cx);
     }
 
     /// Clear text selection
@@ -419,8 +664,12 @@ impl PdfReaderApp {
                 tab.selection_start = None;
                 tab.selection_end = None;
                 tab.selected_text = String::new();
+                tab.selected_chars.clear();
                 tab.selection_regions.clear();
             });
+            self.selection_anchor = None;
+            self.selection_focus = None;
+            self.drag_page_texts.clear();
             cx.notify();
         }
     }
@@ -0,0 +1,106 @@
+//! Opening documents named on the command line or dropped onto the window
+
+use super::PdfReaderApp;
+use crate::cli::OpenRequest;
+use crate::tr;
+use gpui::*;
+use std::path::{Path, PathBuf};
+
+impl PdfReaderApp {
+    /// Open each requested file in its own tab at the requested page and zoom,
+    /// listing the ones that don't exist in the status bar
+    pub fn open_requests(&mut self, requests: Vec<OpenRequest>, cx: &mut Context<Self>) {
+        let mut missing = Vec::new();
+        for request in requests {
+            if request.path.is_dir() {
+                self.choose_from_folder(request.path, cx);
+                continue;
+            }
+            if !request.path.exists() {
+                missing.push(request.path.display().to_string());
+                continue;
+            }
+            let Some(tab_id) = self.open_file_in_new_tab(request.path, cx) else {
+                continue;
+            };
+            if request.page.is_none() && request.zoom.is_none() {
+                continue;
+            }
+            self.state.tabs.update_tab(tab_id, |tab| {
+                if let Some(page) = request.page {
+                    tab.current_page = (page - 1).min(tab.page_count.saturating_sub(1));
+                }
+                if let Some(zoom) = request.zoom {
+                    tab.zoom = zoom;
+                }
+            });
+            self.render_current_tab_page(tab_id, cx);
+        }
+
+        if !missing.is_empty() {
+            let files = missing.join(", ");
+            self.set_status_message(tr!("open.not_found", "files" => files), cx);
+        }
+    }
+
+    /// Open files dropped onto the window; a dropped folder shows a chooser
+    /// over the PDFs in it
+    pub fn open_dropped_paths(&mut self, paths: &[PathBuf], cx: &mut Context<Self>) {
+        let requests = paths
+            .iter()
+            .map(|path| OpenRequest {
+                path: path.clone(),
+                page: None,
+                zoom: None,
+            })
+            .collect();
+        self.open_requests(requests, cx);
+    }
+
+    /// Let the user pick which of the PDFs in `folder` to open
+    fn choose_from_folder(&mut self, folder: PathBuf, cx: &mut Context<Self>) {
+        if !contains_pdfs(&folder) {
+            let folder = folder.display().to_string();
+            self.set_status_message(tr!("open.no_pdfs", "folder" => folder), cx);
+            return;
+        }
+
+        let name = folder
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| folder.display().to_string());
+        let dialog_title = tr!("open.folder_dialog", "folder" => name);
+        cx.spawn(async move |this: WeakEntity<Self>, cx| {
+            let files = rfd::AsyncFileDialog::new()
+                .add_filter("PDF Files", &["pdf"])
+                .set_directory(&folder)
+                .set_title(&dialog_title)
+                .pick_files()
+                .await;
+
+            if let Some(files) = files {
+                this.update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                    for file in files {
+                        this.open_file_in_new_tab(file.path().to_path_buf(), cx);
+                    }
+                })
+                .ok();
+            }
+        })
+        .detach();
+    }
+}
+
+/// Whether `folder` holds at least one file with a .pdf extension
+fn contains_pdfs(folder: &Path) -> bool {
+    let Ok(entries) = std::fs::read_dir(folder) else {
+        return false;
+    };
+    entries.flatten().any(|entry| {
+        let path = entry.path();
+        path.is_file()
+            && path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"))
+    })
+}
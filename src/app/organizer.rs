@@ -0,0 +1,576 @@
+use super::PdfReaderApp;
+use crate::pdf::organizer::{OrganizedPage, PageSource};
+use crate::pdf::PdfDocument;
+use crate::tr;
+use gpui::*;
+use image::RgbaImage;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Height of the organizer thumbnails in logical pixels
+pub const THUMBNAIL_HEIGHT: f32 = 160.0;
+
+/// Thumbnails rendered per trip to the background executor
+const THUMBNAIL_BATCH: usize = 8;
+
+/// A4 in points, used for blank pages when no page is selected
+const DEFAULT_PAGE_SIZE: (f32, f32) = (595.0, 842.0);
+
+/// A page shown in the organizer grid
+pub struct OrganizerEntry {
+    /// Stable identifier, used to match background thumbnail renders and drags
+    pub id: usize,
+    pub page: OrganizedPage,
+    /// Document the page is rendered from; `None` for blank pages
+    pub doc: Option<Arc<PdfDocument>>,
+    /// Page size in points, before the extra rotation
+    pub size: (f32, f32),
+    base_thumbnail: Option<RgbaImage>,
+    pub thumbnail: Option<Arc<RenderImage>>,
+}
+
+impl OrganizerEntry {
+    /// Size of the thumbnail in logical pixels, with the extra rotation applied
+    pub fn display_size(&self) -> (f32, f32) {
+        let (width, height) = if self.page.rotation % 180 == 90 {
+            (self.size.1, self.size.0)
+        } else {
+            self.size
+        };
+        let scale = THUMBNAIL_HEIGHT / self.size.1.max(self.size.0).max(1.0);
+        (width * scale, height * scale)
+    }
+
+    fn refresh_thumbnail(&mut self) {
+        let Some(base) = &self.base_thumbnail else {
+            self.thumbnail = None;
+            return;
+        };
+        let rotated = match self.page.rotation % 360 {
+            90 => image::imageops::rotate90(base),
+            180 => image::imageops::rotate180(base),
+            270 => image::imageops::rotate270(base),
+            _ => base.clone(),
+        };
+        let frame = image::Frame::new(rotated);
+        self.thumbnail = Some(Arc::new(RenderImage::new([frame])));
+    }
+}
+
+/// Size of every page of `doc` in points, A4 for any that can't be read
+fn page_sizes(doc: &PdfDocument) -> Vec<(f32, f32)> {
+    let sizes = doc.get_page_sizes().unwrap_or_default();
+    (0..doc.page_count())
+        .map(|page| sizes.get(page).copied().unwrap_or(DEFAULT_PAGE_SIZE))
+        .collect()
+}
+
+/// Page organizer for one tab: pages are edited here and only written out on save
+pub struct PageOrganizer {
+    pub tab_id: usize,
+    pub entries: Vec<OrganizerEntry>,
+    pub selected: Option<usize>,
+    doc: Arc<PdfDocument>,
+    next_id: usize,
+}
+
+/// Payload carried while dragging a thumbnail
+#[derive(Clone, Copy)]
+pub struct DraggedPage {
+    pub id: usize,
+    pub label: usize,
+}
+
+impl PageOrganizer {
+    fn new(tab_id: usize, doc: Arc<PdfDocument>) -> Self {
+        let mut organizer = Self {
+            tab_id,
+            entries: Vec::new(),
+            selected: None,
+            doc: doc.clone(),
+            next_id: 0,
+        };
+        let sizes = page_sizes(&doc);
+        for (page, size) in sizes.into_iter().enumerate() {
+            let entry = organizer.new_entry(
+                OrganizedPage::new(PageSource::Original(page)),
+                Some(doc.clone()),
+                size,
+            );
+            organizer.entries.push(entry);
+        }
+        organizer
+    }
+
+    fn new_entry(
+        &mut self,
+        page: OrganizedPage,
+        doc: Option<Arc<PdfDocument>>,
+        size: (f32, f32),
+    ) -> OrganizerEntry {
+        self.next_id += 1;
+        OrganizerEntry {
+            id: self.next_id,
+            page,
+            doc,
+            size,
+            base_thumbnail: None,
+            thumbnail: None,
+        }
+    }
+
+    fn index_of(&self, id: usize) -> Option<usize> {
+        self.entries.iter().position(|e| e.id == id)
+    }
+
+    /// Position new pages are inserted at: after the selection, or at the end
+    fn insert_position(&self) -> usize {
+        self.selected
+            .map(|i| i + 1)
+            .unwrap_or(self.entries.len())
+            .min(self.entries.len())
+    }
+
+    /// Move the page with `id` to the position currently held by `target_id`
+    pub fn move_page(&mut self, id: usize, target_id: usize) {
+        let (Some(from), Some(to)) = (self.index_of(id), self.index_of(target_id)) else {
+            return;
+        };
+        if from == to {
+            return;
+        }
+        let entry = self.entries.remove(from);
+        self.entries.insert(to, entry);
+        self.selected = Some(to);
+    }
+
+    pub fn rotate_selected(&mut self, degrees: u16) {
+        if let Some(entry) = self.selected.and_then(|i| self.entries.get_mut(i)) {
+            entry.page.rotation = (entry.page.rotation + degrees) % 360;
+            entry.refresh_thumbnail();
+        }
+    }
+
+    pub fn delete_selected(&mut self) {
+        let Some(index) = self.selected.filter(|i| *i < self.entries.len()) else {
+            return;
+        };
+        self.entries.remove(index);
+        self.selected = if self.entries.is_empty() {
+            None
+        } else {
+            Some(index.min(self.entries.len() - 1))
+        };
+    }
+
+    pub fn duplicate_selected(&mut self) {
+        let Some(index) = self.selected.filter(|i| *i < self.entries.len()) else {
+            return;
+        };
+        let source = &self.entries[index];
+        let (page, doc, size) = (source.page.clone(), source.doc.clone(), source.size);
+        let (base_thumbnail, thumbnail) = (source.base_thumbnail.clone(), source.thumbnail.clone());
+        let mut entry = self.new_entry(page, doc, size);
+        entry.base_thumbnail = base_thumbnail;
+        entry.thumbnail = thumbnail;
+        self.entries.insert(index + 1, entry);
+        self.selected = Some(index + 1);
+    }
+
+    /// Insert a blank page the size of the selected page
+    pub fn insert_blank(&mut self) {
+        let (width, height) = self
+            .selected
+            .and_then(|i| self.entries.get(i))
+            .map(|e| e.size)
+            .unwrap_or(DEFAULT_PAGE_SIZE);
+        let entry = self.new_entry(
+            OrganizedPage::new(PageSource::Blank { width, height }),
+            None,
+            (width, height),
+        );
+        let position = self.insert_position();
+        self.entries.insert(position, entry);
+        self.selected = Some(position);
+    }
+
+    /// Insert every page of another document; returns the ids needing a thumbnail
+    fn insert_document(&mut self, path: PathBuf, doc: Arc<PdfDocument>) -> Vec<usize> {
+        let position = self.insert_position();
+        let mut ids = Vec::new();
+        for (page, size) in page_sizes(&doc).into_iter().enumerate() {
+            let source = PageSource::External {
+                path: path.clone(),
+                page,
+            };
+            let entry = self.new_entry(OrganizedPage::new(source), Some(doc.clone()), size);
+            ids.push(entry.id);
+            self.entries.insert(position + page, entry);
+        }
+        if !ids.is_empty() {
+            self.selected = Some(position);
+        }
+        ids
+    }
+
+    /// Document and page index to render the thumbnail of the page with `id` from
+    fn thumbnail_source(&self, id: usize) -> Option<(Arc<PdfDocument>, usize)> {
+        let entry = self.entries.iter().find(|e| e.id == id)?;
+        match entry.page.source {
+            PageSource::Original(index) | PageSource::External { page: index, .. } => {
+                Some((entry.doc.clone()?, index))
+            }
+            PageSource::Blank { .. } => None,
+        }
+    }
+
+    fn set_thumbnail(&mut self, id: usize, image: RgbaImage) {
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.id == id) {
+            entry.base_thumbnail = Some(image);
+            entry.refresh_thumbnail();
+        }
+    }
+
+    fn pages(&self) -> Vec<OrganizedPage> {
+        self.entries.iter().map(|e| e.page.clone()).collect()
+    }
+}
+
+impl PdfReaderApp {
+    /// Whether the page organizer is showing in place of the active tab's page
+    pub fn organizer_is_active(&self) -> bool {
+        self.organizer
+            .as_ref()
+            .is_some_and(|o| Some(o.tab_id) == self.state.get_active_tab_id())
+    }
+
+    /// Open the page organizer for the active tab, or close it if already open
+    pub fn toggle_page_organizer(&mut self, cx: &mut Context<Self>) {
+        if self.organizer_is_active() {
+            self.close_page_organizer(cx);
+            return;
+        }
+
+        let Some(tab) = self
+            .state
+            .get_active_tab_id()
+            .and_then(|id| self.state.tabs.get_tab(id))
+        else {
+            return;
+        };
+        let Some(pdf_doc) = tab.doc.clone() else {
+            return;
+        };
+
+        let organizer = PageOrganizer::new(tab.id, pdf_doc);
+        let ids = organizer.entries.iter().map(|e| e.id).collect();
+        self.organizer = Some(organizer);
+        self.render_organizer_thumbnails(ids, cx);
+        cx.notify();
+    }
+
+    pub fn close_page_organizer(&mut self, cx: &mut Context<Self>) {
+        if self.organizer.take().is_some() {
+            cx.notify();
+        }
+    }
+
+    /// Render thumbnails on the background executor a few pages at a time,
+    /// loading each document once per batch, and show each batch as it finishes
+    fn render_organizer_thumbnails(&mut self, ids: Vec<usize>, cx: &mut Context<Self>) {
+        let Some(organizer) = self.organizer.as_ref() else {
+            return;
+        };
+        let sources: Vec<(usize, Arc<PdfDocument>, usize)> = ids
+            .into_iter()
+            .filter_map(|id| {
+                let (doc, page) = organizer.thumbnail_source(id)?;
+                Some((id, doc, page))
+            })
+            .collect();
+
+        cx.spawn(async move |this: WeakEntity<Self>, cx| {
+            for run in sources.chunk_by(|a, b| Arc::ptr_eq(&a.1, &b.1)) {
+                for batch in run.chunks(THUMBNAIL_BATCH) {
+                    let doc = batch[0].1.clone();
+                    let pages: Vec<usize> = batch.iter().map(|(_, _, page)| *page).collect();
+                    let result = cx
+                        .background_executor()
+                        .spawn(async move { doc.render_thumbnails(&pages, THUMBNAIL_HEIGHT) })
+                        .await;
+
+                    let still_open = this
+                        .update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                            let Some(organizer) = this.organizer.as_mut() else {
+                                return false;
+                            };
+                            match result {
+                                Ok(images) => {
+                                    for ((id, _, _), image) in batch.iter().zip(images) {
+                                        if let Some(image) = image {
+                                            organizer.set_thumbnail(*id, image);
+                                        }
+                                    }
+                                }
+                                Err(e) => log::error!("Failed to render thumbnails: {}", e),
+                            }
+                            cx.notify();
+                            true
+                        })
+                        .unwrap_or(false);
+                    if !still_open {
+                        return;
+                    }
+                }
+            }
+        })
+        .detach();
+    }
+
+    pub fn update_page_organizer(
+        &mut self,
+        cx: &mut Context<Self>,
+        f: impl FnOnce(&mut PageOrganizer),
+    ) {
+        if let Some(organizer) = self.organizer.as_mut() {
+            f(organizer);
+            cx.notify();
+        }
+    }
+
+    /// Ask for a PDF and insert all of its pages after the selected page
+    pub fn organizer_insert_from_file(&mut self, cx: &mut Context<Self>) {
+        let dialog_title = tr!("organizer.insert_dialog");
+        cx.spawn(async move |this: WeakEntity<Self>, cx| {
+            let file = rfd::AsyncFileDialog::new()
+                .add_filter("PDF Files", &["pdf"])
+                .set_title(&dialog_title)
+                .pick_file()
+                .await;
+            let Some(file) = file else {
+                return;
+            };
+            let path = file.path().to_path_buf();
+
+            this.update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                let doc = match PdfDocument::open(&path) {
+                    Ok(doc) => Arc::new(doc),
+                    Err(e) => {
+                        log::error!("Failed to open PDF: {}", e);
+                        this.set_status_message(tr!("organizer.insert_failed", "error" => e), cx);
+                        return;
+                    }
+                };
+                let Some(organizer) = this.organizer.as_mut() else {
+                    return;
+                };
+                let ids = organizer.insert_document(path, doc);
+                this.render_organizer_thumbnails(ids, cx);
+                cx.notify();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Save the organized pages to a new file and open it in a new tab
+    pub fn save_organized_pages(&mut self, cx: &mut Context<Self>) {
+        let Some(organizer) = self.organizer.as_ref() else {
+            return;
+        };
+        if organizer.entries.is_empty() {
+            self.set_status_message(tr!("organizer.empty"), cx);
+            return;
+        }
+
+        let pages = organizer.pages();
+        let doc = organizer.doc.clone();
+        let stem = doc
+            .path()
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| String::from("document"));
+        let dialog_title = tr!("organizer.save_dialog");
+
+        cx.spawn(async move |this: WeakEntity<Self>, cx| {
+            let output = rfd::AsyncFileDialog::new()
+                .add_filter("PDF Files", &["pdf"])
+                .set_file_name(format!("{}-organized.pdf", stem))
+                .set_title(&dialog_title)
+                .save_file()
+                .await;
+            let Some(output) = output else {
+                return;
+            };
+            let output_path = output.path().to_path_buf();
+            this.update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                this.set_status_message(tr!("organizer.saving"), cx);
+            })
+            .ok();
+
+            let page_count = pages.len();
+            let output = output_path.clone();
+            let result = cx
+                .background_executor()
+                .spawn(async move { doc.save_organized(&pages, &output) })
+                .await;
+
+            this.update(cx, |this: &mut Self, cx: &mut Context<Self>| match result {
+                Ok(()) => {
+                    this.organizer = None;
+                    this.open_file_in_new_tab(output_path, cx);
+                    this.set_status_message(tr!("organizer.saved", "count" => page_count), cx);
+                }
+                Err(e) => {
+                    log::error!("Failed to save organized PDF: {}", e);
+                    this.set_status_message(tr!("organizer.save_failed", "error" => e), cx);
+                }
+            })
+            .ok();
+        })
+        .detach();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PageOrganizer, DEFAULT_PAGE_SIZE};
+    use crate::pdf::organizer::PageSource;
+    use crate::pdf::PdfDocument;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    /// An organizer over a four page document, whose pages get ids 1 to 4
+    fn organizer() -> PageOrganizer {
+        PageOrganizer::new(0, Arc::new(PdfDocument::unloaded("missing.pdf", 4)))
+    }
+
+    fn sources(organizer: &PageOrganizer) -> Vec<PageSource> {
+        organizer
+            .pages()
+            .into_iter()
+            .map(|page| page.source)
+            .collect()
+    }
+
+    fn ids(organizer: &PageOrganizer) -> Vec<usize> {
+        organizer.entries.iter().map(|e| e.id).collect()
+    }
+
+    #[test]
+    fn test_move_page() {
+        let mut organizer = organizer();
+        organizer.move_page(1, 3);
+        assert_eq!(ids(&organizer), vec![2, 3, 1, 4]);
+        assert_eq!(organizer.selected, Some(2));
+
+        organizer.move_page(4, 2);
+        assert_eq!(ids(&organizer), vec![4, 2, 3, 1]);
+        assert_eq!(organizer.selected, Some(0));
+
+        // Unknown ids and dropping a page on itself change nothing
+        organizer.move_page(9, 2);
+        organizer.move_page(3, 3);
+        assert_eq!(ids(&organizer), vec![4, 2, 3, 1]);
+        assert_eq!(organizer.selected, Some(0));
+    }
+
+    #[test]
+    fn test_delete_selected() {
+        let mut organizer = organizer();
+        organizer.delete_selected();
+        assert_eq!(organizer.entries.len(), 4);
+
+        organizer.selected = Some(1);
+        organizer.delete_selected();
+        assert_eq!(ids(&organizer), vec![1, 3, 4]);
+        assert_eq!(organizer.selected, Some(1));
+
+        // Deleting the last page selects the one before it
+        organizer.selected = Some(2);
+        organizer.delete_selected();
+        assert_eq!(ids(&organizer), vec![1, 3]);
+        assert_eq!(organizer.selected, Some(1));
+
+        organizer.delete_selected();
+        organizer.delete_selected();
+        assert!(organizer.entries.is_empty());
+        assert_eq!(organizer.selected, None);
+    }
+
+    #[test]
+    fn test_duplicate_selected() {
+        let mut organizer = organizer();
+        organizer.selected = Some(1);
+        organizer.rotate_selected(90);
+        organizer.duplicate_selected();
+
+        assert_eq!(ids(&organizer), vec![1, 2, 5, 3, 4]);
+        assert_eq!(organizer.selected, Some(2));
+        assert_eq!(organizer.entries[2].page, organizer.entries[1].page);
+        assert_eq!(organizer.entries[2].page.rotation, 90);
+    }
+
+    #[test]
+    fn test_insert_blank() {
+        let mut organizer = organizer();
+        organizer.entries[0].size = (300.0, 400.0);
+        organizer.selected = Some(0);
+        organizer.insert_blank();
+        assert_eq!(
+            sources(&organizer)[1],
+            PageSource::Blank {
+                width: 300.0,
+                height: 400.0
+            }
+        );
+        assert_eq!(organizer.selected, Some(1));
+
+        // With nothing selected the page goes at the end, A4 sized
+        organizer.selected = None;
+        organizer.insert_blank();
+        assert_eq!(
+            sources(&organizer).last(),
+            Some(&PageSource::Blank {
+                width: DEFAULT_PAGE_SIZE.0,
+                height: DEFAULT_PAGE_SIZE.1
+            })
+        );
+        assert_eq!(organizer.selected, Some(5));
+    }
+
+    #[test]
+    fn test_insert_document() {
+        let mut organizer = organizer();
+        organizer.selected = Some(1);
+        let path = PathBuf::from("other.pdf");
+        let inserted = organizer.insert_document(
+            path.clone(),
+            Arc::new(PdfDocument::unloaded("other.pdf", 2)),
+        );
+
+        assert_eq!(inserted, vec![5, 6]);
+        let external = |page| PageSource::External {
+            path: path.clone(),
+            page,
+        };
+        assert_eq!(
+            sources(&organizer),
+            vec![
+                PageSource::Original(0),
+                PageSource::Original(1),
+                external(0),
+                external(1),
+                PageSource::Original(2),
+                PageSource::Original(3),
+            ]
+        );
+        assert_eq!(organizer.selected, Some(2));
+
+        // An empty document inserts nothing and keeps the selection
+        let inserted =
+            organizer.insert_document(path, Arc::new(PdfDocument::unloaded("empty.pdf", 0)));
+        assert!(inserted.is_empty());
+        assert_eq!(organizer.selected, Some(2));
+    }
+}
@@ -0,0 +1,197 @@
+//! Preferences dialog: every setting in one place, applied to the open tabs
+//! as soon as it is saved
+
+use super::dialog::Dialog;
+use super::state::{ScrollMode, SelectionMode, StartupBehaviour, RENDER_DPI_RANGE};
+use super::text_input::TextInput;
+use super::{PdfReaderApp, MAX_ZOOM, MIN_ZOOM};
+use crate::i18n::Language;
+use crate::theme::Theme;
+use crate::tr;
+use gpui::*;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreferencesField {
+    DefaultZoom,
+    RenderDpi,
+}
+
+/// Settings being edited, saved together
+pub struct PreferencesDialog {
+    pub theme: Theme,
+    pub language: Language,
+    pub scroll_mode: ScrollMode,
+    pub default_scroll_mode: Option<ScrollMode>,
+    pub selection_mode: SelectionMode,
+    pub startup: StartupBehaviour,
+    /// Zoom of newly opened documents, in percent
+    pub default_zoom: TextInput,
+    pub render_dpi: TextInput,
+    pub focused: PreferencesField,
+    /// Validation error shown in the dialog
+    pub error: Option<String>,
+}
+
+impl PreferencesDialog {
+    pub fn handle_key(&mut self, keystroke: &Keystroke, cx: &mut App) {
+        if keystroke.key == "tab" {
+            self.focused = match self.focused {
+                PreferencesField::DefaultZoom => PreferencesField::RenderDpi,
+                PreferencesField::RenderDpi => PreferencesField::DefaultZoom,
+            };
+            return;
+        }
+        let input = match self.focused {
+            PreferencesField::DefaultZoom => &mut self.default_zoom,
+            PreferencesField::RenderDpi => &mut self.render_dpi,
+        };
+        if input.handle_key(keystroke, cx) {
+            self.error = None;
+        }
+    }
+
+    /// Validate the text fields into the default zoom and render resolution
+    fn numbers(&self) -> Result<(f32, u32), String> {
+        let (min_percent, max_percent) = ((MIN_ZOOM * 100.0) as u32, (MAX_ZOOM * 100.0) as u32);
+        let percent = parse_in_range(&self.default_zoom.text, min_percent..=max_percent)
+            .ok_or_else(
+                || tr!("preferences.invalid_zoom", "min" => min_percent, "max" => max_percent),
+            )?;
+        let dpi = parse_in_range(&self.render_dpi.text, RENDER_DPI_RANGE).ok_or_else(|| {
+            tr!(
+                "preferences.invalid_dpi",
+                "min" => RENDER_DPI_RANGE.start(),
+                "max" => RENDER_DPI_RANGE.end()
+            )
+        })?;
+        Ok((percent as f32 / 100.0, dpi))
+    }
+}
+
+/// A whole number typed into a field, if it lies in `range`; a trailing "%"
+/// is allowed
+fn parse_in_range<T>(text: &str, range: std::ops::RangeInclusive<T>) -> Option<T>
+where
+    T: FromStr + PartialOrd,
+{
+    let text = text.trim();
+    let text = text.strip_suffix('%').unwrap_or(text).trim_end();
+    text.parse().ok().filter(|value| range.contains(value))
+}
+
+impl PdfReaderApp {
+    pub fn show_preferences(&mut self, cx: &mut Context<Self>) {
+        let state = &self.state;
+        let percent = (state.get_default_zoom() * 100.0).round();
+        let dialog = PreferencesDialog {
+            theme: state.get_theme(),
+            language: state.get_language(),
+            scroll_mode: state.get_scroll_mode(),
+            default_scroll_mode: state.get_default_scroll_mode(),
+            selection_mode: state.get_selection_mode(),
+            startup: state.get_startup(),
+            default_zoom: TextInput::new(percent.to_string()),
+            render_dpi: TextInput::new(state.get_render_dpi().to_string()),
+            focused: PreferencesField::DefaultZoom,
+            error: None,
+        };
+        self.open_dialog(Dialog::Preferences(Box::new(dialog)), cx);
+    }
+
+    pub fn update_preferences_dialog(
+        &mut self,
+        cx: &mut Context<Self>,
+        f: impl FnOnce(&mut PreferencesDialog),
+    ) {
+        if let Some(Dialog::Preferences(dialog)) = self.dialog.as_mut() {
+            f(dialog);
+            cx.notify();
+        }
+    }
+
+    /// Save the dialog's settings and apply them to the window and open tabs
+    pub fn save_preferences(&mut self, cx: &mut Context<Self>) {
+        let Some(Dialog::Preferences(dialog)) = self.dialog.as_mut() else {
+            return;
+        };
+        let (default_zoom, render_dpi) = match dialog.numbers() {
+            Ok(numbers) => numbers,
+            Err(e) => {
+                dialog.error = Some(e);
+                cx.notify();
+                return;
+            }
+        };
+        let Some(Dialog::Preferences(dialog)) = self.dialog.take() else {
+            return;
+        };
+        cx.notify();
+
+        let state = self.state.clone();
+        if dialog.theme != state.get_theme() {
+            state.set_theme(dialog.theme);
+        }
+        if dialog.language != state.get_language() {
+            self.select_language(dialog.language, cx);
+        }
+        if dialog.selection_mode != state.get_selection_mode() {
+            self.clear_selection(cx);
+            state.set_selection_mode(dialog.selection_mode);
+        }
+        state.set_scroll_mode(dialog.scroll_mode);
+        state.set_default_scroll_mode(dialog.default_scroll_mode);
+        state.set_startup(dialog.startup);
+
+        // Tabs still at the old default zoom move to the new one
+        let mut rerender = false;
+        let old_zoom = state.get_default_zoom();
+        if (default_zoom - old_zoom).abs() > f32::EPSILON {
+            state.set_default_zoom(default_zoom);
+            for tab in state.get_all_tabs() {
+                if (tab.zoom - old_zoom).abs() < 0.001 {
+                    state.tabs.update_tab(tab.id, |tab| tab.zoom = default_zoom);
+                    rerender = true;
+                }
+            }
+        }
+        if render_dpi != state.get_render_dpi() {
+            state.set_render_dpi(render_dpi);
+            rerender = true;
+        }
+        // Other tabs are rendered again when switched to
+        if let Some(tab_id) = state.get_active_tab_id().filter(|_| rerender) {
+            self.render_current_tab_page(tab_id, cx);
+        }
+
+        self.set_status_message(tr!("preferences.saved"), cx);
+    }
+
+    /// Open what the startup preference asks for, when no file was given
+    pub fn open_startup_files(&mut self, cx: &mut Context<Self>) {
+        match self.state.get_startup() {
+            StartupBehaviour::Empty => {}
+            StartupBehaviour::LastFile => {
+                let last = self.state.get_recent_files().into_iter().next();
+                if let Some(path) = last.map(PathBuf::from).filter(|p| p.exists()) {
+                    self.open_file_in_new_tab(path, cx);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_in_range;
+
+    #[test]
+    fn test_parse_in_range() {
+        assert_eq!(parse_in_range(" 150 ", 50..=300), Some(150));
+        assert_eq!(parse_in_range("125%", 50..=300), Some(125));
+        assert_eq!(parse_in_range("301", 50..=300), None);
+        assert_eq!(parse_in_range("1.5", 50..=300), None);
+        assert_eq!(parse_in_range("", 72..=600), None);
+    }
+}
@@ -0,0 +1,485 @@
+use super::dialog::Dialog;
+use super::print_jobs::PrintTarget;
+use super::text_input::TextInput;
+use super::{PdfReaderApp, DPI_SCALE};
+use crate::pdf::PdfDocument;
+use crate::print::imposition::{layout_sheets, sheet_size, Sheet};
+use crate::print::preview::render_preview;
+use crate::print::to_file::FileFormat;
+use crate::print::{
+    list_printers, Imposition, Margins, Orientation, PageSet, PaperSize, PrintSettings, PrinterInfo,
+};
+use crate::tr;
+use gpui::*;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Largest size of the preview sheet in logical pixels
+pub const PREVIEW_WIDTH: f32 = 240.0;
+pub const PREVIEW_HEIGHT: f32 = 300.0;
+
+const MAX_COPIES: u32 = 999;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrintField {
+    Pages,
+    Copies,
+    MarginTop,
+    MarginRight,
+    MarginBottom,
+    MarginLeft,
+}
+
+const FIELDS: [PrintField; 6] = [
+    PrintField::Pages,
+    PrintField::Copies,
+    PrintField::MarginTop,
+    PrintField::MarginRight,
+    PrintField::MarginBottom,
+    PrintField::MarginLeft,
+];
+
+/// State of the print dialog
+pub struct PrintDialog {
+    doc: Arc<PdfDocument>,
+    path: PathBuf,
+    page_count: usize,
+    /// Pages the `current` and `selection` keywords stand for
+    current_page: usize,
+    selection: Vec<usize>,
+    /// Size in points of every page, once read in the background
+    page_sizes: Option<Vec<(f32, f32)>>,
+    /// Printers found, or `None` while they are still being listed
+    pub printers: Option<Vec<PrinterInfo>>,
+    pub printer: Option<usize>,
+    pub paper_size: PaperSize,
+    pub orientation: Orientation,
+    pub duplex: bool,
+    pub color: bool,
+    pub scale_to_fit: bool,
+    pub imposition: Imposition,
+    /// What the "Print to file" printer writes
+    pub file_format: FileFormat,
+    pub pages: TextInput,
+    pub copies: TextInput,
+    pub margin_top: TextInput,
+    pub margin_right: TextInput,
+    pub margin_bottom: TextInput,
+    pub margin_left: TextInput,
+    pub focused: PrintField,
+    /// Position of the previewed sheet among the sheets to print
+    pub preview_index: usize,
+    pub preview: Option<Arc<RenderImage>>,
+    /// Size the preview is shown at, in logical pixels
+    pub preview_size: (f32, f32),
+    preview_serial: usize,
+    /// Validation error shown in the dialog
+    pub error: Option<String>,
+}
+
+impl PrintDialog {
+    fn new(
+        doc: Arc<PdfDocument>,
+        path: PathBuf,
+        page_count: usize,
+        current_page: usize,
+        selection: Vec<usize>,
+    ) -> Self {
+        let defaults = PrintSettings::default();
+        let margin = |mm: f32| TextInput::new(format!("{}", mm));
+        Self {
+            doc,
+            path,
+            page_count,
+            current_page,
+            selection,
+            page_sizes: None,
+            printers: None,
+            printer: None,
+            paper_size: defaults.paper_size,
+            orientation: defaults.orientation,
+            duplex: defaults.duplex,
+            color: defaults.color,
+            scale_to_fit: defaults.scale_to_fit,
+            imposition: defaults.imposition,
+            file_format: FileFormat::Pdf,
+            pages: TextInput::default(),
+            copies: TextInput::new(defaults.copies.to_string()),
+            margin_top: margin(defaults.margins.top),
+            margin_right: margin(defaults.margins.right),
+            margin_bottom: margin(defaults.margins.bottom),
+            margin_left: margin(defaults.margins.left),
+            focused: PrintField::Pages,
+            preview_index: 0,
+            preview: None,
+            preview_size: (PREVIEW_WIDTH, PREVIEW_HEIGHT),
+            preview_serial: 0,
+            error: None,
+        }
+    }
+
+    pub fn input(&self, field: PrintField) -> &TextInput {
+        match field {
+            PrintField::Pages => &self.pages,
+            PrintField::Copies => &self.copies,
+            PrintField::MarginTop => &self.margin_top,
+            PrintField::MarginRight => &self.margin_right,
+            PrintField::MarginBottom => &self.margin_bottom,
+            PrintField::MarginLeft => &self.margin_left,
+        }
+    }
+
+    fn input_mut(&mut self, field: PrintField) -> &mut TextInput {
+        match field {
+            PrintField::Pages => &mut self.pages,
+            PrintField::Copies => &mut self.copies,
+            PrintField::MarginTop => &mut self.margin_top,
+            PrintField::MarginRight => &mut self.margin_right,
+            PrintField::MarginBottom => &mut self.margin_bottom,
+            PrintField::MarginLeft => &mut self.margin_left,
+        }
+    }
+
+    pub fn handle_key(&mut self, keystroke: &Keystroke, cx: &mut App) {
+        if keystroke.key == "tab" {
+            let index = FIELDS.iter().position(|f| *f == self.focused).unwrap_or(0);
+            self.focused = FIELDS[(index + 1) % FIELDS.len()];
+            return;
+        }
+        let focused = self.focused;
+        if self.input_mut(focused).handle_key(keystroke, cx) {
+            self.error = None;
+            if focused == PrintField::Pages {
+                self.preview_index = 0;
+            }
+        }
+    }
+
+    pub fn printer_info(&self) -> Option<&PrinterInfo> {
+        self.printers.as_ref()?.get(self.printer?)
+    }
+
+    /// Whether the chosen printer can print on both sides and in colour
+    pub fn printer_supports(&self) -> (bool, bool) {
+        self.printer_info()
+            .map_or((true, true), |p| (p.supports_duplex, p.supports_color))
+    }
+
+    /// Pages that will be printed
+    pub fn selected_pages(&self) -> Result<PageSet, String> {
+        let pages = PageSet::parse(
+            &self.pages.text,
+            self.page_count,
+            self.current_page,
+            &self.selection,
+        )
+        .map_err(|e| e.to_string())?;
+        if pages.is_empty() {
+            return Err(tr!("print.no_pages"));
+        }
+        Ok(pages)
+    }
+
+    /// Sheets that will be printed, once the page sizes are known
+    pub fn sheets(&self) -> Option<Vec<Sheet>> {
+        let settings = self.settings().ok()?;
+        let pages = self.selected_pages().ok()?;
+        Some(layout_sheets(
+            &settings,
+            pages.pages(),
+            self.page_sizes.as_deref()?,
+        ))
+    }
+
+    pub fn sheet_count(&self) -> usize {
+        self.sheets().map_or(0, |sheets| sheets.len())
+    }
+
+    /// Validate the fields into print settings
+    pub fn settings(&self) -> Result<PrintSettings, String> {
+        let pages = self.selected_pages()?;
+        let pages = (pages.len() < self.page_count).then_some(pages);
+
+        let copies = self
+            .copies
+            .text
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|c| (1..=MAX_COPIES).contains(c))
+            .ok_or_else(|| tr!("print.invalid_copies", "max" => MAX_COPIES))?;
+
+        let margin = |input: &TextInput| {
+            input
+                .text
+                .trim()
+                .parse::<f32>()
+                .ok()
+                .filter(|mm| mm.is_finite() && *mm >= 0.0)
+        };
+        let (Some(top), Some(right), Some(bottom), Some(left)) = (
+            margin(&self.margin_top),
+            margin(&self.margin_right),
+            margin(&self.margin_bottom),
+            margin(&self.margin_left),
+        ) else {
+            return Err(tr!("print.invalid_margins"));
+        };
+
+        if let Some(printer) = self
+            .printer_info()
+            .filter(|p| !p.supports_paper(self.paper_size))
+        {
+            return Err(tr!(
+                "print.unsupported_paper",
+                "printer" => printer.name,
+                "paper" => self.paper_size.name()
+            ));
+        }
+
+        let (supports_duplex, supports_color) = self.printer_supports();
+        let settings = PrintSettings {
+            paper_size: self.paper_size,
+            orientation: self.orientation,
+            pages,
+            copies,
+            duplex: self.duplex && supports_duplex,
+            color: self.color && supports_color,
+            scale_to_fit: self.scale_to_fit,
+            margins: Margins {
+                top,
+                right,
+                bottom,
+                left,
+            },
+            imposition: self.imposition,
+        };
+        let (width, height) = settings.paper_dimensions_mm();
+        if left + right >= width || top + bottom >= height {
+            return Err(tr!("print.invalid_margins"));
+        }
+        Ok(settings)
+    }
+}
+
+impl PdfReaderApp {
+    pub fn open_print_dialog(&mut self, cx: &mut Context<Self>) {
+        let Some(tab) = self
+            .state
+            .get_active_tab_id()
+            .and_then(|id| self.state.tabs.get_tab(id))
+        else {
+            return;
+        };
+        let Some(doc) = tab.doc.clone() else {
+            return;
+        };
+        // Pages spanned by the text selection, for the `selection` keyword
+        let selection = match (self.selection_anchor, self.selection_focus) {
+            (Some(anchor), Some(focus)) if !tab.selected_text.is_empty() => {
+                (anchor.page.min(focus.page)..=anchor.page.max(focus.page)).collect()
+            }
+            _ => Vec::new(),
+        };
+        let mut dialog = PrintDialog::new(
+            doc,
+            tab.path.clone(),
+            tab.page_count,
+            tab.current_page,
+            selection,
+        );
+        // Preview the page being read, which is its own sheet until pages are laid out together
+        dialog.preview_index = tab.current_page;
+        let page_doc = dialog.doc.clone();
+        self.open_dialog(Dialog::Print(Box::new(dialog)), cx);
+
+        // Laying out sheets needs every page's size, which means reading each page
+        cx.spawn(async move |this: WeakEntity<Self>, cx| {
+            let page_sizes = cx
+                .background_executor()
+                .spawn(async move { page_doc.get_page_sizes() })
+                .await;
+            this.update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                this.update_print_dialog(cx, |dialog| match page_sizes {
+                    Ok(sizes) => dialog.page_sizes = Some(sizes),
+                    Err(e) => dialog.error = Some(e.to_string()),
+                });
+            })
+            .ok();
+        })
+        .detach();
+
+        cx.spawn(async move |this: WeakEntity<Self>, cx| {
+            let printers = cx
+                .background_executor()
+                .spawn(async move { list_printers() })
+                .await;
+            this.update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                this.update_print_dialog(cx, |dialog| {
+                    dialog.printer = printers
+                        .iter()
+                        .position(|p| p.is_default)
+                        .or((!printers.is_empty()).then_some(0));
+                    dialog.printers = Some(printers);
+                });
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Change the print dialog, then redraw the preview for the new settings
+    pub fn update_print_dialog(
+        &mut self,
+        cx: &mut Context<Self>,
+        f: impl FnOnce(&mut PrintDialog),
+    ) {
+        if let Some(Dialog::Print(dialog)) = self.dialog.as_mut() {
+            f(dialog);
+            cx.notify();
+            self.refresh_print_preview(cx);
+        }
+    }
+
+    /// Render the previewed page onto the paper in the background
+    pub fn refresh_print_preview(&mut self, cx: &mut Context<Self>) {
+        let Some(Dialog::Print(dialog)) = self.dialog.as_mut() else {
+            return;
+        };
+        // Keep showing the last good preview while a field is being edited
+        let Ok(settings) = dialog.settings() else {
+            return;
+        };
+
+        let Some(sheets) = dialog.sheets() else {
+            return;
+        };
+        let Some(sheet) = sheets
+            .get(dialog.preview_index.min(sheets.len().saturating_sub(1)))
+            .cloned()
+        else {
+            return;
+        };
+
+        let (paper_width, paper_height) = sheet_size(&settings);
+        let scale = (PREVIEW_WIDTH / paper_width).min(PREVIEW_HEIGHT / paper_height);
+        dialog.preview_size = (paper_width * scale, paper_height * scale);
+        dialog.preview_serial += 1;
+        let serial = dialog.preview_serial;
+        let doc = dialog.doc.clone();
+
+        cx.spawn(async move |this: WeakEntity<Self>, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { render_preview(&doc, &sheet, &settings, scale * DPI_SCALE) })
+                .await;
+            this.update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                let Some(Dialog::Print(dialog)) = this.dialog.as_mut() else {
+                    return;
+                };
+                // A newer preview was requested meanwhile
+                if dialog.preview_serial != serial {
+                    return;
+                }
+                match result {
+                    Ok(sheet) => {
+                        let frame = image::Frame::new(sheet);
+                        dialog.preview = Some(Arc::new(RenderImage::new([frame])));
+                    }
+                    Err(e) => log::error!("Failed to render print preview: {}", e),
+                }
+                cx.notify();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Show the previous or next sheet to be printed in the preview
+    pub fn step_print_preview(&mut self, forward: bool, cx: &mut Context<Self>) {
+        self.update_print_dialog(cx, |dialog| {
+            let count = dialog.sheet_count();
+            let index = dialog.preview_index.min(count.saturating_sub(1));
+            dialog.preview_index = if forward {
+                (index + 1).min(count.saturating_sub(1))
+            } else {
+                index.saturating_sub(1)
+            };
+        });
+    }
+
+    /// Send the document to the chosen printer with the dialog's settings
+    pub fn run_print(&mut self, cx: &mut Context<Self>) {
+        let Some(Dialog::Print(dialog)) = self.dialog.as_mut() else {
+            return;
+        };
+        let settings = match dialog.settings() {
+            Ok(settings) => settings,
+            Err(e) => {
+                dialog.error = Some(e);
+                cx.notify();
+                return;
+            }
+        };
+        if dialog.printers.as_ref().is_some_and(|p| p.is_empty()) {
+            dialog.error = Some(tr!("print.no_printers"));
+            cx.notify();
+            return;
+        }
+        let printer = dialog.printer_info().map(|p| p.name.clone());
+        let path = dialog.path.clone();
+        let page_count = dialog.page_count;
+        if dialog.printer_info().is_some_and(|p| p.is_file_printer()) {
+            let format = dialog.file_format;
+            self.close_dialog(cx);
+            self.print_to_file(path, settings, page_count, format, cx);
+            return;
+        }
+        self.close_dialog(cx);
+        self.start_print_job(
+            path,
+            settings,
+            page_count,
+            PrintTarget::Printer(printer),
+            cx,
+        );
+    }
+
+    /// Ask where to save, then write the sheets as `format` as a print job
+    fn print_to_file(
+        &mut self,
+        path: PathBuf,
+        settings: PrintSettings,
+        page_count: usize,
+        format: FileFormat,
+        cx: &mut Context<Self>,
+    ) {
+        let file_name = format!(
+            "{}.{}",
+            path.file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            format.extension()
+        );
+        let dialog_title = tr!("print.to_file_dialog");
+
+        cx.spawn(async move |this: WeakEntity<Self>, cx| {
+            let file = rfd::AsyncFileDialog::new()
+                .add_filter(format.name(), &[format.extension()])
+                .set_file_name(&file_name)
+                .set_title(&dialog_title)
+                .save_file()
+                .await;
+            let Some(file) = file else {
+                return;
+            };
+            let output = file.path().to_path_buf();
+            this.update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                let target = PrintTarget::File(format, output);
+                this.start_print_job(path, settings, page_count, target, cx);
+            })
+            .ok();
+        })
+        .detach();
+    }
+}
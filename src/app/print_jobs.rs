@@ -0,0 +1,402 @@
+//! Print jobs sent from the print dialog, followed from the moment they are
+//! submitted until the printer is done with them
+
+use super::PdfReaderApp;
+use crate::print::to_file::{print_to_file, FileFormat};
+use crate::print::{
+    cancel_job, job_status, print_document, JobProgress, JobState, JobStatus, PrintError,
+    PrintSettings,
+};
+use crate::tr;
+use gpui::*;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often a submitted job is asked for its state, and for how long
+const JOB_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const JOB_POLL_LIMIT: Duration = Duration::from_secs(10 * 60);
+/// How often the progress of a job being rendered is redrawn
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+/// Finished jobs kept in the list after newer ones finish
+const FINISHED_JOBS_KEPT: usize = 10;
+
+/// Where a print job goes
+pub enum PrintTarget {
+    /// The named printer, or the default printer
+    Printer(Option<String>),
+    File(FileFormat, PathBuf),
+}
+
+#[derive(Debug, Clone)]
+pub enum JobPhase {
+    /// Being composed, rendered or sent to the printer
+    Sending,
+    /// With the printer, in the state it last reported
+    Submitted(JobStatus),
+    /// Finished, with what became of it
+    Done(String),
+    Failed(String),
+    Cancelled,
+}
+
+pub struct PrintJob {
+    pub id: usize,
+    pub document: String,
+    pub printer: String,
+    pub pages: usize,
+    pub phase: JobPhase,
+    pub progress: Arc<JobProgress>,
+}
+
+impl PrintJob {
+    pub fn is_active(&self) -> bool {
+        matches!(self.phase, JobPhase::Sending | JobPhase::Submitted(_))
+    }
+
+    /// Short description of where the job has got to
+    pub fn status_text(&self) -> String {
+        match &self.phase {
+            JobPhase::Sending => match self.progress.get() {
+                (done, total) if total > 0 => {
+                    tr!("print.job_rendering", "done" => done, "total" => total)
+                }
+                _ => tr!("print.sending"),
+            },
+            JobPhase::Submitted(status) => match status.state {
+                JobState::Pending => tr!("print.state_pending"),
+                JobState::Held => tr!("print.state_held"),
+                JobState::Processing => tr!("print.state_processing"),
+                JobState::Stopped => tr!("print.state_stopped", "reason" => job_reason(status)),
+                // Finished states are moved out of `Submitted` as they arrive
+                _ => tr!("print.sending"),
+            },
+            JobPhase::Done(message) => message.clone(),
+            JobPhase::Failed(error) => tr!("print.state_failed", "error" => error),
+            JobPhase::Cancelled => tr!("print.state_cancelled"),
+        }
+    }
+}
+
+/// Jobs of this session, oldest first
+#[derive(Default)]
+pub struct PrintJobs {
+    pub jobs: Vec<PrintJob>,
+    next_id: usize,
+    /// Whether the jobs popover is open
+    pub open: bool,
+}
+
+impl PrintJobs {
+    pub fn get_mut(&mut self, id: usize) -> Option<&mut PrintJob> {
+        self.jobs.iter_mut().find(|job| job.id == id)
+    }
+
+    pub fn active_count(&self) -> usize {
+        self.jobs.iter().filter(|job| job.is_active()).count()
+    }
+
+    /// The newest job still in progress
+    pub fn latest_active(&self) -> Option<&PrintJob> {
+        self.jobs.iter().rev().find(|job| job.is_active())
+    }
+
+    pub fn clear_finished(&mut self) {
+        self.jobs.retain(|job| job.is_active());
+    }
+
+    fn add(
+        &mut self,
+        document: String,
+        printer: String,
+        pages: usize,
+    ) -> (usize, Arc<JobProgress>) {
+        self.next_id += 1;
+        let progress = Arc::new(JobProgress::default());
+        self.jobs.push(PrintJob {
+            id: self.next_id,
+            document,
+            printer,
+            pages,
+            phase: JobPhase::Sending,
+            progress: progress.clone(),
+        });
+
+        let finished = self.jobs.len() - self.active_count();
+        let mut excess = finished.saturating_sub(FINISHED_JOBS_KEPT);
+        self.jobs.retain(|job| {
+            if excess > 0 && !job.is_active() {
+                excess -= 1;
+                return false;
+            }
+            true
+        });
+        (self.next_id, progress)
+    }
+}
+
+/// What a finished job left behind
+enum Outcome {
+    Submitted(JobStatus),
+    Saved(PathBuf, usize),
+}
+
+impl PdfReaderApp {
+    /// Print `path` to `target` in the background, tracked in the jobs list
+    pub fn start_print_job(
+        &mut self,
+        path: PathBuf,
+        settings: PrintSettings,
+        page_count: usize,
+        target: PrintTarget,
+        cx: &mut Context<Self>,
+    ) {
+        let document = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let printer = match &target {
+            PrintTarget::Printer(Some(name)) => name.clone(),
+            PrintTarget::Printer(None) => tr!("print.default_printer"),
+            PrintTarget::File(..) => tr!("print.to_file"),
+        };
+        let pages = settings.page_list(page_count).len();
+        let (id, progress) = self.print_jobs.add(document, printer, pages);
+        cx.notify();
+
+        // Redraw the progress of rendering backends while the job is sent
+        cx.spawn(async move |this: WeakEntity<Self>, cx| loop {
+            cx.background_executor().timer(PROGRESS_INTERVAL).await;
+            let sending = this
+                .update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                    cx.notify();
+                    this.print_jobs
+                        .get_mut(id)
+                        .is_some_and(|job| matches!(job.phase, JobPhase::Sending))
+                })
+                .unwrap_or(false);
+            if !sending {
+                break;
+            }
+        })
+        .detach();
+
+        cx.spawn(async move |this: WeakEntity<Self>, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move {
+                    match target {
+                        PrintTarget::Printer(printer) => print_document(
+                            &path,
+                            &settings,
+                            page_count,
+                            printer.as_deref(),
+                            &progress,
+                        )
+                        .map(Outcome::Submitted),
+                        PrintTarget::File(format, output) => {
+                            print_to_file(&path, &settings, page_count, format, &output, &progress)
+                                .map(|files| Outcome::Saved(output, files.len()))
+                        }
+                    }
+                })
+                .await;
+            this.update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                let (phase, message) = match result {
+                    Ok(Outcome::Submitted(status)) => {
+                        this.follow_print_job(id, status, cx);
+                        return;
+                    }
+                    Ok(Outcome::Saved(output, count)) => {
+                        let message = tr!(
+                            "print.saved_to_file",
+                            "file" => output.display(),
+                            "count" => count
+                        );
+                        (JobPhase::Done(message.clone()), message)
+                    }
+                    Err(PrintError::Cancelled) => {
+                        (JobPhase::Cancelled, tr!("print.state_cancelled"))
+                    }
+                    Err(e) => {
+                        log::error!("Failed to print: {}", e);
+                        let error = e.to_string();
+                        (
+                            JobPhase::Failed(error.clone()),
+                            tr!("print.failed", "error" => error),
+                        )
+                    }
+                };
+                this.finish_print_job(id, phase, message, cx);
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    fn finish_print_job(
+        &mut self,
+        id: usize,
+        phase: JobPhase,
+        message: String,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(job) = self.print_jobs.get_mut(id) {
+            job.phase = phase;
+        }
+        self.set_status_message(message, cx);
+    }
+
+    /// Ask the printer about job `id` until it is done
+    fn follow_print_job(&mut self, id: usize, status: JobStatus, cx: &mut Context<Self>) {
+        if self.update_print_job(id, status.clone(), cx) {
+            return;
+        }
+
+        cx.spawn(async move |this: WeakEntity<Self>, cx| {
+            let started = std::time::Instant::now();
+            let mut status = status;
+            let lost = loop {
+                if started.elapsed() >= JOB_POLL_LIMIT {
+                    break tr!("print.no_word", "id" => status.id);
+                }
+                cx.background_executor().timer(JOB_POLL_INTERVAL).await;
+                let query = status.clone();
+                status = match cx
+                    .background_executor()
+                    .spawn(async move { job_status(&query) })
+                    .await
+                {
+                    Ok(status) => status,
+                    Err(e) => {
+                        log::warn!("Lost track of print job {}: {}", status.id, e);
+                        break tr!("print.lost_track", "id" => status.id, "error" => e);
+                    }
+                };
+                let latest = status.clone();
+                let finished = this
+                    .update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                        this.update_print_job(id, latest, cx)
+                    })
+                    .unwrap_or(true);
+                if finished {
+                    return;
+                }
+            };
+            // Leave the job finished rather than in progress for good
+            this.update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                if this
+                    .print_jobs
+                    .get_mut(id)
+                    .is_some_and(|job| job.is_active())
+                {
+                    this.finish_print_job(id, JobPhase::Failed(lost.clone()), lost, cx);
+                }
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Record the printer's latest word on job `id`; true once there is no more to follow
+    fn update_print_job(&mut self, id: usize, status: JobStatus, cx: &mut Context<Self>) -> bool {
+        let Some(job) = self.print_jobs.get_mut(id) else {
+            return true;
+        };
+        // Cancelled from the jobs list in the meantime
+        if !job.is_active() {
+            return true;
+        }
+        let phase = match status.state {
+            JobState::Completed => JobPhase::Done(tr!("print.done")),
+            JobState::Canceled => JobPhase::Cancelled,
+            JobState::Aborted => JobPhase::Failed(job_reason(&status)),
+            _ => {
+                job.phase = JobPhase::Submitted(status);
+                cx.notify();
+                return false;
+            }
+        };
+        let message = match &phase {
+            JobPhase::Failed(error) => tr!("print.failed", "error" => error),
+            _ => job_message(&status),
+        };
+        self.finish_print_job(id, phase, message, cx);
+        true
+    }
+
+    /// Stop job `id`: before it reaches the printer, or on the printer
+    pub fn cancel_print_job(&mut self, id: usize, cx: &mut Context<Self>) {
+        let Some(job) = self.print_jobs.get_mut(id) else {
+            return;
+        };
+        let status = match &job.phase {
+            JobPhase::Sending => {
+                // The backend stops before its next sheet
+                job.progress.cancel();
+                cx.notify();
+                return;
+            }
+            JobPhase::Submitted(status) => status.clone(),
+            _ => return,
+        };
+
+        cx.spawn(async move |this: WeakEntity<Self>, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { cancel_job(&status) })
+                .await;
+            this.update(cx, |this: &mut Self, cx: &mut Context<Self>| match result {
+                Ok(()) => {
+                    this.finish_print_job(id, JobPhase::Cancelled, tr!("print.state_cancelled"), cx)
+                }
+                Err(e) => {
+                    log::error!("Failed to cancel print job: {}", e);
+                    this.set_status_message(tr!("print.cancel_failed", "error" => e), cx);
+                }
+            })
+            .ok();
+        })
+        .detach();
+    }
+}
+
+/// What the printer says about a job, else its reason keywords, such as
+/// "media-empty-report"
+fn job_reason(job: &JobStatus) -> String {
+    job.message
+        .clone()
+        .or_else(|| (!job.reasons.is_empty()).then(|| job.reasons.join(", ")))
+        .unwrap_or_else(|| tr!("print.no_reason"))
+}
+
+/// Status bar text for a job the printer has finished with
+fn job_message(job: &JobStatus) -> String {
+    match job.state {
+        JobState::Canceled => tr!("print.job_canceled", "id" => job.id),
+        _ => tr!("print.done"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JobPhase, PrintJobs, FINISHED_JOBS_KEPT};
+
+    #[test]
+    fn test_finished_jobs_pruned() {
+        let mut jobs = PrintJobs::default();
+        let (running, _) = jobs.add("a.pdf".into(), "Office".into(), 3);
+        for _ in 0..FINISHED_JOBS_KEPT + 2 {
+            let (id, _) = jobs.add("b.pdf".into(), "Office".into(), 1);
+            jobs.get_mut(id).unwrap().phase = JobPhase::Cancelled;
+        }
+        jobs.add("c.pdf".into(), "Office".into(), 1);
+
+        // The oldest finished jobs go; running ones stay however old
+        assert_eq!(jobs.jobs.len(), FINISHED_JOBS_KEPT + 2);
+        assert_eq!(jobs.jobs[0].id, running);
+        assert_eq!(jobs.active_count(), 2);
+        jobs.clear_finished();
+        assert_eq!(jobs.jobs.len(), 2);
+    }
+}
@@ -0,0 +1,324 @@
+//! Copying selected text as paragraphs: hyphenation undone, wrapped lines joined,
+//! and bold/italic kept for the HTML and Markdown clipboard flavours
+
+use super::text_selection::{is_blank, is_vertical, line_runs};
+use super::PdfReaderApp;
+use crate::pdf::text_export::{escape_html, escape_markdown, TextExportFormat};
+use crate::pdf::text_layout::{line_join, LineJoin};
+use crate::pdf::TextChar;
+use crate::tr;
+use gpui::*;
+
+/// Text set in one style
+#[derive(Debug, Clone, PartialEq)]
+pub struct StyledSpan {
+    pub text: String,
+    pub bold: bool,
+    pub italic: bool,
+}
+
+pub type Paragraph = Vec<StyledSpan>;
+
+/// A visual line of the selection with its extent in PDF coordinates
+struct Line {
+    spans: Vec<StyledSpan>,
+    x0: f32,
+    x1: f32,
+    bottom: f32,
+    top: f32,
+    font_size: f32,
+    vertical: bool,
+}
+
+fn push_styled(spans: &mut Vec<StyledSpan>, text: &str, bold: bool, italic: bool) {
+    match spans.last_mut() {
+        Some(last) if last.bold == bold && last.italic == italic => last.text.push_str(text),
+        _ => spans.push(StyledSpan {
+            text: text.to_string(),
+            bold,
+            italic,
+        }),
+    }
+}
+
+fn build_line(chars: &[TextChar], vertical: bool) -> Option<Line> {
+    let mut spans: Vec<StyledSpan> = Vec::new();
+    for c in chars {
+        if c.char.is_control() {
+            continue;
+        }
+        if is_blank(c) {
+            // Spaces join whatever span they follow, so styles do not split on them
+            if let Some(last) = spans.last_mut() {
+                if !last.text.ends_with(' ') {
+                    last.text.push(' ');
+                }
+            }
+            continue;
+        }
+        push_styled(
+            &mut spans,
+            c.char.encode_utf8(&mut [0; 4]),
+            c.bold,
+            c.italic,
+        );
+    }
+    if let Some(last) = spans.last_mut() {
+        let trimmed = last.text.trim_end().len();
+        last.text.truncate(trimmed);
+    }
+
+    let visible: Vec<&TextChar> = chars.iter().filter(|c| !is_blank(c)).collect();
+    if visible.is_empty() {
+        return None;
+    }
+    Some(Line {
+        spans,
+        x0: visible.iter().map(|c| c.x).fold(f32::MAX, f32::min),
+        x1: visible
+            .iter()
+            .map(|c| c.x + c.width)
+            .fold(f32::MIN, f32::max),
+        bottom: visible.iter().map(|c| c.y).fold(f32::MAX, f32::min),
+        top: visible
+            .iter()
+            .map(|c| c.y + c.height)
+            .fold(f32::MIN, f32::max),
+        font_size: visible.iter().map(|c| c.font_size).fold(0.0, f32::max),
+        vertical,
+    })
+}
+
+/// Whether `next` carries on the paragraph `prev` ends: the same size of text,
+/// set just after it, and not indented or moved to another column
+fn continues_paragraph(prev: &Line, next: &Line) -> bool {
+    let size = prev.font_size.max(next.font_size).max(1.0);
+    if prev.vertical != next.vertical || (prev.font_size - next.font_size).abs() > size * 0.1 {
+        return false;
+    }
+    // Vertical lines follow one another right to left
+    let (gap, extent, indent) = if prev.vertical {
+        (prev.x0 - next.x1, prev.x1 - prev.x0, prev.top - next.top)
+    } else {
+        (
+            prev.bottom - next.top,
+            prev.top - prev.bottom,
+            next.x0 - prev.x0,
+        )
+    };
+    let extent = extent.max(1.0);
+    gap > -extent * 0.5 && gap < extent * 0.6 && indent < size
+}
+
+/// Group selected characters, in content order and one slice per page, into
+/// paragraphs. A new page always starts a new paragraph.
+pub fn selection_paragraphs(pages: &[Vec<TextChar>]) -> Vec<Paragraph> {
+    let mut paragraphs: Vec<Paragraph> = Vec::new();
+    for chars in pages {
+        let mut previous: Option<Line> = None;
+        for run in line_runs(chars) {
+            let vertical = run
+                .clone()
+                .find(|&i| !is_blank(&chars[i]))
+                .is_some_and(|i| is_vertical(chars, i));
+            let Some(line) = build_line(&chars[run], vertical) else {
+                continue;
+            };
+
+            let joins = previous
+                .as_ref()
+                .is_some_and(|prev| continues_paragraph(prev, &line));
+            match paragraphs.last_mut() {
+                Some(paragraph) if joins => {
+                    if let (Some(last), Some(first)) = (paragraph.last_mut(), line.spans.first()) {
+                        match line_join(&last.text, &first.text) {
+                            LineJoin::Space => last.text.push(' '),
+                            LineJoin::Direct => {}
+                            LineJoin::DropHyphen => {
+                                last.text.pop();
+                            }
+                        }
+                    }
+                    for span in &line.spans {
+                        push_styled(paragraph, &span.text, span.bold, span.italic);
+                    }
+                }
+                _ => paragraphs.push(line.spans.clone()),
+            }
+            previous = Some(line);
+        }
+    }
+    paragraphs.retain(|p| p.iter().any(|s| !s.text.is_empty()));
+    paragraphs
+}
+
+/// Wrap `text` in `open` and `close`, leaving surrounding spaces outside,
+/// since Markdown emphasis may not start or end with a space
+fn wrap_trimmed(out: &mut String, text: &str, open: &str, close: &str) {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        out.push_str(text);
+        return;
+    }
+    let start = text.len() - text.trim_start().len();
+    out.push_str(&text[..start]);
+    out.push_str(open);
+    out.push_str(trimmed);
+    out.push_str(close);
+    out.push_str(&text[start + trimmed.len()..]);
+}
+
+/// Write paragraphs as plain text, Markdown with `*`/`**` emphasis,
+/// or HTML with `<em>`/`<strong>`
+pub fn format_paragraphs(paragraphs: &[Paragraph], format: TextExportFormat) -> String {
+    let formatted: Vec<String> = paragraphs
+        .iter()
+        .map(|paragraph| {
+            let mut out = String::new();
+            for span in paragraph {
+                match format {
+                    TextExportFormat::PlainText => out.push_str(&span.text),
+                    TextExportFormat::Markdown => {
+                        let marker = match (span.bold, span.italic) {
+                            (true, true) => "***",
+                            (true, false) => "**",
+                            (false, true) => "*",
+                            (false, false) => "",
+                        };
+                        wrap_trimmed(&mut out, &escape_markdown(&span.text), marker, marker);
+                    }
+                    TextExportFormat::Html => {
+                        let (open, close) = match (span.bold, span.italic) {
+                            (true, true) => ("<strong><em>", "</em></strong>"),
+                            (true, false) => ("<strong>", "</strong>"),
+                            (false, true) => ("<em>", "</em>"),
+                            (false, false) => ("", ""),
+                        };
+                        wrap_trimmed(&mut out, &escape_html(&span.text), open, close);
+                    }
+                }
+            }
+            match format {
+                TextExportFormat::Html => format!("<p>{}</p>", out),
+                _ => out,
+            }
+        })
+        .collect();
+
+    match format {
+        TextExportFormat::Html => formatted.join("\n"),
+        _ => formatted.join("\n\n"),
+    }
+}
+
+impl PdfReaderApp {
+    /// Copy the selection as paragraphs. Where the platform clipboard supports it,
+    /// HTML and Markdown flavours are offered alongside the plain text.
+    pub fn copy_selected_text(&mut self, cx: &mut Context<Self>) {
+        let Some(tab) = self
+            .state
+            .get_active_tab_id()
+            .and_then(|id| self.state.tabs.get_tab(id))
+        else {
+            return;
+        };
+        let paragraphs = selection_paragraphs(&tab.selected_chars);
+        if paragraphs.is_empty() {
+            return;
+        }
+
+        let plain = format_paragraphs(&paragraphs, TextExportFormat::PlainText);
+        let html = format_paragraphs(&paragraphs, TextExportFormat::Html);
+        let markdown = format_paragraphs(&paragraphs, TextExportFormat::Markdown);
+        if !crate::utils::clipboard::write_rich_text(&plain, &html, &markdown) {
+            cx.write_to_clipboard(ClipboardItem::new_string(plain));
+        }
+    }
+
+    /// Copy the selection exactly as laid out in the PDF, line breaks and hyphens included
+    pub fn copy_raw_text(&mut self, cx: &mut Context<Self>) {
+        if let Some(tab) = self
+            .state
+            .get_active_tab_id()
+            .and_then(|id| self.state.tabs.get_tab(id))
+        {
+            if !tab.selected_text.is_empty() {
+                cx.write_to_clipboard(ClipboardItem::new_string(tab.selected_text));
+            }
+        }
+    }
+
+    /// Copy the selection as Markdown or HTML source, for pasting into editors
+    /// that only accept plain text
+    pub fn copy_selection_as(&mut self, format: TextExportFormat, cx: &mut Context<Self>) {
+        let Some(tab) = self
+            .state
+            .get_active_tab_id()
+            .and_then(|id| self.state.tabs.get_tab(id))
+        else {
+            return;
+        };
+        let paragraphs = selection_paragraphs(&tab.selected_chars);
+        if paragraphs.is_empty() {
+            self.set_status_message(tr!("copy.nothing_selected"), cx);
+            return;
+        }
+        cx.write_to_clipboard(ClipboardItem::new_string(format_paragraphs(
+            &paragraphs,
+            format,
+        )));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_paragraphs, selection_paragraphs};
+    use crate::pdf::text_export::TextExportFormat;
+    use crate::pdf::TextChar;
+
+    /// Characters of `text` on one line from (x, y); `*` toggles bold
+    fn line(text: &str, x: f32, y: f32) -> Vec<TextChar> {
+        let mut bold = false;
+        let mut chars = Vec::new();
+        for c in text.chars() {
+            if c == '*' {
+                bold = !bold;
+                continue;
+            }
+            chars.push(TextChar {
+                char: c,
+                x: x + chars.len() as f32 * 6.0,
+                y,
+                width: 6.0,
+                height: 10.0,
+                font_size: 10.0,
+                bold,
+                italic: false,
+            });
+        }
+        chars
+    }
+
+    #[test]
+    fn test_paragraphs_are_joined_and_styled() {
+        let mut chars = line("A *bold* claim is hyphen-\r\n", 10.0, 180.0);
+        chars.extend(line("ated across lines.\r\n", 10.0, 168.0));
+        // Indented first line starts a new paragraph
+        chars.extend(line("Next <one>", 30.0, 156.0));
+        let paragraphs = selection_paragraphs(&[chars]);
+
+        assert_eq!(paragraphs.len(), 2);
+        assert_eq!(
+            format_paragraphs(&paragraphs, TextExportFormat::PlainText),
+            "A bold claim is hyphenated across lines.\n\nNext <one>"
+        );
+        assert_eq!(
+            format_paragraphs(&paragraphs, TextExportFormat::Markdown),
+            "A **bold** claim is hyphenated across lines.\n\nNext \\<one\\>"
+        );
+        assert_eq!(
+            format_paragraphs(&paragraphs, TextExportFormat::Html),
+            "<p>A <strong>bold</strong> claim is hyphenated across lines.</p>\n<p>Next &lt;one&gt;</p>"
+        );
+    }
+}
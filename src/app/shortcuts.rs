@@ -1,4 +1,9 @@
+use super::keymap::{KeyMatch, KeymapPreset};
 use gpui::*;
+use std::time::Duration;
+
+/// How long the app waits for the next key of a sequence such as "g g"
+const KEY_SEQUENCE_TIMEOUT: Duration = Duration::from_millis(1000);
 
 pub fn handle_key_down_event(
     this: &mut super::PdfReaderApp,
@@ -7,98 +12,84 @@ pub fn handle_key_down_event(
     cx: &mut Context<super::PdfReaderApp>,
 ) {
     let keystroke = &event.keystroke;
-    let key = keystroke.key.as_str();
-    let modifiers = keystroke.modifiers;
+    if keystroke.key == "escape" && this.language_menu_open {
+        this.language_menu_open = false;
+        cx.notify();
+        return;
+    }
+    if this.handle_dialog_key(keystroke, cx) {
+        return;
+    }
 
-    #[cfg(target_os = "macos")]
-    {
-        if modifiers.platform {
-            match key {
-                "o" => this.open_file_dialog(cx),
-                "w" => {
-                    if let Some(tab_id) = this.state.get_active_tab_id() {
-                        this.close_tab(tab_id, cx);
-                    }
-                }
-                "q" => {
-                    cx.quit();
-                }
-                "p" => this.print(cx),
-                "+" | "=" => this.zoom_in(cx),
-                "-" => this.zoom_out(cx),
-                "0" => this.reset_zoom(cx),
-                "1" => this.fit_width(cx),
-                "2" => this.fit_page(cx),
-                "r" => this.rotate_clockwise(cx),
-                "b" => {
-                    this.show_sidebar = !this.show_sidebar;
-                    cx.notify();
-                }
-                "g" => {}
-                "t" => this.toggle_theme(cx),
-                _ => {}
-            }
-        } else if modifiers.platform && modifiers.control && key == "f" {
-            window.toggle_fullscreen();
+    // The organizer takes plain keys for itself; shortcuts with modifiers still apply
+    let modifiers = keystroke.modifiers;
+    let plain = !(modifiers.control || modifiers.platform || modifiers.alt);
+    if this.organizer_is_active() && plain {
+        if keystroke.key == "escape" {
+            this.close_page_organizer(cx);
         }
+        return;
     }
 
-    #[cfg(not(target_os = "macos"))]
+    // With the Vim preset, plain keys are read as Vim commands first
+    if plain
+        && this.pending_keys.is_empty()
+        && this.state.get_keymap_preset() == KeymapPreset::Vim
+        && this.handle_vim_key(keystroke, cx)
     {
-        if modifiers.control {
-            match key {
-                "o" => this.open_file_dialog(cx),
-                "w" => {
-                    if let Some(tab_id) = this.state.get_active_tab_id() {
-                        this.close_tab(tab_id, cx);
-                    }
-                }
-                "q" => {
-                    cx.quit();
-                }
-                "p" => this.print(cx),
-                "+" | "=" => this.zoom_in(cx),
-                "-" => this.zoom_out(cx),
-                "0" => this.reset_zoom(cx),
-                "1" => this.fit_width(cx),
-                "2" => this.fit_page(cx),
-                "r" => this.rotate_clockwise(cx),
-                "b" => {
-                    this.show_sidebar = !this.show_sidebar;
-                    cx.notify();
-                }
-                "g" => {}
-                "t" => this.toggle_theme(cx),
-                _ => {}
-            }
-        } else if !modifiers.control && !modifiers.shift && !modifiers.alt && key == "f11" {
-            window.toggle_fullscreen();
-        }
+        return;
     }
 
-    match key {
-        "left" => this.prev_page(cx),
-        "right" => this.next_page(cx),
-        "pageup" => this.prev_page(cx),
-        "pagedown" => this.next_page(cx),
-        "home" => {
-            if let Some(tab_id) = this.state.get_active_tab_id() {
-                this.state.update_active_tab(|tab| {
-                    tab.current_page = 0;
-                });
-                this.render_current_tab_page(tab_id, cx);
-                cx.notify();
-            }
+    this.pending_keys.push(keystroke.clone());
+    this.pending_keys_serial += 1;
+    let action = match this.keymap.match_keys(&this.pending_keys) {
+        KeyMatch::Pending => {
+            wait_for_next_key(this, cx);
+            return;
         }
-        "end" => {
-            if let Some(tab_id) = this.state.get_active_tab_id() {
-                this.state.update_active_tab(|tab| {
-                    tab.current_page = tab.page_count.saturating_sub(1);
-                });
-                this.render_current_tab_page(tab_id, cx);
-                cx.notify();
-            }
+        KeyMatch::Complete(binding) => Some(binding.action_name()),
+        // A sequence that went nowhere; the last key may start another one
+        KeyMatch::None if this.pending_keys.len() > 1 => {
+            this.pending_keys.clear();
+            return handle_key_down_event(this, event, window, cx);
+        }
+        KeyMatch::None => None,
+    };
+    this.pending_keys.clear();
+
+    if let Some(name) = action {
+        match cx.build_action(&name, None) {
+            Ok(action) => window.dispatch_action(action, cx),
+            Err(e) => log::warn!("Cannot run {}: {}", name, e),
         }
-        _ => {}
     }
 }
+
+/// Run the keys pressed so far on their own if nothing follows them in time
+fn wait_for_next_key(this: &super::PdfReaderApp, cx: &mut Context<super::PdfReaderApp>) {
+    let serial = this.pending_keys_serial;
+    cx.spawn(async move |this: WeakEntity<super::PdfReaderApp>, cx| {
+        cx.background_executor().timer(KEY_SEQUENCE_TIMEOUT).await;
+        let action = this
+            .update(cx, |this: &mut super::PdfReaderApp, _cx| {
+                if this.pending_keys_serial != serial {
+                    return None;
+                }
+                let keys = std::mem::take(&mut this.pending_keys);
+                match this.keymap.exact_match(&keys) {
+                    KeyMatch::Complete(binding) => Some(binding.action_name()),
+                    _ => None,
+                }
+            })
+            .ok()
+            .flatten();
+        if let Some(name) = action {
+            cx.update(|cx| match cx.build_action(&name, None) {
+                Ok(action) => cx.dispatch_action(action.as_ref()),
+                Err(e) => log::warn!("Cannot run {}: {}", name, e),
+            })
+            .ok();
+        }
+    })
+    .detach();
+}
@@ -0,0 +1,142 @@
+use super::tabs::SelectionRegion;
+use super::PdfReaderApp;
+use crate::pdf::image_export::encode_png;
+use crate::tr;
+use gpui::*;
+
+/// Rectangle being dragged out with the snapshot tool, in displayed image coordinates
+#[derive(Debug, Clone, Copy)]
+pub struct SnapshotDrag {
+    pub start: (f32, f32),
+    pub end: (f32, f32),
+}
+
+impl SnapshotDrag {
+    pub fn region(&self) -> SelectionRegion {
+        SelectionRegion {
+            x: self.start.0.min(self.end.0),
+            y: self.start.1.min(self.end.1),
+            width: (self.start.0 - self.end.0).abs(),
+            height: (self.start.1 - self.end.1).abs(),
+        }
+    }
+}
+
+/// Drags smaller than this many pixels either way are treated as stray clicks
+const MIN_SNAPSHOT_SIZE: f32 = 4.0;
+
+impl PdfReaderApp {
+    pub fn start_snapshot(&mut self, x: f32, y: f32, cx: &mut Context<Self>) {
+        self.snapshot_drag = Some(SnapshotDrag {
+            start: (x, y),
+            end: (x, y),
+        });
+        cx.notify();
+    }
+
+    pub fn extend_snapshot(&mut self, x: f32, y: f32, cx: &mut Context<Self>) {
+        if let Some(drag) = &mut self.snapshot_drag {
+            drag.end = (x, y);
+            cx.notify();
+        }
+    }
+
+    /// Re-render the dragged region from the PDF at the snapshot resolution,
+    /// then copy it to the clipboard as a PNG or save it to a file
+    pub fn finish_snapshot(&mut self, cx: &mut Context<Self>) {
+        let Some(drag) = self.snapshot_drag.take() else {
+            return;
+        };
+        cx.notify();
+
+        let region = drag.region();
+        if region.width < MIN_SNAPSHOT_SIZE || region.height < MIN_SNAPSHOT_SIZE {
+            return;
+        }
+        let Some(geometry) = self.page_geometry() else {
+            return;
+        };
+        let Some(tab) = self
+            .state
+            .get_active_tab_id()
+            .and_then(|id| self.state.tabs.get_tab(id))
+        else {
+            return;
+        };
+        let Some(pdf_doc) = tab.doc.clone() else {
+            return;
+        };
+
+        let (left, top) = geometry.to_pdf(region.x, region.y);
+        let (right, bottom) = geometry.to_pdf(region.x + region.width, region.y + region.height);
+        let rect = (left, bottom, right, top);
+        let page = tab.current_page;
+        let rotation = tab.rotation;
+        let dpi = self.state.get_snapshot_dpi();
+        let to_file = self.state.get_snapshot_to_file();
+        let file_name = format!(
+            "{}-page{}-snapshot.png",
+            tab.path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| String::from("document")),
+            page + 1
+        );
+        let dialog_title = tr!("snapshot.dialog");
+
+        cx.spawn(async move |this: WeakEntity<Self>, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move {
+                    let image = pdf_doc.render_region_image(page, rect, dpi as f32, rotation)?;
+                    encode_png(&image)
+                })
+                .await;
+            let bytes = match result {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    log::error!("Failed to render snapshot: {}", e);
+                    this.update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                        this.set_status_message(tr!("snapshot.failed", "error" => e), cx);
+                    })
+                    .ok();
+                    return;
+                }
+            };
+
+            if !to_file {
+                this.update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                    let image = Image::from_bytes(ImageFormat::Png, bytes);
+                    cx.write_to_clipboard(ClipboardItem::new_image(&image));
+                    this.set_status_message(tr!("snapshot.copied", "dpi" => dpi), cx);
+                })
+                .ok();
+                return;
+            }
+
+            let file = rfd::AsyncFileDialog::new()
+                .add_filter("PNG", &["png"])
+                .set_file_name(&file_name)
+                .set_title(&dialog_title)
+                .save_file()
+                .await;
+            let Some(file) = file else {
+                return;
+            };
+            let path = file.path().to_path_buf();
+            let result = std::fs::write(&path, bytes);
+
+            this.update(cx, |this: &mut Self, cx: &mut Context<Self>| match result {
+                Ok(()) => {
+                    this.set_status_message(tr!("snapshot.saved", "file" => path.display()), cx);
+                }
+                Err(e) => {
+                    log::error!("Failed to save snapshot: {}", e);
+                    this.set_status_message(tr!("snapshot.failed", "error" => e), cx);
+                }
+            })
+            .ok();
+        })
+        .detach();
+    }
+}
@@ -0,0 +1,255 @@
+use super::dialog::Dialog;
+use super::text_input::TextInput;
+use super::{PdfReaderApp, TaskProgress};
+use crate::pdf::split::{SplitMode, DEFAULT_BOOKMARK_TEMPLATE, DEFAULT_NAME_TEMPLATE};
+use crate::print::JobProgress;
+use crate::tr;
+use gpui::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often the status bar picks up the parts written so far
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitKind {
+    Ranges,
+    EveryN,
+    Bookmarks,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitField {
+    Value,
+    Template,
+}
+
+/// State of the split dialog
+pub struct SplitDialog {
+    pub tab_id: usize,
+    pub kind: SplitKind,
+    pub ranges: TextInput,
+    pub every: TextInput,
+    pub template: TextInput,
+    pub focused: SplitField,
+    /// Whether the template was edited; if not it follows the split kind
+    template_edited: bool,
+}
+
+impl SplitDialog {
+    fn new(tab_id: usize, has_outline: bool) -> Self {
+        let mut dialog = Self {
+            tab_id,
+            kind: SplitKind::Ranges,
+            ranges: TextInput::default(),
+            every: TextInput::default(),
+            template: TextInput::default(),
+            focused: SplitField::Value,
+            template_edited: false,
+        };
+        dialog.set_kind(if has_outline {
+            SplitKind::Bookmarks
+        } else {
+            SplitKind::Ranges
+        });
+        dialog
+    }
+
+    pub fn set_kind(&mut self, kind: SplitKind) {
+        self.kind = kind;
+        if !self.template_edited {
+            self.template.set_text(match kind {
+                SplitKind::Bookmarks => DEFAULT_BOOKMARK_TEMPLATE,
+                _ => DEFAULT_NAME_TEMPLATE,
+            });
+        }
+        self.focused = match kind {
+            SplitKind::Bookmarks => SplitField::Template,
+            _ => SplitField::Value,
+        };
+    }
+
+    pub fn handle_key(&mut self, keystroke: &Keystroke, cx: &mut App) {
+        if keystroke.key == "tab" {
+            self.focused = match self.focused {
+                SplitField::Value => SplitField::Template,
+                SplitField::Template if self.kind != SplitKind::Bookmarks => SplitField::Value,
+                SplitField::Template => SplitField::Template,
+            };
+            return;
+        }
+        match self.focused {
+            SplitField::Value => match self.kind {
+                SplitKind::Ranges => {
+                    self.ranges.handle_key(keystroke, cx);
+                }
+                SplitKind::EveryN => {
+                    self.every.handle_key(keystroke, cx);
+                }
+                SplitKind::Bookmarks => {}
+            },
+            SplitField::Template => {
+                if self.template.handle_key(keystroke, cx) {
+                    self.template_edited = true;
+                }
+            }
+        }
+    }
+
+    /// The split mode described by the fields, if they are filled in
+    pub fn mode(&self) -> Option<SplitMode> {
+        match self.kind {
+            SplitKind::Ranges => {
+                let ranges = self.ranges.text.trim();
+                (!ranges.is_empty()).then(|| SplitMode::Ranges(ranges.to_string()))
+            }
+            SplitKind::EveryN => self
+                .every
+                .text
+                .trim()
+                .parse()
+                .ok()
+                .filter(|n| *n > 0)
+                .map(SplitMode::EveryN),
+            SplitKind::Bookmarks => Some(SplitMode::Bookmarks),
+        }
+    }
+}
+
+impl PdfReaderApp {
+    pub fn show_split_dialog(&mut self, cx: &mut Context<Self>) {
+        let Some(tab) = self
+            .state
+            .get_active_tab_id()
+            .and_then(|id| self.state.tabs.get_tab(id))
+        else {
+            return;
+        };
+        if tab.doc.is_none() {
+            return;
+        }
+        let has_outline = tab.outline_items.as_ref().is_some_and(|o| !o.is_empty());
+        self.open_dialog(Dialog::Split(SplitDialog::new(tab.id, has_outline)), cx);
+    }
+
+    pub fn update_split_dialog(
+        &mut self,
+        cx: &mut Context<Self>,
+        f: impl FnOnce(&mut SplitDialog),
+    ) {
+        if let Some(Dialog::Split(dialog)) = self.dialog.as_mut() {
+            f(dialog);
+            cx.notify();
+        }
+    }
+
+    /// Ask for an output folder and split the document into it
+    pub fn run_split(&mut self, cx: &mut Context<Self>) {
+        let Some(Dialog::Split(dialog)) = self.dialog.as_ref() else {
+            return;
+        };
+        let Some(mode) = dialog.mode() else {
+            return;
+        };
+        let Some(pdf_doc) = self
+            .state
+            .tabs
+            .get_tab(dialog.tab_id)
+            .and_then(|tab| tab.doc.clone())
+        else {
+            return;
+        };
+        let template = dialog.template.text.clone();
+        let dialog_title = tr!("split.folder_dialog");
+
+        cx.spawn(async move |this: WeakEntity<Self>, cx| {
+            let folder = rfd::AsyncFileDialog::new()
+                .set_title(&dialog_title)
+                .pick_folder()
+                .await;
+            let Some(folder) = folder else {
+                return;
+            };
+            let folder = folder.path().to_path_buf();
+
+            let started = this
+                .update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                    if this.progress.is_some() {
+                        return false;
+                    }
+                    this.close_dialog(cx);
+                    this.progress = Some(TaskProgress {
+                        label: tr!("split.progress"),
+                        done: 0,
+                        total: 0,
+                        cancelled: false,
+                    });
+                    cx.notify();
+                    true
+                })
+                .unwrap_or(false);
+            if !started {
+                return;
+            }
+
+            let progress = Arc::new(JobProgress::default());
+            let finished = Arc::new(AtomicBool::new(false));
+            let task = cx.background_executor().spawn({
+                let folder = folder.clone();
+                let progress = progress.clone();
+                let finished = finished.clone();
+                async move {
+                    let result =
+                        pdf_doc.split_with_progress(&mode, &folder, &template, |done, total| {
+                            if done == 0 {
+                                progress.start(total);
+                            } else {
+                                progress.advance();
+                            }
+                            !progress.is_cancelled()
+                        });
+                    finished.store(true, Ordering::Relaxed);
+                    result
+                }
+            });
+
+            // Mirror the parts written into the status bar until the split is done
+            while !finished.load(Ordering::Relaxed) {
+                cx.background_executor().timer(PROGRESS_INTERVAL).await;
+                this.update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                    if let Some(status) = this.progress.as_mut() {
+                        (status.done, status.total) = progress.get();
+                        if status.cancelled {
+                            progress.cancel();
+                        }
+                    }
+                    cx.notify();
+                })
+                .ok();
+            }
+            let result = task.await;
+
+            this.update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                this.progress = None;
+                let message = match result {
+                    Err(e) => {
+                        log::error!("Failed to split PDF: {}", e);
+                        tr!("split.failed", "error" => e)
+                    }
+                    Ok(files) if progress.is_cancelled() => {
+                        tr!("split.cancelled", "count" => files.len())
+                    }
+                    Ok(files) => tr!(
+                        "split.done",
+                        "count" => files.len(),
+                        "folder" => folder.display()
+                    ),
+                };
+                this.set_status_message(message, cx);
+            })
+            .ok();
+        })
+        .detach();
+    }
+}
@@ -1,9 +1,16 @@
+use crate::app::config::{self, ConfigError, CONFIG_VERSION};
+use crate::app::keymap::KeymapPreset;
 use crate::app::tabs::{Tab, TabManager};
 use crate::app::{MAX_ZOOM, MIN_ZOOM};
 use crate::i18n::Language;
+use crate::pdf::color_filter::{ColorFilter, DEFAULT_CUSTOM_COLORS};
 use crate::pdf::loader::PdfLoader;
-use crate::theme::Theme;
+use crate::theme::{Theme, ThemeColors, UserThemes};
+use crate::utils::path::{get_config_path, get_themes_dir};
+use gpui::WindowAppearance;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
@@ -19,27 +26,83 @@ pub enum SelectionMode {
     #[default]
     Hand, // Hand cursor - for navigation
     TextSelect, // IBeam cursor - for text selection
+    Snapshot,   // Crosshair cursor - for copying a page region as an image
 }
 
+/// What the app shows when started without a file to open
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum StartupBehaviour {
+    #[default]
+    Empty,
+    /// Reopen the most recently opened document
+    LastFile,
+}
+
+/// Resolutions pages may be rendered at on screen, at 100% zoom
+pub const RENDER_DPI_RANGE: RangeInclusive<u32> = 72..=600;
+/// PDF units per inch, which render at one pixel each at 72 dpi
+const POINTS_PER_INCH: f32 = 72.0;
+
+fn default_render_dpi() -> u32 {
+    144
+}
+
+/// Resolutions the snapshot tool renders at
+pub const SNAPSHOT_DPI_CHOICES: [u32; 3] = [150, 300, 600];
+
+fn default_snapshot_dpi() -> u32 {
+    300
+}
+
+fn default_custom_filter_colors() -> ([u8; 3], [u8; 3]) {
+    DEFAULT_CUSTOM_COLORS
+}
+
+/// Settings kept in the config file. Fields missing from the file take their
+/// default values, so new ones need no migration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppConfig {
+    /// Schema version, see `config::CONFIG_VERSION`
+    pub version: u32,
     pub recent_files: Vec<String>,
     pub default_zoom: f32,
     pub theme: Theme,
     pub language: Language,
     pub scroll_mode: ScrollMode,
     pub selection_mode: SelectionMode,
+    pub snapshot_dpi: u32,
+    /// Save snapshots to a file instead of copying them to the clipboard
+    pub snapshot_to_file: bool,
+    /// Page colour filter of each document that has one, by path
+    pub page_filters: HashMap<String, ColorFilter>,
+    /// Foreground and background RGB of the custom page colour filter
+    pub custom_filter_colors: ([u8; 3], [u8; 3]),
+    pub keymap_preset: KeymapPreset,
+    pub render_dpi: u32,
+    pub startup: StartupBehaviour,
+    /// Scroll mode each launch starts in; None keeps the last one used
+    pub default_scroll_mode: Option<ScrollMode>,
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
+            version: CONFIG_VERSION,
             recent_files: Vec::new(),
             default_zoom: 1.0,
             theme: Theme::Dark,
             language: Language::default(),
             scroll_mode: ScrollMode::default(),
             selection_mode: SelectionMode::default(),
+            snapshot_dpi: default_snapshot_dpi(),
+            snapshot_to_file: false,
+            page_filters: HashMap::new(),
+            custom_filter_colors: default_custom_filter_colors(),
+            keymap_preset: KeymapPreset::default(),
+            render_dpi: default_render_dpi(),
+            startup: StartupBehaviour::default(),
+            default_scroll_mode: None,
         }
     }
 }
@@ -47,15 +110,26 @@ impl Default for AppConfig {
 pub struct AppState {
     pub config: Mutex<AppConfig>,
     pub tabs: Arc<TabManager>,
+    pub themes: Mutex<UserThemes>,
+    /// Problem reading or saving the config file, shown in the status bar
+    pub config_error: Mutex<Option<ConfigError>>,
 }
 
 impl AppState {
     pub fn new() -> Self {
-        let config = Self::load_config();
+        let (mut config, config_error) = config::load(&get_config_path());
+        // A language whose locale file is no longer built in falls back to the system's
+        config.language = Language::from_code(config.language.as_str()).unwrap_or_default();
+        if let Some(scroll_mode) = config.default_scroll_mode {
+            config.scroll_mode = scroll_mode;
+        }
+        let (themes, _) = UserThemes::load(&get_themes_dir());
 
         Self {
             config: Mutex::new(config),
             tabs: Arc::new(TabManager::new()),
+            themes: Mutex::new(themes),
+            config_error: Mutex::new(config_error),
         }
     }
 
@@ -67,14 +141,23 @@ impl AppState {
         let page_count = pdf_doc_arc.page_count();
         let outline = pdf_doc_arc.get_outline().ok();
 
+        let mut config = self.config.lock().unwrap();
+        let path_str = path.to_string_lossy().to_string();
+        let color_filter = config
+            .page_filters
+            .get(&path_str)
+            .copied()
+            .unwrap_or_default();
+        let zoom = config.default_zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+
         self.tabs.update_tab(tab_id, |tab| {
             tab.doc = Some(pdf_doc_arc.clone());
+            tab.zoom = zoom;
             tab.page_count = page_count;
             tab.outline_items = outline;
+            tab.color_filter = color_filter;
         });
 
-        let mut config = self.config.lock().unwrap();
-        let path_str = path.to_string_lossy().to_string();
         if !config.recent_files.contains(&path_str) {
             config.recent_files.insert(0, path_str);
             if config.recent_files.len() > 10 {
@@ -168,6 +251,26 @@ impl AppState {
         });
     }
 
+    /// Switch the active document to the next page colour filter and remember
+    /// it for that document
+    pub fn cycle_color_filter(&self) -> Option<ColorFilter> {
+        let tab_id = self.tabs.get_active_tab()?;
+        let tab = self.tabs.get_tab(tab_id)?;
+        let mut config = self.config.lock().unwrap();
+        let filter = tab.color_filter.next(config.custom_filter_colors);
+        self.tabs
+            .update_tab(tab_id, |tab| tab.color_filter = filter);
+
+        let path = tab.path.to_string_lossy().to_string();
+        if filter == ColorFilter::None {
+            config.page_filters.remove(&path);
+        } else {
+            config.page_filters.insert(path, filter);
+        }
+        self.save_config(&config);
+        Some(filter)
+    }
+
     pub fn set_theme(&self, theme: Theme) {
         let mut config = self.config.lock().unwrap();
         config.theme = theme;
@@ -175,18 +278,23 @@ impl AppState {
     }
 
     pub fn get_theme(&self) -> Theme {
-        self.config.lock().unwrap().theme
+        self.config.lock().unwrap().theme.clone()
+    }
+
+    /// Colours of the current theme, under the system's `appearance`
+    pub fn theme_colors(&self, appearance: WindowAppearance) -> ThemeColors {
+        ThemeColors::for_theme(&self.get_theme(), appearance, &self.themes.lock().unwrap())
     }
 
     pub fn set_language(&self, language: Language) {
         let mut config = self.config.lock().unwrap();
+        crate::i18n::I18n::set_language(&language);
         config.language = language;
         self.save_config(&config);
-        crate::i18n::I18n::set_language(language);
     }
 
     pub fn get_language(&self) -> Language {
-        self.config.lock().unwrap().language
+        self.config.lock().unwrap().language.clone()
     }
 
     pub fn set_scroll_mode(&self, scroll_mode: ScrollMode) {
@@ -199,7 +307,16 @@ impl AppState {
         self.config.lock().unwrap().scroll_mode
     }
 
-    #[allow(dead_code)]
+    pub fn set_keymap_preset(&self, preset: KeymapPreset) {
+        let mut config = self.config.lock().unwrap();
+        config.keymap_preset = preset;
+        self.save_config(&config);
+    }
+
+    pub fn get_keymap_preset(&self) -> KeymapPreset {
+        self.config.lock().unwrap().keymap_preset
+    }
+
     pub fn set_selection_mode(&self, selection_mode: SelectionMode) {
         let mut config = self.config.lock().unwrap();
         config.selection_mode = selection_mode;
@@ -214,13 +331,89 @@ impl AppState {
         let mut config = self.config.lock().unwrap();
         config.selection_mode = match config.selection_mode {
             SelectionMode::Hand => SelectionMode::TextSelect,
-            SelectionMode::TextSelect => SelectionMode::Hand,
+            SelectionMode::TextSelect => SelectionMode::Snapshot,
+            SelectionMode::Snapshot => SelectionMode::Hand,
         };
         let mode = config.selection_mode;
         self.save_config(&config);
         mode
     }
 
+    pub fn get_default_zoom(&self) -> f32 {
+        self.config.lock().unwrap().default_zoom
+    }
+
+    pub fn set_default_zoom(&self, zoom: f32) {
+        let mut config = self.config.lock().unwrap();
+        config.default_zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        self.save_config(&config);
+    }
+
+    pub fn get_render_dpi(&self) -> u32 {
+        self.config.lock().unwrap().render_dpi
+    }
+
+    pub fn set_render_dpi(&self, dpi: u32) {
+        let mut config = self.config.lock().unwrap();
+        config.render_dpi = dpi.clamp(*RENDER_DPI_RANGE.start(), *RENDER_DPI_RANGE.end());
+        self.save_config(&config);
+    }
+
+    /// Pixels rendered per PDF unit at 100% zoom, from the render resolution
+    pub fn render_scale(&self) -> f32 {
+        let dpi = self.get_render_dpi();
+        dpi.clamp(*RENDER_DPI_RANGE.start(), *RENDER_DPI_RANGE.end()) as f32 / POINTS_PER_INCH
+    }
+
+    pub fn get_startup(&self) -> StartupBehaviour {
+        self.config.lock().unwrap().startup
+    }
+
+    pub fn set_startup(&self, startup: StartupBehaviour) {
+        let mut config = self.config.lock().unwrap();
+        config.startup = startup;
+        self.save_config(&config);
+    }
+
+    pub fn get_default_scroll_mode(&self) -> Option<ScrollMode> {
+        self.config.lock().unwrap().default_scroll_mode
+    }
+
+    pub fn set_default_scroll_mode(&self, scroll_mode: Option<ScrollMode>) {
+        let mut config = self.config.lock().unwrap();
+        config.default_scroll_mode = scroll_mode;
+        self.save_config(&config);
+    }
+
+    pub fn get_snapshot_dpi(&self) -> u32 {
+        self.config.lock().unwrap().snapshot_dpi
+    }
+
+    /// Switch the snapshot resolution to the next of `SNAPSHOT_DPI_CHOICES`
+    pub fn cycle_snapshot_dpi(&self) -> u32 {
+        let mut config = self.config.lock().unwrap();
+        config.snapshot_dpi = SNAPSHOT_DPI_CHOICES
+            .iter()
+            .copied()
+            .find(|&dpi| dpi > config.snapshot_dpi)
+            .unwrap_or(SNAPSHOT_DPI_CHOICES[0]);
+        let dpi = config.snapshot_dpi;
+        self.save_config(&config);
+        dpi
+    }
+
+    pub fn get_snapshot_to_file(&self) -> bool {
+        self.config.lock().unwrap().snapshot_to_file
+    }
+
+    pub fn toggle_snapshot_to_file(&self) -> bool {
+        let mut config = self.config.lock().unwrap();
+        config.snapshot_to_file = !config.snapshot_to_file;
+        let to_file = config.snapshot_to_file;
+        self.save_config(&config);
+        to_file
+    }
+
     pub fn get_recent_files(&self) -> Vec<String> {
         self.config.lock().unwrap().recent_files.clone()
     }
@@ -231,22 +424,28 @@ impl AppState {
         self.save_config(&config);
     }
 
-    fn load_config() -> AppConfig {
-        let config_path = crate::utils::path::get_config_path();
-        if config_path.exists() {
-            if let Ok(content) = std::fs::read_to_string(&config_path) {
-                if let Ok(config) = serde_json::from_str(&content) {
-                    return config;
-                }
-            }
-        }
-        AppConfig::default()
+    pub fn get_config_error(&self) -> Option<ConfigError> {
+        self.config_error.lock().unwrap().clone()
+    }
+
+    pub fn dismiss_config_error(&self) {
+        self.config_error.lock().unwrap().take();
     }
 
     fn save_config(&self, config: &AppConfig) {
-        let config_path = crate::utils::path::get_config_path();
-        if let Ok(content) = serde_json::to_string_pretty(config) {
-            let _ = std::fs::write(config_path, content);
+        let result = config::save(&get_config_path(), config);
+        let mut error = self.config_error.lock().unwrap();
+        match result {
+            // A save that works again clears its earlier failure
+            Ok(()) => {
+                if matches!(*error, Some(ConfigError::SaveFailed(_))) {
+                    *error = None;
+                }
+            }
+            Err(e) => {
+                log::error!("Failed to save config: {}", e);
+                *error = Some(ConfigError::SaveFailed(e.to_string()));
+            }
         }
     }
 }
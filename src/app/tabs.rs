@@ -1,4 +1,6 @@
-use crate::pdf::{PageText, PdfDocument};
+use crate::pdf::color_filter::ColorFilter;
+use crate::pdf::{PageText, PdfDocument, TextChar};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
@@ -19,14 +21,22 @@ pub struct Tab {
     pub current_page: usize,
     pub zoom: f32,
     pub rotation: usize,
+    // Colour filter for the rendered pages, remembered per document
+    pub color_filter: ColorFilter,
     pub outline_items: Option<Vec<crate::pdf::OutlineItem>>,
+    // Vim marks: page and scroll offset by letter
+    pub marks: HashMap<char, (usize, f32)>,
     pub page_image: Option<Arc<gpui::RenderImage>>,
     pub page_dimensions: Option<(u32, u32)>,
+    // Size of the rendered page in PDF points
+    pub page_size: Option<(f32, f32)>,
     pub page_text: Option<PageText>,
     // Text selection state
     pub selection_start: Option<(f32, f32)>,
     pub selection_end: Option<(f32, f32)>,
     pub selected_text: String,
+    // Selected characters in content order, one entry per page
+    pub selected_chars: Vec<Vec<TextChar>>,
     // Multiple selection regions for multi-line selection
     pub selection_regions: Vec<SelectionRegion>,
     // Image container offset in window coordinates (for coordinate conversion)
@@ -44,13 +54,17 @@ impl Tab {
             current_page: 0,
             zoom: 1.0,
             rotation: 0,
+            color_filter: ColorFilter::None,
             outline_items: None,
+            marks: HashMap::new(),
             page_image: None,
             page_dimensions: None,
+            page_size: None,
             page_text: None,
             selection_start: None,
             selection_end: None,
             selected_text: String::new(),
+            selected_chars: Vec::new(),
             selection_regions: Vec::new(),
             image_offset: None,
         }
@@ -0,0 +1,68 @@
+use super::PdfReaderApp;
+use crate::pdf::text_export::TextExportFormat;
+use crate::tr;
+use gpui::*;
+
+impl PdfReaderApp {
+    /// Export the text of the active document as plain text, Markdown or HTML,
+    /// chosen by the extension of the file picked
+    pub fn export_text(&mut self, cx: &mut Context<Self>) {
+        let Some(tab) = self
+            .state
+            .get_active_tab_id()
+            .and_then(|id| self.state.tabs.get_tab(id))
+        else {
+            return;
+        };
+        let Some(pdf_doc) = tab.doc.clone() else {
+            return;
+        };
+
+        let stem = tab
+            .path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| String::from("document"));
+        let pages: Vec<usize> = (0..tab.page_count).collect();
+        let dialog_title = tr!("text_export.dialog");
+
+        cx.spawn(async move |this: WeakEntity<Self>, cx| {
+            let file = rfd::AsyncFileDialog::new()
+                .add_filter(tr!("text_export.plain_text"), &["txt"])
+                .add_filter("Markdown", &["md"])
+                .add_filter("HTML", &["html", "htm"])
+                .set_file_name(format!("{}.txt", stem))
+                .set_title(&dialog_title)
+                .save_file()
+                .await;
+            let Some(file) = file else {
+                return;
+            };
+            let path = file.path().to_path_buf();
+            let format = TextExportFormat::from_path(&path).unwrap_or(TextExportFormat::PlainText);
+
+            this.update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                this.set_status_message(tr!("text_export.exporting"), cx);
+            })
+            .ok();
+
+            let output = path.clone();
+            let result = cx
+                .background_executor()
+                .spawn(async move { pdf_doc.export_text(&pages, format, &output) })
+                .await;
+
+            this.update(cx, |this: &mut Self, cx: &mut Context<Self>| match result {
+                Ok(()) => {
+                    this.set_status_message(tr!("text_export.done", "file" => path.display()), cx);
+                }
+                Err(e) => {
+                    log::error!("Failed to export text: {}", e);
+                    this.set_status_message(tr!("text_export.failed", "error" => e), cx);
+                }
+            })
+            .ok();
+        })
+        .detach();
+    }
+}
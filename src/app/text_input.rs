@@ -0,0 +1,114 @@
+use crate::theme::ThemeColors;
+use gpui::*;
+
+/// Single line text field edited through the window's key events.
+/// The cursor is a char index into `text`.
+#[derive(Debug, Clone, Default)]
+pub struct TextInput {
+    pub text: String,
+    cursor: usize,
+}
+
+impl TextInput {
+    pub fn new(text: impl Into<String>) -> Self {
+        let text = text.into();
+        let cursor = text.chars().count();
+        Self { text, cursor }
+    }
+
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        *self = Self::new(text);
+    }
+
+    fn byte_index(&self, cursor: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(cursor)
+            .map(|(i, _)| i)
+            .unwrap_or(self.text.len())
+    }
+
+    fn insert(&mut self, text: &str) {
+        let index = self.byte_index(self.cursor);
+        self.text.insert_str(index, text);
+        self.cursor += text.chars().count();
+    }
+
+    /// Apply a key press. Returns true if the key was used by the field.
+    pub fn handle_key(&mut self, keystroke: &Keystroke, cx: &mut App) -> bool {
+        let len = self.text.chars().count();
+        let modifiers = keystroke.modifiers;
+
+        if modifiers.secondary() {
+            if keystroke.key == "v" {
+                if let Some(text) = cx.read_from_clipboard().and_then(|item| item.text()) {
+                    self.insert(text.lines().next().unwrap_or(""));
+                }
+                return true;
+            }
+            return false;
+        }
+
+        match keystroke.key.as_str() {
+            "backspace" => {
+                if self.cursor > 0 {
+                    self.cursor -= 1;
+                    let index = self.byte_index(self.cursor);
+                    self.text.remove(index);
+                }
+            }
+            "delete" => {
+                if self.cursor < len {
+                    let index = self.byte_index(self.cursor);
+                    self.text.remove(index);
+                }
+            }
+            "left" => self.cursor = self.cursor.saturating_sub(1),
+            "right" => self.cursor = (self.cursor + 1).min(len),
+            "home" => self.cursor = 0,
+            "end" => self.cursor = len,
+            _ => match &keystroke.key_char {
+                Some(text) if !text.chars().any(char::is_control) => self.insert(text),
+                _ => return false,
+            },
+        }
+        true
+    }
+
+    pub fn render(&self, placeholder: &str, focused: bool, colors: ThemeColors) -> Div {
+        let index = self.byte_index(self.cursor);
+        let (before, after) = self.text.split_at(index);
+
+        let mut content = div().flex().flex_row().items_center().overflow_hidden();
+        if self.text.is_empty() && !focused {
+            content = content.child(
+                div()
+                    .text_color(colors.text_secondary)
+                    .child(placeholder.to_string()),
+            );
+        } else {
+            content = content.child(before.to_string());
+            if focused {
+                content = content.child(div().w(px(1.0)).h(px(12.0)).bg(colors.text));
+            }
+            content = content.child(after.to_string());
+        }
+
+        div()
+            .h(px(22.0))
+            .px_1()
+            .flex()
+            .items_center()
+            .rounded_sm()
+            .border_1()
+            .border_color(if focused {
+                rgb(0x3399FF)
+            } else {
+                colors.border
+            })
+            .bg(colors.background)
+            .text_size(px(11.0))
+            .text_color(colors.text)
+            .child(content)
+    }
+}
@@ -1,304 +1,344 @@
 use crate::app::tabs::SelectionRegion;
 use crate::pdf::{PageText, TextChar};
+use std::ops::Range;
 
-/// Calculate text selection based on mouse coordinates
-#[allow(clippy::too_many_arguments)]
-pub fn calculate_text_selection(
-    page_text: &PageText,
-    pdf_width: f32,
-    pdf_height: f32,
-    page_width: u32,
-    page_height: u32,
-    start_x: f32,
-    start_y: f32,
-    end_x: f32,
-    end_y: f32,
-) -> (String, Vec<SelectionRegion>) {
-    // Calculate selection rectangle in screen coordinates
-    let screen_min_x = start_x.min(end_x);
-    let screen_max_x = start_x.max(end_x);
-    let screen_min_y = start_y.min(end_y);
-    let screen_max_y = start_y.max(end_y);
-
-    // Calculate scale factors
-    let scale_x = pdf_width / page_width as f32;
-    let scale_y = pdf_height / page_height as f32;
-
-    // Group all characters by line first
-    let mut all_lines: Vec<Vec<&TextChar>> = Vec::new();
-    let line_tolerance = 5.0f32;
+/// A caret position in the document's text: before character `index` of `page`,
+/// in Pdfium's character order. Positions order by page, then index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TextPosition {
+    pub page: usize,
+    pub index: usize,
+}
 
-    for c in &page_text.chars {
-        let char_screen_y = (pdf_height - c.y) / scale_y;
+/// Maps between the displayed page image and PDF page coordinates
+#[derive(Debug, Clone, Copy)]
+pub struct PageGeometry {
+    /// Page size in points, as rendered (after the page's own rotation)
+    pub pdf_width: f32,
+    pub pdf_height: f32,
+    /// Size of the displayed image, after the view rotation
+    pub view_width: f32,
+    pub view_height: f32,
+    /// View rotation in degrees clockwise: 0, 90, 180 or 270
+    pub rotation: usize,
+}
 
-        if let Some(last_line) = all_lines.last_mut() {
-            if let Some(last_char) = last_line.first() {
-                let last_char_screen_y = (pdf_height - last_char.y) / scale_y;
-                if (char_screen_y - last_char_screen_y).abs() < line_tolerance {
-                    last_line.push(c);
-                    continue;
-                }
-            }
+impl PageGeometry {
+    /// Size of the image before the view rotation
+    fn unrotated_size(self) -> (f32, f32) {
+        match self.rotation {
+            90 | 270 => (self.view_height, self.view_width),
+            _ => (self.view_width, self.view_height),
         }
-
-        all_lines.push(vec![c]);
     }
 
-    // Sort lines by Y position (top to bottom)
-    all_lines.sort_by(|a, b| {
-        let a_y = (pdf_height - a.first().unwrap().y) / scale_y;
-        let b_y = (pdf_height - b.first().unwrap().y) / scale_y;
-        a_y.partial_cmp(&b_y).unwrap_or(std::cmp::Ordering::Equal)
-    });
-
-    // Sort characters within each line by X position
-    for line in &mut all_lines {
-        line.sort_by(|a, b| a.x.partial_cmp(&b.x).unwrap_or(std::cmp::Ordering::Equal));
+    /// Convert a point on the displayed image to PDF coordinates (y up)
+    pub fn to_pdf(self, x: f32, y: f32) -> (f32, f32) {
+        let (width, height) = self.unrotated_size();
+        let (ux, uy) = match self.rotation {
+            90 => (y, height - x),
+            180 => (width - x, height - y),
+            270 => (width - y, x),
+            _ => (x, y),
+        };
+        (
+            ux * self.pdf_width / width,
+            self.pdf_height - uy * self.pdf_height / height,
+        )
     }
 
-    // Find which lines are within the selection Y range
-    let mut selected_lines: Vec<&Vec<&TextChar>> = Vec::new();
-
-    for line in &all_lines {
-        if line.is_empty() {
-            continue;
+    /// Convert a point in PDF coordinates to the displayed image
+    pub fn to_view(self, x: f32, y: f32) -> (f32, f32) {
+        let (width, height) = self.unrotated_size();
+        let ux = x * width / self.pdf_width;
+        let uy = (self.pdf_height - y) * height / self.pdf_height;
+        match self.rotation {
+            90 => (height - uy, ux),
+            180 => (width - ux, height - uy),
+            270 => (uy, width - ux),
+            _ => (ux, uy),
         }
-        let first_char = line.first().unwrap();
-        let line_y = (pdf_height - first_char.y) / scale_y;
-        let line_height = first_char.height / scale_y;
-        let line_top = line_y - line_height;
-        let line_bottom = line_y;
+    }
 
-        // Check if this line overlaps with selection Y range
-        if line_bottom >= screen_min_y && line_top <= screen_max_y {
-            selected_lines.push(line);
+    /// The displayed rectangle covering a box given in PDF coordinates
+    fn to_view_region(self, x0: f32, y0: f32, x1: f32, y1: f32) -> SelectionRegion {
+        let (ax, ay) = self.to_view(x0, y0);
+        let (bx, by) = self.to_view(x1, y1);
+        SelectionRegion {
+            x: ax.min(bx),
+            y: ay.min(by),
+            width: (ax - bx).abs(),
+            height: (ay - by).abs(),
         }
     }
+}
 
-    // Build selected text and selection regions
-    let selected_text = build_selected_text(
-        &selected_lines,
-        scale_x,
-        page_width,
-        screen_min_x,
-        screen_max_x,
-    );
-    let selection_regions = build_selection_regions(
-        &selected_lines,
-        scale_x,
-        scale_y,
-        pdf_height,
-        page_width,
-        screen_min_x,
-        screen_max_x,
-    );
+/// Which unit a click selects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionUnit {
+    Word,
+    Line,
+}
 
-    (selected_text, selection_regions)
+/// Whitespace and line breaks Pdfium generates between words and lines
+pub(super) fn is_blank(c: &TextChar) -> bool {
+    c.char.is_whitespace() || c.char.is_control()
 }
 
-fn build_selected_text(
-    selected_lines: &[&Vec<&TextChar>],
-    scale_x: f32,
-    page_width: u32,
-    screen_min_x: f32,
-    screen_max_x: f32,
-) -> String {
-    let mut selected_text_parts: Vec<String> = Vec::new();
-    let num_lines = selected_lines.len();
-    let page_width_f32 = page_width as f32;
+fn overlap(a0: f32, a1: f32, b0: f32, b1: f32) -> f32 {
+    a1.min(b1) - a0.max(b0)
+}
 
-    for (line_idx, line) in selected_lines.iter().enumerate() {
-        // Filter out abnormal characters first
-        let valid_line_chars: Vec<&TextChar> = line
-            .iter()
-            .filter(|c| {
-                let char_x = c.x / scale_x;
-                let char_end_x = (c.x + c.width) / scale_x;
-                char_x < page_width_f32 && char_end_x <= page_width_f32 + 10.0
-            })
-            .copied()
-            .collect();
+/// Whether `next` continues the same horizontal line as `prev`
+fn continues_horizontally(prev: &TextChar, next: &TextChar) -> bool {
+    let size = prev.height.max(next.height).max(1.0);
+    let shared = overlap(prev.y, prev.y + prev.height, next.y, next.y + next.height);
+    let gap = next.x - (prev.x + prev.width);
+    shared > prev.height.min(next.height) * 0.5 && gap > -size && gap < size * 3.0
+}
 
-        let chars_for_line: Vec<&TextChar> = if num_lines == 1 {
-            // Single line: only characters in selection X range
-            valid_line_chars
-                .iter()
-                .filter(|c| {
-                    let char_x = c.x / scale_x;
-                    let char_end_x = (c.x + c.width) / scale_x;
-                    char_end_x >= screen_min_x && char_x <= screen_max_x
-                })
-                .copied()
-                .collect()
-        } else if line_idx == 0 {
-            // First line: from selection start to line end
-            valid_line_chars
-                .iter()
-                .filter(|c| {
-                    let char_end_x = (c.x + c.width) / scale_x;
-                    char_end_x >= screen_min_x
-                })
-                .copied()
-                .collect()
-        } else if line_idx == num_lines - 1 {
-            // Last line: from line start to selection end
-            valid_line_chars
-                .iter()
-                .filter(|c| {
-                    let char_x = c.x / scale_x;
-                    char_x <= screen_max_x
-                })
-                .copied()
-                .collect()
-        } else {
-            // Middle lines: entire line
-            valid_line_chars
-        };
+/// Whether `next` continues the same vertical line (e.g. CJK set top to bottom) as `prev`
+fn continues_vertically(prev: &TextChar, next: &TextChar) -> bool {
+    let size = prev.width.max(next.width).max(1.0);
+    let shared = overlap(prev.x, prev.x + prev.width, next.x, next.x + next.width);
+    let gap = prev.y - (next.y + next.height);
+    shared > prev.width.min(next.width) * 0.5 && gap > -size && gap < size * 3.0
+}
 
-        // Sort characters within this line by x position
-        let mut line_chars_sorted = chars_for_line;
-        line_chars_sorted
-            .sort_by(|a, b| a.x.partial_cmp(&b.x).unwrap_or(std::cmp::Ordering::Equal));
+/// Split characters into visual lines: runs that follow one another on the page,
+/// left to right or top to bottom. Blank characters belong to the run before them.
+pub(super) fn line_runs(chars: &[TextChar]) -> Vec<Range<usize>> {
+    let mut runs: Vec<Range<usize>> = Vec::new();
+    let mut last_visible: Option<usize> = None;
 
-        // Build text for this line
-        let line_text: String = line_chars_sorted.iter().map(|c| c.char).collect();
-        if !line_text.is_empty() {
-            selected_text_parts.push(line_text);
+    for (i, c) in chars.iter().enumerate() {
+        let joins = match last_visible {
+            None => !runs.is_empty(),
+            Some(_) if is_blank(c) => true,
+            Some(prev) => {
+                continues_horizontally(&chars[prev], c) || continues_vertically(&chars[prev], c)
+            }
+        };
+        match runs.last_mut() {
+            Some(run) if joins => run.end = i + 1,
+            _ => runs.push(i..i + 1),
+        }
+        if !is_blank(c) {
+            last_visible = Some(i);
         }
     }
+    runs
+}
 
-    // Join lines with newline
-    selected_text_parts.join("\n")
+/// Whether character `i` is part of vertically set text
+pub(super) fn is_vertical(chars: &[TextChar], i: usize) -> bool {
+    let neighbour = |j: usize| chars.get(j).filter(|c| !is_blank(c));
+    let vertical_next = neighbour(i + 1).is_some_and(|n| continues_vertically(&chars[i], n));
+    let vertical_prev = i
+        .checked_sub(1)
+        .and_then(neighbour)
+        .is_some_and(|p| continues_vertically(p, &chars[i]));
+    vertical_next || vertical_prev
 }
 
-fn build_selection_regions(
-    selected_lines: &[&Vec<&TextChar>],
-    scale_x: f32,
-    scale_y: f32,
-    pdf_height: f32,
-    page_width: u32,
-    screen_min_x: f32,
-    screen_max_x: f32,
-) -> Vec<SelectionRegion> {
-    let mut selection_regions: Vec<SelectionRegion> = Vec::new();
-    let num_lines = selected_lines.len();
-    let page_width_f32 = page_width as f32;
+/// Index of the visible character under `point` (in PDF coordinates), or the nearest one
+fn nearest_char(chars: &[TextChar], (x, y): (f32, f32)) -> Option<usize> {
+    let distance = |c: &TextChar| {
+        let dx = (c.x - x).max(x - (c.x + c.width)).max(0.0);
+        let dy = (c.y - y).max(y - (c.y + c.height)).max(0.0);
+        dx * dx + dy * dy
+    };
+    chars
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| !is_blank(c))
+        .min_by(|(_, a), (_, b)| distance(a).total_cmp(&distance(b)))
+        .map(|(i, _)| i)
+}
 
-    for (line_idx, line_chars) in selected_lines.iter().enumerate() {
-        if line_chars.is_empty() {
-            continue;
-        }
+/// Caret index for a point on the displayed page: before the character under it,
+/// or after it when the point is past the character's middle in writing direction
+pub fn hit_test(page_text: &PageText, geometry: &PageGeometry, x: f32, y: f32) -> Option<usize> {
+    let chars = &page_text.chars;
+    let point = geometry.to_pdf(x, y);
+    let i = nearest_char(chars, point)?;
+    let c = &chars[i];
+    let after = if is_vertical(chars, i) {
+        point.1 < c.y + c.height / 2.0
+    } else {
+        point.0 > c.x + c.width / 2.0
+    };
+    Some(if after { i + 1 } else { i })
+}
 
-        // Get line height from first character
-        let first_char = line_chars.first().unwrap();
-        let line_height = first_char.height / scale_y;
-        let line_top = ((pdf_height - first_char.y) / scale_y) - line_height;
+/// Characters that belong to the same word as each other
+fn char_class(c: char) -> u8 {
+    if c.is_whitespace() || c.is_control() {
+        0
+    } else if c.is_alphanumeric() || c == '_' || c == '\'' {
+        1
+    } else {
+        2
+    }
+}
 
-        // Filter out characters with abnormal x coordinates (e.g., newline markers)
-        // Only keep characters that are within page bounds
-        let valid_chars: Vec<&TextChar> = line_chars
-            .iter()
-            .filter(|c| {
-                let char_x = c.x / scale_x;
-                let char_end_x = (c.x + c.width) / scale_x;
-                // Filter out characters that are beyond page width
-                // (likely formatting markers like newlines)
-                char_x < page_width_f32 && char_end_x <= page_width_f32 + 10.0
-            })
-            .copied()
-            .collect();
+/// Character range of the word or visual line under a point on the displayed page
+pub fn unit_range_at(
+    page_text: &PageText,
+    geometry: &PageGeometry,
+    unit: SelectionUnit,
+    x: f32,
+    y: f32,
+) -> Option<Range<usize>> {
+    let chars = &page_text.chars;
+    let hit = nearest_char(chars, geometry.to_pdf(x, y))?;
+    let run = line_runs(chars).into_iter().find(|r| r.contains(&hit))?;
 
-        if valid_chars.is_empty() {
-            continue;
+    match unit {
+        SelectionUnit::Line => {
+            // Leave out the line break Pdfium puts at the end of the run
+            let end = (run.start..run.end)
+                .rev()
+                .find(|i| !is_blank(&chars[*i]))
+                .map_or(run.end, |i| i + 1);
+            Some(run.start..end)
         }
-
-        // Calculate line bounds from valid characters only
-        // Characters are already sorted by x, so first char has min_x, last char has max_x
-        let line_min_x = valid_chars.first().unwrap().x / scale_x;
-        let line_max_x =
-            valid_chars.last().unwrap().x / scale_x + valid_chars.last().unwrap().width / scale_x;
-
-        // Determine X bounds based on line position
-        let (sel_min_x, sel_max_x) = if num_lines == 1 {
-            // Single line: only characters in selection X range
-            let chars_in_range: Vec<&TextChar> = valid_chars
-                .iter()
-                .filter(|c| {
-                    let char_x = c.x / scale_x;
-                    let char_end_x = (c.x + c.width) / scale_x;
-                    char_end_x >= screen_min_x && char_x <= screen_max_x
-                })
-                .copied()
-                .collect();
-            if chars_in_range.is_empty() {
-                continue;
+        SelectionUnit::Word => {
+            let class = char_class(chars[hit].char);
+            let mut start = hit;
+            while start > run.start && char_class(chars[start - 1].char) == class {
+                start -= 1;
             }
-            let min_x = chars_in_range
-                .iter()
-                .map(|c| c.x / scale_x)
-                .fold(f32::MAX, f32::min);
-            let max_x = chars_in_range
-                .iter()
-                .map(|c| (c.x + c.width) / scale_x)
-                .fold(f32::MIN, f32::max);
-            (min_x, max_x)
-        } else if line_idx == 0 {
-            // First line: from selection start to line end (only actual characters)
-            let chars_in_range: Vec<&TextChar> = valid_chars
-                .iter()
-                .filter(|c| {
-                    let char_end_x = (c.x + c.width) / scale_x;
-                    char_end_x >= screen_min_x
-                })
-                .copied()
-                .collect();
-            if chars_in_range.is_empty() {
-                continue;
+            let mut end = hit + 1;
+            while end < run.end && char_class(chars[end].char) == class {
+                end += 1;
             }
-            let min_x = chars_in_range
-                .iter()
-                .map(|c| c.x / scale_x)
-                .fold(f32::MAX, f32::min);
-            let max_x = line_max_x; // Use actual line end, not selection end
-            (min_x, max_x)
-        } else if line_idx == num_lines - 1 {
-            // Last line: from line start to selection end (only actual characters)
-            let chars_in_range: Vec<&TextChar> = valid_chars
-                .iter()
-                .filter(|c| {
-                    let char_x = c.x / scale_x;
-                    char_x <= screen_max_x
-                })
-                .copied()
-                .collect();
-            if chars_in_range.is_empty() {
-                continue;
+            Some(start..end)
+        }
+    }
+}
+
+/// Text of a run of characters in content order, one visual line per text line
+pub fn selection_text(chars: &[TextChar]) -> String {
+    let mut text = String::new();
+    for run in line_runs(chars) {
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        for c in &chars[run] {
+            match c.char {
+                '\r' => {}
+                '\n' => {
+                    if !text.is_empty() && !text.ends_with('\n') {
+                        text.push('\n');
+                    }
+                }
+                ch => text.push(ch),
             }
-            let min_x = line_min_x; // Use actual line start
-            let max_x = chars_in_range
-                .iter()
-                .map(|c| (c.x + c.width) / scale_x)
-                .fold(f32::MIN, f32::max);
-            (min_x, max_x)
-        } else {
-            // Middle lines: entire line (only actual characters)
-            (line_min_x, line_max_x)
+        }
+    }
+    text.trim_end().to_string()
+}
+
+/// Highlight rectangles for a run of characters, one per visual line
+pub fn selection_regions(chars: &[TextChar], geometry: &PageGeometry) -> Vec<SelectionRegion> {
+    line_runs(chars)
+        .into_iter()
+        .filter_map(|run| {
+            let visible = chars[run].iter().filter(|c| !is_blank(c));
+            let (x0, y0, x1, y1) = visible.fold(None, |bounds, c| {
+                let (x0, y0, x1, y1) = bounds.unwrap_or((f32::MAX, f32::MAX, f32::MIN, f32::MIN));
+                Some((
+                    x0.min(c.x),
+                    y0.min(c.y),
+                    x1.max(c.x + c.width),
+                    y1.max(c.y + c.height),
+                ))
+            })?;
+            let region = geometry.to_view_region(x0, y0, x1, y1);
+            (region.width > 0.0 && region.height > 0.0).then_some(region)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Characters of `text` from (x, y), advancing right, or down when `vertical`
+    fn run(text: &str, x: f32, y: f32, vertical: bool) -> Vec<TextChar> {
+        text.chars()
+            .enumerate()
+            .map(|(i, c)| TextChar {
+                char: c,
+                x: if vertical { x } else { x + i as f32 * 6.0 },
+                y: if vertical { y - i as f32 * 10.0 } else { y },
+                width: 6.0,
+                height: 10.0,
+                font_size: 10.0,
+                bold: false,
+                italic: false,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_selection_follows_columns() {
+        // Two columns in content order, with Pdfium's generated line breaks
+        let mut chars = run("left one\r\n", 10.0, 180.0, false);
+        chars.extend(run("left two\r\n", 10.0, 165.0, false));
+        chars.extend(run("right one\r\n", 110.0, 180.0, false));
+        chars.extend(run("right two", 110.0, 165.0, false));
+        let page_text = PageText {
+            text: String::new(),
+            chars,
+        };
+        let geometry = PageGeometry {
+            pdf_width: 200.0,
+            pdf_height: 200.0,
+            view_width: 200.0,
+            view_height: 200.0,
+            rotation: 0,
         };
 
-        // Clamp to image bounds
-        let clamped_min_x = sel_min_x.max(0.0);
-        let clamped_max_x = sel_max_x.min(page_width as f32);
-        let final_width = clamped_max_x - clamped_min_x;
+        // Drag from "one" in the left column to "right" in the right column
+        let start = hit_test(&page_text, &geometry, 40.0, 15.0).unwrap();
+        let end = hit_test(&page_text, &geometry, 140.0, 15.0).unwrap();
+        assert_eq!(
+            selection_text(&page_text.chars[start..end]),
+            "one\nleft two\nright"
+        );
+        let regions = selection_regions(&page_text.chars[start..end], &geometry);
+        assert_eq!(regions.len(), 3);
+        assert!(regions.iter().all(|r| r.x < 100.0 || r.x >= 110.0));
 
-        if final_width > 0.0 {
-            selection_regions.push(SelectionRegion {
-                x: clamped_min_x,
-                y: line_top,
-                width: final_width,
-                height: line_height,
-            });
-        }
+        let line = unit_range_at(&page_text, &geometry, SelectionUnit::Line, 140.0, 30.0);
+        assert_eq!(selection_text(&page_text.chars[line.unwrap()]), "right two");
+        let word = unit_range_at(&page_text, &geometry, SelectionUnit::Word, 45.0, 30.0);
+        assert_eq!(selection_text(&page_text.chars[word.unwrap()]), "two");
     }
 
-    selection_regions
+    #[test]
+    fn test_vertical_text_and_rotation() {
+        let page_text = PageText {
+            text: String::new(),
+            chars: run("縦書き", 50.0, 150.0, true),
+        };
+        // Displayed turned a quarter clockwise: the page's top edge is on the right
+        let geometry = PageGeometry {
+            pdf_width: 100.0,
+            pdf_height: 200.0,
+            view_width: 200.0,
+            view_height: 100.0,
+            rotation: 90,
+        };
+        let (x, y) = geometry.to_view(53.0, 155.0);
+        assert_eq!(geometry.to_pdf(x, y), (53.0, 155.0));
+
+        // Just below the middle of the first character selects after it
+        let (x, y) = geometry.to_view(53.0, 154.0);
+        assert_eq!(hit_test(&page_text, &geometry, x, y), Some(1));
+        assert_eq!(selection_regions(&page_text.chars, &geometry).len(), 1);
+        assert_eq!(selection_text(&page_text.chars), "縦書き");
+    }
 }
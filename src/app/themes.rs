@@ -0,0 +1,79 @@
+//! Switching between themes, and picking up edits to the user's theme files
+
+use super::PdfReaderApp;
+use crate::app::menu::RefreshMenus;
+use crate::theme::{scan_themes, Theme, UserThemes};
+use crate::tr;
+use crate::utils::path::get_themes_dir;
+use gpui::*;
+use std::time::Duration;
+
+/// How often the themes folder is checked for edits
+const THEME_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Localized name of a theme
+pub fn theme_name(theme: &Theme) -> String {
+    match theme {
+        Theme::Light => tr!("menu.theme_light"),
+        Theme::Dark => tr!("menu.theme_dark"),
+        Theme::System => tr!("menu.theme_system"),
+        Theme::Custom(name) => name.clone(),
+    }
+}
+
+impl PdfReaderApp {
+    pub fn select_theme(&mut self, theme: Theme, cx: &mut Context<Self>) {
+        let missing = match &theme {
+            Theme::Custom(name) => self.state.themes.lock().unwrap().get(name).is_none(),
+            _ => false,
+        };
+        let message = if missing {
+            tr!("theme.missing", "name" => theme_name(&theme))
+        } else {
+            tr!("theme.changed", "name" => theme_name(&theme))
+        };
+        self.state.set_theme(theme);
+        self.set_status_message(message, cx);
+    }
+
+    /// Switch to whichever of the built-in light and dark themes the current
+    /// colours are not
+    pub fn toggle_theme(&mut self, cx: &mut Context<Self>) {
+        let colors = self.state.theme_colors(cx.window_appearance());
+        let theme = if colors.is_dark() {
+            Theme::Light
+        } else {
+            Theme::Dark
+        };
+        self.state.set_theme(theme);
+        cx.notify();
+    }
+
+    /// Reread the themes folder whenever a file in it is added, edited or removed
+    pub(super) fn watch_themes(&self, cx: &mut Context<Self>) {
+        cx.spawn(async move |this: WeakEntity<Self>, cx| loop {
+            cx.background_executor().timer(THEME_POLL_INTERVAL).await;
+            let dir = get_themes_dir();
+            let stamps = cx
+                .background_executor()
+                .spawn(async move { scan_themes(&dir) })
+                .await;
+            let reloaded = this.update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                if !this.state.themes.lock().unwrap().is_stale(&stamps) {
+                    return;
+                }
+                let (themes, errors) = UserThemes::load(&get_themes_dir());
+                *this.state.themes.lock().unwrap() = themes;
+                cx.dispatch_action(&RefreshMenus);
+                if let Some(error) = errors.first() {
+                    this.set_status_message(tr!("theme.invalid", "error" => error), cx);
+                }
+                cx.notify();
+            });
+            if reloaded.is_err() {
+                break;
+            }
+        })
+        .detach();
+    }
+}
@@ -0,0 +1,105 @@
+use crate::app::command_palette::CommandPalette;
+use crate::app::dialog::Dialog;
+use crate::theme::ThemeColors;
+use crate::tr;
+use gpui::*;
+
+use super::super::PdfReaderApp;
+
+const LIST_HEIGHT: f32 = 320.0;
+
+impl PdfReaderApp {
+    /// Title and body of the command palette
+    pub(super) fn render_command_palette(
+        &self,
+        palette: &CommandPalette,
+        colors: ThemeColors,
+        cx: &mut Context<Self>,
+    ) -> (String, AnyElement) {
+        let mut list = div()
+            .id("command-palette-items")
+            .h(px(LIST_HEIGHT))
+            .flex()
+            .flex_col()
+            .overflow_y_scroll()
+            .track_scroll(&palette.scroll);
+
+        if palette.matches.is_empty() {
+            list = list.child(
+                div()
+                    .p_2()
+                    .text_size(px(11.0))
+                    .text_color(colors.text_secondary)
+                    .child(tr!("palette.no_matches")),
+            );
+        }
+        for (position, &index) in palette.matches.iter().enumerate() {
+            let item = &palette.items[index];
+            let selected = position == palette.selected;
+            let mut row = div()
+                .flex()
+                .flex_row()
+                .items_center()
+                .gap_2()
+                .px_2()
+                .py(px(3.0))
+                .rounded_sm()
+                .cursor_pointer();
+            if selected {
+                row = row.bg(colors.background_tertiary);
+            }
+            list = list.child(
+                row.hover(|row| row.bg(colors.background_tertiary))
+                    .child(
+                        div()
+                            .flex_1()
+                            .flex()
+                            .flex_col()
+                            .overflow_hidden()
+                            .child(
+                                div()
+                                    .text_size(px(11.0))
+                                    .text_color(colors.text)
+                                    .text_ellipsis()
+                                    .child(item.label.clone()),
+                            )
+                            .children((!item.detail.is_empty()).then(|| {
+                                div()
+                                    .text_size(px(10.0))
+                                    .text_color(colors.text_secondary)
+                                    .text_ellipsis()
+                                    .child(item.detail.clone())
+                            })),
+                    )
+                    .children(item.shortcut.clone().map(|keys| {
+                        div()
+                            .text_size(px(10.0))
+                            .text_color(colors.text_secondary)
+                            .child(keys)
+                    }))
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(move |this, _event, _window, cx| {
+                            if let Some(Dialog::CommandPalette(palette)) = &mut this.dialog {
+                                palette.select(position);
+                            }
+                            this.run_palette_item(cx);
+                        }),
+                    ),
+            );
+        }
+
+        let body = div()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .child(
+                palette
+                    .query
+                    .render(&tr!("palette.placeholder"), true, colors),
+            )
+            .child(list);
+
+        (tr!("palette.title"), body.into_any_element())
+    }
+}
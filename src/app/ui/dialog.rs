@@ -0,0 +1,69 @@
+use crate::app::dialog::Dialog;
+use crate::theme::ThemeColors;
+use gpui::*;
+
+use super::super::PdfReaderApp;
+
+pub(super) const DIALOG_WIDTH: f32 = 520.0;
+const PRINT_DIALOG_WIDTH: f32 = 760.0;
+
+impl PdfReaderApp {
+    /// Modal overlay for the open dialog, if any
+    pub(super) fn render_dialog(
+        &self,
+        colors: ThemeColors,
+        cx: &mut Context<Self>,
+    ) -> Option<AnyElement> {
+        let dialog = self.dialog.as_ref()?;
+        let width = match dialog {
+            Dialog::Print(_) => PRINT_DIALOG_WIDTH,
+            _ => DIALOG_WIDTH,
+        };
+        let (title, body) = match dialog {
+            Dialog::Merge(dialog) => self.render_merge_dialog(dialog, colors, cx),
+            Dialog::Split(dialog) => self.render_split_dialog(dialog, colors, cx),
+            Dialog::ImageExport(dialog) => self.render_image_export_dialog(dialog, colors, cx),
+            Dialog::Print(dialog) => self.render_print_dialog(dialog, colors, cx),
+            Dialog::Shortcuts => self.render_shortcuts_dialog(colors, cx),
+            Dialog::CommandPalette(palette) => self.render_command_palette(palette, colors, cx),
+            Dialog::Preferences(dialog) => self.render_preferences_dialog(dialog, colors, cx),
+        };
+
+        Some(
+            div()
+                .absolute()
+                .inset_0()
+                .flex()
+                .items_center()
+                .justify_center()
+                .bg(rgba(0x00000080))
+                .occlude()
+                .child(
+                    div()
+                        .w(px(width))
+                        .flex()
+                        .flex_col()
+                        .rounded_md()
+                        .shadow_lg()
+                        .bg(colors.background)
+                        .border_1()
+                        .border_color(colors.border)
+                        .child(
+                            div()
+                                .h(px(28.0))
+                                .px_3()
+                                .flex()
+                                .items_center()
+                                .bg(colors.background_secondary)
+                                .border_b_1()
+                                .border_color(colors.border)
+                                .text_size(px(12.0))
+                                .text_color(colors.text)
+                                .child(title),
+                        )
+                        .child(div().p_3().child(body)),
+                )
+                .into_any_element(),
+        )
+    }
+}
@@ -0,0 +1,174 @@
+use crate::app::image_export::{ImageExportDialog, ImageExportField};
+use crate::app::text_input::TextInput;
+use crate::app::widgets::{toggle_btn, toolbar_btn_enabled};
+use crate::pdf::image_export::{PageImageFormat, DEFAULT_IMAGE_TEMPLATE};
+use crate::theme::ThemeColors;
+use crate::tr;
+use gpui::*;
+
+use super::super::PdfReaderApp;
+
+const ERROR_COLOR: u32 = 0xD93025;
+
+impl PdfReaderApp {
+    /// Title and body of the export-as-images dialog
+    pub(super) fn render_image_export_dialog(
+        &self,
+        dialog: &ImageExportDialog,
+        colors: ThemeColors,
+        cx: &mut Context<Self>,
+    ) -> (String, AnyElement) {
+        let label = |text: String| {
+            div()
+                .w(px(110.0))
+                .text_size(px(11.0))
+                .text_color(colors.text)
+                .child(text)
+        };
+        let input_row =
+            |text: String, input: &TextInput, placeholder: &str, field: ImageExportField| {
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .child(label(text))
+                    .child(
+                        div()
+                            .flex_1()
+                            .cursor_text()
+                            .child(input.render(placeholder, dialog.focused == field, colors))
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(move |this, _event, _window, cx| {
+                                    this.update_image_export_dialog(cx, |d| d.focused = field);
+                                }),
+                            ),
+                    )
+            };
+
+        let pages_row = input_row(
+            tr!("export_images.pages"),
+            &dialog.pages,
+            &tr!("export_images.all_pages"),
+            ImageExportField::Pages,
+        );
+        let dpi_row = input_row(
+            tr!("export_images.dpi"),
+            &dialog.dpi,
+            "150",
+            ImageExportField::Dpi,
+        );
+        let quality_row = (dialog.format == PageImageFormat::Jpeg).then(|| {
+            input_row(
+                tr!("export_images.quality"),
+                &dialog.quality,
+                "90",
+                ImageExportField::Quality,
+            )
+        });
+        let template_row = input_row(
+            tr!("export_images.file_names"),
+            &dialog.template,
+            DEFAULT_IMAGE_TEMPLATE,
+            ImageExportField::Template,
+        );
+
+        let mut formats = div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap_1()
+            .child(label(tr!("export_images.format")));
+        for (format, text) in [
+            (PageImageFormat::Png, "PNG"),
+            (PageImageFormat::Jpeg, "JPEG"),
+        ] {
+            formats = formats.child(toggle_btn(
+                text,
+                dialog.format == format,
+                colors,
+                cx.listener(move |this, _event, _window, cx| {
+                    this.update_image_export_dialog(cx, |d| d.set_format(format));
+                }),
+            ));
+        }
+
+        let mut body = div()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .child(pages_row)
+            .child(dpi_row)
+            .child(formats)
+            .children(quality_row);
+
+        if dialog.format == PageImageFormat::Png {
+            body = body.child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_1()
+                    .child(label(tr!("export_images.background")))
+                    .child(toggle_btn(
+                        &tr!("export_images.white"),
+                        !dialog.transparent,
+                        colors,
+                        cx.listener(|this, _event, _window, cx| {
+                            this.update_image_export_dialog(cx, |d| d.transparent = false);
+                        }),
+                    ))
+                    .child(toggle_btn(
+                        &tr!("export_images.transparent"),
+                        dialog.transparent,
+                        colors,
+                        cx.listener(|this, _event, _window, cx| {
+                            this.update_image_export_dialog(cx, |d| d.transparent = true);
+                        }),
+                    )),
+            );
+        }
+
+        body = body.child(template_row).child(
+            div()
+                .text_size(px(10.0))
+                .text_color(colors.text_secondary)
+                .child(tr!("export_images.template_hint")),
+        );
+
+        if let Some(error) = &dialog.error {
+            body = body.child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(rgb(ERROR_COLOR))
+                    .child(error.clone()),
+            );
+        }
+
+        body = body.child(
+            div()
+                .flex()
+                .flex_row()
+                .gap_1()
+                .child(div().flex_1())
+                .child(toolbar_btn_enabled(
+                    &tr!("dialog.cancel"),
+                    true,
+                    colors,
+                    cx.listener(|this, _event, _window, cx| {
+                        this.close_dialog(cx);
+                    }),
+                ))
+                .child(toolbar_btn_enabled(
+                    &tr!("export_images.export"),
+                    self.progress.is_none(),
+                    colors,
+                    cx.listener(|this, _event, _window, cx| {
+                        this.run_image_export(cx);
+                    }),
+                )),
+        );
+
+        (tr!("export_images.title"), body.into_any_element())
+    }
+}
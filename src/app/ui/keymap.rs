@@ -0,0 +1,169 @@
+use crate::app::keymap::{action_label, BindingSource, KeymapPreset};
+use crate::app::widgets::{toggle_btn, toolbar_btn};
+use crate::theme::ThemeColors;
+use crate::tr;
+use crate::utils::path::get_keymap_path;
+use gpui::*;
+
+use super::super::PdfReaderApp;
+
+const ERROR_COLOR: u32 = 0xD93025;
+const KEYS_WIDTH: f32 = 150.0;
+const LIST_HEIGHT: f32 = 360.0;
+
+impl PdfReaderApp {
+    /// Title and body of the keyboard shortcuts page
+    pub(super) fn render_shortcuts_dialog(
+        &self,
+        colors: ThemeColors,
+        cx: &mut Context<Self>,
+    ) -> (String, AnyElement) {
+        let conflicts = self.keymap.conflicts();
+        let in_conflict = |index: usize| conflicts.iter().any(|&(a, b)| a == index || b == index);
+
+        let mut list = div().flex().flex_col();
+        for (index, binding) in self.keymap.bindings.iter().enumerate() {
+            let label = action_label(&binding.action).unwrap_or_else(|| binding.action.clone());
+            let conflict = in_conflict(index);
+            let source = match binding.source {
+                BindingSource::Default => None,
+                BindingSource::Preset => Some(tr!("keymap.preset_vim")),
+                BindingSource::User => Some(tr!("keymap.custom")),
+            };
+            let text_color = if conflict {
+                rgb(ERROR_COLOR)
+            } else {
+                colors.text
+            };
+            list = list.child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_2()
+                    .py(px(2.0))
+                    .border_b_1()
+                    .border_color(colors.border)
+                    .text_size(px(11.0))
+                    .text_color(text_color)
+                    .child(div().w(px(KEYS_WIDTH)).child(binding.keys_text()))
+                    .child(div().flex_1().child(label))
+                    .children(
+                        conflict.then(|| div().text_size(px(10.0)).child(tr!("keymap.conflict"))),
+                    )
+                    .children(source.map(|source| {
+                        div()
+                            .text_size(px(10.0))
+                            .text_color(colors.text_secondary)
+                            .child(source)
+                    })),
+            );
+        }
+
+        let preset = self.state.get_keymap_preset();
+        if preset == KeymapPreset::Vim {
+            for (keys, description) in [
+                ("j / k", tr!("vim.scroll")),
+                ("J / K", tr!("vim.page")),
+                ("gg / G", tr!("vim.first_last")),
+                ("42G / 42gg", tr!("vim.go_to")),
+                ("m{a-z}", tr!("vim.set_mark")),
+                ("'{a-z}", tr!("vim.jump_to_mark")),
+                ("zz", tr!("vim.centre")),
+            ] {
+                list = list.child(
+                    div()
+                        .flex()
+                        .flex_row()
+                        .items_center()
+                        .gap_2()
+                        .py(px(2.0))
+                        .border_b_1()
+                        .border_color(colors.border)
+                        .text_size(px(11.0))
+                        .text_color(colors.text)
+                        .child(div().w(px(KEYS_WIDTH)).child(keys))
+                        .child(div().flex_1().child(description))
+                        .child(
+                            div()
+                                .text_size(px(10.0))
+                                .text_color(colors.text_secondary)
+                                .child(tr!("keymap.preset_vim")),
+                        ),
+                );
+            }
+        }
+
+        let mut presets = div().flex().flex_row().items_center().gap_1().child(
+            div()
+                .w(px(KEYS_WIDTH))
+                .text_size(px(11.0))
+                .text_color(colors.text)
+                .child(tr!("keymap.preset")),
+        );
+        for (option, text) in [
+            (KeymapPreset::Standard, tr!("keymap.preset_standard")),
+            (KeymapPreset::Vim, tr!("keymap.preset_vim")),
+        ] {
+            presets = presets.child(toggle_btn(
+                &text,
+                preset == option,
+                colors,
+                cx.listener(move |this, _event, _window, cx| {
+                    this.set_keymap_preset(option, cx);
+                }),
+            ));
+        }
+
+        let mut body = div().flex().flex_col().gap_2().child(presets).child(
+            div()
+                .text_size(px(10.0))
+                .text_color(colors.text_secondary)
+                .child(tr!(
+                    "keymap.file_hint",
+                    "file" => get_keymap_path().display()
+                )),
+        );
+        for error in &self.keymap.errors {
+            body = body.child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(rgb(ERROR_COLOR))
+                    .child(error.clone()),
+            );
+        }
+
+        body = body
+            .child(
+                div()
+                    .id("keyboard-shortcuts")
+                    .h(px(LIST_HEIGHT))
+                    .overflow_y_scroll()
+                    .child(list),
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .gap_1()
+                    .child(div().flex_1())
+                    .child(toolbar_btn(
+                        &tr!("keymap.reload"),
+                        colors,
+                        cx.listener(|this, _event, _window, cx| {
+                            this.load_keymap(cx);
+                            cx.notify();
+                        }),
+                    ))
+                    .child(toolbar_btn(
+                        &tr!("keymap.close"),
+                        colors,
+                        cx.listener(|this, _event, _window, cx| {
+                            this.close_dialog(cx);
+                        }),
+                    )),
+            );
+
+        (tr!("keymap.title"), body.into_any_element())
+    }
+}
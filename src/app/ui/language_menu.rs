@@ -0,0 +1,64 @@
+use crate::app::{TITLEBAR_HEIGHT, TOOLBAR_HEIGHT};
+use crate::i18n::Language;
+use crate::theme::ThemeColors;
+use gpui::*;
+
+use super::super::PdfReaderApp;
+
+const MENU_WIDTH: f32 = 180.0;
+
+impl PdfReaderApp {
+    /// Languages below the toolbar's flag button, each named in itself
+    pub(super) fn render_language_menu(
+        &self,
+        colors: ThemeColors,
+        cx: &mut Context<Self>,
+    ) -> Option<AnyElement> {
+        if !self.language_menu_open {
+            return None;
+        }
+        let current = self.state.get_language();
+
+        let mut list = div()
+            .absolute()
+            .right(px(8.0))
+            .top(px(TITLEBAR_HEIGHT + TOOLBAR_HEIGHT))
+            .w(px(MENU_WIDTH))
+            .flex()
+            .flex_col()
+            .py_1()
+            .rounded_md()
+            .shadow_lg()
+            .bg(colors.background)
+            .border_1()
+            .border_color(colors.border)
+            .occlude();
+        for language in Language::all() {
+            let mut row = div()
+                .flex()
+                .flex_row()
+                .items_center()
+                .gap_2()
+                .px_2()
+                .py(px(3.0))
+                .cursor_pointer()
+                .text_size(px(11.0))
+                .text_color(colors.text);
+            if language == current {
+                row = row.bg(colors.background_tertiary);
+            }
+            list = list.child(
+                row.hover(|row| row.bg(colors.background_tertiary))
+                    .child(language.flag())
+                    .child(language.name())
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(move |this, _event, _window, cx| {
+                            this.select_language(language.clone(), cx);
+                        }),
+                    ),
+            );
+        }
+        Some(list.into_any_element())
+    }
+}
@@ -0,0 +1,144 @@
+use crate::app::merge::MergeDialog;
+use crate::app::widgets::toolbar_btn_enabled;
+use crate::theme::ThemeColors;
+use crate::tr;
+use gpui::*;
+
+use super::super::PdfReaderApp;
+
+impl PdfReaderApp {
+    /// Title and body of the merge dialog
+    pub(super) fn render_merge_dialog(
+        &self,
+        dialog: &MergeDialog,
+        colors: ThemeColors,
+        cx: &mut Context<Self>,
+    ) -> (String, AnyElement) {
+        let file_count = dialog.files.len();
+
+        let mut list = div().flex().flex_col().gap_1();
+        if dialog.files.is_empty() {
+            list = list.child(
+                div()
+                    .py_2()
+                    .text_size(px(11.0))
+                    .text_color(colors.text_secondary)
+                    .child(tr!("merge.no_files")),
+            );
+        }
+
+        for (index, file) in dialog.files.iter().enumerate() {
+            let file_name = file
+                .path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| file.path.display().to_string());
+            let focused = dialog.focused == Some(index);
+
+            list = list.child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_1()
+                    .child(
+                        div()
+                            .w(px(20.0))
+                            .text_size(px(11.0))
+                            .text_color(colors.text_secondary)
+                            .child(format!("{}.", index + 1)),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .overflow_hidden()
+                            .text_size(px(11.0))
+                            .text_color(colors.text)
+                            .text_ellipsis()
+                            .child(file_name),
+                    )
+                    .child(
+                        div()
+                            .w(px(120.0))
+                            .cursor_text()
+                            .child(file.pages.render(&tr!("merge.all_pages"), focused, colors))
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(move |this, _event, _window, cx| {
+                                    this.update_merge_dialog(cx, |d| d.focused = Some(index));
+                                }),
+                            ),
+                    )
+                    .child(toolbar_btn_enabled(
+                        "▲",
+                        index > 0,
+                        colors,
+                        cx.listener(move |this, _event, _window, cx| {
+                            this.update_merge_dialog(cx, |d| d.move_file(index, true));
+                        }),
+                    ))
+                    .child(toolbar_btn_enabled(
+                        "▼",
+                        index + 1 < file_count,
+                        colors,
+                        cx.listener(move |this, _event, _window, cx| {
+                            this.update_merge_dialog(cx, |d| d.move_file(index, false));
+                        }),
+                    ))
+                    .child(toolbar_btn_enabled(
+                        "✕",
+                        true,
+                        colors,
+                        cx.listener(move |this, _event, _window, cx| {
+                            this.update_merge_dialog(cx, |d| d.remove_file(index));
+                        }),
+                    )),
+            );
+        }
+
+        let body = div()
+            .flex()
+            .flex_col()
+            .gap_3()
+            .child(list)
+            .child(
+                div()
+                    .text_size(px(10.0))
+                    .text_color(colors.text_secondary)
+                    .child(tr!("merge.range_hint")),
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .gap_1()
+                    .child(toolbar_btn_enabled(
+                        &tr!("merge.add_files"),
+                        true,
+                        colors,
+                        cx.listener(|this, _event, _window, cx| {
+                            this.merge_add_files(cx);
+                        }),
+                    ))
+                    .child(div().flex_1())
+                    .child(toolbar_btn_enabled(
+                        &tr!("dialog.cancel"),
+                        true,
+                        colors,
+                        cx.listener(|this, _event, _window, cx| {
+                            this.close_dialog(cx);
+                        }),
+                    ))
+                    .child(toolbar_btn_enabled(
+                        &tr!("merge.merge"),
+                        file_count > 0,
+                        colors,
+                        cx.listener(|this, _event, _window, cx| {
+                            this.run_merge(cx);
+                        }),
+                    )),
+            );
+
+        (tr!("merge.title"), body.into_any_element())
+    }
+}
@@ -1,32 +1,55 @@
+pub mod command_palette;
+pub mod dialog;
+pub mod image_export;
+pub mod keymap;
+pub mod language_menu;
+pub mod merge;
+pub mod organizer;
 pub mod pdf_view;
+pub mod preferences;
+pub mod print_dialog;
+pub mod print_jobs;
 pub mod sidebar;
+pub mod split;
 pub mod status_bar;
 pub mod titlebar;
 pub mod toolbar;
 
 use crate::app::shortcuts;
-use crate::theme::ThemeColors;
 use gpui::*;
 
 use super::PdfReaderApp;
 
+/// Border shown around the place something dragged would land
+pub const DROP_TARGET_COLOR: u32 = 0x3399FF;
+
 impl PdfReaderApp {
     pub(super) fn render_ui(
         &mut self,
-        _window: &mut Window,
+        window: &mut Window,
         cx: &mut Context<Self>,
     ) -> impl IntoElement {
-        let theme = self.state.get_theme();
-        let colors = ThemeColors::for_theme(theme);
+        let colors = self.state.theme_colors(window.appearance());
         let tabs = self.state.get_all_tabs();
         let active_tab_id = self.state.get_active_tab_id();
 
+        let dialog = self.render_dialog(colors, cx);
+        let language_menu = self.render_language_menu(colors, cx);
+        let print_jobs = self.render_print_jobs_popover(colors, cx);
+
         div()
             .size_full()
+            .relative()
             .flex()
             .flex_col()
             .bg(colors.background)
             .track_focus(&self.focus_handle)
+            .drag_over::<ExternalPaths>(|style, _, _, _| {
+                style.border_2().border_color(rgb(DROP_TARGET_COLOR))
+            })
+            .on_drop(cx.listener(|this, paths: &ExternalPaths, _window, cx| {
+                this.open_dropped_paths(paths.paths(), cx);
+            }))
             .child(self.render_combined_titlebar(&tabs, active_tab_id, colors, cx))
             .child({
                 let has_doc = active_tab_id.is_some();
@@ -44,9 +67,17 @@ impl PdfReaderApp {
                     } else {
                         div().into_any_element()
                     })
-                    .child(self.render_pdf_view(active_tab_id, colors, cx)),
+                    .child(if self.organizer_is_active() {
+                        self.render_page_organizer(colors, cx).into_any_element()
+                    } else {
+                        self.render_pdf_view(active_tab_id, colors, cx)
+                            .into_any_element()
+                    }),
             )
             .child(self.render_status_bar(active_tab_id, colors, cx))
+            .children(print_jobs)
+            .children(language_menu)
+            .children(dialog)
             .on_key_down(cx.listener(|this, event: &KeyDownEvent, window, cx| {
                 shortcuts::handle_key_down_event(this, event, window, cx);
             }))
@@ -0,0 +1,228 @@
+use crate::app::organizer::{DraggedPage, OrganizerEntry, THUMBNAIL_HEIGHT};
+use crate::app::widgets::toolbar_btn_enabled;
+use crate::theme::ThemeColors;
+use crate::tr;
+use gpui::*;
+
+use super::super::PdfReaderApp;
+use super::DROP_TARGET_COLOR;
+
+/// Small badge following the cursor while a page is dragged
+struct DraggedPageView {
+    label: usize,
+}
+
+impl Render for DraggedPageView {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .px_2()
+            .py_1()
+            .rounded_sm()
+            .bg(rgb(DROP_TARGET_COLOR))
+            .text_size(px(11.0))
+            .text_color(rgb(0xffffff))
+            .child(format!("{} {}", tr!("page.label"), self.label))
+    }
+}
+
+impl PdfReaderApp {
+    pub(super) fn render_page_organizer(
+        &self,
+        colors: ThemeColors,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let Some(organizer) = self.organizer.as_ref() else {
+            return div().flex_1().into_any_element();
+        };
+        let has_selection = organizer.selected.is_some();
+
+        let actions = div()
+            .h(px(28.0))
+            .w_full()
+            .flex()
+            .flex_row()
+            .items_center()
+            .px_2()
+            .gap_1()
+            .bg(colors.background_secondary)
+            .border_b_1()
+            .border_color(colors.border)
+            .child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(colors.text)
+                    .mr_2()
+                    .child(tr!("organizer.title")),
+            )
+            .child(toolbar_btn_enabled(
+                "↺",
+                has_selection,
+                colors,
+                cx.listener(|this, _event, _window, cx| {
+                    this.update_page_organizer(cx, |o| o.rotate_selected(270));
+                }),
+            ))
+            .child(toolbar_btn_enabled(
+                "↻",
+                has_selection,
+                colors,
+                cx.listener(|this, _event, _window, cx| {
+                    this.update_page_organizer(cx, |o| o.rotate_selected(90));
+                }),
+            ))
+            .child(div().w(px(4.0)))
+            .child(toolbar_btn_enabled(
+                &tr!("organizer.duplicate"),
+                has_selection,
+                colors,
+                cx.listener(|this, _event, _window, cx| {
+                    this.update_page_organizer(cx, |o| o.duplicate_selected());
+                }),
+            ))
+            .child(toolbar_btn_enabled(
+                &tr!("organizer.delete"),
+                has_selection,
+                colors,
+                cx.listener(|this, _event, _window, cx| {
+                    this.update_page_organizer(cx, |o| o.delete_selected());
+                }),
+            ))
+            .child(div().w(px(4.0)))
+            .child(toolbar_btn_enabled(
+                &tr!("organizer.insert_blank"),
+                true,
+                colors,
+                cx.listener(|this, _event, _window, cx| {
+                    this.update_page_organizer(cx, |o| o.insert_blank());
+                }),
+            ))
+            .child(toolbar_btn_enabled(
+                &tr!("organizer.insert_file"),
+                true,
+                colors,
+                cx.listener(|this, _event, _window, cx| {
+                    this.organizer_insert_from_file(cx);
+                }),
+            ))
+            .child(div().flex_1())
+            .child(
+                div()
+                    .text_size(px(10.0))
+                    .text_color(colors.text_secondary)
+                    .mr_2()
+                    .child(tr!("organizer.page_count", "count" => organizer.entries.len())),
+            )
+            .child(toolbar_btn_enabled(
+                &tr!("organizer.save"),
+                !organizer.entries.is_empty(),
+                colors,
+                cx.listener(|this, _event, _window, cx| {
+                    this.save_organized_pages(cx);
+                }),
+            ))
+            .child(toolbar_btn_enabled(
+                &tr!("organizer.close"),
+                true,
+                colors,
+                cx.listener(|this, _event, _window, cx| {
+                    this.close_page_organizer(cx);
+                }),
+            ));
+
+        let mut grid = div().flex().flex_row().flex_wrap().gap_4().p_4();
+        for (index, entry) in organizer.entries.iter().enumerate() {
+            let selected = organizer.selected == Some(index);
+            grid = grid.child(self.render_organizer_entry(index, entry, selected, colors, cx));
+        }
+
+        div()
+            .flex_1()
+            .h_full()
+            .flex()
+            .flex_col()
+            .bg(colors.pdf_view)
+            .child(actions)
+            .child(
+                div()
+                    .id("page-organizer-grid")
+                    .flex_1()
+                    .overflow_y_scroll()
+                    .child(grid),
+            )
+            .into_any_element()
+    }
+
+    fn render_organizer_entry(
+        &self,
+        index: usize,
+        entry: &OrganizerEntry,
+        selected: bool,
+        colors: ThemeColors,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let (width, height) = entry.display_size();
+        let id = entry.id;
+
+        let preview = match &entry.thumbnail {
+            Some(image) => img(image.clone())
+                .block()
+                .w(px(width))
+                .h(px(height))
+                .into_any_element(),
+            None => div()
+                .w(px(width))
+                .h(px(height))
+                .bg(rgb(0xffffff))
+                .into_any_element(),
+        };
+
+        div()
+            .id(("organizer-page", id))
+            .flex()
+            .flex_col()
+            .items_center()
+            .gap_1()
+            .p_1()
+            .rounded_sm()
+            .border_2()
+            .border_color(if selected {
+                rgb(DROP_TARGET_COLOR)
+            } else {
+                colors.pdf_view
+            })
+            .cursor_pointer()
+            .child(
+                div()
+                    .h(px(THUMBNAIL_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .justify_center()
+                    .child(div().shadow_md().child(preview)),
+            )
+            .child(
+                div()
+                    .text_size(px(10.0))
+                    .text_color(colors.text)
+                    .child((index + 1).to_string()),
+            )
+            .on_click(cx.listener(move |this, _event, _window, cx| {
+                this.update_page_organizer(cx, |o| o.selected = Some(index));
+            }))
+            .on_drag(
+                DraggedPage {
+                    id,
+                    label: index + 1,
+                },
+                |dragged, _offset, _window, cx| {
+                    let label = dragged.label;
+                    cx.new(|_| DraggedPageView { label })
+                },
+            )
+            .drag_over::<DraggedPage>(|style, _, _, _| style.border_color(rgb(DROP_TARGET_COLOR)))
+            .on_drop(
+                cx.listener(move |this, dragged: &DraggedPage, _window, cx| {
+                    this.update_page_organizer(cx, |o| o.move_page(dragged.id, id));
+                }),
+            )
+    }
+}
@@ -1,5 +1,6 @@
 use crate::app::state::{ScrollMode, SelectionMode};
 use crate::app::tabs::SelectionRegion;
+use crate::app::text_selection::SelectionUnit;
 use crate::app::{STATUS_BAR_HEIGHT, TOOLBAR_HEIGHT};
 use crate::theme::ThemeColors;
 use crate::tr;
@@ -43,6 +44,60 @@ fn window_to_image_coords(
 }
 
 impl PdfReaderApp {
+    /// Begin a drag selection at (x, y), or select the word on a double click,
+    /// the line on a triple click, or extend the selection on a shift click.
+    /// A shift click may extend a selection started on another page.
+    fn start_text_selection(
+        &mut self,
+        event: &MouseDownEvent,
+        x: f32,
+        y: f32,
+        cx: &mut Context<Self>,
+    ) {
+        match event.click_count {
+            2 => {
+                self.is_selecting = false;
+                self.select_unit_at(SelectionUnit::Word, x, y, cx);
+            }
+            n if n >= 3 => {
+                self.is_selecting = false;
+                self.select_unit_at(SelectionUnit::Line, x, y, cx);
+            }
+            _ => {
+                let position = self.text_position_at(x, y);
+                match (self.selection_anchor, position) {
+                    (Some(anchor), Some(focus)) if event.modifiers.shift => {
+                        self.is_selecting = true;
+                        self.set_text_selection(anchor, focus, cx);
+                    }
+                    _ => {
+                        self.clear_selection(cx);
+                        self.is_selecting = position.is_some();
+                        self.selection_anchor = position;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Move the end of a drag selection to (x, y)
+    fn extend_text_selection(&mut self, x: f32, y: f32, cx: &mut Context<Self>) {
+        if let (Some(anchor), Some(focus)) = (self.selection_anchor, self.text_position_at(x, y)) {
+            self.set_text_selection(anchor, focus, cx);
+        }
+    }
+
+    /// Finish a drag selection and copy it, as raw text when `raw` is set
+    fn end_text_selection(&mut self, raw: bool, cx: &mut Context<Self>) {
+        self.is_selecting = false;
+        self.drag_page_texts.clear();
+        if raw {
+            self.copy_raw_text(cx);
+        } else {
+            self.copy_selected_text(cx);
+        }
+    }
+
     pub(super) fn render_pdf_view(
         &self,
         active_tab_id: Option<usize>,
@@ -136,7 +191,7 @@ impl PdfReaderApp {
         colors: ThemeColors,
         cx: &mut Context<Self>,
     ) -> impl IntoElement {
-        let selection_color = gpui::rgba(0x3399FF80);
+        let selection_color = colors.selection;
         let selection_mode = self.state.get_selection_mode();
         let is_text_select_mode = selection_mode == SelectionMode::TextSelect;
 
@@ -149,10 +204,10 @@ impl PdfReaderApp {
             .id("pdf-page-container")
             .w(px(width as f32))
             .h(px(height as f32))
-            .cursor(if is_text_select_mode {
-                CursorStyle::IBeam
-            } else {
-                CursorStyle::PointingHand
+            .cursor(match selection_mode {
+                SelectionMode::Hand => CursorStyle::PointingHand,
+                SelectionMode::TextSelect => CursorStyle::IBeam,
+                SelectionMode::Snapshot => CursorStyle::Crosshair,
             })
             .child(
                 img(render_image.clone())
@@ -176,6 +231,21 @@ impl PdfReaderApp {
             );
         }
 
+        if let Some(drag) = &self.snapshot_drag {
+            let region = drag.region();
+            image_container = image_container.child(
+                div()
+                    .absolute()
+                    .left(px(region.x))
+                    .top(px(region.y))
+                    .w(px(region.width))
+                    .h(px(region.height))
+                    .bg(gpui::rgba(0x3399FF20))
+                    .border_1()
+                    .border_color(gpui::rgb(0x3399FF)),
+            );
+        }
+
         if is_text_select_mode {
             image_container = image_container.child(
                 div()
@@ -185,7 +255,6 @@ impl PdfReaderApp {
                     .on_mouse_down(
                         MouseButton::Left,
                         cx.listener(move |this, event: &MouseDownEvent, window, cx| {
-                            this.is_selecting = true;
                             let x: f32 = event.position.x.into();
                             let y: f32 = event.position.y.into();
 
@@ -203,9 +272,7 @@ impl PdfReaderApp {
                                 show_sidebar,
                             );
 
-                            this.selection_start = Some((rel_x, rel_y));
-                            this.selection_end = Some((rel_x, rel_y));
-                            this.clear_selection(cx);
+                            this.start_text_selection(event, rel_x, rel_y, cx);
                         }),
                     )
                     .on_mouse_move(
@@ -228,23 +295,83 @@ impl PdfReaderApp {
                                     show_sidebar,
                                 );
 
-                                this.selection_end = Some((rel_x, rel_y));
-                                if let (Some(start), Some(end)) =
-                                    (this.selection_start, this.selection_end)
-                                {
-                                    this.update_text_selection(start.0, start.1, end.0, end.1, cx);
-                                }
+                                this.extend_text_selection(rel_x, rel_y, cx);
                             }
                         }),
                     )
                     .on_mouse_up(
                         MouseButton::Left,
-                        cx.listener(|this, _event: &MouseUpEvent, _window, cx| {
+                        cx.listener(|this, event: &MouseUpEvent, _window, cx| {
                             if this.is_selecting {
-                                this.is_selecting = false;
-                                this.copy_selected_text(cx);
+                                this.end_text_selection(event.modifiers.alt, cx);
+                            }
+                        }),
+                    ),
+            );
+        }
+
+        if selection_mode == SelectionMode::Snapshot {
+            image_container = image_container.child(
+                div()
+                    .absolute()
+                    .inset_0()
+                    .cursor(CursorStyle::Crosshair)
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(move |this, event: &MouseDownEvent, window, cx| {
+                            let x: f32 = event.position.x.into();
+                            let y: f32 = event.position.y.into();
+
+                            let viewport = window.viewport_size();
+                            let viewport_width: f32 = viewport.width.into();
+                            let viewport_height: f32 = viewport.height.into();
+
+                            let (rel_x, rel_y) = window_to_image_coords(
+                                x,
+                                y,
+                                viewport_width,
+                                viewport_height,
+                                image_width,
+                                image_height,
+                                show_sidebar,
+                            );
+
+                            this.start_snapshot(rel_x, rel_y, cx);
+                        }),
+                    )
+                    .on_mouse_move(
+                        cx.listener(move |this, event: &MouseMoveEvent, window, cx| {
+                            if this.snapshot_drag.is_some() {
+                                let x: f32 = event.position.x.into();
+                                let y: f32 = event.position.y.into();
+
+                                let viewport = window.viewport_size();
+                                let viewport_width: f32 = viewport.width.into();
+                                let viewport_height: f32 = viewport.height.into();
+
+                                let (rel_x, rel_y) = window_to_image_coords(
+                                    x,
+                                    y,
+                                    viewport_width,
+                                    viewport_height,
+                                    image_width,
+                                    image_height,
+                                    show_sidebar,
+                                );
+
+                                this.extend_snapshot(
+                                    rel_x.clamp(0.0, image_width as f32),
+                                    rel_y.clamp(0.0, image_height as f32),
+                                    cx,
+                                );
                             }
                         }),
+                    )
+                    .on_mouse_up(
+                        MouseButton::Left,
+                        cx.listener(|this, _event: &MouseUpEvent, _window, cx| {
+                            this.finish_snapshot(cx);
+                        }),
                     ),
             );
         }
@@ -289,24 +416,34 @@ impl PdfReaderApp {
         _tab_id: usize,
         render_image: Arc<RenderImage>,
         width: u32,
-        _height: u32,
+        height: u32,
         selection_regions: Vec<SelectionRegion>,
         colors: ThemeColors,
         cx: &mut Context<Self>,
     ) -> impl IntoElement {
-        let selection_color = gpui::rgba(0x3399FF80);
+        let selection_color = colors.selection;
         let selection_mode = self.state.get_selection_mode();
         let is_text_select_mode = selection_mode == SelectionMode::TextSelect;
 
         let mut image_container = div()
             .relative()
             .id("pdf-page-container")
-            .cursor(if is_text_select_mode {
-                CursorStyle::IBeam
-            } else {
-                CursorStyle::PointingHand
+            .cursor(match selection_mode {
+                SelectionMode::Hand => CursorStyle::PointingHand,
+                SelectionMode::TextSelect => CursorStyle::IBeam,
+                SelectionMode::Snapshot => CursorStyle::Crosshair,
             })
-            .child(img(render_image.clone()).block().max_w(px(width as f32)));
+            .child(img(render_image.clone()).block().max_w(px(width as f32)))
+            .child({
+                // Record where the page is painted so mouse positions can be made relative to it
+                let page_origin = self.page_origin.clone();
+                canvas(
+                    move |bounds, _window, _cx| page_origin.set(bounds.origin),
+                    |_bounds, _state, _window, _cx| {},
+                )
+                .absolute()
+                .size_full()
+            });
 
         // Render all selection regions
         for region in &selection_regions {
@@ -323,6 +460,21 @@ impl PdfReaderApp {
             );
         }
 
+        if let Some(drag) = &self.snapshot_drag {
+            let region = drag.region();
+            image_container = image_container.child(
+                div()
+                    .absolute()
+                    .left(px(region.x))
+                    .top(px(region.y))
+                    .w(px(region.width))
+                    .h(px(region.height))
+                    .bg(gpui::rgba(0x3399FF20))
+                    .border_1()
+                    .border_color(gpui::rgb(0x3399FF)),
+            );
+        }
+
         if is_text_select_mode {
             image_container = image_container.child(
                 div()
@@ -332,47 +484,84 @@ impl PdfReaderApp {
                     .on_mouse_down(
                         MouseButton::Left,
                         cx.listener(|this, event: &MouseDownEvent, _window, cx| {
-                            this.is_selecting = true;
-                            let x: f32 = event.position.x.into();
-                            let y: f32 = event.position.y.into();
-                            this.selection_start = Some((x, y));
-                            this.selection_end = Some((x, y));
-                            this.clear_selection(cx);
+                            let origin = this.page_origin.get();
+                            let x: f32 = (event.position.x - origin.x).into();
+                            let y: f32 = (event.position.y - origin.y).into();
+                            this.start_text_selection(event, x, y, cx);
                         }),
                     )
                     .on_mouse_move(cx.listener(|this, event: &MouseMoveEvent, _window, cx| {
                         if this.is_selecting {
-                            let x: f32 = event.position.x.into();
-                            let y: f32 = event.position.y.into();
-                            this.selection_end = Some((x, y));
-                            if let (Some(start), Some(end)) =
-                                (this.selection_start, this.selection_end)
-                            {
-                                this.update_text_selection(start.0, start.1, end.0, end.1, cx);
-                            }
+                            let origin = this.page_origin.get();
+                            let x: f32 = (event.position.x - origin.x).into();
+                            let y: f32 = (event.position.y - origin.y).into();
+                            this.extend_text_selection(x, y, cx);
                         }
                     }))
                     .on_mouse_up(
                         MouseButton::Left,
-                        cx.listener(|this, _event: &MouseUpEvent, _window, cx| {
+                        cx.listener(|this, event: &MouseUpEvent, _window, cx| {
                             if this.is_selecting {
-                                this.is_selecting = false;
-                                this.copy_selected_text(cx);
+                                this.end_text_selection(event.modifiers.alt, cx);
                             }
                         }),
                     ),
             );
         }
 
-        div().flex_1().overflow_hidden().bg(colors.pdf_view).child(
-            div()
-                .flex_1()
-                .flex()
-                .flex_col()
-                .items_center()
-                .p_4()
-                .gap_4()
-                .child(image_container),
-        )
+        if selection_mode == SelectionMode::Snapshot {
+            image_container = image_container.child(
+                div()
+                    .absolute()
+                    .inset_0()
+                    .cursor(CursorStyle::Crosshair)
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(|this, event: &MouseDownEvent, _window, cx| {
+                            let origin = this.page_origin.get();
+                            let x: f32 = (event.position.x - origin.x).into();
+                            let y: f32 = (event.position.y - origin.y).into();
+                            this.start_snapshot(x, y, cx);
+                        }),
+                    )
+                    .on_mouse_move(
+                        cx.listener(move |this, event: &MouseMoveEvent, _window, cx| {
+                            if this.snapshot_drag.is_some() {
+                                let origin = this.page_origin.get();
+                                let x: f32 = (event.position.x - origin.x).into();
+                                let y: f32 = (event.position.y - origin.y).into();
+                                this.extend_snapshot(
+                                    x.clamp(0.0, width as f32),
+                                    y.clamp(0.0, height as f32),
+                                    cx,
+                                );
+                            }
+                        }),
+                    )
+                    .on_mouse_up(
+                        MouseButton::Left,
+                        cx.listener(|this, _event: &MouseUpEvent, _window, cx| {
+                            this.finish_snapshot(cx);
+                        }),
+                    ),
+            );
+        }
+
+        div()
+            .id("pdf-smooth-view")
+            .flex_1()
+            .overflow_y_scroll()
+            .track_scroll(&self.page_scroll)
+            .bg(colors.pdf_view)
+            .child(
+                div()
+                    .flex_1()
+                    .flex()
+                    .flex_col()
+                    .items_center()
+                    .p_4()
+                    .gap_4()
+                    .child(image_container),
+            )
     }
 }
@@ -0,0 +1,219 @@
+use crate::app::preferences::{PreferencesDialog, PreferencesField};
+use crate::app::state::{ScrollMode, SelectionMode, StartupBehaviour};
+use crate::app::text_input::TextInput;
+use crate::app::themes::theme_name;
+use crate::app::widgets::{toggle_btn, toolbar_btn};
+use crate::i18n::Language;
+use crate::theme::{user_theme_names, Theme, ThemeColors};
+use crate::tr;
+use gpui::*;
+
+use super::super::PdfReaderApp;
+
+const ERROR_COLOR: u32 = 0xD93025;
+const LABEL_WIDTH: f32 = 130.0;
+
+impl PdfReaderApp {
+    /// Title and body of the preferences dialog
+    pub(super) fn render_preferences_dialog(
+        &self,
+        dialog: &PreferencesDialog,
+        colors: ThemeColors,
+        cx: &mut Context<Self>,
+    ) -> (String, AnyElement) {
+        let label = |text: String| {
+            div()
+                .w(px(LABEL_WIDTH))
+                .flex_none()
+                .text_size(px(11.0))
+                .text_color(colors.text)
+                .child(text)
+        };
+        let row = |text: String| {
+            div()
+                .flex()
+                .flex_row()
+                .flex_wrap()
+                .items_center()
+                .gap_1()
+                .child(label(text))
+        };
+        let input_row = |text: String, input: &TextInput, unit: &str, field: PreferencesField| {
+            row(text)
+                .child(
+                    div()
+                        .w(px(80.0))
+                        .cursor_text()
+                        .child(input.render("", dialog.focused == field, colors))
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |this, _event, _window, cx| {
+                                this.update_preferences_dialog(cx, |d| d.focused = field);
+                            }),
+                        ),
+                )
+                .child(
+                    div()
+                        .text_size(px(11.0))
+                        .text_color(colors.text_secondary)
+                        .child(unit.to_string()),
+                )
+        };
+
+        let mut themes = vec![Theme::Light, Theme::Dark, Theme::System];
+        themes.extend(user_theme_names().into_iter().map(Theme::Custom));
+        let mut theme_row = row(tr!("preferences.theme"));
+        for theme in themes {
+            theme_row = theme_row.child(toggle_btn(
+                &theme_name(&theme),
+                dialog.theme == theme,
+                colors,
+                cx.listener(move |this, _event, _window, cx| {
+                    let theme = theme.clone();
+                    this.update_preferences_dialog(cx, |d| d.theme = theme);
+                }),
+            ));
+        }
+
+        let mut language_row = row(tr!("menu.language"));
+        for language in Language::all() {
+            language_row = language_row.child(toggle_btn(
+                &language.name(),
+                dialog.language == language,
+                colors,
+                cx.listener(move |this, _event, _window, cx| {
+                    let language = language.clone();
+                    this.update_preferences_dialog(cx, |d| d.language = language);
+                }),
+            ));
+        }
+
+        let scroll_modes = [
+            (ScrollMode::Page, tr!("scroll_mode.page")),
+            (ScrollMode::Smooth, tr!("scroll_mode.smooth")),
+        ];
+        let mut scroll_row = row(tr!("preferences.scroll_mode"));
+        for (mode, text) in scroll_modes.clone() {
+            scroll_row = scroll_row.child(toggle_btn(
+                &text,
+                dialog.scroll_mode == mode,
+                colors,
+                cx.listener(move |this, _event, _window, cx| {
+                    this.update_preferences_dialog(cx, |d| d.scroll_mode = mode);
+                }),
+            ));
+        }
+        let mut default_scroll_row = row(tr!("preferences.default_scroll_mode"));
+        let default_scroll_modes = std::iter::once((None, tr!("preferences.last_used")))
+            .chain(scroll_modes.map(|(mode, text)| (Some(mode), text)));
+        for (mode, text) in default_scroll_modes {
+            default_scroll_row = default_scroll_row.child(toggle_btn(
+                &text,
+                dialog.default_scroll_mode == mode,
+                colors,
+                cx.listener(move |this, _event, _window, cx| {
+                    this.update_preferences_dialog(cx, |d| d.default_scroll_mode = mode);
+                }),
+            ));
+        }
+
+        let mut selection_row = row(tr!("preferences.selection_mode"));
+        for (mode, text) in [
+            (SelectionMode::Hand, tr!("preferences.selection_hand")),
+            (SelectionMode::TextSelect, tr!("preferences.selection_text")),
+            (
+                SelectionMode::Snapshot,
+                tr!("preferences.selection_snapshot"),
+            ),
+        ] {
+            selection_row = selection_row.child(toggle_btn(
+                &text,
+                dialog.selection_mode == mode,
+                colors,
+                cx.listener(move |this, _event, _window, cx| {
+                    this.update_preferences_dialog(cx, |d| d.selection_mode = mode);
+                }),
+            ));
+        }
+
+        let mut startup_row = row(tr!("preferences.startup"));
+        for (startup, text) in [
+            (StartupBehaviour::Empty, tr!("preferences.startup_empty")),
+            (
+                StartupBehaviour::LastFile,
+                tr!("preferences.startup_last_file"),
+            ),
+        ] {
+            startup_row = startup_row.child(toggle_btn(
+                &text,
+                dialog.startup == startup,
+                colors,
+                cx.listener(move |this, _event, _window, cx| {
+                    this.update_preferences_dialog(cx, |d| d.startup = startup);
+                }),
+            ));
+        }
+
+        let mut body = div()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .child(theme_row)
+            .child(language_row)
+            .child(scroll_row)
+            .child(default_scroll_row)
+            .child(selection_row)
+            .child(input_row(
+                tr!("preferences.default_zoom"),
+                &dialog.default_zoom,
+                "%",
+                PreferencesField::DefaultZoom,
+            ))
+            .child(input_row(
+                tr!("preferences.render_dpi"),
+                &dialog.render_dpi,
+                "dpi",
+                PreferencesField::RenderDpi,
+            ))
+            .child(
+                div()
+                    .text_size(px(10.0))
+                    .text_color(colors.text_secondary)
+                    .child(tr!("preferences.render_dpi_hint")),
+            )
+            .child(startup_row);
+
+        if let Some(error) = &dialog.error {
+            body = body.child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(rgb(ERROR_COLOR))
+                    .child(error.clone()),
+            );
+        }
+
+        body = body.child(
+            div()
+                .flex()
+                .flex_row()
+                .gap_1()
+                .child(div().flex_1())
+                .child(toolbar_btn(
+                    &tr!("dialog.cancel"),
+                    colors,
+                    cx.listener(|this, _event, _window, cx| {
+                        this.close_dialog(cx);
+                    }),
+                ))
+                .child(toolbar_btn(
+                    &tr!("preferences.save"),
+                    colors,
+                    cx.listener(|this, _event, _window, cx| {
+                        this.save_preferences(cx);
+                    }),
+                )),
+        );
+
+        (tr!("preferences.title"), body.into_any_element())
+    }
+}
@@ -0,0 +1,487 @@
+use crate::app::print_dialog::{PrintDialog, PrintField, PREVIEW_HEIGHT, PREVIEW_WIDTH};
+use crate::app::widgets::{toggle_btn, toolbar_btn, toolbar_btn_enabled};
+use crate::print::imposition::{
+    NUp, NUpOrder, Poster, NUP_CHOICES, OVERLAP_CHOICES, POSTER_CHOICES,
+};
+use crate::print::to_file::FileFormat;
+use crate::print::{Imposition, Orientation, PaperSize};
+use crate::theme::ThemeColors;
+use crate::tr;
+use gpui::prelude::FluentBuilder;
+use gpui::*;
+
+use super::super::PdfReaderApp;
+
+const ERROR_COLOR: u32 = 0xD93025;
+
+impl PdfReaderApp {
+    /// Title and body of the print dialog: settings on the left, preview on the right
+    pub(super) fn render_print_dialog(
+        &self,
+        dialog: &PrintDialog,
+        colors: ThemeColors,
+        cx: &mut Context<Self>,
+    ) -> (String, AnyElement) {
+        let label = |text: String| {
+            div()
+                .w(px(90.0))
+                .flex_none()
+                .text_size(px(11.0))
+                .text_color(colors.text)
+                .child(text)
+        };
+        let row = |text: String| {
+            div()
+                .flex()
+                .flex_row()
+                .flex_wrap()
+                .items_center()
+                .gap_1()
+                .child(label(text))
+        };
+        let input = |field: PrintField, placeholder: &str| {
+            div()
+                .flex_1()
+                .cursor_text()
+                .child(
+                    dialog
+                        .input(field)
+                        .render(placeholder, dialog.focused == field, colors),
+                )
+                .on_mouse_down(
+                    MouseButton::Left,
+                    cx.listener(move |this, _event, _window, cx| {
+                        this.update_print_dialog(cx, |d| d.focused = field);
+                    }),
+                )
+        };
+
+        // Printers are listed in the background when the dialog opens
+        let mut printers = row(tr!("print.printer"));
+        match &dialog.printers {
+            None => {
+                printers = printers.child(
+                    div()
+                        .text_size(px(11.0))
+                        .text_color(colors.text_secondary)
+                        .child(tr!("print.loading_printers")),
+                );
+            }
+            Some(list) if list.is_empty() => {
+                printers = printers.child(
+                    div()
+                        .text_size(px(11.0))
+                        .text_color(colors.text_secondary)
+                        .child(tr!("print.no_printers")),
+                );
+            }
+            Some(list) => {
+                for (index, printer) in list.iter().enumerate() {
+                    let name = if printer.is_file_printer() {
+                        tr!("print.to_file")
+                    } else {
+                        printer.name.clone()
+                    };
+                    printers = printers.child(toggle_btn(
+                        &name,
+                        dialog.printer == Some(index),
+                        colors,
+                        cx.listener(move |this, _event, _window, cx| {
+                            this.update_print_dialog(cx, |d| d.printer = Some(index));
+                        }),
+                    ));
+                }
+            }
+        }
+        let resolution = dialog
+            .printer_info()
+            .and_then(|p| p.resolutions.iter().max())
+            .map(|dpi| {
+                div()
+                    .pl(px(94.0))
+                    .text_size(px(10.0))
+                    .text_color(colors.text_secondary)
+                    .child(tr!("print.resolution", "dpi" => dpi))
+            });
+
+        // The "Print to file" printer writes one of several formats
+        let to_file = dialog.printer_info().is_some_and(|p| p.is_file_printer());
+        let mut file_format = row(tr!("print.file_format"));
+        for format in FileFormat::ALL {
+            file_format = file_format.child(toggle_btn(
+                format.name(),
+                dialog.file_format == format,
+                colors,
+                cx.listener(move |this, _event, _window, cx| {
+                    this.update_print_dialog(cx, |d| d.file_format = format);
+                }),
+            ));
+        }
+
+        let pages = div()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .child(row(tr!("print.pages")).child(input(PrintField::Pages, &tr!("print.all_pages"))))
+            .child(
+                div()
+                    .pl(px(94.0))
+                    .text_size(px(10.0))
+                    .text_color(colors.text_secondary)
+                    .child(tr!("print.pages_hint")),
+            );
+        let copies = row(tr!("print.copies")).child(input(PrintField::Copies, "1"));
+
+        // Only the sizes the printer says it takes, when it says
+        let mut paper = row(tr!("print.paper"));
+        for size in PaperSize::ALL
+            .into_iter()
+            .filter(|&size| dialog.printer_info().is_none_or(|p| p.supports_paper(size)))
+        {
+            paper = paper.child(toggle_btn(
+                size.name(),
+                dialog.paper_size == size,
+                colors,
+                cx.listener(move |this, _event, _window, cx| {
+                    this.update_print_dialog(cx, |d| d.paper_size = size);
+                }),
+            ));
+        }
+
+        let mut orientation = row(tr!("print.orientation"));
+        for (value, text) in [
+            (Orientation::Portrait, tr!("print.portrait")),
+            (Orientation::Landscape, tr!("print.landscape")),
+        ] {
+            orientation = orientation.child(toggle_btn(
+                &text,
+                dialog.orientation == value,
+                colors,
+                cx.listener(move |this, _event, _window, cx| {
+                    this.update_print_dialog(cx, |d| d.orientation = value);
+                }),
+            ));
+        }
+
+        // Two-way choices: (label, text when off, text when on, current value, setter)
+        let choice = |text: String,
+                      off: String,
+                      on: String,
+                      value: bool,
+                      set: fn(&mut PrintDialog, bool)| {
+            row(text)
+                .child(toggle_btn(
+                    &off,
+                    !value,
+                    colors,
+                    cx.listener(move |this, _event, _window, cx| {
+                        this.update_print_dialog(cx, |d| set(d, false));
+                    }),
+                ))
+                .child(toggle_btn(
+                    &on,
+                    value,
+                    colors,
+                    cx.listener(move |this, _event, _window, cx| {
+                        this.update_print_dialog(cx, |d| set(d, true));
+                    }),
+                ))
+        };
+        let (supports_duplex, supports_color) = dialog.printer_supports();
+        let sides = choice(
+            tr!("print.sides"),
+            tr!("print.one_sided"),
+            tr!("print.two_sided"),
+            dialog.duplex && supports_duplex,
+            |d, v| d.duplex = v,
+        );
+        let color = choice(
+            tr!("print.color_mode"),
+            tr!("print.grayscale"),
+            tr!("print.color"),
+            dialog.color && supports_color,
+            |d, v| d.color = v,
+        );
+        let scaling = choice(
+            tr!("print.scaling"),
+            tr!("print.actual_size"),
+            tr!("print.fit"),
+            dialog.scale_to_fit,
+            |d, v| d.scale_to_fit = v,
+        );
+
+        // Laying several pages on a sheet, or one page over several sheets
+        let mut layout = row(tr!("print.layout"));
+        for (value, text) in [
+            (Imposition::None, tr!("print.layout_normal")),
+            (Imposition::NUp(NUp::default()), tr!("print.layout_nup")),
+            (Imposition::Booklet, tr!("print.layout_booklet")),
+            (
+                Imposition::Poster(Poster::default()),
+                tr!("print.layout_poster"),
+            ),
+        ] {
+            layout = layout.child(toggle_btn(
+                &text,
+                std::mem::discriminant(&dialog.imposition) == std::mem::discriminant(&value),
+                colors,
+                cx.listener(move |this, _event, _window, cx| {
+                    this.update_print_dialog(cx, |d| {
+                        if std::mem::discriminant(&d.imposition) != std::mem::discriminant(&value) {
+                            d.imposition = value;
+                            d.preview_index = 0;
+                        }
+                    });
+                }),
+            ));
+        }
+
+        let mut layout_options = Vec::new();
+        match dialog.imposition {
+            Imposition::NUp(nup) => {
+                let mut per_sheet = row(tr!("print.pages_per_sheet"));
+                for count in NUP_CHOICES {
+                    per_sheet = per_sheet.child(toggle_btn(
+                        &count.to_string(),
+                        nup.pages_per_sheet == count,
+                        colors,
+                        cx.listener(move |this, _event, _window, cx| {
+                            this.update_print_dialog(cx, |d| {
+                                if let Imposition::NUp(nup) = &mut d.imposition {
+                                    nup.pages_per_sheet = count;
+                                    d.preview_index = 0;
+                                }
+                            });
+                        }),
+                    ));
+                }
+                let mut order = row(tr!("print.order"));
+                for value in NUpOrder::ALL {
+                    let text = match value {
+                        NUpOrder::LeftToRight => tr!("print.order_left_to_right"),
+                        NUpOrder::RightToLeft => tr!("print.order_right_to_left"),
+                        NUpOrder::TopToBottom => tr!("print.order_top_to_bottom"),
+                    };
+                    order = order.child(toggle_btn(
+                        &text,
+                        nup.order == value,
+                        colors,
+                        cx.listener(move |this, _event, _window, cx| {
+                            this.update_print_dialog(cx, |d| {
+                                if let Imposition::NUp(nup) = &mut d.imposition {
+                                    nup.order = value;
+                                }
+                            });
+                        }),
+                    ));
+                }
+                let borders = choice(
+                    tr!("print.borders"),
+                    tr!("print.borders_off"),
+                    tr!("print.borders_on"),
+                    nup.borders,
+                    |d, v| {
+                        if let Imposition::NUp(nup) = &mut d.imposition {
+                            nup.borders = v;
+                        }
+                    },
+                );
+                layout_options.extend([per_sheet, order, borders]);
+            }
+            Imposition::Poster(poster) => {
+                let mut across = row(tr!("print.sheets_across"));
+                for count in POSTER_CHOICES {
+                    across = across.child(toggle_btn(
+                        &count.to_string(),
+                        poster.sheets_across == count,
+                        colors,
+                        cx.listener(move |this, _event, _window, cx| {
+                            this.update_print_dialog(cx, |d| {
+                                if let Imposition::Poster(poster) = &mut d.imposition {
+                                    poster.sheets_across = count;
+                                    d.preview_index = 0;
+                                }
+                            });
+                        }),
+                    ));
+                }
+                let mut overlap = row(tr!("print.overlap"));
+                for mm in OVERLAP_CHOICES {
+                    overlap = overlap.child(toggle_btn(
+                        &tr!("print.millimetres", "mm" => mm),
+                        poster.overlap == mm,
+                        colors,
+                        cx.listener(move |this, _event, _window, cx| {
+                            this.update_print_dialog(cx, |d| {
+                                if let Imposition::Poster(poster) = &mut d.imposition {
+                                    poster.overlap = mm;
+                                }
+                            });
+                        }),
+                    ));
+                }
+                let marks = choice(
+                    tr!("print.marks"),
+                    tr!("print.marks_off"),
+                    tr!("print.marks_on"),
+                    poster.marks,
+                    |d, v| {
+                        if let Imposition::Poster(poster) = &mut d.imposition {
+                            poster.marks = v;
+                        }
+                    },
+                );
+                layout_options.extend([across, overlap, marks]);
+            }
+            Imposition::None | Imposition::Booklet => {}
+        }
+
+        let mut margins = row(tr!("print.margins"));
+        for (field, text) in [
+            (PrintField::MarginTop, tr!("print.top")),
+            (PrintField::MarginRight, tr!("print.right")),
+            (PrintField::MarginBottom, tr!("print.bottom")),
+            (PrintField::MarginLeft, tr!("print.left")),
+        ] {
+            margins = margins.child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_1()
+                    .w(px(70.0))
+                    .child(
+                        div()
+                            .text_size(px(10.0))
+                            .text_color(colors.text_secondary)
+                            .child(text),
+                    )
+                    .child(input(field, "0")),
+            );
+        }
+
+        let mut settings = div()
+            .flex_1()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .child(printers)
+            .children(resolution)
+            .when(to_file, |settings| settings.child(file_format))
+            .child(pages)
+            .child(copies)
+            .child(paper)
+            .child(orientation)
+            .when(supports_duplex, |settings| settings.child(sides))
+            .when(supports_color, |settings| settings.child(color))
+            .child(scaling)
+            .child(layout)
+            .children(layout_options)
+            .child(margins);
+
+        if let Some(error) = &dialog.error {
+            settings = settings.child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(rgb(ERROR_COLOR))
+                    .child(error.clone()),
+            );
+        }
+
+        // Preview of one sheet, with buttons to page through those to be printed
+        let (preview_width, preview_height) = dialog.preview_size;
+        let sheet_count = dialog.sheet_count();
+        let preview_index = dialog.preview_index.min(sheet_count.saturating_sub(1));
+        let sheet = div()
+            .w(px(preview_width))
+            .h(px(preview_height))
+            .border_1()
+            .border_color(colors.border)
+            .shadow_md()
+            .children(
+                dialog
+                    .preview
+                    .clone()
+                    .map(|image| img(image).w(px(preview_width)).h(px(preview_height))),
+            );
+        let preview = div()
+            .w(px(PREVIEW_WIDTH))
+            .flex_none()
+            .flex()
+            .flex_col()
+            .items_center()
+            .gap_2()
+            .child(
+                div()
+                    .h(px(PREVIEW_HEIGHT))
+                    .flex()
+                    .items_center()
+                    .justify_center()
+                    .child(sheet),
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_1()
+                    .child(toolbar_btn(
+                        "◀",
+                        colors,
+                        cx.listener(|this, _event, _window, cx| {
+                            this.step_print_preview(false, cx);
+                        }),
+                    ))
+                    .child(div().text_size(px(11.0)).text_color(colors.text).child(tr!(
+                        "print.preview_sheet",
+                        "page" => preview_index + 1,
+                        "count" => sheet_count
+                    )))
+                    .child(toolbar_btn(
+                        "▶",
+                        colors,
+                        cx.listener(|this, _event, _window, cx| {
+                            this.step_print_preview(true, cx);
+                        }),
+                    )),
+            );
+
+        let buttons = div()
+            .flex()
+            .flex_row()
+            .gap_1()
+            .child(div().flex_1())
+            .child(toolbar_btn_enabled(
+                &tr!("dialog.cancel"),
+                true,
+                colors,
+                cx.listener(|this, _event, _window, cx| {
+                    this.close_dialog(cx);
+                }),
+            ))
+            .child(toolbar_btn_enabled(
+                &tr!("print.print"),
+                dialog.printers.as_ref().is_some_and(|p| !p.is_empty()),
+                colors,
+                cx.listener(|this, _event, _window, cx| {
+                    this.run_print(cx);
+                }),
+            ));
+
+        let body = div()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .gap_3()
+                    .child(settings)
+                    .child(preview),
+            )
+            .child(buttons);
+
+        (tr!("print.title"), body.into_any_element())
+    }
+}
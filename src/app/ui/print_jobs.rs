@@ -0,0 +1,213 @@
+use crate::app::print_jobs::{JobPhase, PrintJob};
+use crate::app::widgets::toolbar_btn;
+use crate::app::STATUS_BAR_HEIGHT;
+use crate::theme::ThemeColors;
+use crate::tr;
+use gpui::*;
+
+use super::super::PdfReaderApp;
+use super::status_bar::{PROGRESS_BAR_COLOR, PROGRESS_BAR_WIDTH};
+
+const POPOVER_WIDTH: f32 = 320.0;
+const ERROR_COLOR: u32 = 0xD93025;
+
+impl PdfReaderApp {
+    /// Printer icon in the status bar with the newest running job's state;
+    /// opens the jobs popover
+    pub(super) fn render_print_jobs_indicator(
+        &self,
+        colors: ThemeColors,
+        cx: &mut Context<Self>,
+    ) -> Option<AnyElement> {
+        if self.print_jobs.jobs.is_empty() {
+            return None;
+        }
+        let text = match self.print_jobs.latest_active() {
+            Some(job) => match self.print_jobs.active_count() {
+                1 => format!("🖨 {}", job.status_text()),
+                count => format!("🖨 {} · {}", count, job.status_text()),
+            },
+            None => "🖨".to_string(),
+        };
+
+        Some(
+            div()
+                .cursor_pointer()
+                .text_size(px(10.0))
+                .text_color(colors.text)
+                .child(text)
+                .on_mouse_down(
+                    MouseButton::Left,
+                    cx.listener(|this, _event, _window, cx| {
+                        this.print_jobs.open = !this.print_jobs.open;
+                        cx.notify();
+                    }),
+                )
+                .into_any_element(),
+        )
+    }
+
+    /// Jobs of this session above the status bar, newest first, with cancel buttons
+    pub(super) fn render_print_jobs_popover(
+        &self,
+        colors: ThemeColors,
+        cx: &mut Context<Self>,
+    ) -> Option<AnyElement> {
+        if !self.print_jobs.open {
+            return None;
+        }
+
+        let header = div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap_2()
+            .px_2()
+            .py_1()
+            .bg(colors.background_secondary)
+            .border_b_1()
+            .border_color(colors.border)
+            .child(
+                div()
+                    .flex_1()
+                    .text_size(px(11.0))
+                    .text_color(colors.text)
+                    .child(tr!("print.jobs")),
+            )
+            .child(toolbar_btn(
+                &tr!("print.clear_finished"),
+                colors,
+                cx.listener(|this, _event, _window, cx| {
+                    this.print_jobs.clear_finished();
+                    cx.notify();
+                }),
+            ))
+            .child(toolbar_btn(
+                "✕",
+                colors,
+                cx.listener(|this, _event, _window, cx| {
+                    this.print_jobs.open = false;
+                    cx.notify();
+                }),
+            ));
+
+        let mut list = div().flex().flex_col();
+        if self.print_jobs.jobs.is_empty() {
+            list = list.child(
+                div()
+                    .p_2()
+                    .text_size(px(11.0))
+                    .text_color(colors.text_secondary)
+                    .child(tr!("print.no_jobs")),
+            );
+        }
+        for job in self.print_jobs.jobs.iter().rev() {
+            list = list.child(self.render_print_job(job, colors, cx));
+        }
+
+        Some(
+            div()
+                .absolute()
+                .right(px(8.0))
+                .bottom(px(STATUS_BAR_HEIGHT + 4.0))
+                .w(px(POPOVER_WIDTH))
+                .flex()
+                .flex_col()
+                .rounded_md()
+                .shadow_lg()
+                .bg(colors.background)
+                .border_1()
+                .border_color(colors.border)
+                .occlude()
+                .child(header)
+                .child(list)
+                .into_any_element(),
+        )
+    }
+
+    fn render_print_job(
+        &self,
+        job: &PrintJob,
+        colors: ThemeColors,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let id = job.id;
+        let status_color = match job.phase {
+            JobPhase::Failed(_) => rgb(ERROR_COLOR),
+            _ => colors.text_secondary,
+        };
+        let (done, total) = job.progress.get();
+        let progress_bar = (matches!(job.phase, JobPhase::Sending) && total > 0).then(|| {
+            div()
+                .w(px(PROGRESS_BAR_WIDTH))
+                .h(px(6.0))
+                .rounded_sm()
+                .bg(colors.background_tertiary)
+                .child(
+                    div()
+                        .h_full()
+                        .w(px(
+                            PROGRESS_BAR_WIDTH * done.min(total) as f32 / total as f32
+                        ))
+                        .rounded_sm()
+                        .bg(rgb(PROGRESS_BAR_COLOR)),
+                )
+        });
+
+        let details = div()
+            .flex_1()
+            .flex()
+            .flex_col()
+            .overflow_hidden()
+            .child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(colors.text)
+                    .text_ellipsis()
+                    .child(job.document.clone()),
+            )
+            .child(
+                div()
+                    .text_size(px(10.0))
+                    .text_color(colors.text_secondary)
+                    .text_ellipsis()
+                    .child(tr!(
+                        "print.job_summary",
+                        "printer" => job.printer,
+                        "pages" => job.pages
+                    )),
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_2()
+                    .text_size(px(10.0))
+                    .text_color(status_color)
+                    .child(job.status_text())
+                    .children(progress_bar),
+            );
+
+        let cancelling = job.progress.is_cancelled();
+        div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap_2()
+            .px_2()
+            .py_1()
+            .border_b_1()
+            .border_color(colors.border)
+            .child(details)
+            .children((job.is_active() && !cancelling).then(|| {
+                toolbar_btn(
+                    &tr!("print.cancel_job"),
+                    colors,
+                    cx.listener(move |this, _event, _window, cx| {
+                        this.cancel_print_job(id, cx);
+                    }),
+                )
+            }))
+    }
+}
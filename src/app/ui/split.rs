@@ -0,0 +1,137 @@
+use crate::app::split::{SplitDialog, SplitField, SplitKind};
+use crate::app::widgets::{toggle_btn, toolbar_btn_enabled};
+use crate::theme::ThemeColors;
+use crate::tr;
+use gpui::*;
+
+use super::super::PdfReaderApp;
+
+impl PdfReaderApp {
+    /// Title and body of the split dialog
+    pub(super) fn render_split_dialog(
+        &self,
+        dialog: &SplitDialog,
+        colors: ThemeColors,
+        cx: &mut Context<Self>,
+    ) -> (String, AnyElement) {
+        let label = |text: String| {
+            div()
+                .w(px(110.0))
+                .text_size(px(11.0))
+                .text_color(colors.text)
+                .child(text)
+        };
+
+        let mut kinds = div().flex().flex_row().gap_1();
+        for (kind, text) in [
+            (SplitKind::Ranges, tr!("split.by_ranges")),
+            (SplitKind::EveryN, tr!("split.every_n")),
+            (SplitKind::Bookmarks, tr!("split.by_bookmarks")),
+        ] {
+            kinds = kinds.child(toggle_btn(
+                &text,
+                dialog.kind == kind,
+                colors,
+                cx.listener(move |this, _event, _window, cx| {
+                    this.update_split_dialog(cx, |d| d.set_kind(kind));
+                }),
+            ));
+        }
+
+        let value_focused = dialog.focused == SplitField::Value;
+        let value_row = match dialog.kind {
+            SplitKind::Ranges => Some((
+                tr!("split.ranges"),
+                dialog.ranges.render("1-3,4-10,11-", value_focused, colors),
+            )),
+            SplitKind::EveryN => Some((
+                tr!("split.pages_per_file"),
+                dialog.every.render("10", value_focused, colors),
+            )),
+            SplitKind::Bookmarks => None,
+        };
+
+        let mut body = div().flex().flex_col().gap_2().child(kinds);
+
+        if let Some((text, input)) = value_row {
+            body = body.child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .child(label(text))
+                    .child(div().flex_1().cursor_text().child(input).on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(|this, _event, _window, cx| {
+                            this.update_split_dialog(cx, |d| d.focused = SplitField::Value);
+                        }),
+                    )),
+            );
+        } else {
+            body = body.child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(colors.text_secondary)
+                    .child(tr!("split.bookmarks_hint")),
+            );
+        }
+
+        body = body
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .child(label(tr!("split.file_names")))
+                    .child(
+                        div()
+                            .flex_1()
+                            .cursor_text()
+                            .child(dialog.template.render(
+                                "{name}-{n}",
+                                dialog.focused == SplitField::Template,
+                                colors,
+                            ))
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(|this, _event, _window, cx| {
+                                    this.update_split_dialog(cx, |d| {
+                                        d.focused = SplitField::Template
+                                    });
+                                }),
+                            ),
+                    ),
+            )
+            .child(
+                div()
+                    .text_size(px(10.0))
+                    .text_color(colors.text_secondary)
+                    .child(tr!("split.template_hint")),
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .gap_1()
+                    .child(div().flex_1())
+                    .child(toolbar_btn_enabled(
+                        &tr!("dialog.cancel"),
+                        true,
+                        colors,
+                        cx.listener(|this, _event, _window, cx| {
+                            this.close_dialog(cx);
+                        }),
+                    ))
+                    .child(toolbar_btn_enabled(
+                        &tr!("split.split"),
+                        dialog.mode().is_some(),
+                        colors,
+                        cx.listener(|this, _event, _window, cx| {
+                            this.run_split(cx);
+                        }),
+                    )),
+            );
+
+        (tr!("split.title"), body.into_any_element())
+    }
+}
@@ -5,12 +5,16 @@ use gpui::*;
 
 use super::super::PdfReaderApp;
 
+pub(super) const PROGRESS_BAR_WIDTH: f32 = 80.0;
+pub(super) const PROGRESS_BAR_COLOR: u32 = 0x3399FF;
+const ERROR_COLOR: u32 = 0xD93025;
+
 impl PdfReaderApp {
     pub(super) fn render_status_bar(
         &self,
         active_tab_id: Option<usize>,
         colors: ThemeColors,
-        _cx: &mut Context<Self>,
+        cx: &mut Context<Self>,
     ) -> impl IntoElement {
         let (current_page, page_count, zoom_info, file_name) = if let Some(tab_id) = active_tab_id {
             if let Some(tab) = self.state.tabs.get_tab(tab_id) {
@@ -29,8 +33,14 @@ impl PdfReaderApp {
         };
 
         let has_doc = page_count > 0;
+        let status_text = match &self.status_message {
+            Some(message) => message.clone(),
+            None if has_doc => file_name,
+            None => tr!("status.ready"),
+        };
         let current_page_clone = current_page;
         let page_count_clone = page_count;
+        let print_jobs = self.render_print_jobs_indicator(colors, cx);
 
         div()
             .h(px(STATUS_BAR_HEIGHT))
@@ -47,13 +57,19 @@ impl PdfReaderApp {
                 div()
                     .text_size(px(10.0))
                     .text_color(colors.text)
-                    .child(if has_doc {
-                        file_name
-                    } else {
-                        tr!("status.ready")
-                    }),
+                    .text_ellipsis()
+                    .child(status_text),
             )
+            .children(self.render_config_error(cx))
             .child(div().flex_1())
+            .children(self.vim.pending_text().map(|keys| {
+                div()
+                    .text_size(px(10.0))
+                    .text_color(colors.text_secondary)
+                    .child(keys)
+            }))
+            .children(print_jobs)
+            .children(self.render_task_progress(colors, cx))
             .child(
                 div()
                     .text_size(px(10.0))
@@ -71,4 +87,81 @@ impl PdfReaderApp {
                     .child(if has_doc { zoom_info } else { String::new() }),
             )
     }
+
+    /// Problem with the config file, until it is clicked away
+    fn render_config_error(&self, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        let error = self.state.get_config_error()?;
+        Some(
+            div()
+                .flex_shrink()
+                .overflow_hidden()
+                .cursor_pointer()
+                .text_size(px(10.0))
+                .text_color(rgb(ERROR_COLOR))
+                .text_ellipsis()
+                .child(format!("⚠ {} ✕", error.message()))
+                .on_mouse_down(
+                    MouseButton::Left,
+                    cx.listener(|this, _event, _window, cx| {
+                        this.state.dismiss_config_error();
+                        cx.notify();
+                    }),
+                ),
+        )
+    }
+
+    /// Label, bar and cancel button of the running background task
+    fn render_task_progress(
+        &self,
+        colors: ThemeColors,
+        cx: &mut Context<Self>,
+    ) -> Option<impl IntoElement> {
+        let progress = self.progress.as_ref()?;
+        let fraction = if progress.total > 0 {
+            progress.done as f32 / progress.total as f32
+        } else {
+            0.0
+        };
+
+        Some(
+            div()
+                .flex()
+                .flex_row()
+                .items_center()
+                .gap_2()
+                .text_size(px(10.0))
+                .text_color(colors.text)
+                .child(progress.label.clone())
+                .child(
+                    div()
+                        .w(px(PROGRESS_BAR_WIDTH))
+                        .h(px(6.0))
+                        .rounded_sm()
+                        .bg(colors.background_tertiary)
+                        .child(
+                            div()
+                                .h_full()
+                                .w(px(PROGRESS_BAR_WIDTH * fraction))
+                                .rounded_sm()
+                                .bg(rgb(PROGRESS_BAR_COLOR)),
+                        ),
+                )
+                .child(format!("{}/{}", progress.done, progress.total))
+                .child(
+                    div()
+                        .cursor_pointer()
+                        .text_color(colors.text_secondary)
+                        .child(if progress.cancelled { "…" } else { "✕" })
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(|this, _event, _window, cx| {
+                                if let Some(progress) = this.progress.as_mut() {
+                                    progress.cancelled = true;
+                                    cx.notify();
+                                }
+                            }),
+                        ),
+                ),
+        )
+    }
 }
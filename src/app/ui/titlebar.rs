@@ -1,4 +1,5 @@
 use crate::app::tabs::Tab;
+use crate::app::TITLEBAR_HEIGHT;
 use crate::theme::ThemeColors;
 use gpui::prelude::FluentBuilder;
 use gpui::*;
@@ -19,7 +20,7 @@ impl PdfReaderApp {
         let left_padding = px(8.0);
 
         let mut titlebar = div()
-            .h(px(32.0))
+            .h(px(TITLEBAR_HEIGHT))
             .w_full()
             .flex()
             .flex_row()
@@ -1,8 +1,10 @@
 use crate::app::state::{ScrollMode, SelectionMode};
 use crate::app::widgets::{toolbar_btn, toolbar_btn_enabled, toolbar_btn_with_color};
 use crate::app::TOOLBAR_HEIGHT;
-use crate::i18n::Language;
-use crate::theme::{Theme, ThemeColors};
+use crate::pdf::color_filter::ColorFilter;
+use crate::theme::ThemeColors;
+use crate::tr;
+use gpui::prelude::FluentBuilder;
 use gpui::*;
 
 use super::super::PdfReaderApp;
@@ -14,18 +16,13 @@ impl PdfReaderApp {
         colors: ThemeColors,
         cx: &mut Context<Self>,
     ) -> impl IntoElement {
-        let theme = self.state.get_theme();
-        let (theme_emoji, theme_color) = match theme {
-            Theme::Light => ("🌙", colors.moon_color),
-            Theme::Dark => ("☀️", colors.sun_color),
+        let (theme_emoji, theme_color) = if colors.is_dark() {
+            ("☀️", colors.sun_color)
+        } else {
+            ("🌙", colors.moon_color)
         };
 
-        let language = self.state.get_language();
-        let lang_flag = match language {
-            Language::English => "🇺🇸",
-            Language::Chinese => "🇨🇳",
-            Language::Spanish => "🇪🇸",
-        };
+        let lang_flag = self.state.get_language().flag();
 
         let sidebar_emoji = if self.show_sidebar { "📑" } else { "📖" };
 
@@ -35,10 +32,25 @@ impl PdfReaderApp {
             ScrollMode::Smooth => "📜",
         };
 
+        let color_filter = self
+            .state
+            .get_active_tab_id()
+            .and_then(|id| self.state.tabs.get_tab(id))
+            .map(|tab| tab.color_filter)
+            .unwrap_or_default();
+        let color_filter_emoji = match color_filter {
+            ColorFilter::None => "⬜",
+            ColorFilter::Invert => "⬛",
+            ColorFilter::SmartInvert => "🌗",
+            ColorFilter::Sepia => "🟫",
+            ColorFilter::Custom { .. } => "🎨",
+        };
+
         let selection_mode = self.state.get_selection_mode();
         let selection_emoji = match selection_mode {
             SelectionMode::Hand => "👋",
             SelectionMode::TextSelect => "🖱️",
+            SelectionMode::Snapshot => "📷",
         };
 
         div()
@@ -171,6 +183,15 @@ impl PdfReaderApp {
                 }),
             ))
             .child(div().w(px(4.0)))
+            .child(toolbar_btn_enabled(
+                "▦",
+                has_doc,
+                colors,
+                cx.listener(|this, _event, _window, cx| {
+                    this.toggle_page_organizer(cx);
+                }),
+            ))
+            .child(div().w(px(4.0)))
             .child({
                 let this = cx.entity().clone();
                 if has_doc {
@@ -207,6 +228,15 @@ impl PdfReaderApp {
                 }),
             ))
             .child(div().w(px(4.0)))
+            .child(toolbar_btn_enabled(
+                color_filter_emoji,
+                has_doc,
+                colors,
+                cx.listener(|this, _event, _window, cx| {
+                    this.cycle_color_filter(cx);
+                }),
+            ))
+            .child(div().w(px(4.0)))
             .child(toolbar_btn_enabled(
                 selection_emoji,
                 has_doc,
@@ -217,19 +247,39 @@ impl PdfReaderApp {
                     cx.notify();
                 }),
             ))
+            .when(
+                has_doc && selection_mode == SelectionMode::Snapshot,
+                |toolbar| {
+                    let snapshot_target = if self.state.get_snapshot_to_file() {
+                        "💾"
+                    } else {
+                        "📋"
+                    };
+                    toolbar
+                        .child(toolbar_btn(
+                            &tr!("snapshot.dpi", "dpi" => self.state.get_snapshot_dpi()),
+                            colors,
+                            cx.listener(|this, _event, _window, cx| {
+                                this.state.cycle_snapshot_dpi();
+                                cx.notify();
+                            }),
+                        ))
+                        .child(toolbar_btn(
+                            snapshot_target,
+                            colors,
+                            cx.listener(|this, _event, _window, cx| {
+                                this.state.toggle_snapshot_to_file();
+                                cx.notify();
+                            }),
+                        ))
+                },
+            )
             .child(div().flex_1())
             .child(toolbar_btn(
-                lang_flag,
+                &lang_flag,
                 colors,
-                cx.listener(|this, _event, window, cx| {
-                    let current_lang = this.state.get_language();
-                    let next_lang = match current_lang {
-                        Language::English => Language::Chinese,
-                        Language::Chinese => Language::Spanish,
-                        Language::Spanish => Language::English,
-                    };
-                    this.state.set_language(next_lang);
-                    window.dispatch_action(Box::new(crate::app::menu::RefreshMenus), cx);
+                cx.listener(|this, _event, _window, cx| {
+                    this.language_menu_open = !this.language_menu_open;
                     cx.notify();
                 }),
             ))
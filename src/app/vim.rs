@@ -0,0 +1,272 @@
+//! The Vim keymap preset: plain keys read as Vim normal-mode commands, with
+//! counts, marks and two-key commands such as "gg"
+
+use super::state::ScrollMode;
+use super::PdfReaderApp;
+use crate::tr;
+use gpui::*;
+
+/// How far "j" and "k" move the page in smooth scroll mode
+const SCROLL_STEP: f32 = 60.0;
+/// Largest count kept, so long runs of digits cannot overflow
+const MAX_COUNT: usize = 99_999;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VimCommand {
+    ScrollDown(usize),
+    ScrollUp(usize),
+    NextPage(usize),
+    PrevPage(usize),
+    FirstPage,
+    LastPage,
+    /// Page number counted from 1
+    GoToPage(usize),
+    SetMark(char),
+    JumpToMark(char),
+    Centre,
+}
+
+/// What a key did to the command being typed
+#[derive(Debug, PartialEq, Eq)]
+pub enum VimInput {
+    /// Part of a command, or an unfinished one abandoned
+    Consumed,
+    Run(VimCommand),
+    /// Not a Vim key; leave it to the keymap
+    Ignored,
+}
+
+/// Count and first key of the command typed so far
+#[derive(Debug, Default)]
+pub struct VimState {
+    count: Option<usize>,
+    prefix: Option<char>,
+}
+
+impl VimState {
+    pub fn is_empty(&self) -> bool {
+        self.count.is_none() && self.prefix.is_none()
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// The keys typed so far, shown in the status bar like Vim's "showcmd"
+    pub fn pending_text(&self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut text = self.count.map(|c| c.to_string()).unwrap_or_default();
+        text.extend(self.prefix);
+        Some(text)
+    }
+
+    pub fn feed(&mut self, key: char) -> VimInput {
+        let count = self.count;
+        let repeat = count.unwrap_or(1);
+        let command = match (self.prefix.take(), key) {
+            (Some('g'), 'g') => match count {
+                Some(page) => VimCommand::GoToPage(page),
+                None => VimCommand::FirstPage,
+            },
+            (Some('m'), mark) if mark.is_ascii_alphabetic() => VimCommand::SetMark(mark),
+            (Some('\''), mark) if mark.is_ascii_alphabetic() => VimCommand::JumpToMark(mark),
+            (Some('z'), 'z') => VimCommand::Centre,
+            // An unfinished command followed by a key that does not complete it
+            (Some(_), _) => {
+                self.reset();
+                return VimInput::Consumed;
+            }
+            (None, digit @ '0'..='9') if digit != '0' || count.is_some() => {
+                let digit = digit.to_digit(10).unwrap_or(0) as usize;
+                self.count = Some((count.unwrap_or(0) * 10 + digit).min(MAX_COUNT));
+                return VimInput::Consumed;
+            }
+            (None, prefix @ ('g' | 'm' | '\'' | 'z')) => {
+                self.prefix = Some(prefix);
+                return VimInput::Consumed;
+            }
+            (None, 'j') => VimCommand::ScrollDown(repeat),
+            (None, 'k') => VimCommand::ScrollUp(repeat),
+            (None, 'J') => VimCommand::NextPage(repeat),
+            (None, 'K') => VimCommand::PrevPage(repeat),
+            (None, 'G') => match count {
+                Some(page) => VimCommand::GoToPage(page),
+                None => VimCommand::LastPage,
+            },
+            (None, _) => {
+                self.reset();
+                return VimInput::Ignored;
+            }
+        };
+        self.reset();
+        VimInput::Run(command)
+    }
+}
+
+/// The character a keystroke types, if it is a single one
+fn typed_char(keystroke: &Keystroke) -> Option<char> {
+    let text = match &keystroke.key_char {
+        Some(text) => text.clone(),
+        None if keystroke.modifiers.shift => keystroke.key.to_uppercase(),
+        None => keystroke.key.clone(),
+    };
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+impl PdfReaderApp {
+    /// Read a key as part of a Vim command; false when it is not one
+    pub(super) fn handle_vim_key(&mut self, keystroke: &Keystroke, cx: &mut Context<Self>) -> bool {
+        let Some(key) = typed_char(keystroke) else {
+            // Escape abandons a command being typed, as in Vim
+            let abandoned = keystroke.key == "escape" && !self.vim.is_empty();
+            self.vim.reset();
+            cx.notify();
+            return abandoned;
+        };
+        let input = self.vim.feed(key);
+        cx.notify();
+        match input {
+            VimInput::Consumed => true,
+            VimInput::Run(command) => {
+                self.run_vim_command(command, cx);
+                true
+            }
+            VimInput::Ignored => false,
+        }
+    }
+
+    fn run_vim_command(&mut self, command: VimCommand, cx: &mut Context<Self>) {
+        let Some(tab_id) = self.state.get_active_tab_id() else {
+            return;
+        };
+        let Some(tab) = self.state.tabs.get_tab(tab_id) else {
+            return;
+        };
+        let last = tab.page_count.saturating_sub(1);
+        let smooth = self.state.get_scroll_mode() == ScrollMode::Smooth;
+        match command {
+            VimCommand::ScrollDown(count) if smooth => {
+                self.scroll_page(count as f32, tab.current_page, last, cx)
+            }
+            VimCommand::ScrollUp(count) if smooth => {
+                self.scroll_page(-(count as f32), tab.current_page, last, cx)
+            }
+            // Whole pages are shown in page mode, so scrolling turns them
+            VimCommand::ScrollDown(count) | VimCommand::NextPage(count) => {
+                self.show_page((tab.current_page + count).min(last), 0.0, cx)
+            }
+            VimCommand::ScrollUp(count) | VimCommand::PrevPage(count) => {
+                self.show_page(tab.current_page.saturating_sub(count), 0.0, cx)
+            }
+            VimCommand::FirstPage => self.show_page(0, 0.0, cx),
+            VimCommand::LastPage => self.show_page(last, 0.0, cx),
+            VimCommand::GoToPage(page) => self.show_page(page.saturating_sub(1).min(last), 0.0, cx),
+            VimCommand::SetMark(mark) => {
+                let offset: f32 = self.page_scroll.offset().y.into();
+                self.state.tabs.update_tab(tab_id, |tab| {
+                    tab.marks.insert(mark, (tab.current_page, offset));
+                });
+                self.set_status_message(tr!("vim.mark_set", "mark" => mark), cx);
+            }
+            VimCommand::JumpToMark(mark) => match tab.marks.get(&mark) {
+                Some(&(page, offset)) => self.show_page(page.min(last), offset, cx),
+                None => self.set_status_message(tr!("vim.no_mark", "mark" => mark), cx),
+            },
+            VimCommand::Centre => {
+                let max = self.page_scroll.max_offset().height;
+                self.page_scroll.set_offset(point(px(0.0), -max / 2.0));
+                cx.notify();
+            }
+        }
+    }
+
+    /// Go to `page` with the view scrolled `offset` pixels from its top, as
+    /// the scroll handle counts them
+    fn show_page(&mut self, page: usize, offset: f32, cx: &mut Context<Self>) {
+        let Some(tab_id) = self.state.get_active_tab_id() else {
+            return;
+        };
+        let _ = self.state.navigate_to_page(page);
+        self.render_current_tab_page(tab_id, cx);
+        self.page_scroll.set_offset(point(px(0.0), px(offset)));
+        cx.notify();
+    }
+
+    /// Move the page in smooth scroll mode by `steps` lines, down for positive,
+    /// running on from `page` into the next or previous page at either end
+    fn scroll_page(&mut self, steps: f32, page: usize, last: usize, cx: &mut Context<Self>) {
+        let current: f32 = self.page_scroll.offset().y.into();
+        let max: f32 = self.page_scroll.max_offset().height.into();
+        let target = current - steps * SCROLL_STEP;
+        if target < -max && current <= -max {
+            if page < last {
+                self.show_page(page + 1, 0.0, cx);
+            }
+        } else if target > 0.0 && current >= 0.0 {
+            if page > 0 {
+                self.show_page(page - 1, 0.0, cx);
+                self.page_scroll.scroll_to_bottom();
+            }
+        } else {
+            self.page_scroll
+                .set_offset(point(px(0.0), px(target.clamp(-max, 0.0))));
+            cx.notify();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{VimCommand, VimInput, VimState};
+
+    fn feed(state: &mut VimState, keys: &str) -> VimInput {
+        let mut input = VimInput::Ignored;
+        for key in keys.chars() {
+            input = state.feed(key);
+        }
+        input
+    }
+
+    #[test]
+    fn test_vim_commands() {
+        let mut state = VimState::default();
+        assert_eq!(
+            feed(&mut state, "j"),
+            VimInput::Run(VimCommand::ScrollDown(1))
+        );
+        assert_eq!(
+            feed(&mut state, "3K"),
+            VimInput::Run(VimCommand::PrevPage(3))
+        );
+        assert_eq!(feed(&mut state, "gg"), VimInput::Run(VimCommand::FirstPage));
+        assert_eq!(feed(&mut state, "G"), VimInput::Run(VimCommand::LastPage));
+        assert_eq!(feed(&mut state, "42"), VimInput::Consumed);
+        assert_eq!(state.pending_text().as_deref(), Some("42"));
+        assert_eq!(
+            feed(&mut state, "G"),
+            VimInput::Run(VimCommand::GoToPage(42))
+        );
+        assert_eq!(
+            feed(&mut state, "ma"),
+            VimInput::Run(VimCommand::SetMark('a'))
+        );
+        assert_eq!(
+            feed(&mut state, "'a"),
+            VimInput::Run(VimCommand::JumpToMark('a'))
+        );
+        assert_eq!(feed(&mut state, "zz"), VimInput::Run(VimCommand::Centre));
+
+        // An unfinished command is dropped, and other keys are left alone
+        assert_eq!(feed(&mut state, "gx"), VimInput::Consumed);
+        assert!(state.is_empty());
+        assert_eq!(feed(&mut state, "0"), VimInput::Ignored);
+        assert_eq!(feed(&mut state, "5q"), VimInput::Ignored);
+        assert!(state.is_empty());
+    }
+}
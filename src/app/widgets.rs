@@ -54,3 +54,42 @@ where
             .into_any_element()
     }
 }
+
+/// Button showing whether its option is the selected one
+pub fn toggle_btn<F>(
+    label: &str,
+    selected: bool,
+    colors: ThemeColors,
+    on_click: F,
+) -> impl IntoElement
+where
+    F: Fn(&MouseDownEvent, &mut Window, &mut App) + 'static,
+{
+    div()
+        .px_2()
+        .py(px(2.0))
+        .rounded_sm()
+        .cursor_pointer()
+        .border_1()
+        .border_color(if selected {
+            rgb(0x3399FF)
+        } else {
+            colors.border
+        })
+        .bg(if selected {
+            colors.background_tertiary
+        } else {
+            colors.background_secondary
+        })
+        .child(
+            div()
+                .text_size(px(11.0))
+                .text_color(if selected {
+                    colors.text
+                } else {
+                    colors.text_secondary
+                })
+                .child(label.to_string()),
+        )
+        .on_mouse_down(MouseButton::Left, on_click)
+}
@@ -0,0 +1,548 @@
+//! Command line subcommands that run without opening a window
+
+use crate::app::{MAX_ZOOM, MIN_ZOOM};
+use crate::pdf::merge::{merge_pdfs, MergeInput};
+use crate::pdf::page_range::parse_page_range;
+use crate::pdf::split::{SplitMode, DEFAULT_BOOKMARK_TEMPLATE, DEFAULT_NAME_TEMPLATE};
+use crate::pdf::text_export::{format_text, TextExportFormat};
+use crate::pdf::PdfDocument;
+use crate::print::imposition::{NUp, Poster};
+use crate::print::to_file::{print_to_file, FileFormat};
+use crate::print::{
+    Imposition, JobProgress, Margins, Orientation, PageSet, PaperSize, PrintSettings,
+};
+use std::path::{Path, PathBuf};
+
+const USAGE: &str = "\
+Usage:
+  lingpdf [--page N] [--zoom PERCENT] [FILE[#page=N]]...
+  lingpdf merge -o OUTPUT INPUT [--pages RANGE] [INPUT [--pages RANGE]]...
+  lingpdf split -o DIR (--ranges RANGES | --every N | --bookmarks) [--name TEMPLATE] INPUT
+  lingpdf text [-o OUTPUT] [--format txt|md|html] [--pages RANGE] INPUT
+  lingpdf print -o OUTPUT [--format pdf|ps|png] [--pages SET] [--paper SIZE]
+                [--landscape] [--actual-size] [--margins MM] [--grayscale]
+                [--nup N | --booklet | --poster N] INPUT
+
+Each FILE opens in its own tab; it may be a path or a file:// URL, and a
+directory shows a chooser over the PDFs in it. --page and --zoom apply to
+the files after them, and FILE#page=N opens that file at page N.
+
+Commands:
+  merge    Concatenate PDF files, optionally keeping only some pages of each.
+           RANGE applies to the preceding INPUT, e.g. \"1-3,5\" or \"8-\".
+  split    Split INPUT into files in DIR: one per comma separated range
+           (\"1-3,4-10,11-\"), every N pages, or at each top-level bookmark.
+           TEMPLATE names the files using {name}, {n}, {title}, {start}, {end}.
+  text     Extract the text of INPUT in reading order as plain text, Markdown
+           or HTML. The format defaults to OUTPUT's extension; without
+           OUTPUT, plain text is written to standard output.
+  print    Print INPUT to a file as the print dialog's \"Print to file\"
+           printer would: a PDF, a PostScript file or numbered PNG images.
+           SET is e.g. \"1,3,5-9,12-\", \"odd\" or \"even\"; SIZE is A4, A3,
+           A5, Letter, Legal or Tabloid. --nup lays N pages on each sheet,
+           --booklet folds sheets into a booklet and --poster spreads each
+           page over N sheets across.
+";
+
+/// Subcommands, and the ways of asking for help
+const COMMANDS: &[&str] = &["merge", "split", "text", "print", "help", "-h", "--help"];
+
+/// Whether `arg` names a subcommand. A file that happens to share its name
+/// is opened instead.
+fn is_command(arg: &str, exists: impl Fn(&Path) -> bool) -> bool {
+    COMMANDS.contains(&arg) && !exists(Path::new(arg))
+}
+
+/// Run the subcommand named by `args[1]`.
+/// Returns the process exit code, or `None` when the GUI should start instead.
+pub fn run(args: &[String]) -> Option<i32> {
+    let command = args
+        .get(1)
+        .filter(|arg| is_command(arg, |path| path.exists()))?;
+    let rest = &args[2..];
+    attach_console();
+
+    let result = match command.as_str() {
+        "merge" => merge(rest),
+        "split" => split(rest),
+        "text" => text(rest),
+        "print" => print(rest),
+        "help" | "-h" | "--help" => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+        _ => unreachable!("not one of COMMANDS"),
+    };
+
+    match result {
+        Ok(()) => Some(0),
+        Err(e) => {
+            eprintln!("lingpdf {}: {}", command, e);
+            Some(1)
+        }
+    }
+}
+
+/// The Windows build is a GUI program without a console of its own, so write
+/// subcommand output to the console it was started from, if any
+#[cfg(target_os = "windows")]
+fn attach_console() {
+    use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    // Fails when started from Explorer, where there's nowhere to show output
+    let _ = unsafe { AttachConsole(ATTACH_PARENT_PROCESS) };
+}
+
+#[cfg(not(target_os = "windows"))]
+fn attach_console() {}
+
+/// A document to open in a tab when the GUI starts
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenRequest {
+    pub path: PathBuf,
+    /// Page to show, counted from 1
+    pub page: Option<usize>,
+    /// Zoom factor, 1.0 for 100%
+    pub zoom: Option<f32>,
+}
+
+/// Documents named on the command line of the GUI, with the `--page` and
+/// `--zoom` options given before each of them
+pub fn open_requests(args: &[String]) -> anyhow::Result<Vec<OpenRequest>> {
+    let mut page: Option<usize> = None;
+    let mut zoom: Option<f32> = None;
+    let mut requests = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow::anyhow!("{} needs a value", arg))
+        };
+        match arg.as_str() {
+            "--page" => {
+                page = Some(
+                    parse_page_number(value()?)
+                        .ok_or_else(|| anyhow::anyhow!("{} needs a page number", arg))?,
+                );
+            }
+            "--zoom" => {
+                zoom = Some(parse_zoom(value()?).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "{} needs a percentage from {} to {}",
+                        arg,
+                        MIN_ZOOM * 100.0,
+                        MAX_ZOOM * 100.0
+                    )
+                })?);
+            }
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                anyhow::bail!("unknown option {}\n\n{}", arg, USAGE);
+            }
+            _ => {
+                let (path, fragment_page) = parse_target(arg, |path| path.exists())?;
+                requests.push(OpenRequest {
+                    path,
+                    page: fragment_page.or(page),
+                    zoom,
+                });
+            }
+        }
+    }
+    Ok(requests)
+}
+
+/// The file a path or file:// URL argument names, and the page from a
+/// "#page=N" suffix. A suffix is only split off when the whole argument
+/// isn't itself the name of an existing file.
+fn parse_target(
+    arg: &str,
+    exists: impl Fn(&Path) -> bool,
+) -> anyhow::Result<(PathBuf, Option<usize>)> {
+    let (target, page) = match arg.rsplit_once("#page=") {
+        Some((target, page)) if !exists(Path::new(arg)) => match parse_page_number(page) {
+            Some(page) => (target, Some(page)),
+            None => anyhow::bail!("{} is not a page number", page),
+        },
+        _ => (arg, None),
+    };
+
+    let path = match target.split_once("://") {
+        Some(("file", _)) => file_url_path(target)
+            .ok_or_else(|| anyhow::anyhow!("{} is not a valid file URL", target))?,
+        Some((scheme, _)) if scheme.chars().all(|c| c.is_ascii_alphanumeric()) => {
+            anyhow::bail!("{} URLs can't be opened, only local files", scheme);
+        }
+        _ => PathBuf::from(target),
+    };
+    Ok((path, page))
+}
+
+/// The local path of a file:// URL, with %XX escapes decoded
+fn file_url_path(url: &str) -> Option<PathBuf> {
+    let rest = url.strip_prefix("file://")?;
+    // Only "file:///path" and "file://localhost/path" name local files
+    let path = match rest.strip_prefix("localhost") {
+        Some(path) => path,
+        None => rest,
+    };
+    if !path.starts_with('/') {
+        return None;
+    }
+
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    let path = String::from_utf8(decoded).ok()?;
+
+    // "file:///C:/Users" is "C:/Users" on Windows
+    if cfg!(windows) && path.as_bytes().get(2) == Some(&b':') {
+        return Some(PathBuf::from(&path[1..]));
+    }
+    Some(PathBuf::from(path))
+}
+
+fn parse_page_number(text: &str) -> Option<usize> {
+    text.trim().parse().ok().filter(|page| *page > 0)
+}
+
+/// A zoom percentage such as "150" or "150%", as a factor
+fn parse_zoom(text: &str) -> Option<f32> {
+    let text = text.trim();
+    let percent: f32 = text.strip_suffix('%').unwrap_or(text).parse().ok()?;
+    let zoom = percent / 100.0;
+    (MIN_ZOOM..=MAX_ZOOM).contains(&zoom).then_some(zoom)
+}
+
+fn merge(args: &[String]) -> anyhow::Result<()> {
+    let mut output: Option<PathBuf> = None;
+    let mut inputs: Vec<MergeInput> = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("{} needs a file name", arg))?;
+                output = Some(PathBuf::from(value));
+            }
+            "-p" | "--pages" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("{} needs a page range", arg))?;
+                let input = inputs
+                    .last_mut()
+                    .ok_or_else(|| anyhow::anyhow!("{} must follow an input file", arg))?;
+                input.pages = Some(value.clone());
+            }
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                anyhow::bail!("unknown option {}\n\n{}", arg, USAGE);
+            }
+            _ => inputs.push(MergeInput::new(PathBuf::from(arg))),
+        }
+    }
+
+    let output = output.ok_or_else(|| anyhow::anyhow!("missing -o OUTPUT\n\n{}", USAGE))?;
+    if inputs.is_empty() {
+        anyhow::bail!("no input files\n\n{}", USAGE);
+    }
+    if inputs.iter().any(|input| input.path == output) {
+        anyhow::bail!("output {} is also an input", output.display());
+    }
+
+    let page_count = merge_pdfs(&inputs, &output)?;
+    println!(
+        "Merged {} files ({} pages) into {}",
+        inputs.len(),
+        page_count,
+        output.display()
+    );
+    Ok(())
+}
+
+fn split(args: &[String]) -> anyhow::Result<()> {
+    let mut output_dir: Option<PathBuf> = None;
+    let mut mode: Option<SplitMode> = None;
+    let mut template: Option<String> = None;
+    let mut input: Option<PathBuf> = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow::anyhow!("{} needs a value", arg))
+        };
+        match arg.as_str() {
+            "-o" | "--output" => output_dir = Some(PathBuf::from(value()?)),
+            "-r" | "--ranges" => mode = Some(SplitMode::Ranges(value()?.clone())),
+            "-n" | "--every" => {
+                let n = value()?
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("{} needs a page count", arg))?;
+                mode = Some(SplitMode::EveryN(n));
+            }
+            "-b" | "--bookmarks" => mode = Some(SplitMode::Bookmarks),
+            "--name" => template = Some(value()?.clone()),
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                anyhow::bail!("unknown option {}\n\n{}", arg, USAGE);
+            }
+            _ if input.is_some() => anyhow::bail!("only one input file can be split"),
+            _ => input = Some(PathBuf::from(arg)),
+        }
+    }
+
+    let input = input.ok_or_else(|| anyhow::anyhow!("no input file\n\n{}", USAGE))?;
+    let output_dir = output_dir.ok_or_else(|| anyhow::anyhow!("missing -o DIR\n\n{}", USAGE))?;
+    let mode = mode
+        .ok_or_else(|| anyhow::anyhow!("choose --ranges, --every or --bookmarks\n\n{}", USAGE))?;
+    let template = template.unwrap_or_else(|| match mode {
+        SplitMode::Bookmarks => DEFAULT_BOOKMARK_TEMPLATE.to_string(),
+        _ => DEFAULT_NAME_TEMPLATE.to_string(),
+    });
+
+    let doc = PdfDocument::open(&input)?;
+    let files = doc.split(&mode, &output_dir, &template)?;
+    for file in &files {
+        println!("{}", file.display());
+    }
+    Ok(())
+}
+
+fn text(args: &[String]) -> anyhow::Result<()> {
+    let mut output: Option<PathBuf> = None;
+    let mut format: Option<TextExportFormat> = None;
+    let mut pages: Option<String> = None;
+    let mut input: Option<PathBuf> = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow::anyhow!("{} needs a value", arg))
+        };
+        match arg.as_str() {
+            "-o" | "--output" => output = Some(PathBuf::from(value()?)),
+            "-f" | "--format" => {
+                let name = value()?;
+                format = Some(
+                    TextExportFormat::from_extension(name)
+                        .ok_or_else(|| anyhow::anyhow!("unknown format {}", name))?,
+                );
+            }
+            "-p" | "--pages" => pages = Some(value()?.clone()),
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                anyhow::bail!("unknown option {}\n\n{}", arg, USAGE);
+            }
+            _ if input.is_some() => anyhow::bail!("only one input file can be read"),
+            _ => input = Some(PathBuf::from(arg)),
+        }
+    }
+
+    let input = input.ok_or_else(|| anyhow::anyhow!("no input file\n\n{}", USAGE))?;
+    let format = format
+        .or_else(|| output.as_deref().and_then(TextExportFormat::from_path))
+        .unwrap_or(TextExportFormat::PlainText);
+
+    let doc = PdfDocument::open(&input)?;
+    let pages = parse_page_range(pages.as_deref().unwrap_or(""), doc.page_count())?;
+    match output {
+        Some(output) => doc.export_text(&pages, format, &output)?,
+        None => {
+            let title = input
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let laid_out = doc.layout_text(&pages)?;
+            print!("{}", format_text(&laid_out, format, &title));
+        }
+    }
+    Ok(())
+}
+
+fn print(args: &[String]) -> anyhow::Result<()> {
+    let mut output: Option<PathBuf> = None;
+    let mut format: Option<FileFormat> = None;
+    let mut pages: Option<String> = None;
+    let mut settings = PrintSettings::default();
+    let mut input: Option<PathBuf> = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow::anyhow!("{} needs a value", arg))
+        };
+        let mut count = || -> anyhow::Result<usize> {
+            value()?
+                .parse()
+                .ok()
+                .filter(|n| *n > 0)
+                .ok_or_else(|| anyhow::anyhow!("{} needs a number", arg))
+        };
+        match arg.as_str() {
+            "-o" | "--output" => output = Some(PathBuf::from(value()?)),
+            "-f" | "--format" => {
+                let name = value()?;
+                format = Some(
+                    FileFormat::from_extension(name)
+                        .ok_or_else(|| anyhow::anyhow!("unknown format {}", name))?,
+                );
+            }
+            "-p" | "--pages" => pages = Some(value()?.clone()),
+            "--paper" => {
+                let name = value()?;
+                settings.paper_size = PaperSize::ALL
+                    .into_iter()
+                    .find(|size| size.name().eq_ignore_ascii_case(name))
+                    .ok_or_else(|| anyhow::anyhow!("unknown paper size {}", name))?;
+            }
+            "--landscape" => settings.orientation = Orientation::Landscape,
+            "--actual-size" => settings.scale_to_fit = false,
+            "--margins" => {
+                let mm: f32 = value()?
+                    .parse()
+                    .ok()
+                    .filter(|mm: &f32| mm.is_finite() && *mm >= 0.0)
+                    .ok_or_else(|| anyhow::anyhow!("{} needs millimetres", arg))?;
+                settings.margins = Margins {
+                    top: mm,
+                    right: mm,
+                    bottom: mm,
+                    left: mm,
+                };
+            }
+            "--grayscale" => settings.color = false,
+            "--nup" => {
+                settings.imposition = Imposition::NUp(NUp {
+                    pages_per_sheet: count()?,
+                    ..NUp::default()
+                })
+            }
+            "--booklet" => settings.imposition = Imposition::Booklet,
+            "--poster" => {
+                settings.imposition = Imposition::Poster(Poster {
+                    sheets_across: count()?,
+                    ..Poster::default()
+                })
+            }
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                anyhow::bail!("unknown option {}\n\n{}", arg, USAGE);
+            }
+            _ if input.is_some() => anyhow::bail!("only one input file can be printed"),
+            _ => input = Some(PathBuf::from(arg)),
+        }
+    }
+
+    let input = input.ok_or_else(|| anyhow::anyhow!("no input file\n\n{}", USAGE))?;
+    let output = output.ok_or_else(|| anyhow::anyhow!("missing -o OUTPUT\n\n{}", USAGE))?;
+    let format = format
+        .or_else(|| FileFormat::from_path(&output))
+        .unwrap_or(FileFormat::Pdf);
+
+    let doc = PdfDocument::open(&input)?;
+    let page_count = doc.page_count();
+    let pages = PageSet::parse(pages.as_deref().unwrap_or(""), page_count, 0, &[])?;
+    if pages.is_empty() {
+        anyhow::bail!("no pages to print");
+    }
+    settings.pages = (pages.len() < page_count).then_some(pages);
+
+    for file in print_to_file(
+        &input,
+        &settings,
+        page_count,
+        format,
+        &output,
+        &JobProgress::default(),
+    )? {
+        println!("{}", file.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_open_requests() {
+        let requests = open_requests(&args(&[
+            "a.pdf",
+            "--zoom",
+            "150%",
+            "--page",
+            "3",
+            "b.pdf",
+            "c.pdf#page=12",
+        ]))
+        .unwrap();
+        assert_eq!(
+            requests,
+            vec![
+                OpenRequest {
+                    path: PathBuf::from("a.pdf"),
+                    page: None,
+                    zoom: None
+                },
+                OpenRequest {
+                    path: PathBuf::from("b.pdf"),
+                    page: Some(3),
+                    zoom: Some(1.5)
+                },
+                OpenRequest {
+                    path: PathBuf::from("c.pdf"),
+                    page: Some(12),
+                    zoom: Some(1.5)
+                },
+            ]
+        );
+        assert!(open_requests(&args(&["--page", "0", "a.pdf"])).is_err());
+        assert!(open_requests(&args(&["--zoom", "1000", "a.pdf"])).is_err());
+        assert!(open_requests(&args(&["--page"])).is_err());
+        assert!(open_requests(&args(&["https://example.com/a.pdf"])).is_err());
+    }
+
+    #[test]
+    fn test_is_command() {
+        let none = |_: &Path| false;
+        assert!(is_command("merge", none));
+        assert!(is_command("--help", none));
+        assert!(!is_command("a.pdf", none));
+        assert!(!is_command("split", |path| path == Path::new("split")));
+        assert_eq!(run(&args(&["lingpdf", "a.pdf"])), None);
+    }
+
+    #[test]
+    fn test_parse_target() {
+        let none = |_: &Path| false;
+        assert_eq!(
+            parse_target("file:///tmp/My%20Files/a.pdf#page=2", none).unwrap(),
+            (PathBuf::from("/tmp/My Files/a.pdf"), Some(2))
+        );
+        assert_eq!(
+            parse_target("file://localhost/tmp/a.pdf", none).unwrap(),
+            (PathBuf::from("/tmp/a.pdf"), None)
+        );
+        assert!(parse_target("file://server/a.pdf", none).is_err());
+        assert!(parse_target("a.pdf#page=x", none).is_err());
+        // A file whose name really ends in "#page=2" is opened as it is
+        assert_eq!(
+            parse_target("odd#page=2", |_| true).unwrap(),
+            (PathBuf::from("odd#page=2"), None)
+        );
+    }
+}
@@ -1,44 +1,103 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-pub enum Language {
-    English,
-    Chinese,
-    Spanish,
-}
+/// Locale used when the system's cannot be matched
+const DEFAULT_LOCALE: &str = "en";
+
+/// Environment variables naming the user's locale, in the order POSIX gives
+/// them precedence for messages
+const LOCALE_VARIABLES: [&str; 3] = ["LC_ALL", "LC_MESSAGES", "LANG"];
+
+/// A UI language: one of the locale files built into the app, by its code
+/// such as "en" or "zh"
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Language(String);
 
 impl Default for Language {
+    /// The system's language, if there is a locale file for it
     fn default() -> Self {
-        if let Ok(lang) = std::env::var("LANG") {
-            if lang.starts_with("zh") {
-                return Language::Chinese;
-            } else if lang.starts_with("es") {
-                return Language::Spanish;
-            }
-        }
-        Language::English
+        let available = crate::available_locales();
+        let detected = LOCALE_VARIABLES.iter().find_map(|name| {
+            let value = std::env::var(name).ok().filter(|v| !v.is_empty())?;
+            // The first variable set decides, even when no locale file matches it
+            Some(match_locale(&value, available))
+        });
+        Language(detected.flatten().unwrap_or(DEFAULT_LOCALE).to_string())
     }
 }
 
 impl Language {
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            Language::English => "en",
-            Language::Chinese => "zh",
-            Language::Spanish => "es",
-        }
+    /// Every language with a locale file, English first and the rest by code
+    pub fn all() -> Vec<Language> {
+        let mut codes: Vec<&str> = crate::available_locales().to_vec();
+        codes.sort_by_key(|&code| (code != DEFAULT_LOCALE, code));
+        codes
+            .into_iter()
+            .map(|code| Language(code.to_string()))
+            .collect()
+    }
+
+    /// The language of this code, if it has a locale file
+    pub fn from_code(code: &str) -> Option<Language> {
+        match_locale(code, crate::available_locales()).map(|code| Language(code.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Name of the language in itself, from its locale file
+    pub fn name(&self) -> String {
+        rust_i18n::t!("_lang", locale = self.as_str())
+    }
+
+    /// Flag shown on the toolbar button, or the code for languages without one
+    pub fn flag(&self) -> String {
+        let flag = match self.base() {
+            "en" => "🇺🇸",
+            "zh" => "🇨🇳",
+            "es" => "🇪🇸",
+            "fr" => "🇫🇷",
+            "de" => "🇩🇪",
+            "ja" => "🇯🇵",
+            _ => return self.0.to_uppercase(),
+        };
+        flag.to_string()
     }
+
+    /// The language without its region, "pt" for "pt-BR"
+    fn base(&self) -> &str {
+        self.0.split(['-', '_']).next().unwrap_or(&self.0)
+    }
+}
+
+/// The locale file code matching a POSIX locale such as "fr_CA.UTF-8" or
+/// "de_DE@euro": the code with the region if there is one, else without it
+fn match_locale<'a>(value: &str, available: &[&'a str]) -> Option<&'a str> {
+    let locale = value.split(['.', '@']).next().unwrap_or(value);
+    if locale.is_empty() || locale == "C" || locale == "POSIX" {
+        return None;
+    }
+    let normalized = locale.replace('_', "-");
+    let language = normalized.split('-').next().unwrap_or(&normalized);
+    let find = |code: &str| {
+        available
+            .iter()
+            .copied()
+            .find(|available| available.eq_ignore_ascii_case(code))
+    };
+    find(&normalized).or_else(|| find(language))
 }
 
 pub struct I18n;
 
 impl I18n {
-    pub fn new(language: Language) -> Self {
+    pub fn new(language: &Language) -> Self {
         rust_i18n::set_locale(language.as_str());
         Self
     }
 
-    pub fn set_language(language: Language) {
+    pub fn set_language(language: &Language) {
         rust_i18n::set_locale(language.as_str());
     }
 }
@@ -49,6 +108,23 @@ macro_rules! tr {
         rust_i18n::t!($key).to_string()
     };
     ($key:expr, $($arg:expr => $value:expr),+) => {
-        rust_i18n::t!($key, $($arg => $value),+).to_string()
+        rust_i18n::t!($key, locale = &rust_i18n::locale(), $($arg = $value),+).to_string()
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::match_locale;
+
+    #[test]
+    fn test_match_locale() {
+        let available = ["de", "en", "pt-BR", "zh"];
+        assert_eq!(match_locale("de_DE.UTF-8", &available), Some("de"));
+        assert_eq!(match_locale("de_AT@euro", &available), Some("de"));
+        assert_eq!(match_locale("pt_BR.UTF-8", &available), Some("pt-BR"));
+        assert_eq!(match_locale("zh", &available), Some("zh"));
+        assert_eq!(match_locale("fr_FR.UTF-8", &available), None);
+        assert_eq!(match_locale("C.UTF-8", &available), None);
+        assert_eq!(match_locale("POSIX", &available), None);
+    }
+}
@@ -4,6 +4,7 @@
 use std::sync::Arc;
 
 mod app;
+mod cli;
 mod i18n;
 mod pdf;
 mod print;
@@ -13,7 +14,7 @@ mod utils;
 rust_i18n::i18n!("locales", fallback = "en");
 
 use app::actions::register_actions;
-use app::menu::Quit;
+use app::menu::{Preferences, Quit};
 use app::{PdfReaderApp, WINDOW_DEFAULT_HEIGHT, WINDOW_DEFAULT_WIDTH};
 use gpui::{prelude::*, App, Application, Menu, MenuItem, SystemMenuType, WindowHandle};
 
@@ -24,6 +25,8 @@ fn update_menus(cx: &mut App) {
     let mut full_menus = vec![Menu {
         name: "LingPDF".into(),
         items: vec![
+            MenuItem::action(tr!("menu.preferences"), Preferences),
+            MenuItem::separator(),
             MenuItem::os_submenu("Services", SystemMenuType::Services),
             MenuItem::separator(),
             MenuItem::action("Quit", Quit),
@@ -38,7 +41,16 @@ fn main() {
     env_logger::init();
 
     let args: Vec<String> = std::env::args().collect();
-    let file_path = args.get(1).cloned();
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+    let requests = match cli::open_requests(&args[1..]) {
+        Ok(requests) => requests,
+        Err(e) => {
+            eprintln!("lingpdf: {}", e);
+            std::process::exit(1);
+        }
+    };
 
     Application::new().run(move |cx: &mut App| {
         cx.activate(true);
@@ -49,7 +61,7 @@ fn main() {
 
         let app_state = Arc::new(app::state::AppState::new());
         let language = app_state.get_language();
-        i18n::I18n::new(language);
+        i18n::I18n::new(&language);
 
         // Set initial menus
         update_menus(cx);
@@ -61,8 +73,6 @@ fn main() {
             ..Default::default()
         };
 
-        let file_path_clone = file_path.clone();
-
         let window_handle: WindowHandle<PdfReaderApp> = cx
             .open_window(
                 gpui::WindowOptions {
@@ -84,13 +94,10 @@ fn main() {
                     cx.new(move |cx| {
                         let mut app = PdfReaderApp::new(app_state.clone(), window, cx);
 
-                        if let Some(path_str) = &file_path_clone {
-                            let path = std::path::PathBuf::from(path_str);
-                            if path.exists() {
-                                app.open_file_in_new_tab(path, cx);
-                            } else {
-                                log::error!("File not found: {}", path_str);
-                            }
+                        if requests.is_empty() {
+                            app.open_startup_files(cx);
+                        } else {
+                            app.open_requests(requests, cx);
                         }
 
                         app
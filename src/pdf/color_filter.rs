@@ -0,0 +1,140 @@
+//! Colour filters applied to rendered pages, for reading in the dark or on
+//! tinted paper
+
+use serde::{Deserialize, Serialize};
+
+/// How page colours are changed on screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ColorFilter {
+    #[default]
+    None,
+    /// Every colour inverted, images included
+    Invert,
+    /// Lightness inverted with hues kept, and images left as they are
+    SmartInvert,
+    Sepia,
+    /// Black drawn as `foreground` and white paper as `background`, with the
+    /// greys in between blended
+    Custom {
+        foreground: [u8; 3],
+        background: [u8; 3],
+    },
+}
+
+/// Light grey text on a dark slate page
+pub const DEFAULT_CUSTOM_COLORS: ([u8; 3], [u8; 3]) = ([0xE0, 0xE0, 0xE0], [0x1E, 0x2A, 0x38]);
+
+/// A rectangle of pixels, (left, top, right, bottom) with the right and bottom
+/// edges excluded
+pub type PixelRect = (u32, u32, u32, u32);
+
+impl ColorFilter {
+    /// The filter after this one when cycling from the toolbar, using
+    /// `custom` for the custom filter's colours
+    pub fn next(&self, custom: ([u8; 3], [u8; 3])) -> Self {
+        match self {
+            ColorFilter::None => ColorFilter::Invert,
+            ColorFilter::Invert => ColorFilter::SmartInvert,
+            ColorFilter::SmartInvert => ColorFilter::Sepia,
+            ColorFilter::Sepia => ColorFilter::Custom {
+                foreground: custom.0,
+                background: custom.1,
+            },
+            ColorFilter::Custom { .. } => ColorFilter::None,
+        }
+    }
+
+    /// Whether images on the page should keep their colours
+    pub fn preserves_images(&self) -> bool {
+        matches!(self, ColorFilter::SmartInvert)
+    }
+
+    /// Apply the filter to RGBA `pixels` of an image `width` pixels wide,
+    /// leaving the pixels inside `keep` untouched
+    pub fn apply(&self, pixels: &mut [u8], width: u32, keep: &[PixelRect]) {
+        if *self == ColorFilter::None {
+            return;
+        }
+        for (index, pixel) in pixels.as_chunks_mut::<4>().0.iter_mut().enumerate() {
+            if !keep.is_empty() {
+                let (x, y) = ((index as u32) % width, (index as u32) / width);
+                if keep.iter().any(|&(left, top, right, bottom)| {
+                    (left..right).contains(&x) && (top..bottom).contains(&y)
+                }) {
+                    continue;
+                }
+            }
+            let [r, g, b] = self.map([pixel[0], pixel[1], pixel[2]]);
+            pixel[0] = r;
+            pixel[1] = g;
+            pixel[2] = b;
+        }
+    }
+
+    fn map(&self, [r, g, b]: [u8; 3]) -> [u8; 3] {
+        match *self {
+            ColorFilter::None => [r, g, b],
+            ColorFilter::Invert => [255 - r, 255 - g, 255 - b],
+            ColorFilter::SmartInvert => {
+                // Moving every channel by the same amount keeps hue and
+                // saturation while mirroring lightness, (max + min) / 2
+                let shift = 255 - r.max(g).max(b) as i32 - r.min(g).min(b) as i32;
+                [r, g, b].map(|c| (c as i32 + shift).clamp(0, 255) as u8)
+            }
+            ColorFilter::Sepia => {
+                let (r, g, b) = (r as f32, g as f32, b as f32);
+                [
+                    0.393 * r + 0.769 * g + 0.189 * b,
+                    0.349 * r + 0.686 * g + 0.168 * b,
+                    0.272 * r + 0.534 * g + 0.131 * b,
+                ]
+                .map(|c| c.round().min(255.0) as u8)
+            }
+            ColorFilter::Custom {
+                foreground,
+                background,
+            } => {
+                let t = (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32) / 255.0;
+                let mut out = [0; 3];
+                for (channel, out) in out.iter_mut().enumerate() {
+                    let (dark, light) = (foreground[channel] as f32, background[channel] as f32);
+                    *out = (dark + (light - dark) * t).round() as u8;
+                }
+                out
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filters() {
+        assert_eq!(ColorFilter::Invert.map([255, 255, 255]), [0, 0, 0]);
+        // Lightness flips but a pure hue stays put
+        assert_eq!(ColorFilter::SmartInvert.map([255, 255, 255]), [0, 0, 0]);
+        assert_eq!(ColorFilter::SmartInvert.map([255, 0, 0]), [255, 0, 0]);
+        assert_eq!(ColorFilter::SmartInvert.map([200, 100, 100]), [155, 55, 55]);
+        assert_eq!(ColorFilter::Sepia.map([255, 255, 255]), [255, 255, 239]);
+
+        assert_eq!(
+            ColorFilter::None.next(DEFAULT_CUSTOM_COLORS),
+            ColorFilter::Invert
+        );
+        let custom = ColorFilter::Sepia.next(DEFAULT_CUSTOM_COLORS);
+        assert_eq!(custom.map([0, 0, 0]), DEFAULT_CUSTOM_COLORS.0);
+        assert_eq!(custom.map([255, 255, 255]), DEFAULT_CUSTOM_COLORS.1);
+    }
+
+    #[test]
+    fn test_apply_keeps_images() {
+        // Two by two white pixels; the top left one is part of an image
+        let mut pixels = vec![255; 16];
+        ColorFilter::SmartInvert.apply(&mut pixels, 2, &[(0, 0, 1, 1)]);
+        assert_eq!(&pixels[0..4], &[255, 255, 255, 255]);
+        assert_eq!(&pixels[4..8], &[0, 0, 0, 255]);
+        assert_eq!(&pixels[12..16], &[0, 0, 0, 255]);
+    }
+}
@@ -0,0 +1,424 @@
+use super::forms::{FormField, FormValue};
+use super::{PdfError, Result};
+use std::path::Path;
+
+/// File formats supported for exporting and importing form data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormDataFormat {
+    Json,
+    Xfdf,
+    Fdf,
+}
+
+impl FormDataFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_string_lossy().to_lowercase();
+        match ext.as_str() {
+            "json" => Some(FormDataFormat::Json),
+            "xfdf" | "xml" => Some(FormDataFormat::Xfdf),
+            "fdf" => Some(FormDataFormat::Fdf),
+            _ => None,
+        }
+    }
+}
+
+/// Serialize form fields into the given format.
+/// `source_file` is recorded as the target document where the format supports it.
+pub fn export_form_data(
+    fields: &[FormField],
+    format: FormDataFormat,
+    source_file: Option<&str>,
+) -> Result<String> {
+    match format {
+        FormDataFormat::Json => export_json(fields),
+        FormDataFormat::Xfdf => Ok(export_xfdf(fields, source_file)),
+        FormDataFormat::Fdf => Ok(export_fdf(fields, source_file)),
+    }
+}
+
+/// Parse form data into (field name, value) pairs, in file order.
+/// FDF files are export-only.
+pub fn parse_form_data(content: &str, format: FormDataFormat) -> Result<Vec<(String, FormValue)>> {
+    match format {
+        FormDataFormat::Json => parse_json(content),
+        FormDataFormat::Xfdf => parse_xfdf(content),
+        FormDataFormat::Fdf => Err(PdfError::FormDataError(
+            "Importing FDF files is not supported, use JSON or XFDF".to_string(),
+        )),
+    }
+}
+
+fn export_json(fields: &[FormField]) -> Result<String> {
+    let mut map = serde_json::Map::new();
+    for field in fields {
+        let value = match &field.value {
+            FormValue::Text(text) => serde_json::Value::String(text.clone()),
+            FormValue::Bool(checked) => serde_json::Value::Bool(*checked),
+            FormValue::Empty => serde_json::Value::Null,
+        };
+        map.insert(field.name.clone(), value);
+    }
+
+    serde_json::to_string_pretty(&serde_json::Value::Object(map))
+        .map_err(|e| PdfError::FormDataError(format!("Failed to serialize JSON: {}", e)))
+}
+
+fn parse_json(content: &str) -> Result<Vec<(String, FormValue)>> {
+    let value: serde_json::Value = serde_json::from_str(content)
+        .map_err(|e| PdfError::FormDataError(format!("Invalid JSON: {}", e)))?;
+
+    let map = value
+        .as_object()
+        .ok_or_else(|| PdfError::FormDataError("Expected a JSON object".to_string()))?;
+
+    let mut values = Vec::new();
+    for (name, value) in map {
+        let value = match value {
+            serde_json::Value::String(text) => FormValue::Text(text.clone()),
+            serde_json::Value::Bool(checked) => FormValue::Bool(*checked),
+            serde_json::Value::Number(number) => FormValue::Text(number.to_string()),
+            serde_json::Value::Null => FormValue::Empty,
+            _ => {
+                return Err(PdfError::FormDataError(format!(
+                    "Unsupported value for field '{}'",
+                    name
+                )))
+            }
+        };
+        values.push((name.clone(), value));
+    }
+
+    Ok(values)
+}
+
+/// Value written to XFDF/FDF for a field, `None` when the field has no value
+fn field_value_string(value: &FormValue) -> Option<String> {
+    match value {
+        FormValue::Text(text) => Some(text.clone()),
+        FormValue::Bool(true) => Some("Yes".to_string()),
+        FormValue::Bool(false) => Some("Off".to_string()),
+        FormValue::Empty => None,
+    }
+}
+
+fn export_xfdf(fields: &[FormField], source_file: Option<&str>) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<xfdf xmlns=\"http://ns.adobe.com/xfdf/\" xml:space=\"preserve\">\n");
+    if let Some(file) = source_file {
+        out.push_str(&format!("  <f href=\"{}\"/>\n", xml_escape(file)));
+    }
+    out.push_str("  <fields>\n");
+
+    // XFDF nests hierarchical names ("address.city") as nested <field> elements
+    let entries: Vec<(Vec<&str>, Option<String>)> = fields
+        .iter()
+        .map(|f| (f.name.split('.').collect(), field_value_string(&f.value)))
+        .collect();
+    write_xfdf_fields(&mut out, &entries, 2);
+
+    out.push_str("  </fields>\n");
+    out.push_str("</xfdf>\n");
+    out
+}
+
+fn write_xfdf_fields(out: &mut String, entries: &[(Vec<&str>, Option<String>)], depth: usize) {
+    let indent = "  ".repeat(depth);
+    let mut seen: Vec<&str> = Vec::new();
+
+    for (segments, _) in entries {
+        let head = segments[0];
+        if seen.contains(&head) {
+            continue;
+        }
+        seen.push(head);
+
+        out.push_str(&format!(
+            "{}<field name=\"{}\">\n",
+            indent,
+            xml_escape(head)
+        ));
+
+        let mut children = Vec::new();
+        for (other_segments, value) in entries.iter().filter(|(s, _)| s[0] == head) {
+            if other_segments.len() == 1 {
+                if let Some(value) = value {
+                    out.push_str(&format!(
+                        "{}  <value>{}</value>\n",
+                        indent,
+                        xml_escape(value)
+                    ));
+                }
+            } else {
+                children.push((other_segments[1..].to_vec(), value.clone()));
+            }
+        }
+        if !children.is_empty() {
+            write_xfdf_fields(out, &children, depth + 1);
+        }
+
+        out.push_str(&format!("{}</field>\n", indent));
+    }
+}
+
+fn parse_xfdf(content: &str) -> Result<Vec<(String, FormValue)>> {
+    let mut values = Vec::new();
+    let mut name_stack: Vec<String> = Vec::new();
+    let mut rest = content;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        let end = rest
+            .find('>')
+            .ok_or_else(|| PdfError::FormDataError("Unterminated XML tag".to_string()))?;
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        if tag.starts_with('?') || tag.starts_with('!') {
+            continue;
+        }
+
+        if let Some(closing) = tag.strip_prefix('/') {
+            if closing.trim() == "field" {
+                name_stack.pop();
+            }
+            continue;
+        }
+
+        let self_closing = tag.ends_with('/');
+        let tag = tag.trim_end_matches('/');
+        let element = tag.split_whitespace().next().unwrap_or("");
+
+        match element {
+            "field" => {
+                let name = xml_attribute(tag, "name").ok_or_else(|| {
+                    PdfError::FormDataError("<field> element without a name".to_string())
+                })?;
+                if !self_closing {
+                    name_stack.push(name);
+                }
+            }
+            "value" | "value-richtext" => {
+                if name_stack.is_empty() {
+                    return Err(PdfError::FormDataError(
+                        "<value> element outside of a <field>".to_string(),
+                    ));
+                }
+                let text = if self_closing {
+                    String::new()
+                } else {
+                    let close = format!("</{}>", element);
+                    let end = rest
+                        .find(&close)
+                        .ok_or_else(|| PdfError::FormDataError(format!("Missing {}", close)))?;
+                    let text = xml_unescape(&rest[..end]);
+                    rest = &rest[end + close.len()..];
+                    text
+                };
+                values.push((name_stack.join("."), FormValue::Text(text)));
+            }
+            _ => {}
+        }
+    }
+
+    if values.is_empty() && !content.contains("<xfdf") {
+        return Err(PdfError::FormDataError("Not an XFDF document".to_string()));
+    }
+
+    Ok(values)
+}
+
+fn xml_attribute(tag: &str, attribute: &str) -> Option<String> {
+    let pattern = format!("{}=", attribute);
+    let pos = tag.find(&pattern)?;
+    let after = &tag[pos + pattern.len()..];
+    let quote = after.chars().next()?;
+    if quote != '"' && quote != '\'' {
+        return None;
+    }
+    let value_end = after[1..].find(quote)?;
+    Some(xml_unescape(&after[1..1 + value_end]))
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn xml_unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find('&') {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn export_fdf(fields: &[FormField], source_file: Option<&str>) -> String {
+    let mut out = String::new();
+    out.push_str("%FDF-1.2\n");
+    out.push_str("1 0 obj\n<< /FDF << /Fields [\n");
+
+    for field in fields {
+        out.push_str(&format!("<< /T {}", fdf_string(&field.name)));
+        match &field.value {
+            FormValue::Bool(checked) => {
+                out.push_str(if *checked { " /V /Yes" } else { " /V /Off" });
+            }
+            FormValue::Text(text) => {
+                out.push_str(&format!(" /V {}", fdf_string(text)));
+            }
+            FormValue::Empty => {}
+        }
+        out.push_str(" >>\n");
+    }
+
+    out.push(']');
+    if let Some(file) = source_file {
+        out.push_str(&format!(" /F {}", fdf_string(file)));
+    }
+    out.push_str(" >> >>\nendobj\ntrailer\n<< /Root 1 0 R >>\n%%EOF\n");
+    out
+}
+
+/// Encode a PDF string literal, falling back to a UTF-16BE hex string for non-ASCII text
+fn fdf_string(text: &str) -> String {
+    if text.is_ascii() {
+        let escaped = text
+            .replace('\\', "\\\\")
+            .replace('(', "\\(")
+            .replace(')', "\\)")
+            .replace('\r', "\\r")
+            .replace('\n', "\\n");
+        format!("({})", escaped)
+    } else {
+        let mut hex = String::from("<FEFF");
+        for unit in text.encode_utf16() {
+            hex.push_str(&format!("{:04X}", unit));
+        }
+        hex.push('>');
+        hex
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdf::forms::FormFieldKind;
+
+    fn field(name: &str, kind: FormFieldKind, value: FormValue) -> FormField {
+        FormField {
+            name: name.to_string(),
+            kind,
+            value,
+            page: 0,
+            read_only: false,
+        }
+    }
+
+    #[test]
+    fn test_xfdf_round_trip_with_nested_names() {
+        let fields = vec![
+            field(
+                "name",
+                FormFieldKind::Text,
+                FormValue::Text("Ana & <Bo>".to_string()),
+            ),
+            field(
+                "address.city",
+                FormFieldKind::Text,
+                FormValue::Text("Madrid".to_string()),
+            ),
+            field(
+                "address.zip",
+                FormFieldKind::Text,
+                FormValue::Text("28001".to_string()),
+            ),
+            field("agree", FormFieldKind::Checkbox, FormValue::Bool(true)),
+        ];
+
+        let xfdf = export_form_data(&fields, FormDataFormat::Xfdf, Some("form.pdf")).unwrap();
+        let parsed = parse_form_data(&xfdf, FormDataFormat::Xfdf).unwrap();
+
+        assert_eq!(
+            parsed,
+            vec![
+                (
+                    "name".to_string(),
+                    FormValue::Text("Ana & <Bo>".to_string())
+                ),
+                (
+                    "address.city".to_string(),
+                    FormValue::Text("Madrid".to_string())
+                ),
+                (
+                    "address.zip".to_string(),
+                    FormValue::Text("28001".to_string())
+                ),
+                ("agree".to_string(), FormValue::Text("Yes".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        // Not in name order, which the fields must keep
+        let fields = vec![
+            field("choice", FormFieldKind::RadioButton, FormValue::Empty),
+            field("agree", FormFieldKind::Checkbox, FormValue::Bool(false)),
+        ];
+
+        let json = export_form_data(&fields, FormDataFormat::Json, None).unwrap();
+        let parsed = parse_form_data(&json, FormDataFormat::Json).unwrap();
+
+        assert_eq!(
+            parsed,
+            vec![
+                ("choice".to_string(), FormValue::Empty),
+                ("agree".to_string(), FormValue::Bool(false)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fdf_encodes_non_ascii_as_utf16() {
+        let fields = vec![field(
+            "nombre",
+            FormFieldKind::Text,
+            FormValue::Text("José".to_string()),
+        )];
+        let fdf = export_form_data(&fields, FormDataFormat::Fdf, None).unwrap();
+        assert!(fdf.contains("/T (nombre) /V <FEFF004A006F007300E9>"));
+    }
+}
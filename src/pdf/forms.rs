@@ -0,0 +1,318 @@
+use super::{PdfDocument, PdfError, Result};
+use pdfium_render::prelude::*;
+use std::path::Path;
+
+/// Kind of an interactive form field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormFieldKind {
+    Text,
+    Checkbox,
+    RadioButton,
+    ComboBox,
+    ListBox,
+    PushButton,
+    Signature,
+    Unknown,
+}
+
+/// Value of a form field as exchanged with form data files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormValue {
+    Text(String),
+    Bool(bool),
+    Empty,
+}
+
+/// A named form field. Radio button and checkbox groups sharing a name
+/// are reported once, with the value of the checked control.
+#[derive(Debug, Clone)]
+pub struct FormField {
+    pub name: String,
+    #[allow(dead_code)]
+    pub kind: FormFieldKind,
+    pub value: FormValue,
+    #[allow(dead_code)]
+    pub page: usize,
+    #[allow(dead_code)]
+    pub read_only: bool,
+}
+
+/// Outcome of filling a form from imported data
+#[derive(Debug, Clone, Default)]
+pub struct FormFillReport {
+    /// Names of fields that were set
+    pub filled: Vec<String>,
+    /// Names present in the data that match no field in the document
+    pub unmatched: Vec<String>,
+    /// Fields that exist but could not be set, with the reason
+    pub skipped: Vec<(String, String)>,
+}
+
+impl FormFieldKind {
+    fn from_pdfium(field_type: PdfFormFieldType) -> Self {
+        match field_type {
+            PdfFormFieldType::Text => FormFieldKind::Text,
+            PdfFormFieldType::Checkbox => FormFieldKind::Checkbox,
+            PdfFormFieldType::RadioButton => FormFieldKind::RadioButton,
+            PdfFormFieldType::ComboBox => FormFieldKind::ComboBox,
+            PdfFormFieldType::ListBox => FormFieldKind::ListBox,
+            PdfFormFieldType::PushButton => FormFieldKind::PushButton,
+            PdfFormFieldType::Signature => FormFieldKind::Signature,
+            PdfFormFieldType::Unknown => FormFieldKind::Unknown,
+        }
+    }
+
+    /// Whether the field carries a value worth exporting
+    fn has_value(&self) -> bool {
+        !matches!(
+            self,
+            FormFieldKind::PushButton | FormFieldKind::Signature | FormFieldKind::Unknown
+        )
+    }
+}
+
+fn is_truthy(text: &str) -> bool {
+    matches!(
+        text.trim().to_lowercase().as_str(),
+        "yes" | "on" | "true" | "1" | "checked"
+    )
+}
+
+fn field_value(field: &PdfFormField) -> FormValue {
+    let text = |value: Option<String>| value.map(FormValue::Text).unwrap_or(FormValue::Empty);
+
+    match field {
+        PdfFormField::Text(f) => text(f.value()),
+        PdfFormField::ComboBox(f) => text(f.value()),
+        PdfFormField::ListBox(f) => text(f.value()),
+        PdfFormField::Checkbox(f) => FormValue::Bool(f.is_checked().unwrap_or(false)),
+        PdfFormField::RadioButton(f) => {
+            if f.is_checked().unwrap_or(false) {
+                text(f.group_value())
+            } else {
+                FormValue::Empty
+            }
+        }
+        _ => FormValue::Empty,
+    }
+}
+
+impl PdfDocument {
+    /// Enumerate the interactive form fields of the document, in page order
+    pub fn form_fields(&self) -> Result<Vec<FormField>> {
+        let path_str = self.path.to_string_lossy().to_string();
+
+        let pdfium = Self::get_pdfium()?;
+        let doc = pdfium
+            .load_pdf_from_file(&path_str, None)
+            .map_err(|e| PdfError::OpenError(format!("Failed to load PDF: {}", e)))?;
+
+        let mut fields: Vec<FormField> = Vec::new();
+
+        for (page_index, page) in doc.pages().iter().enumerate() {
+            for annotation in page.annotations().iter() {
+                let Some(field) = annotation.as_form_field() else {
+                    continue;
+                };
+
+                let kind = FormFieldKind::from_pdfium(field.field_type());
+                let Some(name) = field.name().filter(|n| !n.is_empty()) else {
+                    continue;
+                };
+                if !kind.has_value() {
+                    continue;
+                }
+
+                let value = field_value(field);
+
+                // Checkbox and radio groups have one widget per control but share a name;
+                // keep a single entry and prefer the value of a checked control.
+                if let Some(existing) = fields.iter_mut().find(|f| f.name == name) {
+                    let unset = matches!(existing.value, FormValue::Empty | FormValue::Bool(false));
+                    if unset && !matches!(value, FormValue::Empty | FormValue::Bool(false)) {
+                        existing.value = value;
+                    }
+                    continue;
+                }
+
+                fields.push(FormField {
+                    name,
+                    kind,
+                    value,
+                    page: page_index,
+                    read_only: field.is_read_only(),
+                });
+            }
+        }
+
+        Ok(fields)
+    }
+
+    /// Fill the form with the given values and save the result to `output`.
+    /// The document on disk backing this `PdfDocument` is left untouched.
+    pub fn fill_form<P: AsRef<Path>>(
+        &self,
+        values: &[(String, FormValue)],
+        output: P,
+    ) -> Result<FormFillReport> {
+        let path_str = self.path.to_string_lossy().to_string();
+
+        let pdfium = Self::get_pdfium()?;
+        let doc = pdfium
+            .load_pdf_from_file(&path_str, None)
+            .map_err(|e| PdfError::OpenError(format!("Failed to load PDF: {}", e)))?;
+
+        let mut report = FormFillReport::default();
+        let mut seen: Vec<String> = Vec::new();
+        // Radio controls to resolve once their export values are known: (name, page, annotation)
+        let mut radio_controls: Vec<(String, usize, usize)> = Vec::new();
+
+        for (page_index, page) in doc.pages().iter().enumerate() {
+            for (annotation_index, mut annotation) in page.annotations().iter().enumerate() {
+                let Some(field) = annotation.as_form_field_mut() else {
+                    continue;
+                };
+                let Some(name) = field.name().filter(|n| !n.is_empty()) else {
+                    continue;
+                };
+                let Some((_, value)) = values.iter().find(|(n, _)| *n == name) else {
+                    continue;
+                };
+
+                let first_widget = !seen.contains(&name);
+                if first_widget {
+                    seen.push(name.clone());
+                }
+
+                if field.is_read_only() {
+                    if first_widget {
+                        report.skipped.push((name, "read only".to_string()));
+                    }
+                    continue;
+                }
+
+                let result = match field {
+                    PdfFormField::Text(f) => {
+                        let text = match value {
+                            FormValue::Text(text) => text.clone(),
+                            FormValue::Bool(checked) => checked.to_string(),
+                            FormValue::Empty => String::new(),
+                        };
+                        f.set_value(&text)
+                    }
+                    PdfFormField::Checkbox(f) => {
+                        let checked = match value {
+                            FormValue::Bool(checked) => *checked,
+                            FormValue::Text(text) => is_truthy(text),
+                            FormValue::Empty => false,
+                        };
+                        f.set_checked(checked)
+                    }
+                    PdfFormField::RadioButton(_) => {
+                        if matches!(value, FormValue::Text(_)) {
+                            radio_controls.push((name, page_index, annotation_index));
+                        } else if first_widget {
+                            report
+                                .skipped
+                                .push((name, "radio buttons need an option value".to_string()));
+                        }
+                        continue;
+                    }
+                    PdfFormField::ComboBox(_) | PdfFormField::ListBox(_) => {
+                        if first_widget {
+                            report
+                                .skipped
+                                .push((name, "choice fields cannot be set".to_string()));
+                        }
+                        continue;
+                    }
+                    _ => {
+                        if first_widget {
+                            report
+                                .skipped
+                                .push((name, "unsupported field type".to_string()));
+                        }
+                        continue;
+                    }
+                };
+
+                match result {
+                    Ok(()) => {
+                        if !report.filled.contains(&name) {
+                            report.filled.push(name);
+                        }
+                    }
+                    Err(e) => report.skipped.push((name, e.to_string())),
+                }
+            }
+        }
+
+        if !radio_controls.is_empty() {
+            // Pdfium does not expose a radio control's export value, but checking a control
+            // sets the group value to it. Probe every control on a scratch copy of the
+            // document so the document being filled only sees the final selection.
+            let scratch = pdfium
+                .load_pdf_from_file(&path_str, None)
+                .map_err(|e| PdfError::OpenError(format!("Failed to load PDF: {}", e)))?;
+            let export_values: Vec<Option<String>> = radio_controls
+                .iter()
+                .map(|(_, page_index, annotation_index)| {
+                    let page = scratch.pages().get(*page_index as PdfPageIndex).ok()?;
+                    let mut annotation = page.annotations().get(*annotation_index).ok()?;
+                    match annotation.as_form_field_mut()? {
+                        PdfFormField::RadioButton(f) => {
+                            f.set_checked().ok()?;
+                            f.group_value()
+                        }
+                        _ => None,
+                    }
+                })
+                .collect();
+
+            for (control, export_value) in radio_controls.iter().zip(export_values) {
+                let (name, page_index, annotation_index) = control;
+                let wanted = values
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .map(|(_, v)| v.clone());
+                if report.filled.contains(name)
+                    || export_value.is_none()
+                    || wanted != export_value.map(FormValue::Text)
+                {
+                    continue;
+                }
+
+                let page = doc
+                    .pages()
+                    .get(*page_index as PdfPageIndex)
+                    .map_err(|e| PdfError::RenderError(format!("Failed to load page: {}", e)))?;
+                let mut annotation = page.annotations().get(*annotation_index).map_err(|e| {
+                    PdfError::RenderError(format!("Failed to load annotation: {}", e))
+                })?;
+                if let Some(PdfFormField::RadioButton(f)) = annotation.as_form_field_mut() {
+                    match f.set_checked() {
+                        Ok(()) => report.filled.push(name.clone()),
+                        Err(e) => report.skipped.push((name.clone(), e.to_string())),
+                    }
+                }
+            }
+        }
+
+        for (name, _) in values {
+            if !seen.contains(name) {
+                report.unmatched.push(name.clone());
+            } else if !report.filled.contains(name)
+                && !report.skipped.iter().any(|(n, _)| n == name)
+            {
+                report
+                    .skipped
+                    .push((name.clone(), "no matching option".to_string()));
+            }
+        }
+
+        doc.save_to_file(output.as_ref())
+            .map_err(|e| PdfError::SaveError(format!("Failed to save PDF: {}", e)))?;
+
+        Ok(report)
+    }
+}
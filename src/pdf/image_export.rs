@@ -0,0 +1,280 @@
+use super::split::sanitize_file_name;
+use super::{PdfDocument, PdfError, Result};
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, RgbaImage};
+use pdfium_render::prelude::*;
+use std::path::Path;
+
+/// File name template used when none is given
+pub const DEFAULT_IMAGE_TEMPLATE: &str = "{name}-{page}";
+
+pub const MIN_EXPORT_DPI: f32 = 18.0;
+pub const MAX_EXPORT_DPI: f32 = 1200.0;
+
+/// Points per inch in PDF user space
+const POINTS_PER_INCH: f32 = 72.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageImageFormat {
+    Png,
+    Jpeg,
+}
+
+impl PageImageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            PageImageFormat::Png => "png",
+            PageImageFormat::Jpeg => "jpg",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ImageExportOptions {
+    pub dpi: f32,
+    pub format: PageImageFormat,
+    /// JPEG quality from 1 to 100
+    pub jpeg_quality: u8,
+    /// Keep the page background transparent; PNG only
+    pub transparent: bool,
+}
+
+/// Expand an image file name template for one page. Supported placeholders:
+/// `{name}` source file name, `{page}` page number padded to the page count's width.
+/// A template without `{page}` gets `-{page}` appended so pages don't overwrite each other.
+pub fn image_file_name(
+    template: &str,
+    source_name: &str,
+    page: usize,
+    page_count: usize,
+    format: PageImageFormat,
+) -> String {
+    let width = page_count.to_string().len();
+    let number = format!("{:0width$}", page + 1, width = width);
+    let template = if template.contains("{page}") {
+        template.to_string()
+    } else if template.trim().is_empty() {
+        String::from("{page}")
+    } else {
+        format!("{}-{{page}}", template)
+    };
+    let name = sanitize_file_name(
+        &template
+            .replace("{name}", source_name)
+            .replace("{page}", &number),
+    );
+    let name = if name.is_empty() { number } else { name };
+    format!("{}.{}", name, format.extension())
+}
+
+/// Encode an image as PNG in memory, e.g. for the clipboard
+pub fn encode_png(image: &RgbaImage) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    image
+        .write_to(
+            &mut std::io::Cursor::new(&mut bytes),
+            image::ImageFormat::Png,
+        )
+        .map_err(|e| PdfError::RenderError(format!("Failed to encode PNG: {}", e)))?;
+    Ok(bytes)
+}
+
+impl PdfDocument {
+    /// Render a page at `dpi`, as straight RGBA
+    pub fn render_page_image(
+        &self,
+        page_num: usize,
+        dpi: f32,
+        transparent: bool,
+    ) -> Result<RgbaImage> {
+        if page_num >= self.page_count {
+            return Err(PdfError::InvalidPage(page_num));
+        }
+
+        let path_str = self.path.to_string_lossy().to_string();
+
+        let pdfium = Self::get_pdfium()?;
+        let doc = pdfium
+            .load_pdf_from_file(&path_str, None)
+            .map_err(|e| PdfError::RenderError(format!("Failed to load PDF: {}", e)))?;
+
+        let page = doc.pages().get(page_num as PdfPageIndex).map_err(|e| {
+            PdfError::RenderError(format!("Failed to load page {}: {}", page_num, e))
+        })?;
+
+        let clear_color = if transparent {
+            PdfColor::new(255, 255, 255, 0)
+        } else {
+            PdfColor::WHITE
+        };
+        let dpi = dpi.clamp(MIN_EXPORT_DPI, MAX_EXPORT_DPI);
+        let render_config = PdfRenderConfig::new()
+            .scale_page_by_factor(dpi / POINTS_PER_INCH)
+            .render_annotations(true)
+            .render_form_data(true)
+            .set_clear_color(clear_color);
+
+        let bitmap = page
+            .render_with_config(&render_config)
+            .map_err(|e| PdfError::RenderError(format!("Failed to render page: {}", e)))?;
+
+        Ok(bitmap.as_image().into_rgba8())
+    }
+
+    /// Render the part of a page inside `rect` at `dpi`, turned by `rotation`
+    /// degrees clockwise. `rect` is (left, bottom, right, top) in points on the
+    /// page as rendered, y up.
+    pub fn render_region_image(
+        &self,
+        page_num: usize,
+        rect: (f32, f32, f32, f32),
+        dpi: f32,
+        rotation: usize,
+    ) -> Result<RgbaImage> {
+        if page_num >= self.page_count {
+            return Err(PdfError::InvalidPage(page_num));
+        }
+
+        let path_str = self.path.to_string_lossy().to_string();
+
+        let pdfium = Self::get_pdfium()?;
+        let doc = pdfium
+            .load_pdf_from_file(&path_str, None)
+            .map_err(|e| PdfError::RenderError(format!("Failed to load PDF: {}", e)))?;
+
+        let page = doc.pages().get(page_num as PdfPageIndex).map_err(|e| {
+            PdfError::RenderError(format!("Failed to load page {}: {}", page_num, e))
+        })?;
+
+        let scale = dpi.clamp(MIN_EXPORT_DPI, MAX_EXPORT_DPI) / POINTS_PER_INCH;
+        let page_width = page.width().value;
+        let page_height = page.height().value;
+
+        // Pixel bounds of the region on the page rendered at `scale`, y down
+        let (left, bottom, right, top) = rect;
+        let x0 = (left.min(right) * scale).floor().max(0.0);
+        let y0 = ((page_height - top.max(bottom)) * scale).floor().max(0.0);
+        let x1 = (left.max(right) * scale)
+            .ceil()
+            .min((page_width * scale).round());
+        let y1 = ((page_height - top.min(bottom)) * scale)
+            .ceil()
+            .min((page_height * scale).round());
+        if x1 <= x0 || y1 <= y0 {
+            return Err(PdfError::RenderError(String::from(
+                "Region is outside the page",
+            )));
+        }
+
+        // Scale the page and shift the region's corner to the bitmap's origin,
+        // so only the region is rasterised. Pdfium draws form fields only for
+        // whole pages; they show here as their appearance streams.
+        let render_config = PdfRenderConfig::new()
+            .set_fixed_size((x1 - x0) as Pixels, (y1 - y0) as Pixels)
+            .render_annotations(true)
+            .transform(scale, 0.0, 0.0, scale, -x0, -y0)
+            .map_err(|e| PdfError::RenderError(format!("Failed to place region: {}", e)))?;
+
+        let bitmap = page
+            .render_with_config(&render_config)
+            .map_err(|e| PdfError::RenderError(format!("Failed to render page: {}", e)))?;
+        let region = bitmap.as_image().into_rgba8();
+
+        Ok(match rotation {
+            90 => image::imageops::rotate90(&region),
+            180 => image::imageops::rotate180(&region),
+            270 => image::imageops::rotate270(&region),
+            _ => region,
+        })
+    }
+
+    /// Render a page and save it to `output` as PNG or JPEG
+    pub fn export_page_image(
+        &self,
+        page_num: usize,
+        options: &ImageExportOptions,
+        output: &Path,
+    ) -> Result<()> {
+        let transparent = options.transparent && options.format == PageImageFormat::Png;
+        let image = self.render_page_image(page_num, options.dpi, transparent)?;
+
+        let save_error = |e: image::ImageError| {
+            PdfError::SaveError(format!("Failed to write {}: {}", output.display(), e))
+        };
+        match options.format {
+            PageImageFormat::Png => image
+                .save_with_format(output, image::ImageFormat::Png)
+                .map_err(save_error),
+            PageImageFormat::Jpeg => {
+                let file = std::fs::File::create(output).map_err(|e| {
+                    PdfError::SaveError(format!("Failed to create {}: {}", output.display(), e))
+                })?;
+                let mut encoder = JpegEncoder::new_with_quality(
+                    std::io::BufWriter::new(file),
+                    options.jpeg_quality.clamp(1, 100),
+                );
+                encoder
+                    .encode_image(&DynamicImage::ImageRgba8(image).into_rgb8())
+                    .map_err(save_error)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_file_name() {
+        assert_eq!(
+            image_file_name(
+                DEFAULT_IMAGE_TEMPLATE,
+                "report",
+                4,
+                120,
+                PageImageFormat::Png
+            ),
+            "report-005.png"
+        );
+        assert_eq!(
+            image_file_name("scan/{page}", "report", 0, 9, PageImageFormat::Jpeg),
+            "scan_1.jpg"
+        );
+        // Without `{page}` each page still gets its own file
+        assert_eq!(
+            image_file_name("{name}", "report", 2, 12, PageImageFormat::Png),
+            "report-03.png"
+        );
+        assert_ne!(
+            image_file_name("scan", "report", 0, 2, PageImageFormat::Png),
+            image_file_name("scan", "report", 1, 2, PageImageFormat::Png)
+        );
+    }
+
+    #[test]
+    #[ignore = "needs libpdfium"]
+    fn test_render_region_image() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("blank.pdf");
+        crate::pdf::write_blank_pdf(&path, 1);
+        let doc = PdfDocument::open(&path).unwrap();
+
+        // One by two inches at 144 dpi, turned on its side
+        let image = doc
+            .render_region_image(0, (72.0, 72.0, 144.0, 216.0), 144.0, 90)
+            .unwrap();
+        assert!((288..=290).contains(&image.width()));
+        assert_eq!(image.height(), 144);
+        assert_eq!(image.get_pixel(10, 10).0, [255, 255, 255, 255]);
+
+        // A region running off the A4 page is cut at its edge
+        let image = doc
+            .render_region_image(0, (500.0, 700.0, 700.0, 900.0), 72.0, 0)
+            .unwrap();
+        assert_eq!(image.width(), 95);
+        assert!(doc
+            .render_region_image(0, (700.0, 0.0, 800.0, 10.0), 72.0, 0)
+            .is_err());
+    }
+}
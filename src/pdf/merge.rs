@@ -0,0 +1,178 @@
+use super::outline_writer::write_outline;
+use super::page_range::parse_page_range;
+use super::{read_outline, OutlineItem, PdfDocument, PdfError, Result};
+use pdfium_render::prelude::*;
+use std::path::{Path, PathBuf};
+
+/// A file to merge, with an optional page range such as "1-3,5"
+#[derive(Debug, Clone)]
+pub struct MergeInput {
+    pub path: PathBuf,
+    pub pages: Option<String>,
+}
+
+impl MergeInput {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, pages: None }
+    }
+
+    /// Title of the top-level bookmark for this file
+    fn title(&self) -> String {
+        self.path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.path.display().to_string())
+    }
+}
+
+/// Rebase an outline onto the merged document. `selection` holds the source page
+/// of each merged page; bookmarks to pages left out are dropped and their
+/// children moved up a level.
+pub(super) fn remap_outline(
+    items: &[OutlineItem],
+    selection: &[usize],
+    offset: usize,
+) -> Vec<OutlineItem> {
+    let mut remapped = Vec::new();
+    for item in items {
+        let children = remap_outline(&item.children, selection, offset);
+        match selection.iter().position(|page| *page == item.page) {
+            Some(position) => remapped.push(OutlineItem {
+                title: item.title.clone(),
+                page: offset + position,
+                children,
+            }),
+            None => remapped.extend(children),
+        }
+    }
+    remapped
+}
+
+/// Concatenate `inputs` into a new PDF at `output`. Each file gets a top-level
+/// bookmark holding its own outline. Returns the number of pages written.
+pub fn merge_pdfs(inputs: &[MergeInput], output: &Path) -> Result<usize> {
+    let pdfium = PdfDocument::get_pdfium()?;
+    let mut merged = pdfium
+        .create_new_pdf()
+        .map_err(|e| PdfError::SaveError(format!("Failed to create PDF: {}", e)))?;
+    let mut outline = Vec::new();
+
+    for input in inputs {
+        let path_str = input.path.to_string_lossy().to_string();
+        let source = pdfium.load_pdf_from_file(&path_str, None).map_err(|e| {
+            PdfError::OpenError(format!("Failed to load {}: {}", input.path.display(), e))
+        })?;
+
+        let page_count = source.pages().len() as usize;
+        let selection = parse_page_range(input.pages.as_deref().unwrap_or(""), page_count)?;
+        if selection.is_empty() {
+            continue;
+        }
+
+        let offset = merged.pages().len() as usize;
+        let page_list: Vec<String> = selection.iter().map(|p| (p + 1).to_string()).collect();
+        merged
+            .pages_mut()
+            .copy_pages_from_document(&source, &page_list.join(","), offset as PdfPageIndex)
+            .map_err(|e| {
+                PdfError::SaveError(format!(
+                    "Failed to import pages from {}: {}",
+                    input.path.display(),
+                    e
+                ))
+            })?;
+
+        // Pdfium is bound for the whole merge, so the outline comes from the
+        // loaded source rather than a second `PdfDocument::open`
+        let source_outline = read_outline(&source);
+        outline.push(OutlineItem {
+            title: input.title(),
+            page: offset,
+            children: remap_outline(&source_outline, &selection, offset),
+        });
+    }
+
+    let page_count = merged.pages().len() as usize;
+    if page_count == 0 {
+        return Err(PdfError::SaveError("No pages to merge".to_string()));
+    }
+
+    merged
+        .save_to_file(output)
+        .map_err(|e| PdfError::SaveError(format!("Failed to save PDF: {}", e)))?;
+    drop(merged);
+
+    write_outline(output, &outline)?;
+
+    Ok(page_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(title: &str, page: usize, children: Vec<OutlineItem>) -> OutlineItem {
+        OutlineItem {
+            title: title.to_string(),
+            page,
+            children,
+        }
+    }
+
+    #[test]
+    fn test_remap_outline() {
+        let outline = vec![
+            item("Intro", 0, Vec::new()),
+            item("Part", 1, vec![item("Chapter", 3, Vec::new())]),
+        ];
+        // Pages 4 and 1 of the source, after 10 pages from earlier files
+        let remapped = remap_outline(&outline, &[3, 0], 10);
+
+        assert_eq!(remapped.len(), 2);
+        assert_eq!(
+            (remapped[0].title.as_str(), remapped[0].page),
+            ("Intro", 11)
+        );
+        assert_eq!(
+            (remapped[1].title.as_str(), remapped[1].page),
+            ("Chapter", 10)
+        );
+    }
+
+    #[test]
+    #[ignore = "needs libpdfium"]
+    fn test_merge_pdfs() {
+        let dir = tempfile::tempdir().unwrap();
+        let (first, second) = (dir.path().join("first.pdf"), dir.path().join("second.pdf"));
+        crate::pdf::write_blank_pdf(&first, 3);
+        crate::pdf::write_blank_pdf(&second, 2);
+        write_outline(&first, &[item("Middle", 1, Vec::new())]).unwrap();
+
+        let output = dir.path().join("merged.pdf");
+        let inputs = vec![
+            MergeInput {
+                path: first,
+                pages: Some("2-3".to_string()),
+            },
+            MergeInput::new(second),
+        ];
+        assert_eq!(merge_pdfs(&inputs, &output).unwrap(), 4);
+
+        let merged = PdfDocument::open(&output).unwrap();
+        assert_eq!(merged.page_count(), 4);
+        let outline = merged.get_outline().unwrap();
+        let titles: Vec<(&str, usize)> = outline
+            .iter()
+            .map(|item| (item.title.as_str(), item.page))
+            .collect();
+        assert_eq!(titles, vec![("first", 0), ("second", 2)]);
+        assert_eq!(outline[0].children.len(), 1);
+        assert_eq!(
+            (
+                outline[0].children[0].title.as_str(),
+                outline[0].children[0].page
+            ),
+            ("Middle", 0)
+        );
+    }
+}
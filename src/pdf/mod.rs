@@ -1,6 +1,18 @@
+pub mod color_filter;
+pub mod form_data;
+pub mod forms;
+pub mod image_export;
 pub mod loader;
+pub mod merge;
+pub mod organizer;
+pub mod outline_writer;
+pub mod page_range;
 pub mod renderer;
+pub mod split;
+pub mod text_export;
+pub mod text_layout;
 
+use color_filter::ColorFilter;
 use std::path::Path;
 use thiserror::Error;
 
@@ -17,6 +29,15 @@ pub enum PdfError {
     #[error("Invalid page number: {0}")]
     InvalidPage(usize),
 
+    #[error("Invalid page range: {0}")]
+    InvalidPageRange(String),
+
+    #[error("Failed to save PDF: {0}")]
+    SaveError(String),
+
+    #[error("Invalid form data: {0}")]
+    FormDataError(String),
+
     #[error("PDF is password protected")]
     #[allow(dead_code)]
     PasswordProtected,
@@ -34,8 +55,10 @@ pub struct TextChar {
     pub y: f32,
     pub width: f32,
     pub height: f32,
-    #[allow(dead_code)]
     pub font_size: f32,
+    /// Style guessed from the font name; see `font_style`
+    pub bold: bool,
+    pub italic: bool,
 }
 
 /// Represents text content extracted from a page
@@ -52,7 +75,7 @@ pub struct PdfDocument {
 }
 
 impl PdfDocument {
-    fn get_pdfium() -> Result<Pdfium> {
+    pub(crate) fn get_pdfium() -> Result<Pdfium> {
         if let Ok(exe_path) = std::env::current_exe() {
             if let Some(exe_dir) = exe_path.parent() {
                 let lib_path = Pdfium::pdfium_platform_library_name_at_path(exe_dir);
@@ -90,7 +113,30 @@ impl PdfDocument {
         self.page_count
     }
 
-    pub fn render_page(&self, page_num: usize, zoom: f32) -> Result<(Vec<u8>, u32, u32)> {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Render a page as RGBA pixels with `filter` applied to its colours
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    pub fn render_page(
+        &self,
+        page_num: usize,
+        zoom: f32,
+        filter: ColorFilter,
+    ) -> Result<(Vec<u8>, u32, u32)> {
+        self.render_page_scaled(page_num, zoom, filter, DPI_SCALE)
+    }
+
+    /// Render a page as `render_page` does, at `scale` pixels per PDF unit
+    /// before zooming
+    pub fn render_page_scaled(
+        &self,
+        page_num: usize,
+        zoom: f32,
+        filter: ColorFilter,
+        scale: f32,
+    ) -> Result<(Vec<u8>, u32, u32)> {
         if page_num >= self.page_count {
             return Err(PdfError::InvalidPage(page_num));
         }
@@ -106,37 +152,7 @@ impl PdfDocument {
             PdfError::RenderError(format!("Failed to load page {}: {}", page_num, e))
         })?;
 
-        let size = page.page_size();
-        let height = size.height().value;
-
-        let render_config = PdfRenderConfig::new()
-            .set_target_height((height * zoom * DPI_SCALE) as i32)
-            .render_annotations(true)
-            .render_form_data(true)
-            .set_reverse_byte_order(true);
-
-        let bitmap = page
-            .render_with_config(&render_config)
-            .map_err(|e| PdfError::RenderError(format!("Failed to render page: {}", e)))?;
-
-        let data = bitmap.as_rgba_bytes().to_vec();
-
-        let width = bitmap.width() as u32;
-        let height = bitmap.height() as u32;
-
-        let mut rgba_data = Vec::with_capacity(data.len());
-        for chunk in data.chunks_exact(4) {
-            let b = chunk[0];
-            let g = chunk[1];
-            let r = chunk[2];
-            let a = chunk[3];
-            rgba_data.push(r);
-            rgba_data.push(g);
-            rgba_data.push(b);
-            rgba_data.push(a);
-        }
-
-        Ok((rgba_data, width, height))
+        render_loaded_page(&page, zoom, filter, scale)
     }
 
     pub fn get_page_size(&self, page_num: usize) -> Result<(f32, f32)> {
@@ -159,47 +175,32 @@ impl PdfDocument {
         Ok((size.width().value, size.height().value))
     }
 
-    /// Get document outline (bookmarks/table of contents)
-    pub fn get_outline(&self) -> Result<Vec<OutlineItem>> {
+    /// Width and height in points of every page, loading the document once
+    pub fn get_page_sizes(&self) -> Result<Vec<(f32, f32)>> {
         let path_str = self.path.to_string_lossy().to_string();
 
         let pdfium = Self::get_pdfium()?;
         let doc = pdfium
             .load_pdf_from_file(&path_str, None)
-            .map_err(|e| PdfError::OpenError(format!("Failed to load PDF: {}", e)))?;
+            .map_err(|e| PdfError::RenderError(format!("Failed to load PDF: {}", e)))?;
 
-        let bookmarks = doc.bookmarks();
-
-        fn convert_bookmarks<'a>(bookmark: &PdfBookmark<'a>) -> OutlineItem {
-            let title = bookmark.title().unwrap_or_else(|| String::from(""));
-            let page = bookmark
-                .destination()
-                .and_then(|dest| dest.page_index().ok())
-                .map(|idx| idx as usize)
-                .unwrap_or(0);
-
-            let mut children = Vec::new();
-            let mut child = bookmark.first_child();
-            while let Some(c) = child {
-                children.push(convert_bookmarks(&c));
-                child = c.next_sibling();
-            }
+        Ok(doc
+            .pages()
+            .iter()
+            .map(|page| (page.width().value, page.height().value))
+            .collect())
+    }
 
-            OutlineItem {
-                title,
-                page,
-                children,
-            }
-        }
+    /// Get document outline (bookmarks/table of contents)
+    pub fn get_outline(&self) -> Result<Vec<OutlineItem>> {
+        let path_str = self.path.to_string_lossy().to_string();
 
-        let mut items = Vec::new();
-        let mut bookmark = bookmarks.root();
-        while let Some(b) = bookmark {
-            items.push(convert_bookmarks(&b));
-            bookmark = b.next_sibling();
-        }
+        let pdfium = Self::get_pdfium()?;
+        let doc = pdfium
+            .load_pdf_from_file(&path_str, None)
+            .map_err(|e| PdfError::OpenError(format!("Failed to load PDF: {}", e)))?;
 
-        Ok(items)
+        Ok(read_outline(&doc))
     }
 
     /// Extract text from a specific page with character positions
@@ -227,23 +228,7 @@ impl PdfDocument {
         })?;
 
         let text = page_text.all();
-        let mut chars = Vec::new();
-
-        // Extract character positions
-        for char_info in page_text.chars().iter() {
-            if let Some(ch) = char_info.unicode_char() {
-                if let Ok(bounds) = char_info.loose_bounds() {
-                    chars.push(TextChar {
-                        char: ch,
-                        x: bounds.left().value,
-                        y: bounds.bottom().value, // Store bottom Y for consistent comparisons
-                        width: bounds.width().value,
-                        height: bounds.height().value,
-                        font_size: char_info.scaled_font_size().value,
-                    });
-                }
-            }
-        }
+        let chars = collect_text_chars(&page, &page_text);
 
         Ok(PageText { text, chars })
     }
@@ -292,9 +277,190 @@ impl PdfDocument {
     }
 }
 
+/// Render a page already loaded into Pdfium; see `PdfDocument::render_page_scaled`
+pub(crate) fn render_loaded_page(
+    page: &PdfPage,
+    zoom: f32,
+    filter: ColorFilter,
+    scale: f32,
+) -> Result<(Vec<u8>, u32, u32)> {
+    let size = page.page_size();
+    let height = size.height().value;
+
+    let render_config = PdfRenderConfig::new()
+        .set_target_height((height * zoom * scale) as i32)
+        .render_annotations(true)
+        .render_form_data(true)
+        .set_reverse_byte_order(true);
+
+    let bitmap = page
+        .render_with_config(&render_config)
+        .map_err(|e| PdfError::RenderError(format!("Failed to render page: {}", e)))?;
+
+    let data = bitmap.as_rgba_bytes().to_vec();
+
+    let width = bitmap.width() as u32;
+    let height = bitmap.height() as u32;
+
+    let mut rgba_data = Vec::with_capacity(data.len());
+    for &[b, g, r, a] in data.as_chunks::<4>().0 {
+        rgba_data.extend_from_slice(&[r, g, b, a]);
+    }
+
+    // Images keep their colours under filters that ask for it
+    let keep = if filter.preserves_images() {
+        let scale = width as f32 / size.width().value.max(1.0);
+        page.objects()
+            .iter()
+            .filter(|object| object.object_type() == PdfPageObjectType::Image)
+            .filter_map(|object| object.bounds().ok())
+            .map(|bounds| {
+                let rect = bounds.to_rect();
+                let px = |points: f32| (points * scale).max(0.0) as u32;
+                let top = size.height().value - rect.top().value;
+                (
+                    px(rect.left().value),
+                    px(top),
+                    px(rect.right().value).min(width),
+                    px(top + rect.height().value).min(height),
+                )
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    filter.apply(&mut rgba_data, width, &keep);
+
+    Ok((rgba_data, width, height))
+}
+
+/// The bookmarks of a document already loaded into Pdfium
+pub(crate) fn read_outline(doc: &pdfium_render::prelude::PdfDocument) -> Vec<OutlineItem> {
+    fn convert_bookmarks<'a>(bookmark: &PdfBookmark<'a>) -> OutlineItem {
+        let title = bookmark.title().unwrap_or_else(|| String::from(""));
+        let page = bookmark
+            .destination()
+            .and_then(|dest| dest.page_index().ok())
+            .map(|idx| idx as usize)
+            .unwrap_or(0);
+
+        let mut children = Vec::new();
+        let mut child = bookmark.first_child();
+        while let Some(c) = child {
+            children.push(convert_bookmarks(&c));
+            child = c.next_sibling();
+        }
+
+        OutlineItem {
+            title,
+            page,
+            children,
+        }
+    }
+
+    let mut items = Vec::new();
+    let mut bookmark = doc.bookmarks().root();
+    while let Some(b) = bookmark {
+        items.push(convert_bookmarks(&b));
+        bookmark = b.next_sibling();
+    }
+
+    items
+}
+
+/// Guess whether a font is bold and/or italic from its name,
+/// e.g. "ABCDEF+Minion-BoldIt" or "Arial,BoldItalic"
+pub fn font_style(font_name: &str) -> (bool, bool) {
+    // Drop the subset tag; the style follows the family name after '-' or ','
+    let name = font_name
+        .split_once('+')
+        .map_or(font_name, |(_, rest)| rest);
+    let name = name.to_lowercase();
+    let style = name.rsplit(['-', ',']).next().unwrap_or(&name);
+
+    let bold = ["bold", "black", "heavy", "demi"]
+        .iter()
+        .any(|w| name.contains(w));
+    let italic = name.contains("italic")
+        || name.contains("oblique")
+        || (style != name && (style.ends_with("it") || style.ends_with("ital")));
+    (bold, italic)
+}
+
+/// Character positions of a page's text, in content order.
+/// Boxes are turned by the page's own rotation, so they match the page as rendered.
+fn collect_text_chars(page: &PdfPage, page_text: &PdfPageText) -> Vec<TextChar> {
+    let quarter_turns = match page.rotation() {
+        Ok(PdfPageRenderRotation::Degrees90) => 1,
+        Ok(PdfPageRenderRotation::Degrees180) => 2,
+        Ok(PdfPageRenderRotation::Degrees270) => 3,
+        _ => 0,
+    };
+    // Page width and height are reported after rotation
+    let (width, height) = if quarter_turns % 2 == 1 {
+        (page.height().value, page.width().value)
+    } else {
+        (page.width().value, page.height().value)
+    };
+    let rotate = |x: f32, y: f32| match quarter_turns {
+        1 => (y, width - x),
+        2 => (width - x, height - y),
+        3 => (height - y, x),
+        _ => (x, y),
+    };
+
+    let mut chars = Vec::new();
+    for char_info in page_text.chars().iter() {
+        if let Some(ch) = char_info.unicode_char() {
+            if let Ok(bounds) = char_info.loose_bounds() {
+                let (x0, y0) = rotate(bounds.left().value, bounds.bottom().value);
+                let (x1, y1) = rotate(bounds.right().value, bounds.top().value);
+                let (bold, italic) = font_style(&char_info.font_name());
+                chars.push(TextChar {
+                    char: ch,
+                    x: x0.min(x1),
+                    y: y0.min(y1), // Store bottom Y for consistent comparisons
+                    width: (x1 - x0).abs(),
+                    height: (y1 - y0).abs(),
+                    font_size: char_info.scaled_font_size().value,
+                    bold,
+                    italic,
+                });
+            }
+        }
+    }
+    chars
+}
+
 #[derive(Debug, Clone)]
 pub struct OutlineItem {
     pub title: String,
     pub page: usize,
     pub children: Vec<OutlineItem>,
 }
+
+/// A document with `page_count` pages that is never loaded, for tests of
+/// code that only keeps it around
+#[cfg(test)]
+impl PdfDocument {
+    pub(crate) fn unloaded(path: &str, page_count: usize) -> Self {
+        Self {
+            path: std::path::PathBuf::from(path),
+            page_count,
+        }
+    }
+}
+
+/// Write a document of `pages` blank A4 pages to `path` for tests. Tests using
+/// it are `#[ignore]`d, as they need the Pdfium library installed.
+#[cfg(test)]
+pub(crate) fn write_blank_pdf(path: &Path, pages: usize) {
+    let pdfium = PdfDocument::get_pdfium().expect("the Pdfium library is not installed");
+    let mut doc = pdfium.create_new_pdf().unwrap();
+    for _ in 0..pages {
+        doc.pages_mut()
+            .create_page_at_end(PdfPagePaperSize::a4())
+            .unwrap();
+    }
+    doc.save_to_file(path).unwrap();
+}
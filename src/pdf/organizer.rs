@@ -0,0 +1,294 @@
+use super::color_filter::ColorFilter;
+use super::{render_loaded_page, PdfDocument, PdfError, Result, DPI_SCALE};
+use image::RgbaImage;
+use pdfium_render::prelude::PdfDocument as PdfiumDocument;
+use pdfium_render::prelude::*;
+use std::path::{Path, PathBuf};
+
+/// Where a page of an organized document comes from
+#[derive(Debug, Clone, PartialEq)]
+pub enum PageSource {
+    /// A page of the document being organized
+    Original(usize),
+    /// A page of another PDF file
+    External { path: PathBuf, page: usize },
+    /// An empty page of the given size in points
+    Blank { width: f32, height: f32 },
+}
+
+/// A page in the output of the page organizer
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrganizedPage {
+    pub source: PageSource,
+    /// Clockwise rotation in degrees added to the page's own /Rotate
+    pub rotation: u16,
+}
+
+impl OrganizedPage {
+    pub fn new(source: PageSource) -> Self {
+        Self {
+            source,
+            rotation: 0,
+        }
+    }
+}
+
+fn rotation_to_degrees(rotation: PdfPageRenderRotation) -> u16 {
+    match rotation {
+        PdfPageRenderRotation::None => 0,
+        PdfPageRenderRotation::Degrees90 => 90,
+        PdfPageRenderRotation::Degrees180 => 180,
+        PdfPageRenderRotation::Degrees270 => 270,
+    }
+}
+
+fn rotation_from_degrees(degrees: u16) -> PdfPageRenderRotation {
+    match degrees % 360 {
+        90 => PdfPageRenderRotation::Degrees90,
+        180 => PdfPageRenderRotation::Degrees180,
+        270 => PdfPageRenderRotation::Degrees270,
+        _ => PdfPageRenderRotation::None,
+    }
+}
+
+/// A step in building an organized document
+#[derive(Debug, PartialEq)]
+enum Run<'a> {
+    /// Consecutive pages of the original document (`file` is `None`) or of
+    /// another file, imported in a single call so that resources shared
+    /// between them (fonts, images) are only copied once
+    Copy {
+        file: Option<&'a Path>,
+        pages: Vec<usize>,
+    },
+    Blank {
+        width: f32,
+        height: f32,
+    },
+}
+
+/// Group `pages` into runs of consecutive pages from the same document
+fn plan_runs(pages: &[OrganizedPage]) -> Vec<Run<'_>> {
+    let mut runs: Vec<Run> = Vec::new();
+    for page in pages {
+        let (file, index) = match &page.source {
+            PageSource::Blank { width, height } => {
+                runs.push(Run::Blank {
+                    width: *width,
+                    height: *height,
+                });
+                continue;
+            }
+            PageSource::Original(index) => (None, *index),
+            PageSource::External { path, page } => (Some(path.as_path()), *page),
+        };
+        match runs.last_mut() {
+            Some(Run::Copy {
+                file: run_file,
+                pages,
+            }) if *run_file == file => pages.push(index),
+            _ => runs.push(Run::Copy {
+                file,
+                pages: vec![index],
+            }),
+        }
+    }
+    runs
+}
+
+impl PdfDocument {
+    /// Render `pages` for the organizer, `size` logical pixels on their longer
+    /// side and unfiltered, loading the document once. Pages that fail to
+    /// render are `None`.
+    pub fn render_thumbnails(&self, pages: &[usize], size: f32) -> Result<Vec<Option<RgbaImage>>> {
+        let path_str = self.path.to_string_lossy().to_string();
+        let pdfium = Self::get_pdfium()?;
+        let doc = pdfium
+            .load_pdf_from_file(&path_str, None)
+            .map_err(|e| PdfError::RenderError(format!("Failed to load PDF: {}", e)))?;
+
+        let thumbnails = pages
+            .iter()
+            .map(|&index| {
+                let page = doc.pages().get(index as PdfPageIndex).ok()?;
+                let zoom = size / page.width().value.max(page.height().value).max(1.0);
+                match render_loaded_page(&page, zoom, ColorFilter::None, DPI_SCALE) {
+                    Ok((data, width, height)) => RgbaImage::from_raw(width, height, data),
+                    Err(e) => {
+                        log::error!("Failed to render thumbnail: {}", e);
+                        None
+                    }
+                }
+            })
+            .collect();
+        Ok(thumbnails)
+    }
+
+    /// Write a new document made of `pages`, in order, to `output`.
+    /// Page rotations are stored in each page's /Rotate attribute.
+    pub fn save_organized<P: AsRef<Path>>(&self, pages: &[OrganizedPage], output: P) -> Result<()> {
+        let path_str = self.path.to_string_lossy().to_string();
+
+        let pdfium = Self::get_pdfium()?;
+        let source = pdfium
+            .load_pdf_from_file(&path_str, None)
+            .map_err(|e| PdfError::OpenError(format!("Failed to load PDF: {}", e)))?;
+
+        let mut external_docs: Vec<(&Path, PdfiumDocument)> = Vec::new();
+        for page in pages {
+            if let PageSource::External { path, .. } = &page.source {
+                if !external_docs.iter().any(|(p, _)| *p == path.as_path()) {
+                    let doc = pdfium
+                        .load_pdf_from_file(&path.to_string_lossy().to_string(), None)
+                        .map_err(|e| {
+                            PdfError::OpenError(format!("Failed to load {}: {}", path.display(), e))
+                        })?;
+                    external_docs.push((path.as_path(), doc));
+                }
+            }
+        }
+
+        let mut output_doc = pdfium
+            .create_new_pdf()
+            .map_err(|e| PdfError::SaveError(format!("Failed to create PDF: {}", e)))?;
+
+        let mut destination = 0;
+        for run in plan_runs(pages) {
+            match run {
+                Run::Blank { width, height } => {
+                    output_doc
+                        .pages_mut()
+                        .create_page_at_index(
+                            PdfPagePaperSize::new_custom(
+                                PdfPoints::new(width),
+                                PdfPoints::new(height),
+                            ),
+                            destination as PdfPageIndex,
+                        )
+                        .map_err(|e| {
+                            PdfError::SaveError(format!("Failed to create blank page: {}", e))
+                        })?;
+                    destination += 1;
+                }
+                Run::Copy { file, pages } => {
+                    let run_doc: &PdfiumDocument = match file {
+                        None => &source,
+                        Some(path) => external_docs
+                            .iter()
+                            .find(|(p, _)| *p == path)
+                            .map(|(_, doc)| doc)
+                            .ok_or_else(|| PdfError::OpenError(path.display().to_string()))?,
+                    };
+                    let page_list: Vec<String> =
+                        pages.iter().map(|p| (p + 1).to_string()).collect();
+                    output_doc
+                        .pages_mut()
+                        .copy_pages_from_document(
+                            run_doc,
+                            &page_list.join(","),
+                            destination as PdfPageIndex,
+                        )
+                        .map_err(|e| {
+                            PdfError::SaveError(format!("Failed to import pages: {}", e))
+                        })?;
+                    destination += pages.len();
+                }
+            }
+        }
+
+        for (index, page) in pages.iter().enumerate() {
+            if page.rotation % 360 == 0 {
+                continue;
+            }
+            let mut output_page = output_doc
+                .pages()
+                .get(index as PdfPageIndex)
+                .map_err(|e| PdfError::SaveError(format!("Failed to load page: {}", e)))?;
+            let current = output_page.rotation().map(rotation_to_degrees).unwrap_or(0);
+            output_page.set_rotation(rotation_from_degrees(current + page.rotation));
+        }
+
+        output_doc
+            .save_to_file(output.as_ref())
+            .map_err(|e| PdfError::SaveError(format!("Failed to save PDF: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_from_degrees() {
+        assert_eq!(rotation_from_degrees(0), PdfPageRenderRotation::None);
+        assert_eq!(rotation_from_degrees(90), PdfPageRenderRotation::Degrees90);
+        assert_eq!(
+            rotation_from_degrees(180),
+            PdfPageRenderRotation::Degrees180
+        );
+        assert_eq!(
+            rotation_from_degrees(270),
+            PdfPageRenderRotation::Degrees270
+        );
+        assert_eq!(rotation_from_degrees(360), PdfPageRenderRotation::None);
+        // A page already at 270 turned another 180
+        assert_eq!(rotation_from_degrees(450), PdfPageRenderRotation::Degrees90);
+        assert_eq!(rotation_to_degrees(rotation_from_degrees(630)), 270);
+    }
+
+    #[test]
+    fn test_plan_runs() {
+        let other = PathBuf::from("other.pdf");
+        let external = |page| {
+            OrganizedPage::new(PageSource::External {
+                path: other.clone(),
+                page,
+            })
+        };
+        let original = |page| OrganizedPage::new(PageSource::Original(page));
+        let blank = OrganizedPage::new(PageSource::Blank {
+            width: 595.0,
+            height: 842.0,
+        });
+        let pages = vec![
+            original(0),
+            original(2),
+            external(1),
+            external(0),
+            blank.clone(),
+            blank,
+            original(1),
+            external(2),
+        ];
+
+        assert_eq!(
+            plan_runs(&pages),
+            vec![
+                Run::Copy {
+                    file: None,
+                    pages: vec![0, 2]
+                },
+                Run::Copy {
+                    file: Some(other.as_path()),
+                    pages: vec![1, 0]
+                },
+                Run::Blank {
+                    width: 595.0,
+                    height: 842.0
+                },
+                Run::Blank {
+                    width: 595.0,
+                    height: 842.0
+                },
+                Run::Copy {
+                    file: None,
+                    pages: vec![1]
+                },
+                Run::Copy {
+                    file: Some(other.as_path()),
+                    pages: vec![2]
+                },
+            ]
+        );
+    }
+}
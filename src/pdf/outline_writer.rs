@@ -0,0 +1,742 @@
+//! Writing bookmarks into an existing PDF file.
+//!
+//! Pdfium can read outlines but has no API to create them, so bookmarks are
+//! appended as an incremental update: new outline objects and a replacement
+//! catalog are written after the end of the file, followed by a cross-reference
+//! section that points back to the original one. Only the small subset of the
+//! PDF syntax needed to find the catalog and the page objects is parsed.
+
+use super::{OutlineItem, PdfError, Result};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Limit on /Prev chains and page tree depth, to stop on malformed files
+const MAX_NESTING: usize = 64;
+
+/// Fewest bytes a cross-reference entry takes, as in "0 0 n" and a line break
+const MIN_XREF_ENTRY: usize = 6;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Object {
+    Null,
+    Bool(bool),
+    Number(String),
+    /// Name without the leading slash, escapes kept as written
+    Name(String),
+    /// Literal or hex string, delimiters included, kept as written
+    String(Vec<u8>),
+    Array(Vec<Object>),
+    Dictionary(Vec<(String, Object)>),
+    Reference(u32, u16),
+}
+
+impl Object {
+    fn get(&self, key: &str) -> Option<&Object> {
+        match self {
+            Object::Dictionary(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_reference(&self) -> Option<(u32, u16)> {
+        match self {
+            Object::Reference(num, generation) => Some((*num, *generation)),
+            _ => None,
+        }
+    }
+
+    fn as_integer(&self) -> Option<u64> {
+        match self {
+            Object::Number(n) => n.parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        match self {
+            Object::Null => out.extend_from_slice(b"null"),
+            Object::Bool(b) => out.extend_from_slice(if *b { b"true" } else { b"false" }),
+            Object::Number(n) => out.extend_from_slice(n.as_bytes()),
+            Object::Name(n) => {
+                out.push(b'/');
+                out.extend_from_slice(n.as_bytes());
+            }
+            Object::String(s) => out.extend_from_slice(s),
+            Object::Array(items) => {
+                out.push(b'[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(b' ');
+                    }
+                    item.write(out);
+                }
+                out.push(b']');
+            }
+            Object::Dictionary(entries) => {
+                out.extend_from_slice(b"<<");
+                for (key, value) in entries {
+                    out.push(b'/');
+                    out.extend_from_slice(key.as_bytes());
+                    out.push(b' ');
+                    value.write(out);
+                }
+                out.extend_from_slice(b">>");
+            }
+            Object::Reference(num, generation) => {
+                out.extend_from_slice(format!("{} {} R", num, generation).as_bytes());
+            }
+        }
+    }
+}
+
+fn is_whitespace(b: u8) -> bool {
+    matches!(b, b'\0' | b'\t' | b'\n' | b'\x0c' | b'\r' | b' ')
+}
+
+fn is_delimiter(b: u8) -> bool {
+    matches!(
+        b,
+        b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%'
+    )
+}
+
+fn syntax_error(what: &str, pos: usize) -> PdfError {
+    PdfError::SaveError(format!(
+        "Unsupported PDF structure: {} at byte {}",
+        what, pos
+    ))
+}
+
+struct Parser<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// A parser starting at `pos`, which comes from the file itself and may
+    /// point past its end
+    fn new(data: &'a [u8], pos: usize) -> Result<Self> {
+        if pos > data.len() {
+            return Err(syntax_error("offset past end of file", pos));
+        }
+        Ok(Self { data, pos })
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b) = self.peek() {
+            if is_whitespace(b) {
+                self.pos += 1;
+            } else if b == b'%' {
+                while let Some(b) = self.peek() {
+                    if b == b'\n' || b == b'\r' {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Read a run of regular characters (a number, keyword or name body)
+    fn token(&mut self) -> &'a [u8] {
+        let start = self.pos;
+        while let Some(b) = self.peek() {
+            if is_whitespace(b) || is_delimiter(b) {
+                break;
+            }
+            self.pos += 1;
+        }
+        &self.data[start..self.pos]
+    }
+
+    fn expect_keyword(&mut self, keyword: &[u8]) -> Result<()> {
+        self.skip_whitespace();
+        let start = self.pos;
+        if self.token() == keyword {
+            Ok(())
+        } else {
+            Err(syntax_error(
+                &format!("expected '{}'", String::from_utf8_lossy(keyword)),
+                start,
+            ))
+        }
+    }
+
+    fn integer(&mut self) -> Result<u64> {
+        self.skip_whitespace();
+        let start = self.pos;
+        std::str::from_utf8(self.token())
+            .ok()
+            .and_then(|t| t.parse().ok())
+            .ok_or_else(|| syntax_error("expected an integer", start))
+    }
+
+    fn object(&mut self) -> Result<Object> {
+        self.skip_whitespace();
+        let start = self.pos;
+        match self.peek() {
+            Some(b'/') => {
+                self.pos += 1;
+                let name = String::from_utf8_lossy(self.token()).to_string();
+                Ok(Object::Name(name))
+            }
+            Some(b'<') if self.data.get(self.pos + 1) == Some(&b'<') => {
+                self.pos += 2;
+                let mut entries = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    if self.data[self.pos..].starts_with(b">>") {
+                        self.pos += 2;
+                        return Ok(Object::Dictionary(entries));
+                    }
+                    let Object::Name(key) = self.object()? else {
+                        return Err(syntax_error("expected a dictionary key", self.pos));
+                    };
+                    let value = self.object()?;
+                    entries.push((key, value));
+                }
+            }
+            Some(b'<') => {
+                let end = self.data[self.pos..]
+                    .iter()
+                    .position(|b| *b == b'>')
+                    .ok_or_else(|| syntax_error("unterminated hex string", start))?;
+                self.pos += end + 1;
+                Ok(Object::String(self.data[start..self.pos].to_vec()))
+            }
+            Some(b'(') => {
+                let mut depth = 0;
+                while let Some(b) = self.peek() {
+                    self.pos += 1;
+                    match b {
+                        b'\\' => self.pos += 1,
+                        b'(' => depth += 1,
+                        b')' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                return Ok(Object::String(self.data[start..self.pos].to_vec()));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                Err(syntax_error("unterminated string", start))
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    if self.peek() == Some(b']') {
+                        self.pos += 1;
+                        return Ok(Object::Array(items));
+                    }
+                    if self.peek().is_none() {
+                        return Err(syntax_error("unterminated array", start));
+                    }
+                    items.push(self.object()?);
+                }
+            }
+            Some(b) if b.is_ascii_digit() || b == b'+' || b == b'-' || b == b'.' => {
+                let number = String::from_utf8_lossy(self.token()).to_string();
+                // An integer followed by another integer and 'R' is an indirect reference
+                let after_number = self.pos;
+                if let Ok(num) = number.parse::<u32>() {
+                    if let Ok(generation) = self.integer() {
+                        self.skip_whitespace();
+                        if self.token() == b"R" {
+                            return Ok(Object::Reference(num, generation as u16));
+                        }
+                    }
+                }
+                self.pos = after_number;
+                Ok(Object::Number(number))
+            }
+            _ => match self.token() {
+                b"true" => Ok(Object::Bool(true)),
+                b"false" => Ok(Object::Bool(false)),
+                b"null" => Ok(Object::Null),
+                _ => Err(syntax_error("unexpected token", start)),
+            },
+        }
+    }
+}
+
+/// Object offsets and trailer of a file's cross-reference tables
+struct Xref {
+    offsets: HashMap<u32, (usize, u16)>,
+    trailer: Object,
+    start: usize,
+}
+
+fn find_startxref(data: &[u8]) -> Result<usize> {
+    let marker = b"startxref";
+    let pos = data
+        .windows(marker.len())
+        .rposition(|w| w == marker)
+        .ok_or_else(|| syntax_error("missing startxref", data.len()))?;
+    let mut parser = Parser::new(data, pos + marker.len())?;
+    Ok(parser.integer()? as usize)
+}
+
+fn read_xref(data: &[u8]) -> Result<Xref> {
+    let start = find_startxref(data)?;
+    let mut offsets = HashMap::new();
+    let mut trailer = None;
+    let mut next = Some(start);
+
+    for _ in 0..MAX_NESTING {
+        let Some(offset) = next.take() else {
+            break;
+        };
+        let mut parser = Parser::new(data, offset)?;
+        // Cross-reference streams are not handled; Pdfium writes classic tables
+        parser.expect_keyword(b"xref")?;
+
+        loop {
+            parser.skip_whitespace();
+            if data[parser.pos..].starts_with(b"trailer") {
+                parser.pos += b"trailer".len();
+                break;
+            }
+            let section = parser.pos;
+            let first = parser.integer()?;
+            let count = parser.integer()?;
+            let end = first
+                .checked_add(count)
+                .filter(|end| *end <= u64::from(u32::MAX))
+                .ok_or_else(|| syntax_error("xref section out of range", section))?;
+            if count > ((data.len() - parser.pos) / MIN_XREF_ENTRY) as u64 {
+                return Err(syntax_error("xref section longer than the file", section));
+            }
+            for num in first as u32..end as u32 {
+                let entry_offset = parser.integer()? as usize;
+                let generation = parser.integer()? as u16;
+                parser.skip_whitespace();
+                let in_use = parser.token() == b"n";
+                // Entries from newer sections, read first, take precedence
+                if in_use && !offsets.contains_key(&num) {
+                    offsets.insert(num, (entry_offset, generation));
+                }
+            }
+        }
+
+        let section_trailer = parser.object()?;
+        next = section_trailer
+            .get("Prev")
+            .and_then(Object::as_integer)
+            .map(|p| p as usize);
+        if trailer.is_none() {
+            trailer = Some(section_trailer);
+        }
+    }
+
+    Ok(Xref {
+        offsets,
+        trailer: trailer.ok_or_else(|| syntax_error("missing trailer", start))?,
+        start,
+    })
+}
+
+fn read_object(data: &[u8], xref: &Xref, num: u32) -> Result<Object> {
+    let (offset, _) = xref
+        .offsets
+        .get(&num)
+        .ok_or_else(|| syntax_error(&format!("object {} not found", num), 0))?;
+    let mut parser = Parser::new(data, *offset)?;
+    parser.integer()?;
+    parser.integer()?;
+    parser.expect_keyword(b"obj")?;
+    parser.object()
+}
+
+/// Collect the page objects of the page tree below `node`, in document order
+fn collect_pages(
+    data: &[u8],
+    xref: &Xref,
+    node: (u32, u16),
+    depth: usize,
+    pages: &mut Vec<(u32, u16)>,
+) -> Result<()> {
+    if depth > MAX_NESTING {
+        return Err(syntax_error("page tree too deep", 0));
+    }
+    let object = read_object(data, xref, node.0)?;
+    match object.get("Kids") {
+        Some(Object::Array(kids)) => {
+            for kid in kids.iter().filter_map(Object::as_reference) {
+                collect_pages(data, xref, kid, depth + 1, pages)?;
+            }
+        }
+        _ => pages.push(node),
+    }
+    Ok(())
+}
+
+/// Encode a PDF text string: a literal string for ASCII, UTF-16BE otherwise
+fn text_string(text: &str) -> Object {
+    if text.chars().all(|c| (' '..='~').contains(&c)) {
+        let mut out = vec![b'('];
+        for b in text.bytes() {
+            if matches!(b, b'(' | b')' | b'\\') {
+                out.push(b'\\');
+            }
+            out.push(b);
+        }
+        out.push(b')');
+        Object::String(out)
+    } else {
+        let mut out = String::from("<FEFF");
+        for unit in text.encode_utf16() {
+            out.push_str(&format!("{:04X}", unit));
+        }
+        out.push('>');
+        Object::String(out.into_bytes())
+    }
+}
+
+fn reference(num: u32) -> Object {
+    Object::Reference(num, 0)
+}
+
+/// Outline item objects laid out with their object numbers
+struct OutlineNode<'a> {
+    id: u32,
+    parent: u32,
+    prev: Option<u32>,
+    next: Option<u32>,
+    first: Option<u32>,
+    last: Option<u32>,
+    count: i64,
+    item: &'a OutlineItem,
+}
+
+/// Assign object numbers to `items` and their descendants. Top-level items are
+/// open and deeper ones closed. Returns the first and last sibling ids and the
+/// number of items visible when the parent is open.
+fn layout<'a>(
+    items: &'a [OutlineItem],
+    parent: u32,
+    depth: usize,
+    next_id: &mut u32,
+    nodes: &mut Vec<OutlineNode<'a>>,
+) -> (Option<u32>, Option<u32>, i64) {
+    let ids: Vec<u32> = items
+        .iter()
+        .map(|_| {
+            *next_id += 1;
+            *next_id - 1
+        })
+        .collect();
+
+    let mut visible = 0;
+    for (i, item) in items.iter().enumerate() {
+        let (first, last, child_visible) =
+            layout(&item.children, ids[i], depth + 1, next_id, nodes);
+        let open = depth == 0;
+        let count = if item.children.is_empty() {
+            0
+        } else if open {
+            child_visible
+        } else {
+            -(item.children.len() as i64)
+        };
+        visible += 1 + count.max(0);
+
+        nodes.push(OutlineNode {
+            id: ids[i],
+            parent,
+            prev: i.checked_sub(1).map(|p| ids[p]),
+            next: ids.get(i + 1).copied(),
+            first,
+            last,
+            count,
+            item,
+        });
+    }
+
+    (ids.first().copied(), ids.last().copied(), visible)
+}
+
+fn write_object(
+    out: &mut Vec<u8>,
+    offsets: &mut Vec<(u32, u16, usize)>,
+    id: (u32, u16),
+    object: &Object,
+) {
+    offsets.push((id.0, id.1, out.len()));
+    out.extend_from_slice(format!("{} {} obj\n", id.0, id.1).as_bytes());
+    object.write(out);
+    out.extend_from_slice(b"\nendobj\n");
+}
+
+/// Build the incremental update adding `items` as the outline of `data`
+fn outline_update(data: &[u8], items: &[OutlineItem]) -> Result<Vec<u8>> {
+    let xref = read_xref(data)?;
+    let root = xref
+        .trailer
+        .get("Root")
+        .and_then(Object::as_reference)
+        .ok_or_else(|| syntax_error("missing /Root", xref.start))?;
+    let size = xref
+        .trailer
+        .get("Size")
+        .and_then(Object::as_integer)
+        .ok_or_else(|| syntax_error("missing /Size", xref.start))? as u32;
+
+    let catalog = read_object(data, &xref, root.0)?;
+    let pages_root = catalog
+        .get("Pages")
+        .and_then(Object::as_reference)
+        .ok_or_else(|| syntax_error("missing /Pages", 0))?;
+    let mut pages = Vec::new();
+    collect_pages(data, &xref, pages_root, 0, &mut pages)?;
+    if pages.is_empty() {
+        return Err(syntax_error("document has no pages", 0));
+    }
+
+    let outlines_id = size;
+    let mut next_id = size + 1;
+    let mut nodes = Vec::new();
+    let (first, last, visible) = layout(items, outlines_id, 0, &mut next_id, &mut nodes);
+
+    let mut out = Vec::new();
+    let mut offsets: Vec<(u32, u16, usize)> = Vec::new();
+    if !data.ends_with(b"\n") {
+        out.push(b'\n');
+    }
+    let base = data.len();
+
+    let mut outlines = vec![("Type".to_string(), Object::Name("Outlines".to_string()))];
+    if let (Some(first), Some(last)) = (first, last) {
+        outlines.push(("First".to_string(), reference(first)));
+        outlines.push(("Last".to_string(), reference(last)));
+    }
+    outlines.push(("Count".to_string(), Object::Number(visible.to_string())));
+    write_object(
+        &mut out,
+        &mut offsets,
+        (outlines_id, 0),
+        &Object::Dictionary(outlines),
+    );
+
+    nodes.sort_by_key(|n| n.id);
+    for node in &nodes {
+        let page = pages[node.item.page.min(pages.len() - 1)];
+        let mut entries = vec![
+            ("Title".to_string(), text_string(&node.item.title)),
+            ("Parent".to_string(), reference(node.parent)),
+        ];
+        let links = [
+            ("Prev", node.prev),
+            ("Next", node.next),
+            ("First", node.first),
+            ("Last", node.last),
+        ];
+        for (key, id) in links {
+            if let Some(id) = id {
+                entries.push((key.to_string(), reference(id)));
+            }
+        }
+        if node.count != 0 {
+            entries.push(("Count".to_string(), Object::Number(node.count.to_string())));
+        }
+        entries.push((
+            "Dest".to_string(),
+            Object::Array(vec![
+                Object::Reference(page.0, page.1),
+                Object::Name("XYZ".to_string()),
+                Object::Null,
+                Object::Null,
+                Object::Null,
+            ]),
+        ));
+        write_object(
+            &mut out,
+            &mut offsets,
+            (node.id, 0),
+            &Object::Dictionary(entries),
+        );
+    }
+
+    let Object::Dictionary(mut catalog_entries) = catalog else {
+        return Err(syntax_error("catalog is not a dictionary", 0));
+    };
+    catalog_entries.retain(|(k, _)| k != "Outlines" && k != "PageMode");
+    catalog_entries.push(("Outlines".to_string(), reference(outlines_id)));
+    catalog_entries.push((
+        "PageMode".to_string(),
+        Object::Name("UseOutlines".to_string()),
+    ));
+    write_object(
+        &mut out,
+        &mut offsets,
+        root,
+        &Object::Dictionary(catalog_entries),
+    );
+
+    // Cross-reference section with one subsection per run of consecutive objects
+    let xref_offset = base + out.len();
+    offsets.sort_by_key(|(num, _, _)| *num);
+    out.extend_from_slice(b"xref\n");
+    let mut i = 0;
+    while i < offsets.len() {
+        let mut j = i + 1;
+        while j < offsets.len() && offsets[j].0 == offsets[j - 1].0 + 1 {
+            j += 1;
+        }
+        out.extend_from_slice(format!("{} {}\n", offsets[i].0, j - i).as_bytes());
+        for (_, generation, offset) in &offsets[i..j] {
+            out.extend_from_slice(
+                format!("{:010} {:05} n\r\n", base + offset, generation).as_bytes(),
+            );
+        }
+        i = j;
+    }
+
+    let mut trailer = vec![
+        ("Size".to_string(), Object::Number(next_id.to_string())),
+        ("Root".to_string(), Object::Reference(root.0, root.1)),
+    ];
+    for key in ["Info", "ID"] {
+        if let Some(value) = xref.trailer.get(key) {
+            trailer.push((key.to_string(), value.clone()));
+        }
+    }
+    trailer.push(("Prev".to_string(), Object::Number(xref.start.to_string())));
+    out.extend_from_slice(b"trailer\n");
+    Object::Dictionary(trailer).write(&mut out);
+    out.extend_from_slice(format!("\nstartxref\n{}\n%%EOF\n", xref_offset).as_bytes());
+
+    Ok(out)
+}
+
+/// Replace the outline of the PDF at `path` with `items`.
+/// Page numbers in `items` are 0-based indices into the file's pages.
+pub fn write_outline(path: &Path, items: &[OutlineItem]) -> Result<()> {
+    let data = std::fs::read(path)
+        .map_err(|e| PdfError::SaveError(format!("Failed to read {}: {}", path.display(), e)))?;
+    let update = outline_update(&data, items)?;
+
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(path)
+        .map_err(|e| PdfError::SaveError(format!("Failed to open {}: {}", path.display(), e)))?;
+    std::io::Write::write_all(&mut file, &update)
+        .map_err(|e| PdfError::SaveError(format!("Failed to write {}: {}", path.display(), e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A two page document with a classic cross-reference table
+    fn sample_pdf() -> Vec<u8> {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] >>",
+        ];
+        let mut data = b"%PDF-1.7\n".to_vec();
+        let mut offsets = Vec::new();
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(data.len());
+            data.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+        }
+        let xref = data.len();
+        data.extend_from_slice(b"xref\n0 5\n0000000000 65535 f\r\n");
+        for offset in offsets {
+            data.extend_from_slice(format!("{:010} 00000 n\r\n", offset).as_bytes());
+        }
+        data.extend_from_slice(
+            format!(
+                "trailer\n<< /Size 5 /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+                xref
+            )
+            .as_bytes(),
+        );
+        data
+    }
+
+    fn item(title: &str, page: usize, children: Vec<OutlineItem>) -> OutlineItem {
+        OutlineItem {
+            title: title.to_string(),
+            page,
+            children,
+        }
+    }
+
+    #[test]
+    fn test_outline_update() {
+        let mut data = sample_pdf();
+        let items = vec![
+            item("First (1)", 0, vec![item("Détails", 1, Vec::new())]),
+            item("Second", 1, Vec::new()),
+        ];
+        let update = outline_update(&data, &items).unwrap();
+        data.extend_from_slice(&update);
+
+        let xref = read_xref(&data).unwrap();
+        assert_eq!(
+            xref.trailer.get("Size").and_then(Object::as_integer),
+            Some(9)
+        );
+
+        let catalog = read_object(&data, &xref, 1).unwrap();
+        assert_eq!(catalog.get("Outlines"), Some(&Object::Reference(5, 0)));
+        assert_eq!(catalog.get("Pages"), Some(&Object::Reference(2, 0)));
+
+        let outlines = read_object(&data, &xref, 5).unwrap();
+        assert_eq!(outlines.get("Count").and_then(Object::as_integer), Some(3));
+
+        let first = read_object(&data, &xref, 6).unwrap();
+        assert_eq!(
+            first.get("Title"),
+            Some(&Object::String(b"(First \\(1\\))".to_vec()))
+        );
+        assert_eq!(first.get("Next"), Some(&Object::Reference(7, 0)));
+        assert_eq!(first.get("First"), Some(&Object::Reference(8, 0)));
+
+        let child = read_object(&data, &xref, 8).unwrap();
+        match child.get("Dest") {
+            Some(Object::Array(dest)) => assert_eq!(dest[0], Object::Reference(4, 0)),
+            other => panic!("unexpected destination: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_offsets_past_end() {
+        let data = sample_pdf();
+        let mut truncated = data.clone();
+        let end = truncated.len();
+        truncated.extend_from_slice(format!("startxref\n{}\n%%EOF\n", end + 1000).as_bytes());
+        assert!(read_xref(&truncated).is_err());
+
+        let mut xref = read_xref(&data).unwrap();
+        xref.offsets.insert(1, (data.len() + 1, 0));
+        assert!(read_object(&data, &xref, 1).is_err());
+    }
+
+    #[test]
+    fn test_xref_section_out_of_range() {
+        for section in ["4294967295 2", "0 18446744073709551615", "0 100000"] {
+            let mut data = sample_pdf();
+            let start = data.len();
+            data.extend_from_slice(
+                format!(
+                    "xref\n{}\n0000000000 65535 f \ntrailer\n<< >>\nstartxref\n{}\n%%EOF\n",
+                    section, start
+                )
+                .as_bytes(),
+            );
+            assert!(read_xref(&data).is_err(), "{}", section);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use super::{PdfError, Result};
+
+/// Parse a page range expression into 0-based page indices, in the order given.
+///
+/// The expression is a comma separated list of 1-based pages (`5`), closed ranges
+/// (`1-3`, or `3-1` for reverse order) and open ranges (`8-` to the last page,
+/// `-4` from the first page). An empty expression selects every page.
+pub fn parse_page_range(spec: &str, page_count: usize) -> Result<Vec<usize>> {
+    let spec = spec.trim();
+    if spec.is_empty() {
+        return Ok((0..page_count).collect());
+    }
+
+    let parse_page = |text: &str| -> Result<usize> {
+        let page: usize = text
+            .trim()
+            .parse()
+            .map_err(|_| PdfError::InvalidPageRange(spec.to_string()))?;
+        if page == 0 || page > page_count {
+            return Err(PdfError::InvalidPage(page));
+        }
+        Ok(page - 1)
+    };
+
+    let mut pages = Vec::new();
+    for part in spec.split(',') {
+        let part = part.trim();
+        if part.is_empty() {
+            return Err(PdfError::InvalidPageRange(spec.to_string()));
+        }
+
+        match part.split_once('-') {
+            None => pages.push(parse_page(part)?),
+            Some((start, end)) => {
+                let start = if start.trim().is_empty() {
+                    0
+                } else {
+                    parse_page(start)?
+                };
+                let end = if end.trim().is_empty() {
+                    page_count.checked_sub(1).ok_or(PdfError::InvalidPage(1))?
+                } else {
+                    parse_page(end)?
+                };
+                if start <= end {
+                    pages.extend(start..=end);
+                } else {
+                    pages.extend((end..=start).rev());
+                }
+            }
+        }
+    }
+
+    Ok(pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_page_range() {
+        assert_eq!(parse_page_range("", 3).unwrap(), vec![0, 1, 2]);
+        assert_eq!(parse_page_range("1-3, 5", 6).unwrap(), vec![0, 1, 2, 4]);
+        assert_eq!(parse_page_range("3-1", 3).unwrap(), vec![2, 1, 0]);
+        assert_eq!(parse_page_range("4-,-2", 5).unwrap(), vec![3, 4, 0, 1]);
+    }
+
+    #[test]
+    fn test_parse_page_range_errors() {
+        assert!(parse_page_range("0", 3).is_err());
+        assert!(parse_page_range("4", 3).is_err());
+        assert!(parse_page_range("1,,2", 3).is_err());
+        assert!(parse_page_range("a-b", 3).is_err());
+    }
+}
@@ -0,0 +1,267 @@
+use super::merge::remap_outline;
+use super::outline_writer::write_outline;
+use super::page_range::parse_page_range;
+use super::{OutlineItem, PdfDocument, PdfError, Result};
+use std::path::{Path, PathBuf};
+
+/// File name template used when none is given
+pub const DEFAULT_NAME_TEMPLATE: &str = "{name}-{n}";
+
+/// File name template used by default when splitting at bookmarks
+pub const DEFAULT_BOOKMARK_TEMPLATE: &str = "{n} {title}";
+
+/// How to cut a document into parts
+#[derive(Debug, Clone, PartialEq)]
+pub enum SplitMode {
+    /// One part per comma separated range, e.g. "1-3,4-10,11-"
+    Ranges(String),
+    /// Consecutive parts of N pages, the last one possibly shorter
+    EveryN(usize),
+    /// One part per top-level bookmark
+    Bookmarks,
+}
+
+/// Pages of one output file, with the bookmark it starts at
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitPart {
+    pub pages: Vec<usize>,
+    pub title: Option<String>,
+}
+
+/// Work out the parts for `mode` without touching any file
+pub fn plan_split(
+    mode: &SplitMode,
+    page_count: usize,
+    outline: &[OutlineItem],
+) -> Result<Vec<SplitPart>> {
+    let part = |pages: Vec<usize>, title: Option<String>| SplitPart { pages, title };
+
+    let parts: Vec<SplitPart> = match mode {
+        SplitMode::Ranges(spec) => {
+            if spec.trim().is_empty() {
+                return Err(PdfError::InvalidPageRange(spec.clone()));
+            }
+            spec.split(',')
+                .map(|range| parse_page_range(range, page_count).map(|pages| part(pages, None)))
+                .collect::<Result<_>>()?
+        }
+        SplitMode::EveryN(n) => {
+            if *n == 0 {
+                return Err(PdfError::InvalidPageRange("0".to_string()));
+            }
+            (0..page_count)
+                .step_by(*n)
+                .map(|start| part((start..(start + n).min(page_count)).collect(), None))
+                .collect()
+        }
+        SplitMode::Bookmarks => {
+            let mut starts: Vec<(usize, &str)> = outline
+                .iter()
+                .filter(|item| item.page < page_count)
+                .map(|item| (item.page, item.title.as_str()))
+                .collect();
+            starts.sort_by_key(|(page, _)| *page);
+            // Several bookmarks on one page start a single part, named after the first
+            starts.dedup_by_key(|(page, _)| *page);
+            if starts.is_empty() {
+                return Err(PdfError::SaveError(
+                    "The document has no bookmarks to split at".to_string(),
+                ));
+            }
+
+            let mut parts = Vec::new();
+            if starts[0].0 > 0 {
+                parts.push(part((0..starts[0].0).collect(), None));
+            }
+            for (i, (start, title)) in starts.iter().enumerate() {
+                let end = starts.get(i + 1).map(|(p, _)| *p).unwrap_or(page_count);
+                parts.push(part((*start..end).collect(), Some(title.to_string())));
+            }
+            parts
+        }
+    };
+
+    Ok(parts.into_iter().filter(|p| !p.pages.is_empty()).collect())
+}
+
+/// Replace characters that are not allowed in file names on common platforms
+pub(super) fn sanitize_file_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    cleaned.trim().trim_matches('.').trim().to_string()
+}
+
+/// Expand a file name template for one part. Supported placeholders:
+/// `{name}` source file name, `{n}` part number, `{title}` bookmark title,
+/// `{start}` and `{end}` first and last page numbers.
+pub fn expand_template(
+    template: &str,
+    source_name: &str,
+    index: usize,
+    part_count: usize,
+    part: &SplitPart,
+) -> String {
+    let width = part_count.to_string().len();
+    let number = format!("{:0width$}", index + 1, width = width);
+    let first = part.pages.first().map(|p| p + 1).unwrap_or(0);
+    let last = part.pages.last().map(|p| p + 1).unwrap_or(0);
+    let title = part
+        .title
+        .clone()
+        .unwrap_or_else(|| source_name.to_string());
+
+    let name = template
+        .replace("{name}", source_name)
+        .replace("{n}", &number)
+        .replace("{title}", &title)
+        .replace("{start}", &first.to_string())
+        .replace("{end}", &last.to_string());
+
+    let name = sanitize_file_name(&name);
+    if name.is_empty() {
+        number
+    } else {
+        name
+    }
+}
+
+impl PdfDocument {
+    /// Split the document into files written to `output_dir`, named from `template`.
+    /// Each part keeps the bookmarks that point into it. Returns the files written.
+    pub fn split(
+        &self,
+        mode: &SplitMode,
+        output_dir: &Path,
+        template: &str,
+    ) -> Result<Vec<PathBuf>> {
+        self.split_with_progress(mode, output_dir, template, |_, _| true)
+    }
+
+    /// `split`, calling `on_part(done, total)` before writing each part. Stops
+    /// when it returns false, keeping the files written so far.
+    pub fn split_with_progress(
+        &self,
+        mode: &SplitMode,
+        output_dir: &Path,
+        template: &str,
+        mut on_part: impl FnMut(usize, usize) -> bool,
+    ) -> Result<Vec<PathBuf>> {
+        let outline = self.get_outline().unwrap_or_default();
+        let parts = plan_split(mode, self.page_count, &outline)?;
+
+        let path_str = self.path.to_string_lossy().to_string();
+        let pdfium = Self::get_pdfium()?;
+        let source = pdfium
+            .load_pdf_from_file(&path_str, None)
+            .map_err(|e| PdfError::OpenError(format!("Failed to load PDF: {}", e)))?;
+
+        let source_name = self
+            .path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| String::from("document"));
+
+        std::fs::create_dir_all(output_dir).map_err(|e| {
+            PdfError::SaveError(format!("Failed to create {}: {}", output_dir.display(), e))
+        })?;
+
+        let mut written: Vec<PathBuf> = Vec::new();
+        for (index, part) in parts.iter().enumerate() {
+            if !on_part(index, parts.len()) {
+                break;
+            }
+            let name = expand_template(template, &source_name, index, parts.len(), part);
+            let mut output = output_dir.join(format!("{}.pdf", name));
+            let mut suffix = 2;
+            while written.contains(&output) || output == self.path {
+                output = output_dir.join(format!("{}-{}.pdf", name, suffix));
+                suffix += 1;
+            }
+
+            let mut document = pdfium
+                .create_new_pdf()
+                .map_err(|e| PdfError::SaveError(format!("Failed to create PDF: {}", e)))?;
+            let page_list: Vec<String> = part.pages.iter().map(|p| (p + 1).to_string()).collect();
+            document
+                .pages_mut()
+                .copy_pages_from_document(&source, &page_list.join(","), 0)
+                .map_err(|e| PdfError::SaveError(format!("Failed to import pages: {}", e)))?;
+            document
+                .save_to_file(&output)
+                .map_err(|e| PdfError::SaveError(format!("Failed to save PDF: {}", e)))?;
+            drop(document);
+
+            let part_outline = remap_outline(&outline, &part.pages, 0);
+            if !part_outline.is_empty() {
+                write_outline(&output, &part_outline)?;
+            }
+
+            written.push(output);
+        }
+
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bookmark(title: &str, page: usize) -> OutlineItem {
+        OutlineItem {
+            title: title.to_string(),
+            page,
+            children: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_plan_split() {
+        let parts = plan_split(&SplitMode::Ranges("1-3,4-5,6-".to_string()), 7, &[]).unwrap();
+        let pages: Vec<_> = parts.iter().map(|p| p.pages.clone()).collect();
+        assert_eq!(pages, vec![vec![0, 1, 2], vec![3, 4], vec![5, 6]]);
+
+        let parts = plan_split(&SplitMode::EveryN(3), 7, &[]).unwrap();
+        let pages: Vec<_> = parts.iter().map(|p| p.pages.clone()).collect();
+        assert_eq!(pages, vec![vec![0, 1, 2], vec![3, 4, 5], vec![6]]);
+
+        let outline = [
+            bookmark("Two", 4),
+            bookmark("One", 2),
+            bookmark("Also one", 2),
+        ];
+        let parts = plan_split(&SplitMode::Bookmarks, 6, &outline).unwrap();
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0].title, None);
+        assert_eq!(parts[1].pages, vec![2, 3]);
+        assert_eq!(parts[1].title.as_deref(), Some("One"));
+        assert_eq!(parts[2].pages, vec![4, 5]);
+    }
+
+    #[test]
+    fn test_expand_template() {
+        let part = SplitPart {
+            pages: vec![4, 5, 6],
+            title: Some("Chapter 2: Results".to_string()),
+        };
+        assert_eq!(
+            expand_template(DEFAULT_NAME_TEMPLATE, "report", 1, 12, &part),
+            "report-02"
+        );
+        assert_eq!(
+            expand_template(DEFAULT_BOOKMARK_TEMPLATE, "report", 1, 3, &part),
+            "2 Chapter 2_ Results"
+        );
+        assert_eq!(
+            expand_template("{name} p{start}-{end}", "report", 0, 1, &part),
+            "report p5-7"
+        );
+        assert_eq!(expand_template("..", "report", 0, 1, &part), "1");
+    }
+}
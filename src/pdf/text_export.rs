@@ -0,0 +1,223 @@
+use super::text_layout::{detect_headings, layout_page, TextBlock};
+use super::{collect_text_chars, PdfDocument, PdfError, Result};
+use pdfium_render::prelude::*;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextExportFormat {
+    PlainText,
+    Markdown,
+    Html,
+}
+
+impl TextExportFormat {
+    /// Format for a file extension or format name such as "md"
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_lowercase().as_str() {
+            "txt" | "text" => Some(TextExportFormat::PlainText),
+            "md" | "markdown" => Some(TextExportFormat::Markdown),
+            "html" | "htm" => Some(TextExportFormat::Html),
+            _ => None,
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Self::from_extension(&path.extension()?.to_string_lossy())
+    }
+}
+
+/// Text of a page after layout analysis
+#[derive(Debug, Clone)]
+pub struct PageBlocks {
+    pub page: usize,
+    pub blocks: Vec<TextBlock>,
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Escape characters Markdown would otherwise read as formatting
+pub(crate) fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '#' | '|'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    // A paragraph that starts like a list item would become one
+    let marker_end = escaped
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(escaped.len());
+    let rest = &escaped[marker_end..];
+    if (marker_end > 0 && (rest.starts_with(". ") || rest.starts_with(") ")))
+        || (marker_end == 0 && (rest.starts_with("- ") || rest.starts_with("+ ")))
+    {
+        escaped.insert(marker_end, '\\');
+    }
+    escaped
+}
+
+/// Write laid out pages as plain text, Markdown or HTML.
+/// Page breaks become a form feed in plain text, a comment in Markdown
+/// and a `<section>` per page in HTML.
+pub fn format_text(pages: &[PageBlocks], format: TextExportFormat, title: &str) -> String {
+    let mut out = String::new();
+
+    match format {
+        TextExportFormat::PlainText => {
+            for (i, page) in pages.iter().enumerate() {
+                if i > 0 {
+                    out.push('\u{c}');
+                }
+                for block in &page.blocks {
+                    out.push_str(&block.text());
+                    out.push_str("\n\n");
+                }
+            }
+        }
+        TextExportFormat::Markdown => {
+            for (i, page) in pages.iter().enumerate() {
+                if i > 0 {
+                    out.push_str("---\n\n");
+                }
+                out.push_str(&format!("<!-- Page {} -->\n\n", page.page + 1));
+                for block in &page.blocks {
+                    if let Some(level) = block.heading {
+                        out.push_str(&"#".repeat(level as usize));
+                        out.push(' ');
+                    }
+                    out.push_str(&escape_markdown(&block.text()));
+                    out.push_str("\n\n");
+                }
+            }
+        }
+        TextExportFormat::Html => {
+            out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+            out.push_str(&format!("<title>{}</title>\n", escape_html(title)));
+            out.push_str("</head>\n<body>\n");
+            for page in pages {
+                out.push_str(&format!(
+                    "<section class=\"page\" id=\"page-{0}\" data-page=\"{0}\">\n",
+                    page.page + 1
+                ));
+                for block in &page.blocks {
+                    let tag = match block.heading {
+                        Some(level) => format!("h{}", level),
+                        None => "p".to_string(),
+                    };
+                    out.push_str(&format!(
+                        "<{0}>{1}</{0}>\n",
+                        tag,
+                        escape_html(&block.text())
+                    ));
+                }
+                out.push_str("</section>\n");
+            }
+            out.push_str("</body>\n</html>\n");
+        }
+    }
+
+    let trimmed = out.trim_end().len();
+    out.truncate(trimmed);
+    out.push('\n');
+    out
+}
+
+impl PdfDocument {
+    /// Lay out the text of `pages` into paragraphs and headings, in reading order
+    pub fn layout_text(&self, pages: &[usize]) -> Result<Vec<PageBlocks>> {
+        let path_str = self.path.to_string_lossy().to_string();
+        let pdfium = Self::get_pdfium()?;
+        let doc = pdfium
+            .load_pdf_from_file(&path_str, None)
+            .map_err(|e| PdfError::RenderError(format!("Failed to load PDF: {}", e)))?;
+
+        let mut result = Vec::with_capacity(pages.len());
+        for &page_num in pages {
+            if page_num >= self.page_count {
+                return Err(PdfError::InvalidPage(page_num));
+            }
+            let page = doc.pages().get(page_num as PdfPageIndex).map_err(|e| {
+                PdfError::RenderError(format!("Failed to load page {}: {}", page_num, e))
+            })?;
+            let page_text = page.text().map_err(|e| {
+                PdfError::RenderError(format!(
+                    "Failed to extract text from page {}: {}",
+                    page_num, e
+                ))
+            })?;
+            result.push(layout_page(&collect_text_chars(&page, &page_text)));
+        }
+
+        // Heading levels depend on the font sizes used across all exported pages
+        detect_headings(&mut result);
+
+        Ok(pages
+            .iter()
+            .zip(result)
+            .map(|(&page, blocks)| PageBlocks { page, blocks })
+            .collect())
+    }
+
+    /// Export the text of `pages` to `output`
+    pub fn export_text(
+        &self,
+        pages: &[usize],
+        format: TextExportFormat,
+        output: &Path,
+    ) -> Result<()> {
+        let laid_out = self.layout_text(pages)?;
+        let title = self
+            .path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        std::fs::write(output, format_text(&laid_out, format, &title)).map_err(|e| {
+            PdfError::SaveError(format!("Failed to write {}: {}", output.display(), e))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_text() {
+        let block = |text: &str, heading| TextBlock {
+            lines: vec![text.to_string()],
+            font_size: 10.0,
+            heading,
+        };
+        let pages = vec![
+            PageBlocks {
+                page: 0,
+                blocks: vec![block("Intro", Some(1)), block("1. Not a <list>", None)],
+            },
+            PageBlocks {
+                page: 1,
+                blocks: vec![block("*end*", None)],
+            },
+        ];
+
+        assert_eq!(
+            format_text(&pages, TextExportFormat::PlainText, "doc"),
+            "Intro\n\n1. Not a <list>\n\n\u{c}*end*\n"
+        );
+        assert_eq!(
+            format_text(&pages, TextExportFormat::Markdown, "doc"),
+            "<!-- Page 1 -->\n\n# Intro\n\n1\\. Not a \\<list\\>\n\n---\n\n<!-- Page 2 -->\n\n\\*end\\*\n"
+        );
+        assert!(format_text(&pages, TextExportFormat::Html, "doc")
+            .contains("<section class=\"page\" id=\"page-2\" data-page=\"2\">\n<p>*end*</p>"));
+    }
+}
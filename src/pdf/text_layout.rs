@@ -0,0 +1,397 @@
+//! Reconstruct lines, columns, paragraphs and headings from positioned characters
+
+use super::TextChar;
+
+/// Horizontal gap, in font sizes, that ends a line fragment (e.g. a column gutter)
+const FRAGMENT_GAP: f32 = 3.0;
+
+/// Horizontal gap, in font sizes, treated as a word break when the PDF has no space
+const WORD_GAP: f32 = 0.2;
+
+/// Fragments wider than this share of the text width never belong to one column
+const WIDE_FRAGMENT: f32 = 0.55;
+
+/// Fragments with fewer characters do not count when looking for column gutters
+const MIN_COLUMN_FRAGMENT_CHARS: usize = 5;
+
+/// Lines each side of a gutter needed before it is treated as a column break
+const MIN_COLUMN_LINES: usize = 3;
+
+/// A font at least this much larger than body text marks a heading
+const HEADING_SCALE: f32 = 1.15;
+
+/// Headings are short; longer blocks stay paragraphs whatever their size
+const MAX_HEADING_CHARS: usize = 200;
+
+/// A run of characters on one line, without large gaps
+#[derive(Debug, Clone)]
+struct Fragment {
+    text: String,
+    chars: usize,
+    x0: f32,
+    x1: f32,
+    top: f32,
+    bottom: f32,
+    font_size: f32,
+}
+
+impl Fragment {
+    fn new(c: &TextChar, size: f32) -> Self {
+        Self {
+            text: c.char.to_string(),
+            chars: 1,
+            x0: c.x,
+            x1: c.x + c.width,
+            top: c.y + c.height,
+            bottom: c.y,
+            font_size: size,
+        }
+    }
+
+    fn height(&self) -> f32 {
+        (self.top - self.bottom).max(1.0)
+    }
+}
+
+/// A paragraph or heading, with its lines in reading order
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextBlock {
+    pub lines: Vec<String>,
+    pub font_size: f32,
+    /// Heading level from 1, set by `detect_headings`
+    pub heading: Option<u8>,
+}
+
+impl TextBlock {
+    /// The block's lines joined into one string, undoing end-of-line hyphenation
+    pub fn text(&self) -> String {
+        join_lines(&self.lines)
+    }
+}
+
+fn char_size(c: &TextChar) -> f32 {
+    if c.font_size > 0.0 {
+        c.font_size
+    } else {
+        c.height.max(1.0)
+    }
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32, 0x2E80..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF | 0xFF00..=0xFFEF)
+}
+
+/// How a wrapped line continues the text before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineJoin {
+    /// Separate the lines with a space
+    Space,
+    /// Append the line directly (CJK text, or a hyphen that is kept)
+    Direct,
+    /// Drop the hyphen ending the text, then append directly
+    DropHyphen,
+}
+
+/// A hyphen at the end of a line is kept without a space, or dropped when a
+/// lowercase letter follows; CJK text is joined without a space.
+pub fn line_join(before: &str, line: &str) -> LineJoin {
+    let (Some(last), Some(first)) = (before.chars().last(), line.chars().next()) else {
+        return LineJoin::Direct;
+    };
+    if last == '-' {
+        let before_hyphen = before.chars().rev().nth(1);
+        if first.is_lowercase() && before_hyphen.is_some_and(|c| c.is_alphabetic()) {
+            LineJoin::DropHyphen
+        } else {
+            LineJoin::Direct
+        }
+    } else if is_cjk(last) || is_cjk(first) || last.is_whitespace() {
+        LineJoin::Direct
+    } else {
+        LineJoin::Space
+    }
+}
+
+/// Join wrapped lines with spaces, undoing hyphenation as `line_join` describes
+pub fn join_lines<S: AsRef<str>>(lines: &[S]) -> String {
+    let mut text = String::new();
+    for line in lines {
+        let line = line.as_ref().trim();
+        if line.is_empty() {
+            continue;
+        }
+        match line_join(&text, line) {
+            LineJoin::Space => text.push(' '),
+            LineJoin::Direct => {}
+            LineJoin::DropHyphen => {
+                text.pop();
+            }
+        }
+        text.push_str(line);
+    }
+    text
+}
+
+/// Group characters, in content order, into line fragments
+fn build_fragments(chars: &[TextChar]) -> Vec<Fragment> {
+    let mut fragments: Vec<Fragment> = Vec::new();
+    let mut current: Option<Fragment> = None;
+    let mut pending_space = false;
+
+    for c in chars {
+        if c.char.is_whitespace() || c.char.is_control() {
+            pending_space = current.is_some();
+            continue;
+        }
+        let size = char_size(c);
+
+        if let Some(fragment) = current.as_mut() {
+            let tolerance = fragment.height().min(c.height.max(1.0)) * 0.5;
+            let same_line = (c.y - fragment.bottom).abs() < tolerance;
+            let gap = c.x - fragment.x1;
+            let limit = fragment.font_size.max(size);
+            if same_line && gap > -limit * 0.5 && gap < limit * FRAGMENT_GAP {
+                if pending_space || gap > limit * WORD_GAP {
+                    fragment.text.push(' ');
+                }
+                fragment.text.push(c.char);
+                fragment.chars += 1;
+                fragment.x1 = fragment.x1.max(c.x + c.width);
+                fragment.top = fragment.top.max(c.y + c.height);
+                fragment.font_size = fragment.font_size.max(size);
+                pending_space = false;
+                continue;
+            }
+        }
+
+        if let Some(fragment) = current.take() {
+            fragments.push(fragment);
+        }
+        current = Some(Fragment::new(c, size));
+        pending_space = false;
+    }
+
+    fragments.extend(current);
+    fragments
+}
+
+/// X positions of the gaps between text columns
+fn find_gutters(fragments: &[Fragment]) -> Vec<f32> {
+    let left = fragments.iter().map(|f| f.x0).fold(f32::MAX, f32::min);
+    let right = fragments.iter().map(|f| f.x1).fold(f32::MIN, f32::max);
+    let width = right - left;
+    if width <= 0.0 {
+        return Vec::new();
+    }
+
+    let mut spans: Vec<(f32, f32)> = fragments
+        .iter()
+        .filter(|f| f.chars >= MIN_COLUMN_FRAGMENT_CHARS && f.x1 - f.x0 < width * WIDE_FRAGMENT)
+        .map(|f| (f.x0, f.x1))
+        .collect();
+    spans.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut sizes: Vec<f32> = fragments.iter().map(|f| f.font_size).collect();
+    sizes.sort_by(f32::total_cmp);
+    let min_gap = sizes.get(sizes.len() / 2).copied().unwrap_or(10.0);
+
+    let mut gutters = Vec::new();
+    let mut covered_to: Option<f32> = None;
+    for (x0, x1) in spans {
+        match covered_to {
+            Some(end) if x0 - end >= min_gap => {
+                let gutter = (end + x0) / 2.0;
+                let lines_left = fragments.iter().filter(|f| f.x1 <= gutter).count();
+                let lines_right = fragments.iter().filter(|f| f.x0 >= gutter).count();
+                if lines_left >= MIN_COLUMN_LINES && lines_right >= MIN_COLUMN_LINES {
+                    gutters.push(gutter);
+                }
+                covered_to = Some(x1);
+            }
+            Some(end) => covered_to = Some(end.max(x1)),
+            None => covered_to = Some(x1),
+        }
+    }
+    gutters
+}
+
+/// Order fragments for reading: top to bottom, and column by column between
+/// fragments that span several columns (titles, figures, footers).
+/// Returns each fragment with the region it belongs to.
+fn reading_order(mut fragments: Vec<Fragment>) -> Vec<(usize, Fragment)> {
+    let gutters = find_gutters(&fragments);
+    fragments.sort_by(|a, b| b.top.total_cmp(&a.top).then(a.x0.total_cmp(&b.x0)));
+
+    let column_of = |f: &Fragment| -> Option<usize> {
+        if gutters.iter().any(|g| f.x0 < *g && f.x1 > *g) {
+            None
+        } else {
+            Some(gutters.iter().filter(|g| f.x0 >= **g).count())
+        }
+    };
+
+    let mut ordered = Vec::with_capacity(fragments.len());
+    let mut band: Vec<(usize, Fragment)> = Vec::new();
+    let mut region_base = 0;
+    let flush = |band: &mut Vec<(usize, Fragment)>, ordered: &mut Vec<(usize, Fragment)>| {
+        // Stable sort keeps each column top to bottom
+        band.sort_by_key(|(column, _)| *column);
+        ordered.append(band);
+    };
+
+    for fragment in fragments {
+        match column_of(&fragment) {
+            Some(column) => band.push((region_base + column, fragment)),
+            None => {
+                flush(&mut band, &mut ordered);
+                region_base += gutters.len() + 1;
+                ordered.push((region_base, fragment));
+                region_base += 1;
+            }
+        }
+    }
+    flush(&mut band, &mut ordered);
+    ordered
+}
+
+/// Break a page's characters into blocks in reading order.
+/// Headings are not marked; see `detect_headings`.
+pub fn layout_page(chars: &[TextChar]) -> Vec<TextBlock> {
+    let fragments = build_fragments(chars);
+    if fragments.is_empty() {
+        return Vec::new();
+    }
+
+    let mut blocks: Vec<TextBlock> = Vec::new();
+    let mut previous: Option<(usize, Fragment)> = None;
+
+    for (region, fragment) in reading_order(fragments) {
+        let continues = previous.as_ref().is_some_and(|(prev_region, prev)| {
+            let gap = prev.bottom - fragment.top;
+            let same_size = (prev.font_size - fragment.font_size).abs()
+                <= prev.font_size.max(fragment.font_size) * 0.1;
+            *prev_region == region
+                && same_size
+                && gap > -prev.height() * 0.5
+                && gap < prev.height() * 0.6
+        });
+
+        match blocks.last_mut() {
+            Some(block) if continues => {
+                block.lines.push(fragment.text.clone());
+                block.font_size = block.font_size.max(fragment.font_size);
+            }
+            _ => blocks.push(TextBlock {
+                lines: vec![fragment.text.clone()],
+                font_size: fragment.font_size,
+                heading: None,
+            }),
+        }
+        previous = Some((region, fragment));
+    }
+
+    blocks
+}
+
+fn size_key(size: f32) -> i32 {
+    (size * 2.0).round() as i32
+}
+
+/// Mark short blocks set larger than the body text as headings. The largest
+/// size becomes level 1, the next level 2 and so on, up to level 6.
+pub fn detect_headings(pages: &mut [Vec<TextBlock>]) {
+    let mut usage: Vec<(i32, usize)> = Vec::new();
+    for block in pages.iter().flatten() {
+        let key = size_key(block.font_size);
+        let count = block.lines.iter().map(|l| l.chars().count()).sum::<usize>();
+        match usage.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, total)) => *total += count,
+            None => usage.push((key, count)),
+        }
+    }
+    let Some(body) = usage
+        .iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(k, _)| *k)
+    else {
+        return;
+    };
+    let body_size = body as f32 / 2.0;
+
+    let is_heading = |block: &TextBlock| {
+        block.font_size >= body_size * HEADING_SCALE
+            && block.lines.len() <= 3
+            && block.text().chars().count() <= MAX_HEADING_CHARS
+    };
+
+    let mut heading_sizes: Vec<i32> = pages
+        .iter()
+        .flatten()
+        .filter(|b| is_heading(b))
+        .map(|b| size_key(b.font_size))
+        .collect();
+    heading_sizes.sort_unstable_by(|a, b| b.cmp(a));
+    heading_sizes.dedup();
+
+    for block in pages.iter_mut().flatten() {
+        if is_heading(block) {
+            let key = size_key(block.font_size);
+            let rank = heading_sizes.iter().position(|k| *k == key).unwrap_or(0);
+            block.heading = Some((rank + 1).min(6) as u8);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Characters of `text` laid out left to right, `advance` apart
+    fn line(text: &str, x: f32, y: f32, size: f32) -> Vec<TextChar> {
+        let advance = size * 0.5;
+        text.chars()
+            .enumerate()
+            .map(|(i, c)| TextChar {
+                char: c,
+                x: x + i as f32 * advance,
+                y,
+                width: advance,
+                height: size,
+                font_size: size,
+                bold: false,
+                italic: false,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_two_columns_under_a_title() {
+        let mut chars = line("A Title That Spans Both Columns", 50.0, 700.0, 20.0);
+        // Rows are emitted across both columns, as some generators do
+        for (i, y) in [650.0, 638.0, 626.0].into_iter().enumerate() {
+            chars.extend(line(&format!("left line {}", i), 50.0, y, 10.0));
+            chars.extend(line(&format!("right line {}", i), 200.0, y, 10.0));
+        }
+        chars.extend(line("7", 150.0, 40.0, 10.0));
+
+        let mut pages = vec![layout_page(&chars)];
+        detect_headings(&mut pages);
+        let blocks = &pages[0];
+
+        assert_eq!(blocks.len(), 4);
+        assert_eq!(blocks[0].heading, Some(1));
+        assert_eq!(blocks[1].text(), "left line 0 left line 1 left line 2");
+        assert_eq!(blocks[2].text(), "right line 0 right line 1 right line 2");
+        assert_eq!(blocks[2].heading, None);
+        assert_eq!(blocks[3].text(), "7");
+    }
+
+    #[test]
+    fn test_join_lines() {
+        assert_eq!(
+            join_lines(&["a hyphen-", "ated word", "and X-", "Ray"]),
+            "a hyphenated word and X-Ray"
+        );
+        assert_eq!(join_lines(&["中文", "文本"]), "中文文本");
+    }
+}
@@ -0,0 +1,556 @@
+//! Imposition: laying several pages on one sheet (N-up), folding sheets into a
+//! booklet, or tiling one page across several sheets (poster).
+//!
+//! The layout is worked out here once and used both for the print preview and
+//! for composing the PDF that is sent to the printer, so every backend prints
+//! the same sheets.
+
+use super::{Orientation, PrintError, PrintSettings, Result, MM_PER_POINT};
+use crate::pdf::PdfDocument;
+use pdfium_render::prelude::*;
+use std::path::Path;
+
+/// Pages per sheet offered for N-up printing
+pub const NUP_CHOICES: [usize; 5] = [2, 4, 6, 9, 16];
+/// Sheets across offered for poster printing
+pub const POSTER_CHOICES: [usize; 4] = [2, 3, 4, 5];
+/// Overlap between poster tiles offered, in millimetres
+pub const OVERLAP_CHOICES: [f32; 4] = [0.0, 5.0, 10.0, 20.0];
+
+/// Space between pages on an N-up sheet, in millimetres
+const NUP_GAP: f32 = 3.0;
+/// Width of borders and marks, in millimetres
+const LINE_WIDTH: f32 = 0.2;
+
+/// A rectangle in millimetres from the top left of the sheet: (x, y, width, height)
+pub type MmRect = (f32, f32, f32, f32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Imposition {
+    /// One page per sheet
+    #[default]
+    None,
+    NUp(NUp),
+    /// Sheets printed on both sides that fold and nest into a booklet
+    Booklet,
+    Poster(Poster),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NUp {
+    pub pages_per_sheet: usize,
+    pub order: NUpOrder,
+    /// Draw a frame around each page
+    pub borders: bool,
+}
+
+impl Default for NUp {
+    fn default() -> Self {
+        Self {
+            pages_per_sheet: 4,
+            order: NUpOrder::LeftToRight,
+            borders: true,
+        }
+    }
+}
+
+/// Order in which pages fill the cells of an N-up sheet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NUpOrder {
+    /// Along rows, left to right
+    LeftToRight,
+    /// Along rows, right to left
+    RightToLeft,
+    /// Down columns, left to right
+    TopToBottom,
+}
+
+impl NUpOrder {
+    pub const ALL: [NUpOrder; 3] = [
+        NUpOrder::LeftToRight,
+        NUpOrder::RightToLeft,
+        NUpOrder::TopToBottom,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Poster {
+    /// The page is enlarged to span this many sheets across; as many rows of
+    /// sheets as its height needs are added
+    pub sheets_across: usize,
+    /// Millimetres each tile repeats of its neighbour, for gluing
+    pub overlap: f32,
+    /// Draw lines where a tile's overlap ends
+    pub marks: bool,
+}
+
+impl Default for Poster {
+    fn default() -> Self {
+        Self {
+            sheets_across: 2,
+            overlap: 10.0,
+            marks: true,
+        }
+    }
+}
+
+/// A page drawn on a sheet
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placement {
+    pub page: usize,
+    /// Where the page lands, after turning
+    pub rect: MmRect,
+    /// Turned a quarter clockwise to better fill its cell
+    pub rotated: bool,
+    /// Only this part of the sheet shows the page
+    pub clip: MmRect,
+}
+
+/// One side of paper as it will be printed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sheet {
+    pub placements: Vec<Placement>,
+    /// Frames drawn around pages
+    pub borders: Vec<MmRect>,
+    /// Straight lines from (x1, y1) to (x2, y2)
+    pub marks: Vec<(f32, f32, f32, f32)>,
+}
+
+/// Size of the sheets in millimetres. Booklets are always laid out on landscape
+/// sheets, two portrait pages side by side.
+pub fn sheet_size(settings: &PrintSettings) -> (f32, f32) {
+    let (width, height) = settings.paper_dimensions_mm();
+    match settings.imposition {
+        Imposition::Booklet => (width.max(height), width.min(height)),
+        _ => (width, height),
+    }
+}
+
+/// Area inside the margins
+fn printable_area(settings: &PrintSettings) -> MmRect {
+    let (width, height) = sheet_size(settings);
+    let margins = settings.margins;
+    (
+        margins.left,
+        margins.top,
+        (width - margins.left - margins.right).max(1.0),
+        (height - margins.top - margins.bottom).max(1.0),
+    )
+}
+
+/// Columns and rows of an N-up grid, with more cells along the sheet's long side
+fn nup_grid(pages_per_sheet: usize, sheet: (f32, f32)) -> (usize, usize) {
+    let (long, short) = match pages_per_sheet {
+        2 => (2, 1),
+        4 => (2, 2),
+        6 => (3, 2),
+        9 => (3, 3),
+        16 => (4, 4),
+        n => {
+            let side = (n as f32).sqrt().ceil().max(1.0) as usize;
+            (side, n.div_ceil(side))
+        }
+    };
+    if sheet.0 >= sheet.1 {
+        (long, short)
+    } else {
+        (short, long)
+    }
+}
+
+/// Fit a page of `page_size` points into `cell`, turning it when that makes it
+/// larger, and centre it
+fn fit_page(page: usize, page_size: (f32, f32), cell: MmRect, clip: MmRect) -> Placement {
+    let (page_width, page_height) = (
+        page_size.0.max(1.0) * MM_PER_POINT,
+        page_size.1.max(1.0) * MM_PER_POINT,
+    );
+    let (_, _, cell_width, cell_height) = cell;
+    let rotated = (page_width > page_height) != (cell_width > cell_height);
+    let (width, height) = if rotated {
+        (page_height, page_width)
+    } else {
+        (page_width, page_height)
+    };
+    let scale = (cell_width / width).min(cell_height / height);
+    let (width, height) = (width * scale, height * scale);
+    Placement {
+        page,
+        rect: (
+            cell.0 + (cell_width - width) / 2.0,
+            cell.1 + (cell_height - height) / 2.0,
+            width,
+            height,
+        ),
+        rotated,
+        clip,
+    }
+}
+
+/// Positions in `pages` of the (left, right) pages on each side of a booklet's
+/// sheets, fronts and backs in turn. The count is padded to a multiple of four
+/// with blanks (`None`) at the end.
+pub fn booklet_order(count: usize) -> Vec<(Option<usize>, Option<usize>)> {
+    let padded = count.div_ceil(4) * 4;
+    let slot = |index: usize| (index < count).then_some(index);
+    (0..padded / 4)
+        .flat_map(|sheet| {
+            let outer = 2 * sheet;
+            [
+                (slot(padded - 1 - outer), slot(outer)),
+                (slot(outer + 1), slot(padded - 2 - outer)),
+            ]
+        })
+        .collect()
+}
+
+/// Lay `pages` out on sheets as `settings.imposition` asks. `page_sizes` holds
+/// the size in points of every page of the document.
+pub fn layout_sheets(
+    settings: &PrintSettings,
+    pages: &[usize],
+    page_sizes: &[(f32, f32)],
+) -> Vec<Sheet> {
+    let size_of = |page: usize| page_sizes.get(page).copied().unwrap_or((612.0, 792.0));
+    let (sheet_width, sheet_height) = sheet_size(settings);
+    let whole_sheet = (0.0, 0.0, sheet_width, sheet_height);
+    let area = printable_area(settings);
+    let (area_x, area_y, area_width, area_height) = area;
+
+    match settings.imposition {
+        Imposition::None => pages
+            .iter()
+            .map(|&page| {
+                let size = size_of(page);
+                Sheet {
+                    placements: vec![Placement {
+                        page,
+                        rect: settings.page_placement(size),
+                        rotated: false,
+                        clip: whole_sheet,
+                    }],
+                    ..Default::default()
+                }
+            })
+            .collect(),
+
+        Imposition::NUp(nup) => {
+            let (columns, rows) = nup_grid(nup.pages_per_sheet, (sheet_width, sheet_height));
+            let cell_width = (area_width - NUP_GAP * (columns - 1) as f32) / columns as f32;
+            let cell_height = (area_height - NUP_GAP * (rows - 1) as f32) / rows as f32;
+            pages
+                .chunks(columns * rows)
+                .map(|chunk| {
+                    let mut sheet = Sheet::default();
+                    for (index, &page) in chunk.iter().enumerate() {
+                        let (column, row) = match nup.order {
+                            NUpOrder::LeftToRight => (index % columns, index / columns),
+                            NUpOrder::RightToLeft => {
+                                (columns - 1 - index % columns, index / columns)
+                            }
+                            NUpOrder::TopToBottom => (index / rows, index % rows),
+                        };
+                        let cell = (
+                            area_x + column as f32 * (cell_width + NUP_GAP),
+                            area_y + row as f32 * (cell_height + NUP_GAP),
+                            cell_width,
+                            cell_height,
+                        );
+                        let placement = fit_page(page, size_of(page), cell, whole_sheet);
+                        if nup.borders {
+                            sheet.borders.push(placement.rect);
+                        }
+                        sheet.placements.push(placement);
+                    }
+                    sheet
+                })
+                .collect()
+        }
+
+        Imposition::Booklet => {
+            let half = area_width / 2.0;
+            let left_cell = (area_x, area_y, half, area_height);
+            let right_cell = (area_x + half, area_y, half, area_height);
+            booklet_order(pages.len())
+                .into_iter()
+                .map(|(left, right)| {
+                    let mut sheet = Sheet::default();
+                    // Pages sit against the fold in the middle
+                    if let Some(index) = left {
+                        let mut placement =
+                            fit_page(pages[index], size_of(pages[index]), left_cell, whole_sheet);
+                        placement.rect.0 = area_x + half - placement.rect.2;
+                        sheet.placements.push(placement);
+                    }
+                    if let Some(index) = right {
+                        let mut placement =
+                            fit_page(pages[index], size_of(pages[index]), right_cell, whole_sheet);
+                        placement.rect.0 = area_x + half;
+                        sheet.placements.push(placement);
+                    }
+                    sheet
+                })
+                .collect()
+        }
+
+        Imposition::Poster(poster) => {
+            let across = poster.sheets_across.max(1);
+            let overlap = poster.overlap.clamp(0.0, area_width.min(area_height) / 2.0);
+            let (step_x, step_y) = (area_width - overlap, area_height - overlap);
+            let mut sheets = Vec::new();
+            for &page in pages {
+                let (page_width, page_height) = size_of(page);
+                let width = across as f32 * step_x + overlap;
+                let height = width * page_height.max(1.0) / page_width.max(1.0);
+                let down = ((height - overlap) / step_y).ceil().max(1.0) as usize;
+                for row in 0..down {
+                    for column in 0..across {
+                        let mut sheet = Sheet {
+                            placements: vec![Placement {
+                                page,
+                                rect: (
+                                    area_x - column as f32 * step_x,
+                                    area_y - row as f32 * step_y,
+                                    width,
+                                    height,
+                                ),
+                                rotated: false,
+                                clip: area,
+                            }],
+                            ..Default::default()
+                        };
+                        // Lay this tile over its left and upper neighbours up to these lines
+                        if poster.marks && overlap > 0.0 {
+                            if column > 0 {
+                                let x = area_x + overlap;
+                                sheet.marks.push((x, area_y, x, area_y + area_height));
+                            }
+                            if row > 0 {
+                                let y = area_y + overlap;
+                                sheet.marks.push((area_x, y, area_x + area_width, y));
+                            }
+                        }
+                        sheets.push(sheet);
+                    }
+                }
+            }
+            sheets
+        }
+    }
+}
+
+/// Compose the imposed sheets for `pages` of `source` into a new PDF at
+/// `output`. Returns the settings to print that PDF with: one sheet per page,
+/// at actual size, without further margins.
+pub fn impose_pdf(
+    source: &Path,
+    pages: &[usize],
+    settings: &PrintSettings,
+    output: &Path,
+) -> Result<PrintSettings> {
+    let failed = |what: &str, e: &dyn std::fmt::Display| {
+        PrintError::Failed(format!("Failed to {}: {}", what, e))
+    };
+    let pdfium = PdfDocument::get_pdfium().map_err(|e| failed("load Pdfium", &e))?;
+    let source_doc = pdfium
+        .load_pdf_from_file(source, None)
+        .map_err(|e| failed("open PDF", &e))?;
+    let page_sizes: Vec<(f32, f32)> = source_doc
+        .pages()
+        .iter()
+        .map(|page| (page.width().value, page.height().value))
+        .collect();
+    let sheets = layout_sheets(settings, pages, &page_sizes);
+    let mut imposed = pdfium
+        .create_new_pdf()
+        .map_err(|e| failed("create PDF", &e))?;
+
+    let (sheet_width, sheet_height) = sheet_size(settings);
+    let points = |mm: f32| PdfPoints::new(mm / MM_PER_POINT);
+    // Millimetres from the top left to points from the bottom left
+    let pdf_rect = |(x, y, width, height): MmRect| {
+        PdfRect::new(
+            points(sheet_height - y - height),
+            points(x),
+            points(sheet_height - y),
+            points(x + width),
+        )
+    };
+
+    for sheet in &sheets {
+        let mut page = imposed
+            .pages_mut()
+            .create_page_at_end(PdfPagePaperSize::Custom(
+                points(sheet_width),
+                points(sheet_height),
+            ))
+            .map_err(|e| failed("add sheet", &e))?;
+
+        for placement in &sheet.placements {
+            let source_page = source_doc
+                .pages()
+                .get(placement.page as PdfPageIndex)
+                .map_err(|e| failed("load page", &e))?;
+            let media = source_page
+                .boundaries()
+                .media()
+                .map_err(|e| failed("read page size", &e))?
+                .bounds;
+            let (media_width, media_height) = (media.width().value, media.height().value);
+            let quarter_turns = (match source_page.rotation() {
+                Ok(PdfPageRenderRotation::Degrees90) => 1,
+                Ok(PdfPageRenderRotation::Degrees180) => 2,
+                Ok(PdfPageRenderRotation::Degrees270) => 3,
+                _ => 0,
+            } + placement.rotated as usize)
+                % 4;
+            // Turn clockwise about the page's own box, keeping it at the origin
+            let (a, b, c, d, e, f) = match quarter_turns {
+                1 => (0.0, -1.0, 1.0, 0.0, 0.0, media_width),
+                2 => (-1.0, 0.0, 0.0, -1.0, media_width, media_height),
+                3 => (0.0, 1.0, -1.0, 0.0, media_height, 0.0),
+                _ => (1.0, 0.0, 0.0, 1.0, 0.0, 0.0),
+            };
+            let turned_width = if quarter_turns % 2 == 1 {
+                media_height
+            } else {
+                media_width
+            };
+            let target = pdf_rect(placement.rect);
+            let scale = target.width().value / turned_width.max(1.0);
+
+            let mut object = source_page
+                .objects()
+                .copy_into_x_object_form_object(&mut imposed)
+                .map_err(|e| failed("copy page", &e))?;
+            object
+                .translate(-media.left(), -media.bottom())
+                .and_then(|_| object.transform(a, b, c, d, e, f))
+                .and_then(|_| object.scale(scale, scale))
+                .and_then(|_| object.translate(target.left(), target.bottom()))
+                .map_err(|e| failed("place page", &e))?;
+            page.objects_mut()
+                .add_object(object)
+                .map_err(|e| failed("place page", &e))?;
+
+            // Cover whatever spills outside the clip, as poster tiles do
+            let (clip_x, clip_y, clip_width, clip_height) = placement.clip;
+            let spills = [
+                (0.0, 0.0, sheet_width, clip_y),
+                (
+                    0.0,
+                    clip_y + clip_height,
+                    sheet_width,
+                    sheet_height - clip_y - clip_height,
+                ),
+                (0.0, 0.0, clip_x, sheet_height),
+                (
+                    clip_x + clip_width,
+                    0.0,
+                    sheet_width - clip_x - clip_width,
+                    sheet_height,
+                ),
+            ];
+            for spill in spills.into_iter().filter(|r| r.2 > 0.0 && r.3 > 0.0) {
+                page.objects_mut()
+                    .create_path_object_rect(pdf_rect(spill), None, None, Some(PdfColor::WHITE))
+                    .map_err(|e| failed("clip page", &e))?;
+            }
+        }
+
+        for border in &sheet.borders {
+            page.objects_mut()
+                .create_path_object_rect(
+                    pdf_rect(*border),
+                    Some(PdfColor::BLACK),
+                    Some(points(LINE_WIDTH)),
+                    None,
+                )
+                .map_err(|e| failed("draw border", &e))?;
+        }
+        for &(x1, y1, x2, y2) in &sheet.marks {
+            page.objects_mut()
+                .create_path_object_line(
+                    points(x1),
+                    points(sheet_height - y1),
+                    points(x2),
+                    points(sheet_height - y2),
+                    PdfColor::BLACK,
+                    points(LINE_WIDTH),
+                )
+                .map_err(|e| failed("draw mark", &e))?;
+        }
+    }
+
+    imposed
+        .save_to_file(output)
+        .map_err(|e| failed("save imposed PDF", &e))?;
+
+    Ok(PrintSettings {
+        orientation: if sheet_width > sheet_height {
+            Orientation::Landscape
+        } else {
+            Orientation::Portrait
+        },
+        pages: None,
+        scale_to_fit: false,
+        margins: super::Margins {
+            top: 0.0,
+            right: 0.0,
+            bottom: 0.0,
+            left: 0.0,
+        },
+        // Booklet sheets only fold into a booklet when printed on both sides
+        duplex: settings.duplex || settings.imposition == Imposition::Booklet,
+        imposition: Imposition::None,
+        ..settings.clone()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_booklet_order() {
+        // Five pages pad to eight: two sheets, the last three sides blank
+        assert_eq!(
+            booklet_order(5),
+            vec![
+                (None, Some(0)),
+                (Some(1), None),
+                (None, Some(2)),
+                (Some(3), Some(4)),
+            ]
+        );
+        assert!(booklet_order(0).is_empty());
+    }
+
+    #[test]
+    fn test_layout_sheets() {
+        let sizes = vec![(595.0, 842.0); 10];
+        let pages: Vec<usize> = (0..10).collect();
+        let mut settings = PrintSettings {
+            imposition: Imposition::NUp(NUp::default()),
+            ..Default::default()
+        };
+        let sheets = layout_sheets(&settings, &pages, &sizes);
+        assert_eq!(sheets.len(), 3);
+        assert_eq!(sheets[2].placements.len(), 2);
+        assert_eq!(sheets[0].borders.len(), 4);
+
+        // Two-up on portrait paper turns the pages to fill the halves
+        settings.imposition = Imposition::NUp(NUp {
+            pages_per_sheet: 2,
+            ..NUp::default()
+        });
+        let sheets = layout_sheets(&settings, &pages, &sizes);
+        assert!(sheets[0].placements.iter().all(|p| p.rotated));
+
+        // An A4 page twice as wide as A4 paper needs two rows of two sheets
+        settings.imposition = Imposition::Poster(Poster::default());
+        let sheets = layout_sheets(&settings, &pages[..1], &sizes);
+        assert_eq!(sheets.len(), 4);
+        assert_eq!(sheets[3].marks.len(), 2);
+    }
+}
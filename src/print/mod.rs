@@ -1,13 +1,18 @@
+pub mod imposition;
 pub mod platform;
+pub mod preview;
+pub mod to_file;
 
+use crate::pdf::page_range::parse_page_range;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum PrintError {
     #[error("Failed to print: {0}")]
-    PrintError(String),
-    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    Failed(String),
+    #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
     #[error("Platform error: {0}")]
     PlatformError(String),
     #[cfg(any(target_os = "windows", target_os = "linux"))]
@@ -16,21 +21,71 @@ pub enum PrintError {
     #[cfg(any(target_os = "windows", target_os = "linux"))]
     #[error("No printer available")]
     NoPrinter,
+    #[error("Invalid pages \"{0}\": {1}")]
+    InvalidPages(String, String),
+    #[error("Print job cancelled")]
+    Cancelled,
 }
 
 pub type Result<T> = std::result::Result<T, PrintError>;
 
-#[cfg(any(target_os = "windows", target_os = "linux"))]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct PrinterInfo {
     pub name: String,
     pub is_default: bool,
     pub supports_color: bool,
     pub supports_duplex: bool,
+    /// PWG names of the paper sizes the printer takes; empty when unknown
+    pub media: Vec<String>,
+    /// Resolutions the printer prints at, in dots per inch; empty when unknown
+    pub resolutions: Vec<u32>,
 }
 
-#[cfg(any(target_os = "windows", target_os = "linux"))]
-#[derive(Debug, Clone, Copy)]
+impl PrinterInfo {
+    /// Whether the printer takes `paper`, assuming it does when it doesn't say
+    pub fn supports_paper(&self, paper: PaperSize) -> bool {
+        self.media.is_empty() || self.media.iter().any(|m| m == paper.pwg_name())
+    }
+}
+
+/// How far a print job has got, as IPP's `job-state` reports it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    Pending,
+    Held,
+    Processing,
+    Stopped,
+    Canceled,
+    Aborted,
+    Completed,
+}
+
+#[derive(Debug, Clone)]
+pub struct JobStatus {
+    pub id: u32,
+    pub printer: String,
+    pub state: JobState,
+    /// `job-state-reasons` keywords, such as `media-empty-report`
+    pub reasons: Vec<String>,
+    /// What the printer says about the job, if anything
+    pub message: Option<String>,
+}
+
+impl JobStatus {
+    /// A job the backend has finished with by the time printing returns
+    #[cfg(any(target_os = "windows", target_os = "macos"))]
+    pub fn completed(printer: &str) -> Self {
+        Self {
+            id: 0,
+            printer: printer.to_string(),
+            state: JobState::Completed,
+            reasons: Vec::new(),
+            message: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PaperSize {
     A4,
     A3,
@@ -40,8 +95,27 @@ pub enum PaperSize {
     Tabloid,
 }
 
-#[cfg(any(target_os = "windows", target_os = "linux"))]
 impl PaperSize {
+    pub const ALL: [PaperSize; 6] = [
+        PaperSize::A4,
+        PaperSize::A3,
+        PaperSize::A5,
+        PaperSize::Letter,
+        PaperSize::Legal,
+        PaperSize::Tabloid,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PaperSize::A4 => "A4",
+            PaperSize::A3 => "A3",
+            PaperSize::A5 => "A5",
+            PaperSize::Letter => "Letter",
+            PaperSize::Legal => "Legal",
+            PaperSize::Tabloid => "Tabloid",
+        }
+    }
+
     pub fn dimensions_mm(&self) -> (f32, f32) {
         match self {
             PaperSize::A4 => (210.0, 297.0),
@@ -52,37 +126,145 @@ impl PaperSize {
             PaperSize::Tabloid => (279.0, 432.0),
         }
     }
+
+    /// Self-describing media name from PWG 5101.1, as IPP printers use
+    pub fn pwg_name(&self) -> &'static str {
+        match self {
+            PaperSize::A4 => "iso_a4_210x297mm",
+            PaperSize::A3 => "iso_a3_297x420mm",
+            PaperSize::A5 => "iso_a5_148x210mm",
+            PaperSize::Letter => "na_letter_8.5x11in",
+            PaperSize::Legal => "na_legal_8.5x14in",
+            PaperSize::Tabloid => "na_ledger_11x17in",
+        }
+    }
 }
 
-#[cfg(any(target_os = "windows", target_os = "linux"))]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Orientation {
     Portrait,
     Landscape,
 }
 
-#[cfg(any(target_os = "windows", target_os = "linux"))]
-#[derive(Debug, Clone)]
-pub struct PageRange {
-    pub start: usize,
-    pub end: usize,
+/// Pages to print as 0-based indices, ascending and without repeats
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageSet(Vec<usize>);
+
+impl PageSet {
+    #[cfg(target_os = "windows")]
+    pub fn all(page_count: usize) -> Self {
+        Self((0..page_count).collect())
+    }
+
+    /// Parse a page set expression such as `1,3,5-9,12-`.
+    ///
+    /// Besides the pages and ranges `parse_page_range` understands, each comma
+    /// separated part may be `odd`, `even`, `current` (the page being read) or
+    /// `selection` (the pages the text selection spans). An empty expression
+    /// selects every page.
+    pub fn parse(
+        spec: &str,
+        page_count: usize,
+        current_page: usize,
+        selection: &[usize],
+    ) -> Result<Self> {
+        let invalid = |reason: String| PrintError::InvalidPages(spec.trim().to_string(), reason);
+        if spec.trim().is_empty() {
+            return Ok(Self((0..page_count).collect()));
+        }
+
+        let mut pages = std::collections::BTreeSet::new();
+        for part in spec.split(',') {
+            match part.trim().to_lowercase().as_str() {
+                "odd" => pages.extend((0..page_count).step_by(2)),
+                "even" => pages.extend((1..page_count).step_by(2)),
+                "current" if current_page < page_count => {
+                    pages.insert(current_page);
+                }
+                "selection" if selection.is_empty() => {
+                    return Err(invalid("nothing is selected".to_string()));
+                }
+                "selection" => pages.extend(selection.iter().filter(|&&p| p < page_count)),
+                "" => return Err(invalid("empty part".to_string())),
+                range => pages.extend(
+                    parse_page_range(range, page_count).map_err(|e| invalid(e.to_string()))?,
+                ),
+            }
+        }
+        Ok(Self(pages.into_iter().collect()))
+    }
+
+    pub fn pages(&self) -> &[usize] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Runs of consecutive pages as inclusive (first, last) pairs
+    #[cfg(any(target_os = "linux", test))]
+    pub fn runs(&self) -> Vec<(usize, usize)> {
+        let mut runs: Vec<(usize, usize)> = Vec::new();
+        for &page in &self.0 {
+            match runs.last_mut() {
+                Some((_, last)) if *last + 1 == page => *last = page,
+                _ => runs.push((page, page)),
+            }
+        }
+        runs
+    }
 }
 
-#[cfg(any(target_os = "windows", target_os = "linux"))]
-impl PageRange {
-    pub fn new(start: usize, end: usize) -> Self {
-        Self { start, end }
+/// Shared between a running print job and whoever follows it: how many
+/// sheets have been rendered out of how many, and whether to stop
+#[derive(Debug, Default)]
+pub struct JobProgress {
+    done: AtomicUsize,
+    total: AtomicUsize,
+    cancelled: AtomicBool,
+}
+
+impl JobProgress {
+    pub fn start(&self, total: usize) {
+        self.total.store(total, Ordering::Relaxed);
+        self.done.store(0, Ordering::Relaxed);
     }
 
-    pub fn all(page_count: usize) -> Self {
-        Self {
-            start: 0,
-            end: page_count.saturating_sub(1),
+    pub fn advance(&self) {
+        self.done.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Sheets done and in all; the total is 0 until the backend knows it
+    pub fn get(&self) -> (usize, usize) {
+        (
+            self.done.load(Ordering::Relaxed),
+            self.total.load(Ordering::Relaxed),
+        )
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// `PrintError::Cancelled` once the job has been cancelled, to stop with `?`
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(PrintError::Cancelled)
+        } else {
+            Ok(())
         }
     }
 }
 
-#[cfg(any(target_os = "windows", target_os = "linux"))]
 #[derive(Debug, Clone, Copy)]
 pub struct Margins {
     pub top: f32,
@@ -91,7 +273,6 @@ pub struct Margins {
     pub left: f32,
 }
 
-#[cfg(any(target_os = "windows", target_os = "linux"))]
 impl Default for Margins {
     fn default() -> Self {
         Self {
@@ -103,48 +284,194 @@ impl Default for Margins {
     }
 }
 
-#[cfg(any(target_os = "windows", target_os = "linux"))]
 #[derive(Debug, Clone)]
 pub struct PrintSettings {
     pub paper_size: PaperSize,
     pub orientation: Orientation,
-    pub page_range: Option<PageRange>,
+    /// Pages to print; every page when `None`
+    pub pages: Option<PageSet>,
     pub copies: u32,
     pub duplex: bool,
     pub color: bool,
     pub scale_to_fit: bool,
     pub margins: Margins,
+    pub imposition: Imposition,
 }
 
-#[cfg(any(target_os = "windows", target_os = "linux"))]
 impl Default for PrintSettings {
     fn default() -> Self {
         Self {
             paper_size: PaperSize::A4,
             orientation: Orientation::Portrait,
-            page_range: None,
+            pages: None,
             copies: 1,
             duplex: false,
             color: true,
             scale_to_fit: true,
             margins: Margins::default(),
+            imposition: Imposition::None,
+        }
+    }
+}
+
+impl PrintSettings {
+    /// Paper width and height in millimetres, turned for landscape
+    pub fn paper_dimensions_mm(&self) -> (f32, f32) {
+        let (width, height) = self.paper_size.dimensions_mm();
+        match self.orientation {
+            Orientation::Portrait => (width, height),
+            Orientation::Landscape => (height, width),
         }
     }
+
+    /// Pages to print out of a document of `page_count` pages
+    pub fn page_list(&self, page_count: usize) -> Vec<usize> {
+        match &self.pages {
+            Some(pages) => pages.pages().to_vec(),
+            None => (0..page_count).collect(),
+        }
+    }
+
+    /// Where a page of `page_size` points lands on the paper: (x, y, width, height)
+    /// in millimetres from the top left. The page is centred in the area inside
+    /// the margins, shrunk or enlarged to fill it when `scale_to_fit` is set and
+    /// printed at actual size otherwise.
+    pub fn page_placement(&self, page_size: (f32, f32)) -> (f32, f32, f32, f32) {
+        let (paper_width, paper_height) = self.paper_dimensions_mm();
+        let area_width = (paper_width - self.margins.left - self.margins.right).max(1.0);
+        let area_height = (paper_height - self.margins.top - self.margins.bottom).max(1.0);
+
+        let page_width = page_size.0.max(1.0) * MM_PER_POINT;
+        let page_height = page_size.1.max(1.0) * MM_PER_POINT;
+        let scale = if self.scale_to_fit {
+            (area_width / page_width).min(area_height / page_height)
+        } else {
+            1.0
+        };
+        let (width, height) = (page_width * scale, page_height * scale);
+        (
+            self.margins.left + (area_width - width) / 2.0,
+            self.margins.top + (area_height - height) / 2.0,
+            width,
+            height,
+        )
+    }
 }
 
-#[cfg(any(target_os = "windows", target_os = "linux"))]
+pub const MM_PER_POINT: f32 = 25.4 / 72.0;
+
 pub trait Printer {
     fn get_printers() -> Result<Vec<PrinterInfo>>;
+
+    /// Submit `pdf_path`, reporting rendered sheets to `progress` and stopping
+    /// with `PrintError::Cancelled` if it is cancelled first
     fn print_pdf(
         pdf_path: &Path,
         settings: &PrintSettings,
         printer_name: Option<&str>,
-    ) -> Result<()>;
-    fn show_print_dialog(pdf_path: &Path) -> Result<()>;
+        progress: &JobProgress,
+    ) -> Result<JobStatus>;
+
+    /// Current state of a job `print_pdf` submitted. Backends that finish the
+    /// job before `print_pdf` returns have nothing more to report.
+    fn job_status(job: &JobStatus) -> Result<JobStatus> {
+        Ok(job.clone())
+    }
+
+    /// Cancel a job the printer still has
+    fn cancel_job(_job: &JobStatus) -> Result<()> {
+        Err(PrintError::Failed(
+            "The job has already been printed".to_string(),
+        ))
+    }
 }
 
+pub use imposition::Imposition;
 pub use platform::PlatformPrinter;
 
-pub fn show_print_dialog(pdf_path: &Path) -> Result<()> {
-    PlatformPrinter::show_print_dialog(pdf_path)
+/// Printers to offer: those the system knows, then the "Print to file" printer,
+/// which is always there
+pub fn list_printers() -> Vec<PrinterInfo> {
+    let mut printers = PlatformPrinter::get_printers().unwrap_or_else(|e| {
+        log::warn!("Failed to list printers: {}", e);
+        Vec::new()
+    });
+    printers.push(PrinterInfo::file_printer());
+    printers
+}
+
+/// Print `pdf_path` with `settings`. N-up, booklet and poster jobs are first
+/// composed into a temporary PDF of finished sheets, so every backend prints
+/// them the same way. Returns the submitted job, to follow with `job_status`.
+pub fn print_document(
+    pdf_path: &Path,
+    settings: &PrintSettings,
+    page_count: usize,
+    printer_name: Option<&str>,
+    progress: &JobProgress,
+) -> Result<JobStatus> {
+    if settings.imposition == Imposition::None {
+        return PlatformPrinter::print_pdf(pdf_path, settings, printer_name, progress);
+    }
+
+    let pages = settings.page_list(page_count);
+    // Jobs may overlap, so each gets its own file
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    let imposed = std::env::temp_dir().join(format!("print-{}-{}.pdf", std::process::id(), stamp));
+    let result =
+        imposition::impose_pdf(pdf_path, &pages, settings, &imposed).and_then(|sheet_settings| {
+            progress.check()?;
+            PlatformPrinter::print_pdf(&imposed, &sheet_settings, printer_name, progress)
+        });
+    // The job has been spooled by now, so the sheets are no longer needed
+    let _ = std::fs::remove_file(&imposed);
+    result
+}
+
+/// Ask the printer how `job` is getting on
+pub fn job_status(job: &JobStatus) -> Result<JobStatus> {
+    PlatformPrinter::job_status(job)
+}
+
+pub fn cancel_job(job: &JobStatus) -> Result<()> {
+    PlatformPrinter::cancel_job(job)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_placement() {
+        let mut settings = PrintSettings::default();
+        // A US Letter page on A4 paper with 10 mm margins is shrunk to the width
+        let (x, y, width, height) = settings.page_placement((612.0, 792.0));
+        assert!((width - 190.0).abs() < 0.01);
+        assert!((x - 10.0).abs() < 0.01);
+        assert!((y + height / 2.0 - 148.5).abs() < 0.01);
+
+        settings.scale_to_fit = false;
+        settings.orientation = Orientation::Landscape;
+        let (x, _, width, _) = settings.page_placement((72.0, 72.0));
+        assert!((width - 25.4).abs() < 0.01);
+        assert!((x + width / 2.0 - 148.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_page_set() {
+        let parse = |spec: &str| PageSet::parse(spec, 14, 6, &[9, 10]);
+        assert_eq!(parse("").unwrap().len(), 14);
+        let set = parse("1,3,5-9,12-").unwrap();
+        assert_eq!(set.pages(), &[0, 2, 4, 5, 6, 7, 8, 11, 12, 13]);
+        assert_eq!(set.runs(), vec![(0, 0), (2, 2), (4, 8), (11, 13)]);
+        assert_eq!(parse("even, 2").unwrap().pages(), &[1, 3, 5, 7, 9, 11, 13]);
+        assert_eq!(parse("odd").unwrap().len(), 7);
+        assert_eq!(parse("Current,selection").unwrap().pages(), &[6, 9, 10]);
+
+        assert!(parse("1,,2").is_err());
+        assert!(parse("15").is_err());
+        assert!(PageSet::parse("selection", 14, 0, &[]).is_err());
+    }
 }
@@ -8,6 +8,8 @@ mod macos;
 #[cfg(target_os = "macos")]
 pub use macos::PlatformPrinter;
 
+#[cfg(target_os = "linux")]
+mod ipp;
 #[cfg(target_os = "linux")]
 mod linux;
 #[cfg(target_os = "linux")]
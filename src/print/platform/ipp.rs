@@ -0,0 +1,539 @@
+//! A small IPP/1.1 client (RFC 8010/8011) for talking to CUPS, or any IPP
+//! server, over HTTP on a TCP or Unix socket
+
+use crate::print::{PrintError, Result};
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+pub const PRINT_JOB: u16 = 0x0002;
+pub const CANCEL_JOB: u16 = 0x0008;
+pub const GET_JOB_ATTRIBUTES: u16 = 0x0009;
+pub const CUPS_GET_DEFAULT: u16 = 0x4001;
+pub const CUPS_GET_PRINTERS: u16 = 0x4002;
+
+const OPERATION_GROUP: u8 = 0x01;
+const JOB_GROUP: u8 = 0x02;
+const END_OF_ATTRIBUTES: u8 = 0x03;
+pub const PRINTER_GROUP: u8 = 0x04;
+
+const TAG_INTEGER: u8 = 0x21;
+const TAG_BOOLEAN: u8 = 0x22;
+const TAG_ENUM: u8 = 0x23;
+const TAG_RESOLUTION: u8 = 0x32;
+const TAG_RANGE: u8 = 0x33;
+const TAG_BEGIN_COLLECTION: u8 = 0x34;
+const TAG_END_COLLECTION: u8 = 0x37;
+const TAG_NAME: u8 = 0x42;
+const TAG_KEYWORD: u8 = 0x44;
+const TAG_URI: u8 = 0x45;
+const TAG_CHARSET: u8 = 0x47;
+const TAG_LANGUAGE: u8 = 0x48;
+const TAG_MIME_TYPE: u8 = 0x49;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An attribute value. Strings of every kind (text, name, keyword, URI...)
+/// are kept as `Text`; values this client has no use for as `Other`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IppValue {
+    Integer(i32),
+    Boolean(bool),
+    Enum(i32),
+    Text(String),
+    /// Cross-feed and feed resolution, and the units (3 = dots per inch)
+    Resolution(i32, i32, u8),
+    Range(i32, i32),
+    Other(u8, Vec<u8>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IppAttribute {
+    pub name: String,
+    pub values: Vec<IppValue>,
+}
+
+/// An IPP response: its status code and attribute groups in order
+#[derive(Debug, Default)]
+pub struct IppResponse {
+    pub status: u16,
+    pub groups: Vec<(u8, Vec<IppAttribute>)>,
+}
+
+impl IppResponse {
+    /// Status codes below 0x0100 are successes
+    pub fn is_success(&self) -> bool {
+        self.status < 0x0100
+    }
+
+    /// Groups of the given tag, such as each printer in a CUPS-Get-Printers reply
+    pub fn groups_tagged(&self, tag: u8) -> impl Iterator<Item = &[IppAttribute]> {
+        self.groups
+            .iter()
+            .filter(move |(group, _)| *group == tag)
+            .map(|(_, attributes)| attributes.as_slice())
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&IppAttribute> {
+        self.groups
+            .iter()
+            .flat_map(|(_, attributes)| attributes)
+            .find(|a| a.name == name)
+    }
+}
+
+/// Find an attribute in a group
+pub fn find<'a>(attributes: &'a [IppAttribute], name: &str) -> Option<&'a IppAttribute> {
+    attributes.iter().find(|a| a.name == name)
+}
+
+impl IppAttribute {
+    pub fn text(&self) -> Option<&str> {
+        self.values.iter().find_map(|v| match v {
+            IppValue::Text(text) => Some(text.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn texts(&self) -> Vec<String> {
+        self.values
+            .iter()
+            .filter_map(|v| match v {
+                IppValue::Text(text) => Some(text.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn integer(&self) -> Option<i32> {
+        self.values.iter().find_map(|v| match v {
+            IppValue::Integer(n) | IppValue::Enum(n) => Some(*n),
+            _ => None,
+        })
+    }
+
+    pub fn boolean(&self) -> Option<bool> {
+        self.values.iter().find_map(|v| match v {
+            IppValue::Boolean(b) => Some(*b),
+            _ => None,
+        })
+    }
+}
+
+/// Builds an IPP request. The operation group starts with the charset and
+/// language every request must carry.
+pub struct IppRequest {
+    data: Vec<u8>,
+    group: u8,
+}
+
+impl IppRequest {
+    pub fn new(operation: u16, request_id: u32) -> Self {
+        let mut data = vec![0x01, 0x01];
+        data.extend_from_slice(&operation.to_be_bytes());
+        data.extend_from_slice(&request_id.to_be_bytes());
+        data.push(OPERATION_GROUP);
+        let request = Self {
+            data,
+            group: OPERATION_GROUP,
+        };
+        request
+            .value(TAG_CHARSET, "attributes-charset", b"utf-8")
+            .value(TAG_LANGUAGE, "attributes-natural-language", b"en")
+    }
+
+    /// Start the job attributes group
+    pub fn job_group(mut self) -> Self {
+        if self.group != JOB_GROUP {
+            self.data.push(JOB_GROUP);
+            self.group = JOB_GROUP;
+        }
+        self
+    }
+
+    /// Start another group, as a reply does for each printer
+    #[cfg(test)]
+    pub fn group(mut self, tag: u8) -> Self {
+        self.data.push(tag);
+        self.group = tag;
+        self
+    }
+
+    fn value(mut self, tag: u8, name: &str, value: &[u8]) -> Self {
+        self.data.push(tag);
+        self.data
+            .extend_from_slice(&(name.len() as u16).to_be_bytes());
+        self.data.extend_from_slice(name.as_bytes());
+        self.data
+            .extend_from_slice(&(value.len() as u16).to_be_bytes());
+        self.data.extend_from_slice(value);
+        self
+    }
+
+    pub fn uri(self, name: &str, value: &str) -> Self {
+        self.value(TAG_URI, name, value.as_bytes())
+    }
+
+    pub fn name(self, name: &str, value: &str) -> Self {
+        self.value(TAG_NAME, name, value.as_bytes())
+    }
+
+    pub fn keyword(self, name: &str, value: &str) -> Self {
+        self.value(TAG_KEYWORD, name, value.as_bytes())
+    }
+
+    /// A keyword attribute with several values, such as `requested-attributes`
+    pub fn keywords(mut self, name: &str, values: &[&str]) -> Self {
+        for (index, value) in values.iter().enumerate() {
+            // Additional values repeat the tag with an empty name
+            self = self.value(
+                TAG_KEYWORD,
+                if index == 0 { name } else { "" },
+                value.as_bytes(),
+            );
+        }
+        self
+    }
+
+    #[cfg(test)]
+    pub fn boolean(self, name: &str, value: bool) -> Self {
+        self.value(TAG_BOOLEAN, name, &[value as u8])
+    }
+
+    pub fn mime_type(self, name: &str, value: &str) -> Self {
+        self.value(TAG_MIME_TYPE, name, value.as_bytes())
+    }
+
+    pub fn integer(self, name: &str, value: i32) -> Self {
+        self.value(TAG_INTEGER, name, &value.to_be_bytes())
+    }
+
+    pub fn enumeration(self, name: &str, value: i32) -> Self {
+        self.value(TAG_ENUM, name, &value.to_be_bytes())
+    }
+
+    /// A rangeOfInteger attribute with one value per (first, last) range
+    pub fn ranges(mut self, name: &str, ranges: &[(i32, i32)]) -> Self {
+        for (index, (first, last)) in ranges.iter().enumerate() {
+            let mut value = first.to_be_bytes().to_vec();
+            value.extend_from_slice(&last.to_be_bytes());
+            self = self.value(TAG_RANGE, if index == 0 { name } else { "" }, &value);
+        }
+        self
+    }
+
+    /// The encoded request, followed by `document` for Print-Job
+    pub fn finish(mut self, document: &[u8]) -> Vec<u8> {
+        self.data.push(END_OF_ATTRIBUTES);
+        self.data.extend_from_slice(document);
+        self.data
+    }
+}
+
+/// Decode an IPP response body
+pub fn parse_response(data: &[u8]) -> Result<IppResponse> {
+    let malformed = || PrintError::Failed("Malformed IPP response".to_string());
+    let mut reader = Reader { data, pos: 0 };
+    reader.take(2).ok_or_else(malformed)?;
+    let status = reader.u16().ok_or_else(malformed)?;
+    reader.take(4).ok_or_else(malformed)?;
+
+    let mut response = IppResponse {
+        status,
+        groups: Vec::new(),
+    };
+    let mut collection_depth = 0usize;
+    loop {
+        let tag = reader.u8().ok_or_else(malformed)?;
+        if tag == END_OF_ATTRIBUTES {
+            break;
+        }
+        if tag < 0x10 {
+            response.groups.push((tag, Vec::new()));
+            continue;
+        }
+
+        let name_len = reader.u16().ok_or_else(malformed)? as usize;
+        let name =
+            String::from_utf8_lossy(reader.take(name_len).ok_or_else(malformed)?).to_string();
+        let value_len = reader.u16().ok_or_else(malformed)? as usize;
+        let raw = reader.take(value_len).ok_or_else(malformed)?;
+
+        // Collections (such as media-col-database) are skipped whole
+        match tag {
+            TAG_BEGIN_COLLECTION => {
+                collection_depth += 1;
+                continue;
+            }
+            TAG_END_COLLECTION => {
+                collection_depth = collection_depth.saturating_sub(1);
+                continue;
+            }
+            _ if collection_depth > 0 => continue,
+            _ => {}
+        }
+
+        let value = decode_value(tag, raw);
+        let Some((_, attributes)) = response.groups.last_mut() else {
+            return Err(malformed());
+        };
+        match attributes.last_mut() {
+            Some(attribute) if name.is_empty() => attribute.values.push(value),
+            _ => attributes.push(IppAttribute {
+                name,
+                values: vec![value],
+            }),
+        }
+    }
+    Ok(response)
+}
+
+fn decode_value(tag: u8, raw: &[u8]) -> IppValue {
+    let int = |bytes: &[u8]| bytes.try_into().ok().map(i32::from_be_bytes);
+    match tag {
+        TAG_INTEGER if raw.len() == 4 => IppValue::Integer(int(raw).unwrap_or_default()),
+        TAG_ENUM if raw.len() == 4 => IppValue::Enum(int(raw).unwrap_or_default()),
+        TAG_BOOLEAN if raw.len() == 1 => IppValue::Boolean(raw[0] != 0),
+        TAG_RESOLUTION if raw.len() == 9 => IppValue::Resolution(
+            int(&raw[0..4]).unwrap_or_default(),
+            int(&raw[4..8]).unwrap_or_default(),
+            raw[8],
+        ),
+        TAG_RANGE if raw.len() == 8 => IppValue::Range(
+            int(&raw[0..4]).unwrap_or_default(),
+            int(&raw[4..8]).unwrap_or_default(),
+        ),
+        // Text, name, keyword, URI, charset, language and MIME type
+        0x41..=0x49 => IppValue::Text(String::from_utf8_lossy(raw).to_string()),
+        _ => IppValue::Other(tag, raw.to_vec()),
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos + len)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.take(2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+    }
+}
+
+/// Where the IPP server listens
+#[derive(Debug, Clone)]
+pub enum IppServer {
+    Tcp(String, u16),
+    Unix(std::path::PathBuf),
+}
+
+impl IppServer {
+    /// The server named by `CUPS_SERVER` (a socket path or host[:port]), else the
+    /// local CUPS socket, else CUPS on localhost
+    pub fn from_env() -> Self {
+        if let Ok(server) = std::env::var("CUPS_SERVER") {
+            if server.starts_with('/') {
+                return IppServer::Unix(server.into());
+            }
+            if let Some((host, port)) = server.rsplit_once(':') {
+                if let Ok(port) = port.parse() {
+                    return IppServer::Tcp(host.to_string(), port);
+                }
+            }
+            return IppServer::Tcp(server, 631);
+        }
+        for socket in ["/run/cups/cups.sock", "/var/run/cups/cups.sock"] {
+            if std::path::Path::new(socket).exists() {
+                return IppServer::Unix(socket.into());
+            }
+        }
+        IppServer::Tcp("localhost".to_string(), 631)
+    }
+
+    /// `ipp://` URI of a resource on this server, such as `/printers/Office`
+    pub fn uri(&self, resource: &str) -> String {
+        match self {
+            IppServer::Tcp(host, port) => format!("ipp://{}:{}{}", host, port, resource),
+            IppServer::Unix(_) => format!("ipp://localhost{}", resource),
+        }
+    }
+
+    fn host_header(&self) -> String {
+        match self {
+            IppServer::Tcp(host, port) => format!("{}:{}", host, port),
+            IppServer::Unix(_) => "localhost".to_string(),
+        }
+    }
+
+    /// POST `request` to `resource` and decode the reply
+    pub fn send(&self, resource: &str, request: &[u8]) -> Result<IppResponse> {
+        let unreachable =
+            |e: std::io::Error| PrintError::Failed(format!("Cannot reach the print server: {}", e));
+        let head = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/ipp\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n",
+            resource,
+            self.host_header(),
+            request.len()
+        );
+
+        let mut reply = Vec::new();
+        match self {
+            IppServer::Tcp(host, port) => {
+                let address = (host.as_str(), *port)
+                    .to_socket_addrs()
+                    .map_err(unreachable)?
+                    .next()
+                    .ok_or_else(|| PrintError::Failed(format!("Unknown print server {}", host)))?;
+                let mut stream =
+                    TcpStream::connect_timeout(&address, CONNECT_TIMEOUT).map_err(unreachable)?;
+                stream
+                    .set_read_timeout(Some(READ_TIMEOUT))
+                    .map_err(unreachable)?;
+                exchange(&mut stream, head.as_bytes(), request, &mut reply).map_err(unreachable)?;
+            }
+            IppServer::Unix(path) => {
+                let mut stream = UnixStream::connect(path).map_err(unreachable)?;
+                stream
+                    .set_read_timeout(Some(READ_TIMEOUT))
+                    .map_err(unreachable)?;
+                exchange(&mut stream, head.as_bytes(), request, &mut reply).map_err(unreachable)?;
+            }
+        }
+
+        let body = http_body(&reply)?;
+        parse_response(&body)
+    }
+}
+
+fn exchange(
+    stream: &mut (impl Read + Write),
+    head: &[u8],
+    body: &[u8],
+    reply: &mut Vec<u8>,
+) -> std::io::Result<()> {
+    stream.write_all(head)?;
+    stream.write_all(body)?;
+    stream.flush()?;
+    stream.read_to_end(reply)?;
+    Ok(())
+}
+
+/// Body of an HTTP response, checking the status and undoing chunked encoding
+fn http_body(reply: &[u8]) -> Result<Vec<u8>> {
+    let malformed = || PrintError::Failed("Malformed reply from the print server".to_string());
+    let split = reply
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(malformed)?;
+    let head = String::from_utf8_lossy(&reply[..split]);
+    let body = &reply[split + 4..];
+
+    let mut lines = head.lines();
+    let status_line = lines.next().unwrap_or_default();
+    let status: u16 = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(malformed)?;
+    if status != 200 {
+        return Err(PrintError::Failed(format!(
+            "Print server refused the request: {}",
+            status_line
+        )));
+    }
+
+    let chunked = lines.any(|line| {
+        let line = line.to_ascii_lowercase();
+        line.starts_with("transfer-encoding:") && line.contains("chunked")
+    });
+    if !chunked {
+        return Ok(body.to_vec());
+    }
+
+    let mut decoded = Vec::new();
+    let mut rest = body;
+    loop {
+        let end = rest
+            .windows(2)
+            .position(|w| w == b"\r\n")
+            .ok_or_else(malformed)?;
+        let size_text = String::from_utf8_lossy(&rest[..end]);
+        let size = usize::from_str_radix(size_text.split(';').next().unwrap_or("").trim(), 16)
+            .map_err(|_| malformed())?;
+        rest = &rest[end + 2..];
+        if size == 0 {
+            break;
+        }
+        decoded.extend_from_slice(rest.get(..size).ok_or_else(malformed)?);
+        rest = rest.get(size + 2..).unwrap_or_default();
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_encoding() {
+        let request = IppRequest::new(GET_JOB_ATTRIBUTES, 7)
+            .integer("job-id", 12)
+            .finish(&[]);
+        assert_eq!(&request[..8], &[1, 1, 0, 9, 0, 0, 0, 7]);
+        assert_eq!(request[8], OPERATION_GROUP);
+        assert_eq!(request.last(), Some(&END_OF_ATTRIBUTES));
+        // job-id: integer tag, 6 byte name, 4 byte value
+        let job_id = &request[request.len() - 16..request.len() - 1];
+        assert_eq!(job_id[0], TAG_INTEGER);
+        assert_eq!(&job_id[3..9], b"job-id");
+        assert_eq!(&job_id[11..], &12i32.to_be_bytes());
+    }
+
+    #[test]
+    fn test_parse_response() {
+        // A reply has the request's layout with a status code in place of the
+        // operation; add a printer group with a boolean and a collection to skip
+        let mut data = IppRequest::new(0, 1)
+            .keywords("sides-supported", &["one-sided", "two-sided-long-edge"])
+            .finish(&[]);
+        data.pop();
+        data.extend_from_slice(&[PRINTER_GROUP, TAG_BOOLEAN, 0, 15]);
+        data.extend_from_slice(b"color-supported");
+        data.extend_from_slice(&[0, 1, 1]);
+        data.extend_from_slice(&[TAG_BEGIN_COLLECTION, 0, 1, b'c', 0, 0]);
+        data.extend_from_slice(&[TAG_KEYWORD, 0, 0, 0, 1, b'x']);
+        data.extend_from_slice(&[TAG_END_COLLECTION, 0, 0, 0, 0]);
+        data.push(END_OF_ATTRIBUTES);
+
+        let response = parse_response(&data).unwrap();
+        assert!(response.is_success());
+        let sides = response.attribute("sides-supported").unwrap();
+        assert_eq!(sides.texts(), vec!["one-sided", "two-sided-long-edge"]);
+        assert_eq!(
+            response.attribute("color-supported").unwrap().boolean(),
+            Some(true)
+        );
+        assert!(response.attribute("c").is_none());
+        assert_eq!(response.groups_tagged(PRINTER_GROUP).count(), 1);
+    }
+
+    #[test]
+    fn test_http_body() {
+        let reply = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n2\r\nde\r\n0\r\n\r\n";
+        assert_eq!(http_body(reply).unwrap(), b"abcde");
+        assert!(http_body(b"HTTP/1.1 403 Forbidden\r\n\r\n").is_err());
+    }
+}
@@ -1,158 +1,485 @@
-use crate::print::{PrintError, PrintSettings, Printer, PrinterInfo, Result};
+use super::ipp::{self, find, IppAttribute, IppRequest, IppResponse, IppServer, IppValue};
+use crate::print::{
+    JobProgress, JobState, JobStatus, Orientation, PrintError, PrintSettings, Printer, PrinterInfo,
+    Result, MM_PER_POINT,
+};
 use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
 
+/// Printer attributes asked for when listing printers
+const PRINTER_ATTRIBUTES: &[&str] = &[
+    "printer-name",
+    "color-supported",
+    "sides-supported",
+    "media-supported",
+    "printer-resolution-supported",
+];
+
+const JOB_ATTRIBUTES: &[&str] = &[
+    "job-id",
+    "job-state",
+    "job-state-reasons",
+    "job-state-message",
+];
+
+/// Talks to CUPS (or any IPP server named by `CUPS_SERVER`) over IPP
 pub struct LinuxPrinter;
 
+static REQUEST_ID: AtomicU32 = AtomicU32::new(1);
+
 impl LinuxPrinter {
-    fn print_pdf_native(
-        pdf_path: &Path,
-        settings: &PrintSettings,
-        printer_name: Option<&str>,
-    ) -> Result<()> {
-        use std::process::Command;
-
-        // Get printer name
-        let printer = if let Some(name) = printer_name {
-            name.to_string()
-        } else {
-            // Get default printer using lpstat
-            let output = Command::new("lpstat")
-                .args(&["-d"])
-                .output()
-                .ok()
-                .and_then(|o| {
-                    let s = String::from_utf8_lossy(&o.stdout);
-                    s.find("destination: ")
-                        .map(|pos| s[pos + 12..].trim().to_string())
-                })
-                .ok_or(PrintError::NoPrinter)?;
-            output
-        };
+    fn request(operation: u16) -> IppRequest {
+        IppRequest::new(operation, REQUEST_ID.fetch_add(1, Ordering::Relaxed))
+    }
 
-        // Build lp command with native options
-        let mut cmd = Command::new("lp");
-        cmd.arg("-d").arg(&printer);
+    fn printer_resource(name: &str) -> String {
+        format!("/printers/{}", name)
+    }
 
-        if settings.copies > 1 {
-            cmd.arg("-n").arg(settings.copies.to_string());
-        }
+    fn user_name() -> String {
+        std::env::var("USER").unwrap_or_else(|_| "anonymous".to_string())
+    }
 
-        if let Some(ref range) = settings.page_range {
-            let range_str = format!("{}-{}", range.start + 1, range.end + 1);
-            cmd.arg("-P").arg(range_str);
+    /// Turn an unsuccessful reply into an error, using the server's own message
+    fn check(response: IppResponse) -> Result<IppResponse> {
+        if response.is_success() {
+            return Ok(response);
         }
+        let message = response
+            .attribute("status-message")
+            .and_then(|a| a.text())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("IPP status 0x{:04x}", response.status));
+        Err(PrintError::Failed(message))
+    }
 
-        if settings.duplex {
-            cmd.arg("-o").arg("sides=two-sided-long-edge");
-        } else {
-            cmd.arg("-o").arg("sides=one-sided");
-        }
+    fn default_printer(server: &IppServer) -> Result<String> {
+        let request = Self::request(ipp::CUPS_GET_DEFAULT)
+            .keywords("requested-attributes", &["printer-name"])
+            .finish(&[]);
+        let response = Self::check(server.send("/", &request)?)?;
+        response
+            .attribute("printer-name")
+            .and_then(|a| a.text())
+            .map(str::to_string)
+            .ok_or(PrintError::NoPrinter)
+    }
 
-        if settings.color {
-            cmd.arg("-o").arg("print-color-mode=color");
-        } else {
-            cmd.arg("-o").arg("print-color-mode=monochrome");
-        }
+    fn printer_info(attributes: &[IppAttribute], default: Option<&str>) -> Option<PrinterInfo> {
+        let name = find(attributes, "printer-name")?.text()?.to_string();
+        let sides = find(attributes, "sides-supported").map_or_else(Vec::new, |a| a.texts());
+        let resolutions = find(attributes, "printer-resolution-supported")
+            .map(|a| {
+                a.values
+                    .iter()
+                    .filter_map(|v| match v {
+                        // Units 3 are dots per inch, 4 dots per centimetre
+                        IppValue::Resolution(x, _, 3) => Some(*x as u32),
+                        IppValue::Resolution(x, _, 4) => Some((*x as f32 * 2.54).round() as u32),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Some(PrinterInfo {
+            is_default: default == Some(name.as_str()),
+            supports_color: find(attributes, "color-supported")
+                .and_then(|a| a.boolean())
+                .unwrap_or(false),
+            supports_duplex: sides.iter().any(|s| s.starts_with("two-sided")),
+            media: find(attributes, "media-supported").map_or_else(Vec::new, |a| a.texts()),
+            resolutions,
+            name,
+        })
+    }
 
-        match settings.orientation {
-            crate::print::Orientation::Portrait => cmd.arg("-o").arg("orientation-requested=3"),
-            crate::print::Orientation::Landscape => cmd.arg("-o").arg("orientation-requested=4"),
+    fn parse_job(printer: &str, attributes: &IppResponse) -> Result<JobStatus> {
+        let id = attributes
+            .attribute("job-id")
+            .and_then(|a| a.integer())
+            .ok_or_else(|| PrintError::Failed("The print server returned no job".to_string()))?;
+        // RFC 8011 section 5.3.7
+        let state = match attributes.attribute("job-state").and_then(|a| a.integer()) {
+            Some(4) => JobState::Held,
+            Some(5) => JobState::Processing,
+            Some(6) => JobState::Stopped,
+            Some(7) => JobState::Canceled,
+            Some(8) => JobState::Aborted,
+            Some(9) => JobState::Completed,
+            _ => JobState::Pending,
         };
+        let reasons = attributes
+            .attribute("job-state-reasons")
+            .map(|a| a.texts())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|r| r != "none")
+            .collect();
+        let message = attributes
+            .attribute("job-state-message")
+            .and_then(|a| a.text())
+            .filter(|m| !m.is_empty())
+            .map(str::to_string);
+        Ok(JobStatus {
+            id: id as u32,
+            printer: printer.to_string(),
+            state,
+            reasons,
+            message,
+        })
+    }
 
-        let media = match settings.paper_size {
-            crate::print::PaperSize::A4 => "A4",
-            crate::print::PaperSize::A3 => "A3",
-            crate::print::PaperSize::A5 => "A5",
-            crate::print::PaperSize::Letter => "Letter",
-            crate::print::PaperSize::Legal => "Legal",
-            crate::print::PaperSize::Tabloid => "Tabloid",
-        };
-        cmd.arg("-o").arg(format!("media={}", media));
+    /// Print-Job request for `document` with `settings` as job attributes
+    fn print_job_request(
+        server: &IppServer,
+        printer: &str,
+        job_name: &str,
+        settings: &PrintSettings,
+        document: &[u8],
+    ) -> Vec<u8> {
+        let mut request = Self::request(ipp::PRINT_JOB)
+            .uri("printer-uri", &server.uri(&Self::printer_resource(printer)))
+            .name("requesting-user-name", &Self::user_name())
+            .name("job-name", job_name)
+            .mime_type("document-format", "application/pdf")
+            .job_group()
+            .integer("copies", settings.copies as i32);
 
-        cmd.arg(pdf_path);
+        let sides = match (settings.duplex, settings.orientation) {
+            (false, _) => "one-sided",
+            (true, Orientation::Portrait) => "two-sided-long-edge",
+            // Landscape sheets, such as booklets, turn over on the short edge
+            (true, Orientation::Landscape) => "two-sided-short-edge",
+        };
+        request = request
+            .keyword("sides", sides)
+            .keyword(
+                "print-color-mode",
+                if settings.color {
+                    "color"
+                } else {
+                    "monochrome"
+                },
+            )
+            .enumeration(
+                "orientation-requested",
+                match settings.orientation {
+                    Orientation::Portrait => 3,
+                    Orientation::Landscape => 4,
+                },
+            )
+            .keyword("media", settings.paper_size.pwg_name())
+            .keyword(
+                "print-scaling",
+                if settings.scale_to_fit { "fit" } else { "none" },
+            );
 
-        let output = cmd
-            .output()
-            .map_err(|e| PrintError::PrintError(format!("Failed to execute lp command: {}", e)))?;
+        if let Some(ref pages) = settings.pages {
+            let ranges: Vec<(i32, i32)> = pages
+                .runs()
+                .into_iter()
+                .map(|(first, last)| (first as i32 + 1, last as i32 + 1))
+                .collect();
+            request = request.ranges("page-ranges", &ranges);
+        }
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(PrintError::PrintError(format!(
-                "lp command failed: {}",
-                stderr
-            )));
+        // CUPS takes page margins in points
+        let margins = settings.margins;
+        for (attribute, mm) in [
+            ("page-top", margins.top),
+            ("page-right", margins.right),
+            ("page-bottom", margins.bottom),
+            ("page-left", margins.left),
+        ] {
+            request = request.integer(attribute, (mm / MM_PER_POINT).round() as i32);
         }
 
-        Ok(())
+        request.finish(document)
     }
-}
 
-impl Printer for LinuxPrinter {
-    fn get_printers() -> Result<Vec<PrinterInfo>> {
-        use std::process::Command;
-
-        let output = Command::new("lpstat")
-            .args(&["-a"])
-            .output()
-            .map_err(|e| PrintError::InitError(format!("Failed to list printers: {}", e)))?;
-
-        let output_str = String::from_utf8_lossy(&output.stdout);
-        let default_printer = Command::new("lpstat")
-            .args(&["-d"])
-            .output()
-            .ok()
-            .and_then(|o| {
-                let s = String::from_utf8_lossy(&o.stdout);
-                s.find("destination: ")
-                    .map(|pos| s[pos + 12..].trim().to_string())
-            });
-
-        let mut printers = Vec::new();
-
-        for line in output_str.lines() {
-            if let Some(pos) = line.find(' ') {
-                let name = &line[..pos];
-                if !name.is_empty() {
-                    let is_default = default_printer.as_ref().map(|d| d == name).unwrap_or(false);
-                    printers.push(PrinterInfo {
-                        name: name.to_string(),
-                        is_default,
-                        supports_color: true,
-                        supports_duplex: true,
-                    });
-                }
-            }
-        }
+    /// Printers on `server`, the default one marked
+    pub fn get_printers_from(server: &IppServer) -> Result<Vec<PrinterInfo>> {
+        let default = Self::default_printer(server).ok();
+        let request = Self::request(ipp::CUPS_GET_PRINTERS)
+            .keywords("requested-attributes", PRINTER_ATTRIBUTES)
+            .finish(&[]);
+        let response = Self::check(
+            server
+                .send("/", &request)
+                .map_err(|e| PrintError::InitError(e.to_string()))?,
+        )?;
 
+        let printers: Vec<PrinterInfo> = response
+            .groups_tagged(ipp::PRINTER_GROUP)
+            .filter_map(|attributes| Self::printer_info(attributes, default.as_deref()))
+            .collect();
         if printers.is_empty() {
             return Err(PrintError::NoPrinter);
         }
-
         Ok(printers)
     }
 
+    /// Send `pdf_path` to a printer on `server`
+    pub fn print_pdf_on(
+        server: &IppServer,
+        pdf_path: &Path,
+        settings: &PrintSettings,
+        printer_name: Option<&str>,
+        progress: &JobProgress,
+    ) -> Result<JobStatus> {
+        let printer = match printer_name {
+            Some(name) => name.to_string(),
+            None => Self::default_printer(server)?,
+        };
+        let document = std::fs::read(pdf_path).map_err(|e| {
+            PrintError::Failed(format!("Failed to read {}: {}", pdf_path.display(), e))
+        })?;
+        let job_name = pdf_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "document".to_string());
+
+        let request = Self::print_job_request(server, &printer, &job_name, settings, &document);
+        // Once sent, the job can only be cancelled on the server
+        progress.check()?;
+        let response = Self::check(server.send(&Self::printer_resource(&printer), &request)?)?;
+        Self::parse_job(&printer, &response)
+    }
+
+    /// Current state of `job` on `server`
+    pub fn job_status_on(server: &IppServer, job: &JobStatus) -> Result<JobStatus> {
+        let request = Self::request(ipp::GET_JOB_ATTRIBUTES)
+            .uri(
+                "printer-uri",
+                &server.uri(&Self::printer_resource(&job.printer)),
+            )
+            .integer("job-id", job.id as i32)
+            .name("requesting-user-name", &Self::user_name())
+            .keywords("requested-attributes", JOB_ATTRIBUTES)
+            .finish(&[]);
+        let response = Self::check(server.send("/jobs/", &request)?)?;
+        Self::parse_job(&job.printer, &response)
+    }
+
+    /// Cancel `job` on `server`
+    pub fn cancel_job_on(server: &IppServer, job: &JobStatus) -> Result<()> {
+        let request = Self::request(ipp::CANCEL_JOB)
+            .uri(
+                "printer-uri",
+                &server.uri(&Self::printer_resource(&job.printer)),
+            )
+            .integer("job-id", job.id as i32)
+            .name("requesting-user-name", &Self::user_name())
+            .finish(&[]);
+        Self::check(server.send("/jobs/", &request)?).map(|_| ())
+    }
+}
+
+impl Printer for LinuxPrinter {
+    fn get_printers() -> Result<Vec<PrinterInfo>> {
+        Self::get_printers_from(&IppServer::from_env())
+    }
+
     fn print_pdf(
         pdf_path: &Path,
         settings: &PrintSettings,
         printer_name: Option<&str>,
-    ) -> Result<()> {
-        Self::print_pdf_native(pdf_path, settings, printer_name)
+        progress: &JobProgress,
+    ) -> Result<JobStatus> {
+        Self::print_pdf_on(
+            &IppServer::from_env(),
+            pdf_path,
+            settings,
+            printer_name,
+            progress,
+        )
     }
 
-    fn show_print_dialog(pdf_path: &Path) -> Result<()> {
-        use std::process::Command;
+    fn job_status(job: &JobStatus) -> Result<JobStatus> {
+        Self::job_status_on(&IppServer::from_env(), job)
+    }
 
-        // Try xdg-open first to open with default PDF viewer
-        let result = Command::new("xdg-open").arg(pdf_path).spawn();
+    fn cancel_job(job: &JobStatus) -> Result<()> {
+        Self::cancel_job_on(&IppServer::from_env(), job)
+    }
+}
+
+pub use LinuxPrinter as PlatformPrinter;
 
-        if result.is_ok() {
-            return Ok(());
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::print::PageSet;
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixListener;
+
+    /// Body of an HTTP request read off `stream`
+    fn read_request(stream: &mut impl Read) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut buffer = [0u8; 4096];
+        loop {
+            let read = stream.read(&mut buffer).unwrap();
+            data.extend_from_slice(&buffer[..read]);
+            if let Some(split) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+                let head = String::from_utf8_lossy(&data[..split]).to_lowercase();
+                let length: usize = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length:"))
+                    .and_then(|length| length.trim().parse().ok())
+                    .unwrap_or(0);
+                if data.len() >= split + 4 + length || read == 0 {
+                    return data[split + 4..].to_vec();
+                }
+            }
+            assert!(read > 0, "request ended early");
         }
+    }
 
-        // Fallback to lp with default settings
+    /// HTTP reply carrying `body` in chunks of a few bytes
+    fn chunked_reply(body: &[u8]) -> Vec<u8> {
+        let mut reply =
+            b"HTTP/1.1 200 OK\r\nContent-Type: application/ipp\r\nTransfer-Encoding: chunked\r\n\r\n"
+                .to_vec();
+        for chunk in body.chunks(16) {
+            reply.extend_from_slice(format!("{:x}\r\n", chunk.len()).as_bytes());
+            reply.extend_from_slice(chunk);
+            reply.extend_from_slice(b"\r\n");
+        }
+        reply.extend_from_slice(b"0\r\n\r\n");
+        reply
+    }
+
+    fn reply(body: &[u8]) -> Vec<u8> {
+        let mut reply = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/ipp\r\nContent-Length: {}\r\n\r\n",
+            body.len()
+        )
+        .into_bytes();
+        reply.extend_from_slice(body);
+        reply
+    }
+
+    #[test]
+    fn test_ipp_server_exchange() {
+        // A stand-in for CUPS answering each connection with the next reply
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("cups.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+        let replies = vec![
+            reply(
+                &IppRequest::new(0, 1)
+                    .group(ipp::PRINTER_GROUP)
+                    .name("printer-name", "Office")
+                    .finish(&[]),
+            ),
+            chunked_reply(
+                &IppRequest::new(0, 2)
+                    .group(ipp::PRINTER_GROUP)
+                    .name("printer-name", "Office")
+                    .boolean("color-supported", true)
+                    .keywords("sides-supported", &["one-sided", "two-sided-long-edge"])
+                    .keywords(
+                        "media-supported",
+                        &["iso_a4_210x297mm", "na_letter_8.5x11in"],
+                    )
+                    .group(ipp::PRINTER_GROUP)
+                    .name("printer-name", "Labels")
+                    .boolean("color-supported", false)
+                    .keywords("sides-supported", &["one-sided"])
+                    .finish(&[]),
+            ),
+            reply(
+                &IppRequest::new(0, 3)
+                    .job_group()
+                    .integer("job-id", 42)
+                    .enumeration("job-state", 5)
+                    .keywords("job-state-reasons", &["job-printing"])
+                    .finish(&[]),
+            ),
+            // server-error-not-accepting-jobs
+            reply(
+                &IppRequest::new(0x0507, 4)
+                    .name("status-message", "Office is not accepting jobs")
+                    .finish(&[]),
+            ),
+        ];
+        let server = std::thread::spawn(move || {
+            let mut operations = Vec::new();
+            for reply in replies {
+                let (mut stream, _) = listener.accept().unwrap();
+                let request = read_request(&mut stream);
+                operations.push((u16::from_be_bytes([request[2], request[3]]), request));
+                stream.write_all(&reply).unwrap();
+            }
+            operations
+        });
+        let cups = IppServer::Unix(socket);
+
+        let printers = LinuxPrinter::get_printers_from(&cups).unwrap();
+        assert_eq!(printers.len(), 2);
+        assert_eq!(printers[0].name, "Office");
+        assert!(printers[0].is_default && printers[0].supports_color);
+        assert!(printers[0].supports_duplex);
+        assert_eq!(printers[0].media.len(), 2);
+        assert_eq!(printers[1].name, "Labels");
+        assert!(!printers[1].is_default && !printers[1].supports_duplex);
+
+        let pdf = dir.path().join("a.pdf");
+        std::fs::write(&pdf, b"%PDF-1.4").unwrap();
         let settings = PrintSettings::default();
-        Self::print_pdf(pdf_path, &settings, None)
+        let progress = JobProgress::default();
+        let job =
+            LinuxPrinter::print_pdf_on(&cups, &pdf, &settings, Some("Office"), &progress).unwrap();
+        assert_eq!(job.id, 42);
+        assert_eq!(job.printer, "Office");
+        assert_eq!(job.state, JobState::Processing);
+        assert_eq!(job.reasons, vec!["job-printing"]);
+
+        match LinuxPrinter::print_pdf_on(&cups, &pdf, &settings, Some("Office"), &progress) {
+            Err(PrintError::Failed(message)) => assert_eq!(message, "Office is not accepting jobs"),
+            other => panic!("expected the server's status message, got {:?}", other),
+        }
+
+        let operations = server.join().unwrap();
+        let codes: Vec<u16> = operations.iter().map(|(code, _)| *code).collect();
+        assert_eq!(
+            codes,
+            vec![
+                ipp::CUPS_GET_DEFAULT,
+                ipp::CUPS_GET_PRINTERS,
+                ipp::PRINT_JOB,
+                ipp::PRINT_JOB
+            ]
+        );
+        assert!(operations[2].1.ends_with(b"%PDF-1.4"));
     }
-}
 
-pub use LinuxPrinter as PlatformPrinter;
+    #[test]
+    fn test_print_job_request() {
+        let server = IppServer::Tcp("localhost".to_string(), 631);
+        let settings = PrintSettings {
+            pages: Some(PageSet::parse("1-3,5", 10, 0, &[]).unwrap()),
+            copies: 2,
+            ..Default::default()
+        };
+        let request =
+            LinuxPrinter::print_job_request(&server, "Office", "a.pdf", &settings, b"%PDF");
+        assert!(request.ends_with(b"\x03%PDF"));
+
+        // Reading the request back as attributes shows what the server gets
+        let mut attributes = request[..request.len() - 4].to_vec();
+        attributes[2..4].copy_from_slice(&[0, 0]);
+        let parsed = ipp::parse_response(&attributes).unwrap();
+        let uri = parsed.attribute("printer-uri").unwrap();
+        assert_eq!(uri.text(), Some("ipp://localhost:631/printers/Office"));
+        assert_eq!(parsed.attribute("copies").unwrap().integer(), Some(2));
+        assert_eq!(
+            parsed.attribute("page-ranges").unwrap().values,
+            vec![IppValue::Range(1, 3), IppValue::Range(5, 5)]
+        );
+        assert_eq!(
+            parsed.attribute("media").unwrap().text(),
+            Some("iso_a4_210x297mm")
+        );
+    }
+}
@@ -1,44 +1,61 @@
-use crate::print::{PrintError, Result};
+use crate::print::{
+    JobProgress, JobStatus, PrintError, PrintSettings, Printer, PrinterInfo, Result,
+};
+use crate::tr;
+use objc::runtime::{Object, BOOL, NO};
+use objc::{class, msg_send, sel, sel_impl};
+use std::ffi::c_void;
 use std::path::Path;
 
-#[cfg(target_os = "macos")]
-use objc::runtime::Object;
-#[cfg(target_os = "macos")]
-use objc::{class, msg_send, sel, sel_impl};
+/// Opaque `dispatch_queue_t` target
+#[repr(C)]
+struct DispatchQueue {
+    _private: [u8; 0],
+}
+
+// libdispatch and pthread, both part of libSystem
+extern "C" {
+    static _dispatch_main_q: DispatchQueue;
+    fn dispatch_sync_f(
+        queue: *const DispatchQueue,
+        context: *mut c_void,
+        work: extern "C" fn(*mut c_void),
+    );
+    fn pthread_main_np() -> i32;
+}
 
 pub struct MacOSPrinter;
 
 impl MacOSPrinter {
-    #[cfg(target_os = "macos")]
+    /// Show the system print panel for `pdf_path` and print it from there.
+    /// Must be called on the main thread.
     pub fn show_print_dialog(pdf_path: &Path) -> Result<()> {
         use std::ffi::CString;
 
         let path_str = pdf_path
             .to_str()
-            .ok_or_else(|| PrintError::PrintError("Invalid PDF path".to_string()))?;
+            .ok_or_else(|| PrintError::Failed("Invalid PDF path".to_string()))?;
 
         unsafe {
             let path_cstr = CString::new(path_str)
-                .map_err(|_| PrintError::PrintError("Invalid path encoding".to_string()))?;
+                .map_err(|_| PrintError::Failed("Invalid path encoding".to_string()))?;
 
             let ns_string: *mut Object =
                 msg_send![class!(NSString), stringWithUTF8String: path_cstr.as_ptr()];
             if ns_string.is_null() {
-                return Err(PrintError::PrintError(
-                    "Failed to create NSString".to_string(),
-                ));
+                return Err(PrintError::Failed("Failed to create NSString".to_string()));
             }
 
             let ns_url: *mut Object = msg_send![class!(NSURL), fileURLWithPath: ns_string];
             if ns_url.is_null() {
-                return Err(PrintError::PrintError("Failed to create NSURL".to_string()));
+                return Err(PrintError::Failed("Failed to create NSURL".to_string()));
             }
 
             let pdf_doc: *mut Object = msg_send![class!(PDFDocument), alloc];
             let pdf_doc: *mut Object = msg_send![pdf_doc, initWithURL: ns_url];
 
             if pdf_doc.is_null() {
-                return Err(PrintError::PrintError(
+                return Err(PrintError::Failed(
                     "Failed to create PDFDocument".to_string(),
                 ));
             }
@@ -48,9 +65,7 @@ impl MacOSPrinter {
 
             if pdf_view.is_null() {
                 let _: () = msg_send![pdf_doc, release];
-                return Err(PrintError::PrintError(
-                    "Failed to create PDFView".to_string(),
-                ));
+                return Err(PrintError::Failed("Failed to create PDFView".to_string()));
             }
 
             let _: () = msg_send![pdf_view, setDocument: pdf_doc];
@@ -62,26 +77,83 @@ impl MacOSPrinter {
             if print_op.is_null() {
                 let _: () = msg_send![pdf_view, release];
                 let _: () = msg_send![pdf_doc, release];
-                return Err(PrintError::PrintError(
+                return Err(PrintError::Failed(
                     "Failed to create NSPrintOperation".to_string(),
                 ));
             }
 
-            let _: () = msg_send![print_op, runOperation];
+            let printed: BOOL = msg_send![print_op, runOperation];
 
             let _: () = msg_send![pdf_view, release];
             let _: () = msg_send![pdf_doc, release];
+
+            if printed == NO {
+                return Err(PrintError::Cancelled);
+            }
         }
 
         Ok(())
     }
+}
+
+impl Printer for MacOSPrinter {
+    /// The system print panel, where the printer itself is chosen
+    fn get_printers() -> Result<Vec<PrinterInfo>> {
+        Ok(vec![PrinterInfo {
+            name: tr!("print.system_panel"),
+            is_default: true,
+            supports_color: true,
+            supports_duplex: true,
+            ..Default::default()
+        }])
+    }
+
+    /// Hand the sheets to the system print panel, which takes the printer,
+    /// copies and duplex from the user
+    fn print_pdf(
+        pdf_path: &Path,
+        _settings: &PrintSettings,
+        printer_name: Option<&str>,
+        progress: &JobProgress,
+    ) -> Result<JobStatus> {
+        progress.check()?;
+        on_main_thread(|| Self::show_print_dialog(pdf_path))?;
+        // The panel has spooled the job by the time it closes
+        let printer = printer_name.map(str::to_string).unwrap_or_default();
+        Ok(JobStatus::completed(&printer))
+    }
+}
 
-    #[cfg(not(target_os = "macos"))]
-    pub fn show_print_dialog(_pdf_path: &Path) -> Result<()> {
-        Err(PrintError::PrintError(
-            "Native print dialog not supported on this platform".to_string(),
-        ))
+/// A closure waiting to run on the main thread, and what it returned
+struct MainThreadTask<F, R> {
+    work: Option<F>,
+    result: Option<R>,
+}
+
+extern "C" fn run_main_thread_task<F: FnOnce() -> R, R>(context: *mut c_void) {
+    let task = unsafe { &mut *(context as *mut MainThreadTask<F, R>) };
+    task.result = task.work.take().map(|work| work());
+}
+
+/// Run `work` on the main thread, where AppKit panels have to be shown, and
+/// wait for it. Print jobs are sent from the background executor.
+fn on_main_thread<F: FnOnce() -> R + Send, R: Send>(work: F) -> R {
+    if unsafe { pthread_main_np() } != 0 {
+        return work();
+    }
+    let mut task = MainThreadTask {
+        work: Some(work),
+        result: None,
+    };
+    unsafe {
+        dispatch_sync_f(
+            &_dispatch_main_q,
+            &mut task as *mut MainThreadTask<F, R> as *mut c_void,
+            run_main_thread_task::<F, R>,
+        );
     }
+    task.result
+        .expect("dispatch_sync_f returns once the task has run")
 }
 
 pub use MacOSPrinter as PlatformPrinter;
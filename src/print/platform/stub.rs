@@ -1,4 +1,6 @@
-use crate::print::{PrintError, PrintSettings, Printer, PrinterInfo, Result};
+use crate::print::{
+    JobProgress, JobStatus, PrintError, PrintSettings, Printer, PrinterInfo, Result,
+};
 use std::path::Path;
 
 pub struct StubPrinter;
@@ -14,13 +16,8 @@ impl Printer for StubPrinter {
         _pdf_path: &Path,
         _settings: &PrintSettings,
         _printer_name: Option<&str>,
-    ) -> Result<()> {
-        Err(PrintError::PlatformError(
-            "Printing not supported on this platform".to_string(),
-        ))
-    }
-
-    fn show_print_dialog(_pdf_path: &Path) -> Result<()> {
+        _progress: &JobProgress,
+    ) -> Result<JobStatus> {
         Err(PrintError::PlatformError(
             "Printing not supported on this platform".to_string(),
         ))
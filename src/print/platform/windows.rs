@@ -1,25 +1,26 @@
+use crate::pdf::color_filter::ColorFilter;
 use crate::pdf::PdfDocument;
-use crate::print::{PrintError, PrintSettings, Printer, PrinterInfo, Result};
+use crate::print::{
+    JobProgress, JobStatus, Orientation, PaperSize, PrintError, PrintSettings, Printer,
+    PrinterInfo, Result,
+};
 use std::ffi::OsString;
 use std::os::windows::ffi::OsStrExt;
 use std::path::Path;
 use windows::{
     core::PCWSTR,
-    Win32::Foundation::HWND,
     Win32::Graphics::Gdi::{
         CreateDCW, DeleteDC, GetDeviceCaps, StretchDIBits, BITMAPINFO, BITMAPINFOHEADER, BI_RGB,
         DIB_RGB_COLORS, HDC, LOGPIXELSX, LOGPIXELSY, SRCCOPY,
     },
     Win32::Graphics::Printing::{EnumPrintersW, PRINTER_ENUM_LOCAL, PRINTER_INFO_2W},
-    Win32::UI::Controls::Dialogs::{
-        PrintDlgW, PD_ALLPAGES, PD_NOPAGENUMS, PD_NOSELECTION, PD_RETURNDC, PRINTDLGW,
-    },
 };
 
 // Import GDI printing functions from winspool
 extern "system" {
     fn StartDocW(hdc: HDC, lpdi: *const DOCINFOW) -> i32;
     fn EndDoc(hdc: HDC) -> i32;
+    fn AbortDoc(hdc: HDC) -> i32;
     fn StartPage(hdc: HDC) -> i32;
     fn EndPage(hdc: HDC) -> i32;
 }
@@ -35,21 +36,102 @@ struct DOCINFOW {
     fwType: u32,
 }
 
+// Printer settings functions from winspool
+#[link(name = "winspool")]
+extern "system" {
+    fn OpenPrinterW(name: PCWSTR, printer: *mut isize, defaults: *const core::ffi::c_void) -> i32;
+    fn ClosePrinter(printer: isize) -> i32;
+    fn DocumentPropertiesW(
+        hwnd: isize,
+        printer: isize,
+        device_name: PCWSTR,
+        output: *mut DEVMODEW,
+        input: *const DEVMODEW,
+        mode: u32,
+    ) -> i32;
+}
+
+/// The printer fields of DEVMODEW; drivers append private data after it
+#[repr(C)]
+#[allow(non_snake_case, dead_code)]
+#[derive(Debug, Clone, Copy)]
+struct DEVMODEW {
+    dmDeviceName: [u16; 32],
+    dmSpecVersion: u16,
+    dmDriverVersion: u16,
+    dmSize: u16,
+    dmDriverExtra: u16,
+    dmFields: u32,
+    dmOrientation: i16,
+    dmPaperSize: i16,
+    dmPaperLength: i16,
+    dmPaperWidth: i16,
+    dmScale: i16,
+    dmCopies: i16,
+    dmDefaultSource: i16,
+    dmPrintQuality: i16,
+    dmColor: i16,
+    dmDuplex: i16,
+    dmYResolution: i16,
+    dmTTOption: i16,
+    dmCollate: i16,
+    dmFormName: [u16; 32],
+    dmLogPixels: u16,
+    dmBitsPerPel: u32,
+    dmPelsWidth: u32,
+    dmPelsHeight: u32,
+    dmNup: u32,
+    dmDisplayFrequency: u32,
+    dmICMMethod: u32,
+    dmICMIntent: u32,
+    dmMediaType: u32,
+    dmDitherType: u32,
+    dmReserved1: u32,
+    dmReserved2: u32,
+    dmPanningWidth: u32,
+    dmPanningHeight: u32,
+}
+
+const DM_ORIENTATION: u32 = 0x0001;
+const DM_PAPERSIZE: u32 = 0x0002;
+const DM_PAPERLENGTH: u32 = 0x0004;
+const DM_PAPERWIDTH: u32 = 0x0008;
+const DM_COPIES: u32 = 0x0100;
+const DM_COLOR: u32 = 0x0800;
+const DM_DUPLEX: u32 = 0x1000;
+const DM_COLLATE: u32 = 0x8000;
+
+const DM_OUT_BUFFER: u32 = 2;
+const DM_IN_BUFFER: u32 = 8;
+const IDOK: i32 = 1;
+
 pub struct WindowsPrinter;
 
 impl WindowsPrinter {
-    fn get_printer_dc(printer_name: &str) -> Result<HDC> {
+    /// A device context for `printer_name` set up with `settings`, and how many
+    /// times to send the document because the driver can't make the copies
+    fn get_printer_dc(printer_name: &str, settings: &PrintSettings) -> Result<(HDC, u32)> {
         let printer_name_wide: Vec<u16> = OsString::from(printer_name)
             .encode_wide()
             .chain(Some(0))
             .collect();
+        let devmode = Self::printer_devmode(&printer_name_wide, settings);
+        let devmode_ptr = devmode
+            .as_ref()
+            .map(|buffer| buffer.as_ptr() as *const DEVMODEW);
+
+        let copies = settings.copies.max(1);
+        let driver_copies = devmode_ptr.is_some_and(|devmode| unsafe {
+            (*devmode).dmFields & DM_COPIES != 0 && (*devmode).dmCopies as u32 >= copies
+        });
 
         unsafe {
             let hdc = CreateDCW(
                 PCWSTR::null(),
                 PCWSTR(printer_name_wide.as_ptr()),
                 PCWSTR::null(),
-                None,
+                devmode_ptr
+                    .map(|devmode| devmode as *const windows::Win32::Graphics::Gdi::DEVMODEW),
             );
 
             if hdc.is_invalid() {
@@ -57,68 +139,163 @@ impl WindowsPrinter {
                     "Failed to create printer DC".to_string(),
                 ))
             } else {
-                Ok(hdc)
+                Ok((hdc, if driver_copies { 1 } else { copies }))
+            }
+        }
+    }
+
+    /// The printer's DEVMODE with copies, duplex, colour, paper and orientation
+    /// from `settings`, as the driver accepted them; `None` if it can't be read
+    fn printer_devmode(printer_name: &[u16], settings: &PrintSettings) -> Option<Vec<u64>> {
+        let name = PCWSTR(printer_name.as_ptr());
+        unsafe {
+            let mut printer = 0isize;
+            if OpenPrinterW(name, &mut printer, std::ptr::null()) == 0 {
+                return None;
+            }
+
+            let size =
+                DocumentPropertiesW(0, printer, name, std::ptr::null_mut(), std::ptr::null(), 0);
+            let mut buffer = Vec::new();
+            if size as usize >= std::mem::size_of::<DEVMODEW>() {
+                // Held as u64s so the DEVMODEW is aligned
+                buffer = vec![0u64; (size as usize).div_ceil(8)];
+                let devmode = buffer.as_mut_ptr() as *mut DEVMODEW;
+                let read =
+                    DocumentPropertiesW(0, printer, name, devmode, std::ptr::null(), DM_OUT_BUFFER);
+                if read == IDOK {
+                    Self::apply_settings(&mut *devmode, settings);
+                    // Let the driver merge the changes and drop what it can't do
+                    let merged = DocumentPropertiesW(
+                        0,
+                        printer,
+                        name,
+                        devmode,
+                        devmode,
+                        DM_IN_BUFFER | DM_OUT_BUFFER,
+                    );
+                    if merged != IDOK {
+                        buffer.clear();
+                    }
+                } else {
+                    buffer.clear();
+                }
             }
+            ClosePrinter(printer);
+            (!buffer.is_empty()).then_some(buffer)
+        }
+    }
+
+    fn apply_settings(devmode: &mut DEVMODEW, settings: &PrintSettings) {
+        devmode.dmCopies = settings.copies.clamp(1, i16::MAX as u32) as i16;
+        devmode.dmCollate = 1;
+        devmode.dmDuplex = match (settings.duplex, settings.orientation) {
+            (false, _) => 1,
+            (true, Orientation::Portrait) => 2,
+            // Landscape sheets, such as booklets, turn over on the short edge
+            (true, Orientation::Landscape) => 3,
+        };
+        devmode.dmColor = if settings.color { 2 } else { 1 };
+        devmode.dmOrientation = match settings.orientation {
+            Orientation::Portrait => 1,
+            Orientation::Landscape => 2,
+        };
+        devmode.dmPaperSize = match settings.paper_size {
+            PaperSize::Letter => 1,
+            PaperSize::Tabloid => 3,
+            PaperSize::Legal => 5,
+            PaperSize::A3 => 8,
+            PaperSize::A4 => 9,
+            PaperSize::A5 => 11,
+        };
+        // In tenths of a millimetre, portrait way up
+        let (width, length) = settings.paper_size.dimensions_mm();
+        devmode.dmPaperWidth = (width * 10.0).round() as i16;
+        devmode.dmPaperLength = (length * 10.0).round() as i16;
+        devmode.dmFields |= DM_COPIES
+            | DM_COLLATE
+            | DM_DUPLEX
+            | DM_COLOR
+            | DM_ORIENTATION
+            | DM_PAPERSIZE
+            | DM_PAPERWIDTH
+            | DM_PAPERLENGTH;
+    }
+
+    /// Send the document `copies` times, one print job each
+    fn print_pdf_to_dc(
+        hdc: HDC,
+        pdf_path: &Path,
+        settings: &PrintSettings,
+        copies: u32,
+        progress: &JobProgress,
+    ) -> Result<()> {
+        let pdf_doc = PdfDocument::open(pdf_path)
+            .map_err(|e| PrintError::Failed(format!("Failed to open PDF: {}", e)))?;
+
+        let page_count = pdf_doc.page_count();
+        let pages = settings
+            .pages
+            .clone()
+            .unwrap_or_else(|| crate::print::PageSet::all(page_count));
+
+        let doc_name: Vec<u16> = OsString::from("PDF Document")
+            .encode_wide()
+            .chain(Some(0))
+            .collect();
+
+        let doc_info = DOCINFOW {
+            cbSize: std::mem::size_of::<DOCINFOW>() as i32,
+            lpszDocName: PCWSTR(doc_name.as_ptr()),
+            lpszOutput: PCWSTR::null(),
+            lpszDatatype: PCWSTR::null(),
+            fwType: 0,
+        };
+
+        progress.start(pages.len() * copies as usize);
+        for _ in 0..copies {
+            Self::print_copy(hdc, &doc_info, &pdf_doc, &pages, settings, progress)?;
         }
+
+        Ok(())
     }
 
-    fn print_pdf_to_dc(hdc: HDC, pdf_path: &Path, settings: &PrintSettings) -> Result<()> {
+    /// Print `pages` as one job
+    fn print_copy(
+        hdc: HDC,
+        doc_info: &DOCINFOW,
+        pdf_doc: &PdfDocument,
+        pages: &crate::print::PageSet,
+        settings: &PrintSettings,
+        progress: &JobProgress,
+    ) -> Result<()> {
+        let page_count = pdf_doc.page_count();
         unsafe {
             let dpi_x = GetDeviceCaps(hdc, LOGPIXELSX);
             let dpi_y = GetDeviceCaps(hdc, LOGPIXELSY);
 
-            let (page_width_mm, page_height_mm) = settings.paper_size.dimensions_mm();
-            let page_width_px = (page_width_mm / 25.4 * dpi_x as f32) as i32;
-            let page_height_px = (page_height_mm / 25.4 * dpi_y as f32) as i32;
-
-            let pdf_doc = PdfDocument::open(pdf_path)
-                .map_err(|e| PrintError::PrintError(format!("Failed to open PDF: {}", e)))?;
-
-            let page_count = pdf_doc.page_count();
-            let page_range = settings
-                .page_range
-                .clone()
-                .unwrap_or_else(|| crate::print::PageRange::all(page_count));
-
-            let doc_name: Vec<u16> = OsString::from("PDF Document")
-                .encode_wide()
-                .chain(Some(0))
-                .collect();
-
-            let doc_info = DOCINFOW {
-                cbSize: std::mem::size_of::<DOCINFOW>() as i32,
-                lpszDocName: PCWSTR(doc_name.as_ptr()),
-                lpszOutput: PCWSTR::null(),
-                lpszDatatype: PCWSTR::null(),
-                fwType: 0,
-            };
-
-            if StartDocW(hdc, &doc_info) <= 0 {
-                return Err(PrintError::PrintError(
-                    "Failed to start document".to_string(),
-                ));
+            if StartDocW(hdc, doc_info) <= 0 {
+                return Err(PrintError::Failed("Failed to start document".to_string()));
             }
 
-            for page_num in page_range.start..=page_range.end {
+            for &page_num in pages.pages() {
                 if page_num >= page_count {
                     break;
                 }
+                // Drop what has been spooled so far rather than print half a job
+                if progress.is_cancelled() {
+                    AbortDoc(hdc);
+                    return Err(PrintError::Cancelled);
+                }
 
                 if StartPage(hdc) <= 0 {
                     EndDoc(hdc);
-                    return Err(PrintError::PrintError("Failed to start page".to_string()));
+                    return Err(PrintError::Failed("Failed to start page".to_string()));
                 }
 
-                if let Err(e) = Self::render_pdf_page_to_dc(
-                    hdc,
-                    &pdf_doc,
-                    page_num,
-                    page_width_px,
-                    page_height_px,
-                    dpi_x,
-                    dpi_y,
-                    settings,
-                ) {
+                if let Err(e) =
+                    Self::render_pdf_page_to_dc(hdc, pdf_doc, page_num, dpi_x, dpi_y, settings)
+                {
                     EndPage(hdc);
                     EndDoc(hdc);
                     return Err(e);
@@ -126,12 +303,13 @@ impl WindowsPrinter {
 
                 if EndPage(hdc) <= 0 {
                     EndDoc(hdc);
-                    return Err(PrintError::PrintError("Failed to end page".to_string()));
+                    return Err(PrintError::Failed("Failed to end page".to_string()));
                 }
+                progress.advance();
             }
 
             if EndDoc(hdc) <= 0 {
-                return Err(PrintError::PrintError("Failed to end document".to_string()));
+                return Err(PrintError::Failed("Failed to end document".to_string()));
             }
 
             Ok(())
@@ -142,31 +320,27 @@ impl WindowsPrinter {
         hdc: HDC,
         pdf_doc: &PdfDocument,
         page_num: usize,
-        page_width: i32,
-        page_height: i32,
         dpi_x: i32,
         _dpi_y: i32,
         settings: &PrintSettings,
     ) -> Result<()> {
         let zoom = (dpi_x as f32 / 72.0) * 2.0;
-        let (bitmap_data, width, height) = pdf_doc
-            .render_page(page_num, zoom)
-            .map_err(|e| PrintError::PrintError(format!("Failed to render page: {}", e)))?;
-
-        let (dest_width, dest_height) = if settings.scale_to_fit {
-            let scale_x = page_width as f32 / width as f32;
-            let scale_y = page_height as f32 / height as f32;
-            let scale = scale_x.min(scale_y);
-            (
-                (width as f32 * scale) as i32,
-                (height as f32 * scale) as i32,
-            )
-        } else {
-            (width as i32, height as i32)
-        };
-
-        let x_offset = (page_width - dest_width) / 2;
-        let y_offset = (page_height - dest_height) / 2;
+        let (bitmap_data, width, height) =
+            pdf_doc
+                .render_page(page_num, zoom, ColorFilter::None)
+                .map_err(|e| PrintError::Failed(format!("Failed to render page: {}", e)))?;
+
+        // Place the page inside the margins, in device pixels
+        let page_size = pdf_doc
+            .get_page_size(page_num)
+            .map_err(|e| PrintError::Failed(format!("Failed to read page size: {}", e)))?;
+        let (x_mm, y_mm, width_mm, height_mm) = settings.page_placement(page_size);
+        let px_per_mm = dpi_x as f32 / 25.4;
+        let x_offset = (x_mm * px_per_mm) as i32;
+        let y_offset = (y_mm * px_per_mm) as i32;
+        // GDI clips anything past the paper edge when printing at actual size
+        let dest_width = (width_mm * px_per_mm) as i32;
+        let dest_height = (height_mm * px_per_mm) as i32;
 
         unsafe {
             let mut bmi: BITMAPINFO = std::mem::zeroed();
@@ -194,7 +368,7 @@ impl WindowsPrinter {
             );
 
             if result == 0 || result == -1 {
-                return Err(PrintError::PrintError(
+                return Err(PrintError::Failed(
                     "Failed to draw bitmap to printer".to_string(),
                 ));
             }
@@ -202,56 +376,6 @@ impl WindowsPrinter {
 
         Ok(())
     }
-
-    fn show_print_dialog_with_settings(_pdf_path: &Path) -> Result<(PrintSettings, String)> {
-        unsafe {
-            // Try to get printers, but don't fail if none are available
-            let printer_name = match Self::get_printers() {
-                Ok(printers) if !printers.is_empty() => printers
-                    .iter()
-                    .find(|p| p.is_default)
-                    .map(|p| p.name.clone())
-                    .or_else(|| printers.first().map(|p| p.name.clone()))
-                    .unwrap_or_default(),
-                _ => String::new(),
-            };
-
-            // Setup PRINTDLG structure
-            let mut pd: PRINTDLGW = std::mem::zeroed();
-            pd.lStructSize = std::mem::size_of::<PRINTDLGW>() as u32;
-            pd.hwndOwner = HWND(0);
-            pd.Flags = PD_ALLPAGES | PD_RETURNDC | PD_NOSELECTION | PD_NOPAGENUMS;
-            pd.nFromPage = 1;
-            pd.nToPage = 1;
-            pd.nMinPage = 1;
-            pd.nMaxPage = 0xFFFF;
-            pd.nCopies = 1;
-
-            // Show print dialog - this will show even if no printers are available
-            if PrintDlgW(&mut pd) == false {
-                return Err(PrintError::PrintError("Print dialog cancelled".to_string()));
-            }
-
-            // Extract settings from dialog
-            let settings = PrintSettings {
-                paper_size: crate::print::PaperSize::A4,
-                orientation: crate::print::Orientation::Portrait,
-                page_range: None,
-                copies: pd.nCopies as u32,
-                duplex: false,
-                color: true,
-                scale_to_fit: true,
-                margins: crate::print::Margins::default(),
-            };
-
-            // Clean up DC from dialog
-            if !pd.hDC.is_invalid() {
-                let _ = DeleteDC(pd.hDC);
-            }
-
-            Ok((settings, printer_name))
-        }
-    }
 }
 
 impl Printer for WindowsPrinter {
@@ -313,6 +437,7 @@ impl Printer for WindowsPrinter {
                     is_default,
                     supports_color: true,
                     supports_duplex: true,
+                    ..Default::default()
                 });
             }
 
@@ -328,7 +453,8 @@ impl Printer for WindowsPrinter {
         pdf_path: &Path,
         settings: &PrintSettings,
         printer_name: Option<&str>,
-    ) -> Result<()> {
+        progress: &JobProgress,
+    ) -> Result<JobStatus> {
         let printer = if let Some(name) = printer_name {
             name.to_string()
         } else {
@@ -340,19 +466,15 @@ impl Printer for WindowsPrinter {
                 .ok_or(PrintError::NoPrinter)?
         };
 
-        let hdc = Self::get_printer_dc(&printer)?;
-        let result = Self::print_pdf_to_dc(hdc, pdf_path, settings);
+        let (hdc, copies) = Self::get_printer_dc(&printer, settings)?;
+        let result = Self::print_pdf_to_dc(hdc, pdf_path, settings, copies, progress);
 
         unsafe {
             let _ = DeleteDC(hdc);
         }
 
-        result
-    }
-
-    fn show_print_dialog(pdf_path: &Path) -> Result<()> {
-        let (settings, printer_name) = Self::show_print_dialog_with_settings(pdf_path)?;
-        Self::print_pdf(pdf_path, &settings, Some(&printer_name))
+        // The pages have gone to the spooler by the time EndDoc returns
+        result.map(|()| JobStatus::completed(&printer))
     }
 }
 
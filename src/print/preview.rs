@@ -0,0 +1,114 @@
+//! Print preview: a sheet rendered as it will be printed
+
+use crate::pdf::{PdfDocument, Result};
+use crate::print::imposition::{sheet_size, Sheet};
+use crate::print::PrintSettings;
+use image::{imageops, Rgba, RgbaImage};
+
+const INK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+/// Render `sheet` onto paper of the chosen size, `px_per_mm` pixels per
+/// millimetre, with its pages placed and scaled as `settings` will print them
+pub fn render_preview(
+    doc: &PdfDocument,
+    sheet: &Sheet,
+    settings: &PrintSettings,
+    px_per_mm: f32,
+) -> Result<RgbaImage> {
+    let (paper_width, paper_height) = sheet_size(settings);
+    let mut paper = RgbaImage::from_pixel(
+        (paper_width * px_per_mm).round().max(1.0) as u32,
+        (paper_height * px_per_mm).round().max(1.0) as u32,
+        Rgba([255, 255, 255, 255]),
+    );
+    let px = |mm: f32| (mm * px_per_mm).round() as i64;
+
+    for placement in &sheet.placements {
+        let (x, y, width, height) = placement.rect;
+        let (width_px, height_px) = (px(width).max(1) as u32, px(height).max(1) as u32);
+        let page_size = doc.get_page_size(placement.page)?;
+        let page_width = if placement.rotated {
+            page_size.1
+        } else {
+            page_size.0
+        };
+        let dpi = width_px as f32 / page_width.max(1.0) * 72.0;
+        let rendered = doc.render_page_image(placement.page, dpi, false)?;
+        let rendered = if placement.rotated {
+            imageops::rotate90(&rendered)
+        } else {
+            rendered
+        };
+        let placed = imageops::resize(
+            &rendered,
+            width_px,
+            height_px,
+            imageops::FilterType::Triangle,
+        );
+
+        // Only the clip shows the page; parts outside the paper are cut off too
+        let (clip_x, clip_y, clip_width, clip_height) = placement.clip;
+        let left = px(x).max(px(clip_x));
+        let top = px(y).max(px(clip_y));
+        let right = (px(x) + width_px as i64).min(px(clip_x + clip_width));
+        let bottom = (px(y) + height_px as i64).min(px(clip_y + clip_height));
+        if right > left && bottom > top {
+            let visible = imageops::crop_imm(
+                &placed,
+                (left - px(x)) as u32,
+                (top - px(y)) as u32,
+                (right - left) as u32,
+                (bottom - top) as u32,
+            );
+            imageops::overlay(&mut paper, &*visible, left, top);
+        }
+    }
+
+    for &(x, y, width, height) in &sheet.borders {
+        draw_line(&mut paper, px(x), px(y), px(x + width), px(y));
+        draw_line(
+            &mut paper,
+            px(x),
+            px(y + height),
+            px(x + width),
+            px(y + height),
+        );
+        draw_line(&mut paper, px(x), px(y), px(x), px(y + height));
+        draw_line(
+            &mut paper,
+            px(x + width),
+            px(y),
+            px(x + width),
+            px(y + height),
+        );
+    }
+    for &(x1, y1, x2, y2) in &sheet.marks {
+        draw_line(&mut paper, px(x1), px(y1), px(x2), px(y2));
+    }
+
+    if !settings.color {
+        to_grayscale(&mut paper);
+    }
+    Ok(paper)
+}
+
+/// Turn `image` grey, as a monochrome printer would print it
+pub(crate) fn to_grayscale(image: &mut RgbaImage) {
+    for pixel in image.pixels_mut() {
+        let [r, g, b, a] = pixel.0;
+        let luma = (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32).round() as u8;
+        *pixel = Rgba([luma, luma, luma, a]);
+    }
+}
+
+/// One pixel wide line; imposition only draws horizontal and vertical ones
+fn draw_line(image: &mut RgbaImage, x1: i64, y1: i64, x2: i64, y2: i64) {
+    let (width, height) = (image.width() as i64, image.height() as i64);
+    for x in x1.min(x2)..=x1.max(x2) {
+        for y in y1.min(y2)..=y1.max(y2) {
+            if (0..width).contains(&x) && (0..height).contains(&y) {
+                image.put_pixel(x as u32, y as u32, INK);
+            }
+        }
+    }
+}
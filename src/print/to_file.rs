@@ -0,0 +1,270 @@
+//! The "Print to file" printer: the sheets a printer would get, written as a
+//! PDF, a PostScript file or a series of PNG images
+
+use super::imposition::impose_pdf;
+use super::preview::to_grayscale;
+use super::{JobProgress, PrintError, PrintSettings, PrinterInfo, Result};
+use crate::pdf::PdfDocument;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use image::{ImageFormat, RgbaImage};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Name the virtual printer is listed under
+pub const FILE_PRINTER_NAME: &str = "Print to file";
+
+/// Resolution of PostScript and PNG output
+const FILE_DPI: f32 = 300.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    Pdf,
+    PostScript,
+    Png,
+}
+
+impl FileFormat {
+    pub const ALL: [FileFormat; 3] = [FileFormat::Pdf, FileFormat::PostScript, FileFormat::Png];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FileFormat::Pdf => "PDF",
+            FileFormat::PostScript => "PostScript",
+            FileFormat::Png => "PNG",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            FileFormat::Pdf => "pdf",
+            FileFormat::PostScript => "ps",
+            FileFormat::Png => "png",
+        }
+    }
+
+    /// Format for a file extension or format name such as "ps"
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_lowercase().as_str() {
+            "pdf" => Some(FileFormat::Pdf),
+            "ps" | "postscript" => Some(FileFormat::PostScript),
+            "png" => Some(FileFormat::Png),
+            _ => None,
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Self::from_extension(&path.extension()?.to_string_lossy())
+    }
+}
+
+impl PrinterInfo {
+    pub fn file_printer() -> Self {
+        Self {
+            name: FILE_PRINTER_NAME.to_string(),
+            is_default: false,
+            supports_color: true,
+            supports_duplex: false,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_file_printer(&self) -> bool {
+        self.name == FILE_PRINTER_NAME
+    }
+}
+
+/// Print `pdf_path` to `output` as `format`, laid out as `settings` would be on
+/// paper. A PNG series is numbered after `output`'s name, `out-001.png` and
+/// so on, unless there is only one sheet. Returns the files written.
+pub fn print_to_file(
+    pdf_path: &Path,
+    settings: &PrintSettings,
+    page_count: usize,
+    format: FileFormat,
+    output: &Path,
+    progress: &JobProgress,
+) -> Result<Vec<PathBuf>> {
+    let pages = settings.page_list(page_count);
+    if format == FileFormat::Pdf {
+        impose_pdf(pdf_path, &pages, settings, output)?;
+        return Ok(vec![output.to_path_buf()]);
+    }
+
+    // Rasterise the composed sheets, so the images match the PDF exactly
+    let sheets_path = output.with_extension(format!("{}.tmp.pdf", format.extension()));
+    let sheet_settings = impose_pdf(pdf_path, &pages, settings, &sheets_path)?;
+    let result = PdfDocument::open(&sheets_path)
+        .map_err(|e| PrintError::Failed(e.to_string()))
+        .and_then(|sheets| {
+            progress.start(sheets.page_count());
+            let render = |index: usize| -> Result<RgbaImage> {
+                progress.check()?;
+                let mut image = sheets
+                    .render_page_image(index, FILE_DPI, false)
+                    .map_err(|e| PrintError::Failed(e.to_string()))?;
+                if !sheet_settings.color {
+                    to_grayscale(&mut image);
+                }
+                progress.advance();
+                Ok(image)
+            };
+            match format {
+                FileFormat::Png => write_png_series(sheets.page_count(), render, output),
+                _ => write_postscript(sheets.page_count(), render, !settings.color, output)
+                    .map(|()| vec![output.to_path_buf()]),
+            }
+        });
+    let _ = std::fs::remove_file(&sheets_path);
+    // Half a PostScript file is no use to anyone
+    if format == FileFormat::PostScript && matches!(result, Err(PrintError::Cancelled)) {
+        let _ = std::fs::remove_file(output);
+    }
+    result
+}
+
+fn write_png_series(
+    count: usize,
+    render: impl Fn(usize) -> Result<RgbaImage>,
+    output: &Path,
+) -> Result<Vec<PathBuf>> {
+    let stem = output
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "sheet".to_string());
+    let mut files = Vec::with_capacity(count);
+    for index in 0..count {
+        let path = if count == 1 {
+            output.to_path_buf()
+        } else {
+            output.with_file_name(format!("{}-{:03}.png", stem, index + 1))
+        };
+        render(index)?
+            .save_with_format(&path, ImageFormat::Png)
+            .map_err(|e| {
+                PrintError::Failed(format!("Failed to write {}: {}", path.display(), e))
+            })?;
+        files.push(path);
+    }
+    Ok(files)
+}
+
+/// Write the sheets as PostScript (LanguageLevel 3), one compressed image per page
+fn write_postscript(
+    count: usize,
+    render: impl Fn(usize) -> Result<RgbaImage>,
+    gray: bool,
+    output: &Path,
+) -> Result<()> {
+    let write_failed = |e: std::io::Error| {
+        PrintError::Failed(format!("Failed to write {}: {}", output.display(), e))
+    };
+    let file = std::fs::File::create(output).map_err(write_failed)?;
+    let mut out = std::io::BufWriter::new(file);
+
+    let points = |pixels: u32| pixels as f32 * 72.0 / FILE_DPI;
+    write!(
+        out,
+        "%!PS-Adobe-3.0\n%%Creator: LingPDF\n%%LanguageLevel: 3\n%%Pages: {}\n\
+         %%BoundingBox: (atend)\n%%EndComments\n",
+        count
+    )
+    .map_err(write_failed)?;
+
+    // Pages are rendered one at a time, so the overall box goes in the trailer
+    let (mut max_width, mut max_height) = (0.0f32, 0.0f32);
+    for index in 0..count {
+        let page = render(index)?;
+        let (width, height) = (page.width(), page.height());
+        max_width = max_width.max(points(width));
+        max_height = max_height.max(points(height));
+        let (colors, color_space) = if gray {
+            (1, "/DeviceGray")
+        } else {
+            (3, "/DeviceRGB")
+        };
+        let mut samples = Vec::with_capacity((width * height) as usize * colors);
+        for pixel in page.pixels() {
+            samples.extend_from_slice(&pixel.0[..colors]);
+        }
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&samples).map_err(write_failed)?;
+        let compressed = encoder.finish().map_err(write_failed)?;
+
+        write!(
+            out,
+            "%%Page: {number} {number}\n%%PageBoundingBox: 0 0 {pw} {ph}\n\
+             << /PageSize [{pw} {ph}] >> setpagedevice\n\
+             gsave\n{pw} {ph} scale\n{color_space} setcolorspace\n\
+             << /ImageType 1 /Width {width} /Height {height} /BitsPerComponent 8\n\
+             /Decode [{decode}] /ImageMatrix [{width} 0 0 -{height} 0 {height}]\n\
+             /DataSource currentfile /ASCII85Decode filter /FlateDecode filter >> image\n",
+            number = index + 1,
+            pw = points(width),
+            ph = points(height),
+            decode = if gray { "0 1" } else { "0 1 0 1 0 1" },
+        )
+        .map_err(write_failed)?;
+        out.write_all(&ascii85(&compressed)).map_err(write_failed)?;
+        out.write_all(b"\ngrestore\nshowpage\n")
+            .map_err(write_failed)?;
+    }
+
+    write!(
+        out,
+        "%%Trailer\n%%BoundingBox: 0 0 {} {}\n%%EOF\n",
+        max_width.ceil(),
+        max_height.ceil()
+    )
+    .map_err(write_failed)?;
+    out.flush().map_err(write_failed)
+}
+
+/// ASCII85 encoding as PostScript's ASCII85Decode reads it, ending in `~>`
+fn ascii85(data: &[u8]) -> Vec<u8> {
+    const LINE: usize = 72;
+    let mut encoded = Vec::with_capacity(data.len() * 5 / 4 + data.len() / LINE + 3);
+    let mut column = 0;
+    let mut push = |encoded: &mut Vec<u8>, byte: u8| {
+        encoded.push(byte);
+        column += 1;
+        if column == LINE {
+            encoded.push(b'\n');
+            column = 0;
+        }
+    };
+
+    for chunk in data.chunks(4) {
+        let mut group = [0u8; 4];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let value = u32::from_be_bytes(group);
+        if value == 0 && chunk.len() == 4 {
+            push(&mut encoded, b'z');
+            continue;
+        }
+        let mut digits = [0u8; 5];
+        let mut rest = value;
+        for digit in digits.iter_mut().rev() {
+            *digit = (rest % 85) as u8 + b'!';
+            rest /= 85;
+        }
+        // A final group of n bytes is written as n + 1 characters
+        for &digit in &digits[..chunk.len() + 1] {
+            push(&mut encoded, digit);
+        }
+    }
+    encoded.extend_from_slice(b"~>");
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ascii85() {
+        assert_eq!(ascii85(b"Man "), b"9jqo^~>");
+        assert_eq!(ascii85(&[0, 0, 0, 0]), b"z~>");
+        assert_eq!(ascii85(b"."), b"/c~>");
+    }
+}
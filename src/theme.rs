@@ -1,14 +1,22 @@
+use crate::utils::path::get_themes_dir;
 use gpui::*;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum Theme {
     Light,
     #[default]
     Dark,
+    /// Light or dark, following the system appearance
+    System,
+    /// A theme file in the themes folder, by name
+    Custom(String),
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct ThemeColors {
     pub background: Rgba,
     pub background_secondary: Rgba,
@@ -21,16 +29,31 @@ pub struct ThemeColors {
     pub pdf_view: Rgba,
     pub moon_color: Rgba,
     pub sun_color: Rgba,
+    /// Highlight over selected text
+    pub selection: Rgba,
 }
 
 impl ThemeColors {
-    pub fn for_theme(theme: Theme) -> Self {
+    /// Colours of `theme`, with `appearance` deciding between light and dark
+    /// when following the system. Missing user themes fall back to dark.
+    pub fn for_theme(theme: &Theme, appearance: WindowAppearance, user: &UserThemes) -> Self {
         match theme {
             Theme::Light => Self::light(),
             Theme::Dark => Self::dark(),
+            Theme::System => match appearance {
+                WindowAppearance::Light | WindowAppearance::VibrantLight => Self::light(),
+                WindowAppearance::Dark | WindowAppearance::VibrantDark => Self::dark(),
+            },
+            Theme::Custom(name) => user.get(name).unwrap_or_else(Self::dark),
         }
     }
 
+    /// Whether the background is dark, so light text and a sun icon suit it
+    pub fn is_dark(&self) -> bool {
+        let bg = self.background;
+        0.299 * bg.r + 0.587 * bg.g + 0.114 * bg.b < 0.5
+    }
+
     fn light() -> Self {
         Self {
             background: rgb(0xffffff),
@@ -44,6 +67,7 @@ impl ThemeColors {
             pdf_view: rgb(0xf0f0f0),
             moon_color: rgb(0x1a1a1a),
             sun_color: rgb(0xffcc00),
+            selection: rgba(0x3399ff80),
         }
     }
 
@@ -60,6 +84,157 @@ impl ThemeColors {
             pdf_view: rgb(0x404040),
             moon_color: rgb(0xcccccc),
             sun_color: rgb(0xffdd44),
+            selection: rgba(0x3399ff80),
+        }
+    }
+
+    fn field_mut(&mut self, name: &str) -> Option<&mut Rgba> {
+        Some(match name {
+            "background" => &mut self.background,
+            "background_secondary" => &mut self.background_secondary,
+            "background_tertiary" => &mut self.background_tertiary,
+            "text" => &mut self.text,
+            "text_secondary" => &mut self.text_secondary,
+            "border" => &mut self.border,
+            "toolbar" => &mut self.toolbar,
+            "status_bar" => &mut self.status_bar,
+            "pdf_view" => &mut self.pdf_view,
+            "moon_color" => &mut self.moon_color,
+            "sun_color" => &mut self.sun_color,
+            "selection" => &mut self.selection,
+            _ => return None,
+        })
+    }
+
+    /// Read a theme file's contents, TOML unless `json` is set. Colours are
+    /// "#rrggbb" or "#rrggbbaa"; any left out come from the `base` palette,
+    /// "light" or "dark".
+    pub fn parse(source: &str, json: bool) -> anyhow::Result<Self> {
+        let mut entries: HashMap<String, String> = if json {
+            serde_json::from_str(source)?
+        } else {
+            toml::from_str(source)?
+        };
+        let mut colors = match entries.remove("base").as_deref() {
+            Some("light") => Self::light(),
+            None | Some("dark") => Self::dark(),
+            Some(other) => anyhow::bail!("unknown base \"{}\", use light or dark", other),
+        };
+        for (name, value) in entries {
+            let field = colors
+                .field_mut(&name)
+                .ok_or_else(|| anyhow::anyhow!("unknown colour \"{}\"", name))?;
+            *field = parse_hex(&value)
+                .ok_or_else(|| anyhow::anyhow!("{} is not a colour: \"{}\"", name, value))?;
+        }
+        Ok(colors)
+    }
+}
+
+fn parse_hex(value: &str) -> Option<Rgba> {
+    let hex = value.strip_prefix('#')?;
+    let number = u32::from_str_radix(hex, 16).ok()?;
+    match hex.len() {
+        6 => Some(rgb(number)),
+        8 => Some(rgba(number)),
+        _ => None,
+    }
+}
+
+/// Themes read from `.toml` and `.json` files in the themes folder, named
+/// after the files
+#[derive(Default)]
+pub struct UserThemes {
+    themes: BTreeMap<String, ThemeColors>,
+    /// Each file's modification time when it was read, to notice edits
+    stamps: Vec<(PathBuf, SystemTime)>,
+}
+
+impl UserThemes {
+    /// Read every theme in `dir`, along with the errors of files that could not be read
+    pub fn load(dir: &Path) -> (Self, Vec<String>) {
+        let stamps = scan_themes(dir);
+        let mut themes = BTreeMap::new();
+        let mut errors = Vec::new();
+        for (path, _) in &stamps {
+            let Some(name) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+                continue;
+            };
+            let json = path.extension().is_some_and(|e| e == "json");
+            let colors = std::fs::read_to_string(path)
+                .map_err(anyhow::Error::from)
+                .and_then(|source| ThemeColors::parse(&source, json));
+            match colors {
+                Ok(colors) => {
+                    themes.insert(name, colors);
+                }
+                Err(e) => {
+                    log::warn!("Failed to load theme {}: {}", path.display(), e);
+                    errors.push(format!("{}: {}", name, e));
+                }
+            }
         }
+        (Self { themes, stamps }, errors)
+    }
+
+    pub fn get(&self, name: &str) -> Option<ThemeColors> {
+        self.themes.get(name).copied()
+    }
+
+    /// Whether `stamps`, from [`scan_themes`], differ from the files last read
+    pub fn is_stale(&self, stamps: &[(PathBuf, SystemTime)]) -> bool {
+        self.stamps != stamps
+    }
+}
+
+/// Theme files in `dir` with their modification times, sorted by path
+pub fn scan_themes(dir: &Path) -> Vec<(PathBuf, SystemTime)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut stamps: Vec<(PathBuf, SystemTime)> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == "toml" || e == "json"))
+        .filter_map(|path| {
+            let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            Some((path, modified))
+        })
+        .collect();
+    stamps.sort();
+    stamps
+}
+
+/// Names of the theme files in the themes folder, for the theme menu
+pub fn user_theme_names() -> Vec<String> {
+    scan_themes(&get_themes_dir())
+        .into_iter()
+        .filter_map(|(path, _)| Some(path.file_stem()?.to_string_lossy().to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_hex, ThemeColors};
+    use gpui::{rgb, rgba};
+
+    #[test]
+    fn test_parse_theme() {
+        let toml = "base = \"light\"\nbackground = \"#fdf6e3\"\nselection = \"#268bd260\"\n";
+        let colors = ThemeColors::parse(toml, false).unwrap();
+        assert_eq!(colors.background, rgb(0xfdf6e3));
+        assert_eq!(colors.selection, rgba(0x268bd260));
+        // Colours left out come from the base palette
+        assert_eq!(colors.text, ThemeColors::light().text);
+        assert!(!colors.is_dark());
+
+        let json = r##"{"text": "#eeeeee"}"##;
+        let colors = ThemeColors::parse(json, true).unwrap();
+        assert_eq!(colors.text, rgb(0xeeeeee));
+        assert!(colors.is_dark());
+
+        assert!(ThemeColors::parse("backgroud = \"#000000\"", false).is_err());
+        assert!(ThemeColors::parse("text = \"red\"", false).is_err());
+        assert_eq!(parse_hex("#12345"), None);
     }
 }
@@ -0,0 +1,350 @@
+//! Clipboard flavours GPUI does not offer: HTML and Markdown alongside plain text
+
+#[cfg(target_os = "macos")]
+use objc::runtime::Object;
+#[cfg(target_os = "macos")]
+use objc::{class, msg_send, sel, sel_impl};
+
+/// Put plain text, HTML and Markdown versions of the same text on the clipboard.
+/// Returns false where the platform does not support this, so the caller can fall
+/// back to copying the plain text.
+#[cfg(target_os = "macos")]
+pub fn write_rich_text(plain: &str, html: &str, markdown: &str) -> bool {
+    use objc::runtime::BOOL;
+    use std::ffi::CString;
+
+    let flavours = [
+        ("public.utf8-plain-text", plain),
+        ("public.html", html),
+        ("net.daringfireball.markdown", markdown),
+    ];
+    let Ok(flavours) = flavours
+        .into_iter()
+        .map(|(uti, text)| Ok((CString::new(uti)?, CString::new(text)?)))
+        .collect::<Result<Vec<_>, std::ffi::NulError>>()
+    else {
+        return false;
+    };
+
+    unsafe {
+        let pasteboard: *mut Object = msg_send![class!(NSPasteboard), generalPasteboard];
+        if pasteboard.is_null() {
+            return false;
+        }
+        let _: isize = msg_send![pasteboard, clearContents];
+
+        for (uti, text) in &flavours {
+            let ns_type: *mut Object =
+                msg_send![class!(NSString), stringWithUTF8String: uti.as_ptr()];
+            let ns_text: *mut Object =
+                msg_send![class!(NSString), stringWithUTF8String: text.as_ptr()];
+            if ns_type.is_null() || ns_text.is_null() {
+                return false;
+            }
+            let written: BOOL = msg_send![pasteboard, setString: ns_text forType: ns_type];
+            if written == objc::runtime::NO {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Put plain text, HTML and Markdown versions of the same text on the clipboard,
+/// through Wayland's data-control protocol or by owning the X11 CLIPBOARD selection.
+/// Returns false where neither works, so the caller can fall back to plain text.
+#[cfg(target_os = "linux")]
+pub fn write_rich_text(plain: &str, html: &str, markdown: &str) -> bool {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() && linux::write_wayland(plain, html, markdown)
+    {
+        return true;
+    }
+    std::env::var_os("DISPLAY").is_some() && linux::write_x11(plain, html, markdown)
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use wl_clipboard_rs::copy::{MimeSource, MimeType, Options, Source};
+    use x11rb::connection::{Connection, RequestConnection};
+    use x11rb::protocol::xproto::{
+        Atom, AtomEnum, ConnectionExt as _, CreateWindowAux, EventMask, PropMode,
+        SelectionNotifyEvent, SelectionRequestEvent, Window, WindowClass, SELECTION_NOTIFY_EVENT,
+    };
+    use x11rb::protocol::Event;
+    use x11rb::rust_connection::RustConnection;
+    use x11rb::wrapper::ConnectionExt as _;
+
+    /// Plain text targets other X11 programs ask for
+    const PLAIN_TARGETS: [&str; 3] = ["UTF8_STRING", "text/plain;charset=utf-8", "text/plain"];
+
+    pub(super) fn write_wayland(plain: &str, html: &str, markdown: &str) -> bool {
+        let source = |text: &str, mime_type| MimeSource {
+            source: Source::Bytes(text.as_bytes().into()),
+            mime_type,
+        };
+        let sources = vec![
+            source(plain, MimeType::Text),
+            source(html, MimeType::Specific("text/html".to_string())),
+            source(markdown, MimeType::Specific("text/markdown".to_string())),
+        ];
+        // Serves paste requests from a background thread until replaced
+        match Options::new().copy_multi(sources) {
+            Ok(()) => true,
+            Err(e) => {
+                log::debug!("Wayland rich clipboard unavailable: {}", e);
+                false
+            }
+        }
+    }
+
+    pub(super) fn write_x11(plain: &str, html: &str, markdown: &str) -> bool {
+        let mut flavours: Vec<(&str, Vec<u8>)> = PLAIN_TARGETS
+            .iter()
+            .map(|target| (*target, plain.as_bytes().to_vec()))
+            .collect();
+        flavours.push(("text/html", html.as_bytes().to_vec()));
+        flavours.push(("text/markdown", markdown.as_bytes().to_vec()));
+
+        match SelectionOwner::take(flavours) {
+            Ok(owner) => {
+                std::thread::spawn(move || owner.serve());
+                true
+            }
+            Err(e) => {
+                log::debug!("X11 rich clipboard unavailable: {}", e);
+                false
+            }
+        }
+    }
+
+    /// An invisible window owning the CLIPBOARD selection, answering paste
+    /// requests until another program takes the selection over
+    struct SelectionOwner {
+        conn: RustConnection,
+        window: Window,
+        clipboard: Atom,
+        targets: Atom,
+        flavours: Vec<(Atom, Vec<u8>)>,
+    }
+
+    impl SelectionOwner {
+        fn take(flavours: Vec<(&str, Vec<u8>)>) -> anyhow::Result<Self> {
+            let (conn, screen) = x11rb::connect(None)?;
+            // Large selections need the INCR protocol, which is not implemented
+            let size: usize = flavours
+                .iter()
+                .map(|(_, data)| data.len())
+                .max()
+                .unwrap_or(0);
+            if size + 64 > conn.maximum_request_bytes() {
+                anyhow::bail!("{} bytes is too large for a single request", size);
+            }
+
+            let root = conn.setup().roots[screen].root;
+            let window = conn.generate_id()?;
+            conn.create_window(
+                x11rb::COPY_DEPTH_FROM_PARENT,
+                window,
+                root,
+                0,
+                0,
+                1,
+                1,
+                0,
+                WindowClass::INPUT_ONLY,
+                x11rb::COPY_FROM_PARENT,
+                &CreateWindowAux::new(),
+            )?;
+
+            let intern = |name: &str| -> anyhow::Result<Atom> {
+                Ok(conn.intern_atom(false, name.as_bytes())?.reply()?.atom)
+            };
+            let clipboard = intern("CLIPBOARD")?;
+            let targets = intern("TARGETS")?;
+            let flavours = flavours
+                .into_iter()
+                .map(|(name, data)| Ok((intern(name)?, data)))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            conn.set_selection_owner(window, clipboard, x11rb::CURRENT_TIME)?;
+            if conn.get_selection_owner(clipboard)?.reply()?.owner != window {
+                anyhow::bail!("another client kept the clipboard");
+            }
+            conn.flush()?;
+
+            Ok(Self {
+                conn,
+                window,
+                clipboard,
+                targets,
+                flavours,
+            })
+        }
+
+        fn serve(self) {
+            loop {
+                match self.conn.wait_for_event() {
+                    Ok(Event::SelectionRequest(request)) => {
+                        if let Err(e) = self.answer(&request) {
+                            log::debug!("Failed to answer a clipboard request: {}", e);
+                        }
+                    }
+                    Ok(Event::SelectionClear(_)) | Err(_) => break,
+                    Ok(_) => {}
+                }
+            }
+            let _ = self.conn.destroy_window(self.window);
+            let _ = self.conn.flush();
+        }
+
+        fn answer(&self, request: &SelectionRequestEvent) -> anyhow::Result<()> {
+            // Obsolete clients leave the property out and expect the target's name
+            let mut property = match request.property {
+                x11rb::NONE => request.target,
+                property => property,
+            };
+            if request.target == self.targets {
+                let mut atoms = vec![self.targets];
+                atoms.extend(self.flavours.iter().map(|(atom, _)| *atom));
+                self.conn.change_property32(
+                    PropMode::REPLACE,
+                    request.requestor,
+                    property,
+                    AtomEnum::ATOM,
+                    &atoms,
+                )?;
+            } else if let Some((atom, data)) = self
+                .flavours
+                .iter()
+                .find(|(atom, _)| *atom == request.target)
+            {
+                self.conn.change_property8(
+                    PropMode::REPLACE,
+                    request.requestor,
+                    property,
+                    *atom,
+                    data,
+                )?;
+            } else {
+                property = x11rb::NONE;
+            }
+
+            let notify = SelectionNotifyEvent {
+                response_type: SELECTION_NOTIFY_EVENT,
+                sequence: 0,
+                time: request.time,
+                requestor: request.requestor,
+                selection: self.clipboard,
+                target: request.target,
+                property,
+            };
+            self.conn
+                .send_event(false, request.requestor, EventMask::NO_EVENT, notify)?;
+            self.conn.flush()?;
+            Ok(())
+        }
+    }
+}
+
+/// Put plain text, HTML and Markdown versions of the same text on the clipboard,
+/// as CF_UNICODETEXT, the "HTML Format" and a registered "text/markdown" format.
+/// Returns false if the clipboard can't be opened, so the caller can fall back.
+#[cfg(target_os = "windows")]
+pub fn write_rich_text(plain: &str, html: &str, markdown: &str) -> bool {
+    use windows::core::w;
+    use windows::Win32::Foundation::{GlobalFree, HANDLE, HWND};
+    use windows::Win32::System::DataExchange::{
+        CloseClipboard, EmptyClipboard, OpenClipboard, RegisterClipboardFormatW, SetClipboardData,
+    };
+    use windows::Win32::System::Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE};
+    use windows::Win32::System::Ole::CF_UNICODETEXT;
+
+    /// Copy `bytes` into global memory and hand it to the clipboard, which
+    /// owns it from then on
+    unsafe fn set_data(format: u32, bytes: &[u8]) -> bool {
+        let Ok(memory) = GlobalAlloc(GMEM_MOVEABLE, bytes.len()) else {
+            return false;
+        };
+        let target = GlobalLock(memory) as *mut u8;
+        if target.is_null() {
+            let _ = GlobalFree(memory);
+            return false;
+        }
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), target, bytes.len());
+        let _ = GlobalUnlock(memory);
+        if SetClipboardData(format, HANDLE(memory.0 as isize)).is_err() {
+            let _ = GlobalFree(memory);
+            return false;
+        }
+        true
+    }
+
+    let utf16: Vec<u8> = plain
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    let mut cf_html = cf_html(html).into_bytes();
+    cf_html.push(0);
+    let mut markdown = markdown.as_bytes().to_vec();
+    markdown.push(0);
+
+    unsafe {
+        if OpenClipboard(HWND(0)).is_err() {
+            return false;
+        }
+        let html_format = RegisterClipboardFormatW(w!("HTML Format"));
+        let markdown_format = RegisterClipboardFormatW(w!("text/markdown"));
+        let written = EmptyClipboard().is_ok()
+            && set_data(CF_UNICODETEXT.0 as u32, &utf16)
+            && set_data(html_format, &cf_html)
+            && set_data(markdown_format, &markdown);
+        let _ = CloseClipboard();
+        written
+    }
+}
+
+/// Wrap an HTML fragment in the CF_HTML header Windows programs expect, whose
+/// byte offsets locate the document and the fragment within it
+#[cfg(any(target_os = "windows", test))]
+fn cf_html(fragment: &str) -> String {
+    const HEADER: &str = "Version:0.9\r\nStartHTML:{sh}\r\nEndHTML:{eh}\r\n\
+                          StartFragment:{sf}\r\nEndFragment:{ef}\r\n";
+    const PREFIX: &str = "<html><body>\r\n<!--StartFragment-->";
+    const SUFFIX: &str = "<!--EndFragment-->\r\n</body></html>";
+
+    // Each placeholder becomes a 10-digit number
+    let header_len = HEADER.len() + 4 * (10 - 4);
+    let start_html = header_len;
+    let start_fragment = start_html + PREFIX.len();
+    let end_fragment = start_fragment + fragment.len();
+    let end_html = end_fragment + SUFFIX.len();
+
+    let header = HEADER
+        .replace("{sh}", &format!("{:010}", start_html))
+        .replace("{eh}", &format!("{:010}", end_html))
+        .replace("{sf}", &format!("{:010}", start_fragment))
+        .replace("{ef}", &format!("{:010}", end_fragment));
+    format!("{}{}{}{}", header, PREFIX, fragment, SUFFIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cf_html;
+
+    #[test]
+    fn test_cf_html_offsets() {
+        let fragment = "<p>Caf\u{e9} <strong>bold</strong></p>";
+        let data = cf_html(fragment);
+        let offset = |key: &str| -> usize {
+            let start = data.find(key).unwrap() + key.len();
+            data[start..start + 10].parse().unwrap()
+        };
+        assert_eq!(
+            &data[offset("StartFragment:")..offset("EndFragment:")],
+            fragment
+        );
+        assert!(data[offset("StartHTML:")..].starts_with("<html>"));
+        assert_eq!(offset("EndHTML:"), data.len());
+    }
+}
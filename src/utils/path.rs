@@ -11,6 +11,22 @@ pub fn get_config_path() -> PathBuf {
     path
 }
 
+/// The user's keyboard shortcuts, over the platform's defaults
+pub fn get_keymap_path() -> PathBuf {
+    let mut path = config_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push(APP_NAME);
+    path.push("keymap.json");
+    path
+}
+
+/// Folder the user's theme files are read from
+pub fn get_themes_dir() -> PathBuf {
+    let mut path = config_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push(APP_NAME);
+    path.push("themes");
+    path
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;